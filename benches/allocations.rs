@@ -0,0 +1,107 @@
+//! Counts the allocations of a walk of a generated tree with each backend, e.g. `cargo
+//! bench --bench allocations`, for matchers the names tell apart and for those that need
+//! the whole path. The tree goes into the temporary directory and is removed afterwards;
+//! a directory given as argument is walked as it is instead.
+
+use clap::ValueEnum;
+use quaero::{Backend, Matcher, WalkBuilder, WalkStats};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+const DIRECTORIES: usize = 200;
+const FILES: usize = 250;
+
+/// The system allocator, counting what it is asked for.
+struct Counting;
+
+static ALLOCATIONS: AtomicU64 = AtomicU64::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// A tree of `DIRECTORIES` directories of `FILES` files each, a tenth of them sources.
+fn generate(root: &Path) -> std::io::Result<()> {
+    for directory in 0..DIRECTORIES {
+        let directory = root.join(format!("directory-{}", directory));
+        std::fs::create_dir_all(&directory)?;
+        for file in 0..FILES {
+            let extension = if file % 10 == 0 { "rs" } else { "txt" };
+            std::fs::write(directory.join(format!("file-{}.{}", file, extension)), b"")?;
+        }
+    }
+    Ok(())
+}
+
+/// The allocations of a walk, how many entries it listed and how many it matched.
+fn count(root: &Path, backend: Backend, matcher: &Matcher) -> (u64, u64, usize) {
+    let stats = Arc::new(WalkStats::default());
+    let walk = WalkBuilder::new(root)
+        .backend(backend)
+        .matcher(matcher.clone())
+        .stats(stats.clone())
+        .build();
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let matched = walk.filter(Result::is_ok).count();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    (allocations, stats.entries.load(Ordering::Relaxed), matched)
+}
+
+fn main() -> anyhow::Result<()> {
+    // `cargo bench` passes --bench, which is no directory.
+    let given = std::env::args().skip(1).find(|arg| !arg.starts_with("--"));
+    let (root, generated) = match given {
+        Some(given) => (PathBuf::from(given), false),
+        None => {
+            let root = std::env::temp_dir().join(format!("quaero-bench-{}", std::process::id()));
+            generate(&root)?;
+            (root, true)
+        }
+    };
+
+    let matchers = [
+        ("by name", Matcher::Extension(vec!["rs".to_owned()])),
+        (
+            "by path",
+            Matcher::PathRegex(regex::Regex::new(r"directory-1\d*/.*\.rs$")?),
+        ),
+    ];
+    for (name, matcher) in &matchers {
+        for backend in [Backend::Std, Backend::Fast, Backend::IoUring] {
+            let (allocations, entries, matched) = count(&root, backend, matcher);
+            println!(
+                "{:<8}  {:<8}  {:>9} allocations  {:>5.2} an entry  {} matches",
+                name,
+                backend
+                    .to_possible_value()
+                    .map_or_else(String::new, |value| value.get_name().to_owned()),
+                allocations,
+                allocations as f64 / entries.max(1) as f64,
+                matched
+            );
+        }
+    }
+
+    if generated {
+        std::fs::remove_dir_all(&root)?;
+    }
+    Ok(())
+}
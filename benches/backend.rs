@@ -0,0 +1,79 @@
+//! Times a walk of a generated tree with each backend, e.g. `cargo bench --bench
+//! backend`. The tree goes into the temporary directory and is removed afterwards; a
+//! directory given as argument is walked as it is instead.
+
+use clap::ValueEnum;
+use quaero::{Backend, Matcher, WalkBuilder};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const DIRECTORIES: usize = 200;
+const FILES: usize = 250;
+const RUNS: usize = 5;
+
+/// A tree of `DIRECTORIES` directories of `FILES` files each, a tenth of them sources.
+fn generate(root: &Path) -> std::io::Result<()> {
+    for directory in 0..DIRECTORIES {
+        let directory = root.join(format!("directory-{}", directory));
+        std::fs::create_dir_all(&directory)?;
+        for file in 0..FILES {
+            let extension = if file % 10 == 0 { "rs" } else { "txt" };
+            std::fs::write(directory.join(format!("file-{}.{}", file, extension)), b"")?;
+        }
+    }
+    Ok(())
+}
+
+/// The fastest of `RUNS` walks, and how many entries it matched.
+fn time(root: &Path, backend: Backend, matcher: &Matcher) -> (Duration, usize) {
+    (0..RUNS)
+        .map(|_| {
+            let started = Instant::now();
+            let matched = WalkBuilder::new(root)
+                .backend(backend)
+                .matcher(matcher.clone())
+                .build()
+                .filter(Result::is_ok)
+                .count();
+            (started.elapsed(), matched)
+        })
+        .min()
+        .expect("there is at least one run")
+}
+
+fn main() -> anyhow::Result<()> {
+    // `cargo bench` passes --bench, which is no directory.
+    let given = std::env::args().skip(1).find(|arg| !arg.starts_with("--"));
+    let (root, generated) = match given {
+        Some(given) => (PathBuf::from(given), false),
+        None => {
+            let root = std::env::temp_dir().join(format!("quaero-bench-{}", std::process::id()));
+            generate(&root)?;
+            (root, true)
+        }
+    };
+
+    let matchers = [
+        ("by name", Matcher::Extension(vec!["rs".to_owned()])),
+        ("everything", Matcher::All(Vec::new())),
+    ];
+    for (name, matcher) in &matchers {
+        for backend in [Backend::Std, Backend::Fast, Backend::IoUring] {
+            let (elapsed, matched) = time(&root, backend, matcher);
+            println!(
+                "{:<10}  {:<8}  {:>8.2} ms  {} matches",
+                name,
+                backend
+                    .to_possible_value()
+                    .map_or_else(String::new, |value| value.get_name().to_owned()),
+                elapsed.as_secs_f64() * 1000.0,
+                matched
+            );
+        }
+    }
+
+    if generated {
+        std::fs::remove_dir_all(&root)?;
+    }
+    Ok(())
+}
@@ -0,0 +1,126 @@
+//! Times writing the matches of a generated tree into a pipe, e.g. `cargo bench --bench
+//! output`: line by line with a flush after each, as `println!` does, against through a
+//! buffer as quaero does, and quaero itself printing them all. The tree goes into the
+//! temporary directory and is removed afterwards; a directory given as argument is
+//! walked as it is instead.
+
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+const DIRECTORIES: usize = 200;
+const FILES: usize = 500;
+const RUNS: usize = 5;
+/// As much as quaero buffers before it writes.
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// A tree of `DIRECTORIES` directories of `FILES` files each.
+fn generate(root: &Path) -> std::io::Result<()> {
+    for directory in 0..DIRECTORIES {
+        let directory = root.join(format!("directory-{}", directory));
+        std::fs::create_dir_all(&directory)?;
+        for file in 0..FILES {
+            std::fs::write(directory.join(format!("file-{}.txt", file)), b"")?;
+        }
+    }
+    Ok(())
+}
+
+/// The fastest of `RUNS` runs of `run`.
+fn fastest(mut run: impl FnMut() -> std::io::Result<()>) -> std::io::Result<Duration> {
+    let mut fastest = Duration::MAX;
+    for _ in 0..RUNS {
+        let started = Instant::now();
+        run()?;
+        fastest = fastest.min(started.elapsed());
+    }
+    Ok(fastest)
+}
+
+/// Writes `paths` a line each through `wrap` into a pipe another thread drains.
+fn write_paths<W: Write>(
+    paths: &[PathBuf],
+    wrap: impl FnOnce(std::io::PipeWriter) -> W,
+    flush_lines: bool,
+) -> std::io::Result<()> {
+    let (mut reader, writer) = std::io::pipe()?;
+    let drain = std::thread::spawn(move || std::io::copy(&mut reader, &mut std::io::sink()));
+
+    let mut out = wrap(writer);
+    for path in paths {
+        writeln!(out, "{}", path.display())?;
+        if flush_lines {
+            out.flush()?;
+        }
+    }
+    out.flush()?;
+    drop(out);
+
+    drain.join().expect("drain thread panicked")?;
+    Ok(())
+}
+
+/// Runs quaero over `root` with its output going into a pipe read to the end.
+fn quaero(root: &Path) -> std::io::Result<()> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_quaero"))
+        .args(["--no-config", "-uuu", "-t", "file", "-f"])
+        .arg(root)
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let mut stdout = child.stdout.take().expect("stdout is piped");
+    std::io::copy(&mut stdout, &mut std::io::sink())?;
+    child.wait()?;
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    // `cargo bench` passes --bench, which is no directory.
+    let given = std::env::args().skip(1).find(|arg| !arg.starts_with("--"));
+    let (root, generated) = match given {
+        Some(given) => (PathBuf::from(given), false),
+        None => {
+            let root = std::env::temp_dir().join(format!("quaero-bench-{}", std::process::id()));
+            generate(&root)?;
+            (root, true)
+        }
+    };
+
+    let paths = quaero::WalkBuilder::new(&root)
+        .policy(quaero::IgnorePolicy::from_unrestricted(3))
+        .build()
+        .filter_map(Result::ok)
+        .map(|entry| entry.path)
+        .collect::<Vec<_>>();
+
+    let runs = [
+        (
+            "per line",
+            fastest(|| write_paths(&paths, |writer| writer, true))?,
+        ),
+        (
+            "buffered",
+            fastest(|| {
+                write_paths(
+                    &paths,
+                    |writer| BufWriter::with_capacity(BUFFER_SIZE, writer),
+                    false,
+                )
+            })?,
+        ),
+        ("quaero", fastest(|| quaero(&root))?),
+    ];
+    for (name, elapsed) in runs {
+        println!(
+            "{:<8}  {:>8.2} ms  {} paths",
+            name,
+            elapsed.as_secs_f64() * 1000.0,
+            paths.len()
+        );
+    }
+
+    if generated {
+        std::fs::remove_dir_all(&root)?;
+    }
+    Ok(())
+}
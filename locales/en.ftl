@@ -0,0 +1,248 @@
+error-no-filter = Either a target to find, a file type or another filter must be specified
+conflict-without = without { $option }: { $command }
+error-delete-without-filter = Refusing to --delete without a filter to narrow the search
+error-pipe-through-failed = --pipe-through command `{ $command }` failed: { $status }
+error-watch-command-failed = --{ $option } command `{ $command }` failed: { $status }
+error-unknown-workspace = No workspace named '{ $name }' in the config file
+error-score-without-fuzzy = --sort score needs a --fuzzy pattern to score against
+error-watch-tree = --watch prints matches as they appear, which --format tree cannot do
+error-checkpoint-tree = --format tree prints the matches once they are all found, which a checkpoint cannot resume
+error-bucket-format = --bucket-by-age prints sections of matches, or an object a section with --format ndjson, which csv, tsv and tree have no place for
+error-events-sections = --format ndjson-events prints every match as it is found, where --group-by and --bucket-by-age print sections of them instead
+error-no-index = No index covers { $path }, build one with `quaero index build`
+error-no-config-location = Can't tell where the config file goes, set $XDG_CONFIG_HOME or $HOME
+error-output = Could not write the matches out
+error-config-exists = { $path } exists already, pass --force to overwrite it
+error-batch-empty = { $path } has no [[query]] to run
+error-batch-query = In query '{ $name }'
+error-batch-duplicate-name = There is more than one query named '{ $name }'
+error-batch-duplicate-output = More than one query writes to { $path }
+error-batch-unknown-report = Unknown report '{ $report }', expected one of { $reports }
+error-batch-report-options = buckets, top and top-depth only apply to a report
+error-batch-top-depth = top-depth has to be at least 1
+error-du-archives = `quaero du` can't look into archives, leave out --archives
+error-clean-archives = `quaero clean` can't remove what is in archives, leave out --archives
+error-audit-archives = `quaero audit` doesn't look into archives, leave out --archives
+error-mdfind-platform = --mdfind asks Spotlight, which only macOS has
+error-locate-pattern = --from-locate asks the locate database for the target, --glob or --extension, and none was given
+error-no-rename-log = there are no renames to take back
+error-batch-archives = `quaero batch` can't look into archives, leave out --archives
+
+delete-skipping-non-empty = skipping non-empty directory { $path }
+delete-summary = { $dry-run ->
+        [yes] would remove
+       *[no] removed
+    } { $files ->
+        [one] { $files } file
+       *[other] { $files } files
+    } and { $directories ->
+        [one] { $directories } directory
+       *[other] { $directories } directories
+    }, skipped { $skipped }
+
+transfer-skipping-existing = skipping { $path }, { $destination } is already there
+transfer-summary = { $action ->
+        [move] { $dry-run ->
+            [yes] would move
+           *[no] moved
+        }
+       *[copy] { $dry-run ->
+            [yes] would copy
+           *[no] copied
+        }
+    } { $files ->
+        [one] { $files } file
+       *[other] { $files } files
+    }, skipped { $skipped }
+
+touch-summary = { $dry-run ->
+        [yes] would touch
+       *[no] touched
+    } { $files ->
+        [one] { $files } file
+       *[other] { $files } files
+    }, skipped { $skipped }
+
+change-summary = { $dry-run ->
+        [yes] would change
+       *[no] changed
+    } { $files ->
+        [one] { $files } entry
+       *[other] { $files } entries
+    }, { $unchanged } already as asked, { $failed } failed
+
+rename-summary = { $dry-run ->
+        [yes] would rename
+       *[no] renamed
+    } { $files ->
+        [one] { $files } entry
+       *[other] { $files } entries
+    }, skipped { $skipped }
+rename-logged = renames logged in { $path }, `quaero undo-rename` takes them back
+rename-skipping-invalid = skipping { $path }, "{ $name }" is no name
+rename-skipping-not-utf8 = skipping { $path }, a path that isn't UTF-8 can't be logged to be taken back
+undo-rename-summary = { $files ->
+        [one] renamed { $files } entry back
+       *[other] renamed { $files } entries back
+    }, skipped { $skipped }
+undo-rename-skipping = skipping { $path }, it is gone or { $destination } is taken
+
+replace-skipping-hard-links = skipping { $path }, rewriting it would part it from its other { $links ->
+        [one] hard link
+       *[other] { $links } hard links
+    }
+replace-attributes-not-copied = the rewritten file couldn't be given the extended attributes { $names }
+replace-summary = { $dry-run ->
+        [yes] would make
+       *[no] made
+    } { $replacements ->
+        [one] { $replacements } replacement
+       *[other] { $replacements } replacements
+    } in { $files ->
+        [one] { $files } file
+       *[other] { $files } files
+    }, skipped { $skipped }
+
+audit-heading = { $category }: { $count ->
+        [one] { $count } entry
+       *[other] { $count } entries
+    }
+
+collisions-heading = { $kind ->
+        [case] names that differ only in case
+       *[normalization] names that differ only in their Unicode normalization
+    }: { $count }
+
+timed-out = timed out after { $entries ->
+        [one] { $entries } entry
+       *[other] { $entries } entries
+    }, { $matches ->
+        [one] { $matches } match
+       *[other] { $matches } matches
+    }
+
+lock-waiting = waiting for the lock on { $path } held by PID { $pid }
+lock-held = { $path } is locked, held by PID { $pid }
+
+dupes-summary = { $dry-run ->
+        [yes] would remove
+       *[no] removed
+    } { $files ->
+        [one] { $files } duplicate
+       *[other] { $files } duplicates
+    }, freeing { $size } bytes
+
+index-built = indexed { $entries } entries below { $root }
+index-updated = updated the index of { $root }: { $entries } entries, { $directories } directories read again
+index-stale = the index of { $root } was last updated { $hours } hours ago, see `quaero index update`
+
+config-ok = { $path }: no problems found
+config-written = wrote a starter config to { $path }
+config-no-roots = workspace '{ $name }' has no roots
+config-missing-root = root { $path } is not a directory
+config-duplicate-label = another root of this workspace is labeled '{ $label }' already
+config-absolute-exclude = exclude { $path } is absolute, but excludes are relative to the root
+config-exclude-not-name = { $name } is not a name, excludes skip entries by their name alone
+config-unrestricted-range = unrestricted goes from 0 to 3, not { $level }
+config-no-threads = threads has to be at least 1
+config-not-compound = '{ $extension }' is not an extension of several parts such as tar.gz
+
+lint-warning = warning: { $message }
+lint-depth-zero = --depth 0 leaves out everything, the entries right below the starting directory are at depth 1
+lint-name-slash = target '{ $name }' never matches as it is compared with names, which have no '/', see --full-path or path:
+lint-fuzzy-slash = fuzzy pattern '{ $pattern }' never matches as it is compared with names, which have no '/', see --full-path
+lint-regex-trailing-slash = regex '{ $regex }' never matches as paths never end with '/'
+lint-regex-anchored = regex '{ $regex }' is matched against the whole path, which here always starts with '{ $prefix }'
+lint-avoid-missing = avoided path { $path } doesn't exist
+lint-avoid-outside = avoided path { $path } is outside every directory searched
+lint-root-missing = starting directory { $path } doesn't exist
+
+stats-summary =
+    entries scanned: { $entries }
+    directories traversed: { $directories }
+    matches: { $matches }
+    matched size: { $size } bytes
+    errors skipped: { $errors }
+    entries vanished mid-scan: { $vanished }
+    elapsed: { $elapsed }s
+page-summary = page { $page } of { $pages }, { $matches } { $matches ->
+        [one] match
+       *[other] matches
+    }
+ago = { $unit ->
+        [second] { $count ->
+            [one] { $count } second ago
+           *[other] { $count } seconds ago
+        }
+        [minute] { $count ->
+            [one] { $count } minute ago
+           *[other] { $count } minutes ago
+        }
+        [hour] { $count ->
+            [one] { $count } hour ago
+           *[other] { $count } hours ago
+        }
+        [day] { $count ->
+            [one] { $count } day ago
+           *[other] { $count } days ago
+        }
+        [month] { $count ->
+            [one] { $count } month ago
+           *[other] { $count } months ago
+        }
+       *[year] { $count ->
+            [one] { $count } year ago
+           *[other] { $count } years ago
+        }
+    }
+ago-future = in the future
+max-per-dir-more = { $directory }: … and { $count } more
+also-count = { $count } { $count ->
+        [one] match
+       *[other] matches
+    }
+stats-filters = filters, in the order they ended up being evaluated:
+stats-filter = {"  "}{ $filter }: { $matched } of { $evaluated } passed ({ $percent }%), { $rejected } rejected
+bench-heading = backend  threads    wall ms    entries    entries/s directories stat calls errors
+
+capability-available = available
+capability-missing = missing: { $reason }
+capability-not-built = not built in, build with --features { $feature }
+capability-linux-only = only available on Linux
+capability-warning = warning: { $option } goes without { $capability }: { $reason }
+mounts-unavailable = warning: no file system can be passed over by type: { $reason }
+watch-unavailable = warning: --watch can't watch for changes: { $reason }
+watch-root-unavailable = warning: --watch can't watch { $path }: { $reason }
+watch-growth = { $path } grew by { $size }
+watch-metadata-changed = { $path }: { $changes }
+watch-mode-changed = mode { $from } -> { $to }
+watch-owner-changed = owner { $from } -> { $to }
+
+du-total = { $size } in total
+progress = { $directories } directories, { $matches } matches:
+interrupted = interrupted after { $entries ->
+        [one] { $entries } entry
+       *[other] { $entries } entries
+    }, { $matches ->
+        [one] { $matches } match
+       *[other] { $matches } matches
+    }
+report-size-bucket = { $range }  { $count } { $count ->
+        [one] file
+       *[other] files
+    }, { $size }
+report-size-total = total: { $count } { $count ->
+        [one] file
+       *[other] files
+    }, { $size }
+report-size-any = any size
+report-group-none = (none)
+age-bucket = { $range }: { $count } { $count ->
+        [one] match
+       *[other] matches
+    }
+age-any = any age
+report-largest = the { $count ->
+        [one] largest file
+       *[other] { $count } largest files
+    }:
@@ -0,0 +1,420 @@
+error-no-filter = Bisogna specificare un obiettivo da trovare, un tipo di file o un altro filtro
+conflict-without = senza { $option }: { $command }
+error-delete-without-filter = --delete non viene eseguito senza un filtro che restringa la ricerca
+error-pipe-through-failed = Il comando `{ $command }` di --pipe-through non è riuscito: { $status }
+error-watch-command-failed = Il comando `{ $command }` di --{ $option } non è riuscito: { $status }
+error-unknown-workspace = Nessun workspace chiamato '{ $name }' nel file di configurazione
+error-score-without-fuzzy = --sort score richiede un pattern --fuzzy con cui calcolare il punteggio
+error-watch-tree = --watch stampa i risultati man mano che compaiono, cosa che --format tree non può fare
+error-checkpoint-tree = --format tree stampa le corrispondenze quando sono state trovate tutte, il che un checkpoint non può riprendere
+error-bucket-format = --bucket-by-age stampa sezioni di risultati, o un oggetto per sezione con --format ndjson, per cui csv, tsv e tree non hanno posto
+error-events-sections = --format ndjson-events stampa ogni risultato man mano che viene trovato, mentre --group-by e --bucket-by-age ne stampano invece delle sezioni
+error-no-index = Nessun indice copre { $path }, se ne può creare uno con `quaero index build`
+error-no-config-location = Impossibile stabilire dove va il file di configurazione, impostare $XDG_CONFIG_HOME o $HOME
+error-output = Non è stato possibile scrivere i risultati
+error-config-exists = { $path } esiste già, usare --force per sovrascriverlo
+error-batch-empty = { $path } non contiene nessun [[query]] da eseguire
+error-batch-query = Nella query '{ $name }'
+error-batch-duplicate-name = C'è più di una query chiamata '{ $name }'
+error-batch-duplicate-output = Più di una query scrive in { $path }
+error-batch-unknown-report = Report '{ $report }' sconosciuto, atteso uno tra { $reports }
+error-batch-report-options = buckets, top e top-depth valgono solo per un report
+error-batch-top-depth = top-depth deve essere almeno 1
+error-du-archives = `quaero du` non può guardare dentro gli archivi, togliere --archives
+error-clean-archives = `quaero clean` non può rimuovere ciò che è negli archivi, togliere --archives
+error-audit-archives = `quaero audit` non guarda dentro gli archivi, togliere --archives
+error-mdfind-platform = --mdfind interroga Spotlight, che c'è solo su macOS
+error-locate-pattern = --from-locate chiede al database di locate il target, --glob o --extension, e non ne è stato dato nessuno
+error-no-rename-log = non ci sono rinomine da annullare
+error-batch-archives = `quaero batch` non può guardare dentro gli archivi, togliere --archives
+
+delete-skipping-non-empty = salto la directory non vuota { $path }
+delete-summary = { $dry-run ->
+        [yes] verrebbero rimossi
+       *[no] rimossi
+    } { $files ->
+        [one] { $files } file
+       *[other] { $files } file
+    } e { $directories ->
+        [one] { $directories } directory
+       *[other] { $directories } directory
+    }, saltati { $skipped }
+
+transfer-skipping-existing = salto { $path }, { $destination } esiste già
+transfer-summary = { $action ->
+        [move] { $dry-run ->
+            [yes] verrebbero spostati
+           *[no] spostati
+        }
+       *[copy] { $dry-run ->
+            [yes] verrebbero copiati
+           *[no] copiati
+        }
+    } { $files ->
+        [one] { $files } file
+       *[other] { $files } file
+    }, saltati { $skipped }
+
+touch-summary = { $dry-run ->
+        [yes] verrebbero aggiornati
+       *[no] aggiornati
+    } { $files ->
+        [one] { $files } file
+       *[other] { $files } file
+    }, saltati { $skipped }
+
+change-summary = { $dry-run ->
+        [yes] verrebbero cambiate
+       *[no] cambiate
+    } { $files ->
+        [one] { $files } voce
+       *[other] { $files } voci
+    }, { $unchanged } già come richiesto, { $failed } non riuscite
+
+rename-summary = { $dry-run ->
+        [yes] verrebbero rinominate
+       *[no] rinominate
+    } { $files ->
+        [one] { $files } voce
+       *[other] { $files } voci
+    }, saltate { $skipped }
+rename-logged = rinomine registrate in { $path }, `quaero undo-rename` le annulla
+rename-skipping-invalid = salto { $path }, "{ $name }" non è un nome
+rename-skipping-not-utf8 = salto { $path }, un percorso che non è UTF-8 non si può registrare per annullarlo
+undo-rename-summary = { $files ->
+        [one] riportata { $files } voce al nome di prima
+       *[other] riportate { $files } voci al nome di prima
+    }, saltate { $skipped }
+undo-rename-skipping = salto { $path }, non c'è più o { $destination } è occupato
+
+replace-skipping-hard-links = salto { $path }, riscriverlo lo separerebbe { $links ->
+        [one] dall'altro collegamento fisico
+       *[other] dagli altri { $links } collegamenti fisici
+    }
+replace-attributes-not-copied = al file riscritto non si sono potuti dare gli attributi estesi { $names }
+replace-summary = { $dry-run ->
+        [yes] sostituzioni da fare
+       *[no] sostituzioni fatte
+    }: { $replacements } in { $files } file, saltati { $skipped }
+
+audit-heading = { $category }: { $count ->
+        [one] { $count } voce
+       *[other] { $count } voci
+    }
+
+collisions-heading = { $kind ->
+        [case] nomi che differiscono soltanto per maiuscole e minuscole
+       *[normalization] nomi che differiscono soltanto per la normalizzazione Unicode
+    }: { $count }
+
+timed-out = tempo scaduto dopo { $entries ->
+        [one] { $entries } voce
+       *[other] { $entries } voci
+    }, { $matches ->
+        [one] { $matches } risultato
+       *[other] { $matches } risultati
+    }
+
+lock-waiting = in attesa del lock su { $path } detenuto dal PID { $pid }
+lock-held = { $path } è bloccato, detenuto dal PID { $pid }
+
+dupes-summary = { $dry-run ->
+        [yes] verrebbero rimossi
+       *[no] rimossi
+    } { $files ->
+        [one] { $files } duplicato
+       *[other] { $files } duplicati
+    }, liberando { $size } byte
+
+index-built = indicizzate { $entries } voci sotto { $root }
+index-updated = aggiornato l'indice di { $root }: { $entries } voci, { $directories } directory rilette
+index-stale = l'indice di { $root } è stato aggiornato l'ultima volta { $hours } ore fa, vedi `quaero index update`
+
+config-ok = { $path }: nessun problema trovato
+config-written = scritta una configurazione iniziale in { $path }
+config-no-roots = il workspace '{ $name }' non ha radici
+config-missing-root = la radice { $path } non è una directory
+config-duplicate-label = un'altra radice di questo workspace ha già l'etichetta '{ $label }'
+config-absolute-exclude = l'esclusione { $path } è assoluta, ma le esclusioni sono relative alla radice
+config-exclude-not-name = { $name } non è un nome, le esclusioni saltano le voci soltanto in base al nome
+config-unrestricted-range = unrestricted va da 0 a 3, non { $level }
+config-no-threads = threads deve essere almeno 1
+config-not-compound = '{ $extension }' non è un'estensione di più parti come tar.gz
+
+lint-warning = attenzione: { $message }
+lint-depth-zero = --depth 0 esclude tutto, le voci subito sotto la directory di partenza sono a profondità 1
+lint-name-slash = l'obiettivo '{ $name }' non corrisponde mai perché viene confrontato con i nomi, che non contengono '/', vedi --full-path o path:
+lint-fuzzy-slash = il pattern fuzzy '{ $pattern }' non corrisponde mai perché viene confrontato con i nomi, che non contengono '/', vedi --full-path
+lint-regex-trailing-slash = l'espressione regolare '{ $regex }' non corrisponde mai perché i percorsi non finiscono mai con '/'
+lint-regex-anchored = l'espressione regolare '{ $regex }' viene confrontata con l'intero percorso, che qui inizia sempre con '{ $prefix }'
+lint-avoid-missing = il percorso da evitare { $path } non esiste
+lint-avoid-outside = il percorso da evitare { $path } è fuori da ogni directory in cui si cerca
+lint-root-missing = la directory di partenza { $path } non esiste
+
+stats-summary =
+    voci esaminate: { $entries }
+    directory attraversate: { $directories }
+    risultati: { $matches }
+    dimensione dei risultati: { $size } byte
+    errori saltati: { $errors }
+    voci sparite durante la ricerca: { $vanished }
+    tempo impiegato: { $elapsed }s
+page-summary = pagina { $page } di { $pages }, { $matches } { $matches ->
+        [one] risultato
+       *[other] risultati
+    }
+ago = { $unit ->
+        [second] { $count ->
+            [one] { $count } secondo fa
+           *[other] { $count } secondi fa
+        }
+        [minute] { $count ->
+            [one] { $count } minuto fa
+           *[other] { $count } minuti fa
+        }
+        [hour] { $count ->
+            [one] { $count } ora fa
+           *[other] { $count } ore fa
+        }
+        [day] { $count ->
+            [one] { $count } giorno fa
+           *[other] { $count } giorni fa
+        }
+        [month] { $count ->
+            [one] { $count } mese fa
+           *[other] { $count } mesi fa
+        }
+       *[year] { $count ->
+            [one] { $count } anno fa
+           *[other] { $count } anni fa
+        }
+    }
+ago-future = nel futuro
+max-per-dir-more = { $directory }: … e altri { $count }
+also-count = { $count } { $count ->
+        [one] risultato
+       *[other] risultati
+    }
+stats-filters = filtri, nell'ordine in cui hanno finito per essere valutati:
+stats-filter = {"  "}{ $filter }: { $matched } su { $evaluated } accettate ({ $percent }%), { $rejected } scartate
+bench-heading = backend   thread   ms reali       voci       voci/s   directory       stat errori
+
+capability-available = disponibile
+capability-missing = mancante: { $reason }
+capability-not-built = non compilato, compilare con --features { $feature }
+capability-linux-only = disponibile soltanto su Linux
+capability-warning = attenzione: { $option } fa a meno di { $capability }: { $reason }
+mounts-unavailable = attenzione: nessun file system può essere saltato in base al tipo: { $reason }
+watch-unavailable = attenzione: --watch non può osservare i cambiamenti: { $reason }
+watch-root-unavailable = attenzione: --watch non può osservare { $path }: { $reason }
+watch-growth = { $path } è cresciuto di { $size }
+watch-metadata-changed = { $path }: { $changes }
+watch-mode-changed = permessi { $from } -> { $to }
+watch-owner-changed = proprietario { $from } -> { $to }
+
+du-total = { $size } in totale
+progress = { $directories } directory, { $matches } risultati:
+interrupted = interrotto dopo { $entries ->
+        [one] { $entries } voce
+       *[other] { $entries } voci
+    }, { $matches ->
+        [one] { $matches } risultato
+       *[other] { $matches } risultati
+    }
+report-size-bucket = { $range }  { $count } file, { $size }
+report-size-total = totale: { $count } file, { $size }
+report-size-any = qualsiasi dimensione
+report-group-none = (nessuna)
+age-bucket = { $range }: { $count } { $count ->
+        [one] risultato
+       *[other] risultati
+    }
+age-any = qualsiasi età
+report-largest = { $count ->
+        [one] il file più grande
+       *[other] i { $count } file più grandi
+    }:
+
+about-mark-used = Registra che un percorso è stato scelto tra i risultati, alimentando l'archivio di frecency
+about-capabilities = Stampa quali delle funzionalità su cui si basano alcune opzioni sono supportate dalla piattaforma e da questa build, e perché no dove non lo sono
+about-bench = Cronometra gli attraversamenti di una directory con ogni backend e alcuni numeri di thread, stampando l'esecuzione più veloce di ciascuno con quante voci ha attraversato al secondo e le directory e le chiamate a stat che ha richiesto
+help-bench-directory = Directory da attraversare, tutto ciò che contiene come con -uuu
+help-bench-threads = Numeri di thread tra cui dividere l'attraversamento, ognuno dei quali attraversa la sottodirectory successiva della directory. Potenze di due fino a uno per core se omesso
+help-runs = Quante volte attraversare con ogni backend e numero di thread, di cui conta la più veloce
+help-bench-extension = Cronometra una ricerca dei file con questa estensione invece di un attraversamento di ogni voce, dove i backend veloci scartano il resto senza uno stat
+about-completions = Stampa uno script che completa le opzioni, i sottocomandi e i loro valori in una shell
+about-man = Stampa la pagina di manuale, generata dalle opzioni e dai sottocomandi di questa build
+about-self-update = Sostituisce questo eseguibile con l'ultima release da GitHub
+about-dupes = Trova tra i risultati i file con lo stesso contenuto, stampando ogni gruppo di duplicati
+help-delete-dupes = Rimuove tutti i file di ogni gruppo tranne uno, quello scelto da --keep
+help-keep = Quale file di ogni gruppo tiene --delete-dupes
+about-du = Stampa quanti byte sommano i file sotto ogni directory trovata, e il totale di tutte
+about-clean = Rimuove i file trovati sotto una directory più vecchi di --older-than, tranne i --keep-latest più recenti di ogni directory, per quanto vecchi
+help-directory = Directory di cui rimuovere i file vecchi
+help-older-than = Rimuove soltanto i file modificati l'ultima volta più di questo tempo fa, un numero seguito da s, m, h, d o w, ad es. 30d
+help-pattern = Glob a cui corrispondono i nomi dei file da rimuovere, come '*.log'. Se ripetuto, deve corrispondere almeno uno
+help-keep-latest = Quanti dei file trovati modificati più di recente tenere in ogni directory, qualunque cosa dica --older-than
+about-audit = Cerca ciò che chiede un controllo di sicurezza: file set-user-ID e set-group-ID, ciò in cui chiunque può scrivere e file senza proprietario, stampando a parte ogni tipo trovato
+about-collisions = Stampa i nomi di ogni directory che differiscono soltanto per maiuscole e minuscole o per la normalizzazione Unicode, che i file system di macOS e Windows prendono per lo stesso nome
+help-collisions-directory = Directory in cui cercare, quella corrente se non indicata
+about-batch = Esegue insieme le query con nome di un file, attraversando le directory una volta sola per tutte
+help-queries = File TOML con una tabella [[query]] per ogni query: il nome, un expr nel linguaggio di --expr ed eventualmente un file di output, un report e i buckets, top e top-depth del report
+about-undo-rename = Annulla le rinomine di un --rename, dall'ultima, lasciando quelle la cui voce si è spostata nel frattempo
+help-log = Il registro delle rinomine, come l'ha indicato --rename, l'ultimo se omesso
+about-index = Gestisce gli indici in cui --use-index cerca invece di attraversare le directory
+about-build = Attraversa una directory e indicizza tutto ciò che contiene, sostituendo l'eventuale indice precedente
+about-update = Aggiorna gli indici, rileggendo soltanto le directory cambiate
+help-root = Directory da indicizzare, o da aggiornare
+about-config = Controlla il file di configurazione o ne scrive uno iniziale
+about-check = Controlla che un file di configurazione non contenga impostazioni inesistenti o insensate, indicando dove si trova ogni problema
+help-file = File di configurazione da controllare, quello letto da quaero se omesso
+about-init = Scrive un file di configurazione iniziale, con ogni impostazione commentata
+help-force = Sovrascrive il file di configurazione se ne esiste già uno
+help-path = Percorso scelto
+help-shell = Shell in cui completare
+help-out-dir = Scrive invece in questa directory una pagina per quaero e una per ogni sottocomando, chiamate quaero.1, quaero-dupes.1 e così via
+help-check = Controlla soltanto se è disponibile una nuova release
+
+help-target = Obiettivo da trovare
+help-full-path = Confronta l'obiettivo, --regex, --glob, --fuzzy e i predicati regex:, glob: e fuzzy: di --expr con l'intero percorso invece che con il nome; l'obiettivo corrisponde alle ultime componenti del percorso, come src/main.rs
+help-from = Directory da cui iniziare la ricerca
+help-workspace = Cerca in ogni radice di un workspace definito nel file di configurazione
+help-type = Tipo di file da cercare
+help-avoid = Directory da evitare
+help-extension = Estensione da cercare, come rs o tar.gz, in maiuscolo o minuscolo e con o senza il punto iniziale
+help-depth = Quanto in profondità scendere: 0 per la sola directory di partenza, 1 anche per ciò che contiene, 2 anche per ciò che c'è in quelle directory e così via. La directory di partenza è sempre una voce a sé, a profondità 0, che i filtri trovano come qualunque altra
+help-exact-depth = Trova soltanto le voci esattamente a questa profondità, contata come per --depth, senza scendere oltre
+help-path-components = Percorsi di un certo numero di componenti così come vengono stampati, esclusi `.` e `..`: esattamente N, più di +N o meno di -N, ad es. 3 per src/walk/mod.rs
+help-below = Voci N livelli sotto una directory con questo nome, i suoi figli essendo 1 sotto: esattamente N, più di +N o meno di -N, ad es. tests:2 per tests/unit/parse.rs
+help-ignore-file = Salta anche ciò che corrisponde alla lista di esclusioni in questo percorso, con la sintassi di gitignore qualunque cosa dica -u e i pattern ancorati alla directory corrente. Si può indicare più volte
+help-bfs = Attraversa in ampiezza, trovando tutti i risultati a una profondità prima di quelli più in basso, ad es. perché con --interactive arrivino prima i risultati meno annidati
+help-backend = Come leggere le directory: con la libreria standard, o su Linux con grandi blocchi di getdents64, il che conviene nelle ricerche per nome. io-uring, sperimentale, chiede anche i metadati di tutte le voci di una directory insieme, per i file system di rete e FUSE
+help-no-atime = Legge i file, per contenuti, hash e tipi, senza aggiornarne l'ora di accesso, e su Linux elenca le directory con i backend veloci senza aggiornare la loro. Le ricerche per soli metadati non aprono mai file. Solo per i file di cui si è proprietari, gli altri sono letti come al solito
+help-no-follow = Non segue mai i collegamenti simbolici: li confronta come collegamenti e non vi entra. Il comportamento predefinito
+help-follow-roots = Segue i collegamenti simbolici dati come radici o letti da --stdin, ma nessuno di quelli trovati visitando
+help-follow = Segue ogni collegamento simbolico: lo confronta con tipo, dimensione e date di ciò a cui porta, ed entra in esso se è una directory. I collegamenti rotti restano collegamenti
+help-one-file-system = Non entra nelle directory su cui sono montati altri file system, così una ricerca in / resta fuori dai mount di rete e FUSE
+help-skip-network-fs = Salta i punti di mount dei file system di rete, NFS, SMB, sshfs e simili, senza nemmeno guardarli, perché un mount morto non blocchi la ricerca
+help-exclude-fstype = Salta i punti di mount dei file system di questi tipi, separati da virgole come li chiama /proc/mounts, ad esempio nfs,cifs,fuse.sshfs; fuse vale per tutti i file system FUSE
+help-skip-bundles = Elenca i bundle di macOS, le directory che finiscono in .app, .framework, .bundle, .plugin o .kext, senza entrarvi, come i singoli file che Finder mostra
+help-skip-marked = Non entra nelle directory che contengono un file con questo nome, ad es. .nobackup, ma le elenca comunque. Se ripetuto, basta uno qualsiasi dei nomi a marcare una directory
+help-repo = Cerca nell'intero repository git in cui si trova la directory corrente, dalla sua radice, ma non nei repository e nei sottomoduli annidati al suo interno
+help-descend-into-repos = Entra anche nei repository e nei sottomoduli annidati in quello di --repo
+help-regex = Espressione regolare da confrontare con il nome, vedi --full-path. Se data più volte, ne basta una che corrisponda
+help-glob = Glob da confrontare con il nome, come '*.yml', vedi --full-path. Se dato più volte, ne basta uno che corrisponda
+help-patterns-from = File di modelli da confrontare con il nome, uno per riga: glob, o espressioni regolari dopo regex:, tralasciando righe vuote e commenti #. Deve corrispondere uno qualsiasi, vedi --full-path. Indicato più volte, uno qualsiasi di quelli di ogni file
+help-exclude-from = File di modelli, come per --patterns-from, le cui corrispondenze vengono tralasciate insieme a tutto ciò che sta sotto le directory tra di esse
+help-size = Dimensione in byte, eventualmente con suffisso k, M, G o T: esattamente N, più di +N o meno di -N
+help-allocated-size = Byte allocati su disco, come --size: meno della dimensione se un file ha dei buchi, di più se il suo ultimo blocco non è pieno
+help-sparse = File sparsi, con meno di metà della loro dimensione allocata su disco, come immagini di dischi e database
+help-perm = Permessi da cercare: esattamente MODE, tutti quelli di -MODE o almeno uno di /MODE, dove MODE è ottale (644) o simbolico (u+w,g-x)
+help-setuid = Voci con il bit set-user-ID, che vengono eseguite come il loro proprietario. Abbreviazione di --perm -4000
+help-setgid = Voci con il bit set-group-ID, che vengono eseguite come il loro gruppo o fanno appartenere a esso ciò che vi viene creato. Abbreviazione di --perm -2000
+help-sticky = Voci con lo sticky bit, directory in cui solo il proprietario di un file può rimuoverlo. Abbreviazione di --perm -1000
+help-world-writable = Voci in cui chiunque può scrivere, --perm -o+w tranne i link simbolici, i cui bit non significano nulla
+help-executable-by = Trova soltanto gli eseguibili che questi possono eseguire: me, come decide access(2) per l'utente effettivo, il proprietario del file o qualsiasi utente
+help-readable = Voci che l'utente che esegue quaero può leggere, o ciò a cui puntano, come decide access(2) per l'utente e i gruppi effettivi, ACL e mount in sola lettura compresi
+help-writable = Voci in cui l'utente che esegue quaero può scrivere, o ciò a cui puntano, come decide access(2), ad es. --writable -f /etc per i file che vi si possono davvero modificare
+help-nlink = Numero di hard link: esattamente N, più di +N o meno di -N
+help-entries = Directory con un certo numero di voci direttamente al loro interno: esattamente N, più di +N o meno di -N, ad es. --entries +10000 per quelle che rallentano tutto ciò che le elenca
+help-inode = Numero di inode della voce, su qualsiasi dispositivo
+help-same-file = Collegamenti fisici al file in PATH, o al file a cui punta: le voci con il suo dispositivo e il suo inode
+help-git-status = File che git riporta con questo stato nel repository in cui inizia la ricerca: modificati nel working tree, non tracciati o nell'area di stage. Se ripetuto, uno qualsiasi
+help-modified-on = Modificato l'ultima volta in un giorno, dato come AAAA-MM-GG, da mezzanotte a mezzanotte nel fuso orario locale
+help-modified-month = Modificato l'ultima volta in un mese, dato come AAAA-MM, nel fuso orario locale
+help-owner = Proprietario della voce, per nome utente
+help-group = Gruppo della voce, per nome del gruppo
+help-uid = Proprietario della voce, per id utente numerico
+help-mine = Voci di proprietà dell'utente che esegue quaero, il suo id utente effettivo
+help-gid = Gruppo della voce, per id di gruppo numerico
+help-nouser = Voci il cui proprietario non esiste nel database degli utenti
+help-nogroup = Voci il cui gruppo non esiste nel database dei gruppi
+help-broken = Link simbolici la cui destinazione non esiste
+help-links-to = Link simbolici la cui destinazione, risolvendo ogni link lungo il percorso, corrisponde a un'espressione regolare
+help-has-xattr = Voci con un attributo esteso con questo nome, come user.comment o com.apple.quarantine. Quelli di un link simbolico sono i suoi
+help-xattr = Voci il cui attributo esteso NAME ha esattamente il valore VALUE
+help-filter-script = Voci che uno script Lua lascia passare: restituisce una funzione, chiamata con una tabella con percorso, nome, tipo, dimensione, permessi, date e altro di ogni voce, che dice se corrisponde
+help-mime = File regolari il cui contenuto, a giudicare dai primi byte, è di un tipo MIME come 'image/png' o di un tipo qualsiasi sotto uno come 'image/*'
+help-magic = File regolari il cui contenuto, a giudicare dai primi byte, è di un tipo come png, pdf, zip, tar o elf, comunque si chiamino
+help-text = File regolari senza byte NUL nei primi 8 KiB, come li considerano testo le ricerche nel contenuto
+help-binary = File regolari con un byte NUL nei primi 8 KiB
+help-encoding = File regolari validi per intero in una codifica. --expr 'not encoding:utf8' trova i sorgenti che manderebbero in errore uno strumento che si aspetta UTF-8
+help-lines = File di testo di N righe, più di +N o meno di -N, ad es. +2000 per i sorgenti da spezzare
+help-max-line-length = File di testo la cui riga più lunga è di N caratteri, più lunga con +N o più corta con -N
+help-fuzzy = Caratteri che compaiono in ordine nel nome, non necessariamente adiacenti, ad es. 'mnrs' per main.rs. Vedi --sort score e --full-path
+help-expr = Confronta un'espressione di predicati chiave:valore combinati con and, or, not e parentesi, ad es. '(ext:rs or ext:toml) and not path:target and size:+1k'
+help-unrestricted = Cerca di più: -u ignora i file .gitignore/.ignore/.quaeroignore, -uu mostra anche i file nascosti e -uuu non salta nemmeno le esclusioni predefinite (.git, .hg, .svn) né si ferma alle cache marcate da un CACHEDIR.TAG
+help-delete = Rimuove i file trovati invece di stamparli
+help-content = Cerca nel contenuto dei file trovati, stampando percorso:riga:testo per ogni occorrenza
+help-copy-to = Copia i file trovati in questa directory invece di stamparli, saltando le directory e ciò per cui un file è già d'intralcio
+help-move-to = Sposta i file trovati in questa directory invece di stamparli, saltando le directory e ciò per cui un file è già d'intralcio
+help-touch = Imposta gli orari di accesso e di modifica dei file trovati ad adesso, o a --mtime, invece di stamparli. Dei collegamenti vengono toccati i collegamenti stessi
+help-mtime = L'orario che imposta --touch: now, i secondi dall'epoca dopo una @, o un giorno come AAAA-MM-GG seguito da un orario come HH:MM o HH:MM:SS dopo una T, nel fuso orario locale
+help-chmod = Dà alle voci trovate questi permessi invece di stamparle: in ottale, o simbolici come li accetta chmod, ad es. go-w o u=rw,a+X. I link restano come sono
+help-chown = Dà le voci trovate a questo utente, e a questo gruppo dopo i due punti, invece di stamparle: UTENTE, UTENTE:GRUPPO o :GRUPPO. I link vengono ceduti essi stessi
+help-replace = Sostituisce ciò che un'espressione regolare trova nei file di testo individuati, scritta VECCHIO->NUOVO, dove $1 o ${"{"}nome{"}"} in NUOVO stanno per ciò che ha trovato un gruppo, ad es. '(\w+)_old->${"{"}1{"}"}_new'. I file sono riscritti per intero e rinominati al loro posto, quelli a cui puntano i collegamenti seguiti, e i file con altri collegamenti fisici sono saltati; con --dry-run un diff mostra cosa cambierebbe
+help-rename = Rinomina le voci trovate con una sostituzione sul nome, scritta come la scrive sed, ad es. 's/(.+)\.jpeg$/$1.jpg/', con il flag g per ogni corrispondenza e i per ignorare maiuscole e minuscole. Un nome resta com'è piuttosto che prendere il posto di un altro; `quaero undo-rename` annulla le rinomine
+help-preserve-structure = Mantiene il percorso di ogni file sotto la radice in cui è stato trovato, nella directory di --copy-to o --move-to, invece di metterli tutti uno accanto all'altro
+help-delete-dirs = Rimuove anche le directory trovate, purché vuote
+help-dry-run = Riporta soltanto cosa farebbero --delete, --copy-to, --move-to, --touch, --chmod, --chown, --replace o --rename
+help-sort = Stampa i risultati ordinati secondo questa chiave invece che nell'ordine in cui vengono trovati
+help-sort-by = Stampa i risultati ordinati secondo ciò che questo modello produce per ciascuno, confrontando le sequenze di cifre come numeri, ad es. '{"{"}ext{"}"}/{"{"}size{"}"}'. Accetta i campi di --template
+help-count = Stampa soltanto il numero di risultati
+help-quiet = Non stampa nulla, esce con 0 se qualcosa corrisponde e con 1 altrimenti
+help-template = Stampa ogni risultato con questo modello invece del solo percorso, ad es. '{"{"}path{"}"}\t{"{"}size:h{"}"}\t{"{"}mtime:%Y-%m-%d{"}"}'. Campi: path, name, stem, ext, parent, type, size, allocated, perm, nlink, entries, inode, device, uid, gid, user, group, mtime, atime, ctime, depth, link_target, resolved, label e hash
+help-human-readable = Stampa le dimensioni e le ore di --template che non dicono come in forma di 4.2M o 1.3G e di 2 ore fa. --format csv, tsv e ndjson hanno sempre byte e ore ISO 8601
+help-iso-time = Stampa ogni ora di --template in ISO 8601, ad es. 2026-10-14T09:48:22+02:00, qualunque cosa dicano il modello o --human-readable, perché gli script la possano leggere
+help-format = Stampa i risultati in un formato strutturato, con percorso, nome, tipo, dimensione, data di modifica e permessi di ciascuno
+help-pipe-through = Passa i percorsi dei risultati a un comando della shell, uno per riga, tenendo i percorsi che restituisce
+help-filter-cmd = Tiene solo i risultati per cui un comando della shell esce con stato 0, eseguito una volta per ciascuno con {"{"}{"}"} sostituito dal suo percorso, o con il percorso aggiunto alla fine, ad es. 'head -c2 {"{"}{"}"} | grep -q PK'. Ciò che il comando stampa viene scartato
+help-jobs = Quanti comandi di --filter-cmd eseguire alla volta, uno per core se non indicato
+help-logical = Stampa i percorsi come sono stati attraversati, passando per eventuali collegamenti simbolici (predefinito)
+help-physical = Stampa i percorsi risolvendo i collegamenti simbolici che portano ai risultati
+help-hyperlink = Crea collegamenti ai file dei percorsi stampati su un terminale, hyperlink OSC 8 che iTerm2, WezTerm, kitty, GNOME Terminal e altri aprono con un clic. I terminali per cui non è noto il supporto ricevono percorsi semplici
+help-quote = Mette tra virgolette i percorsi con spazi, virgolette, caratteri di controllo o qualsiasi altra cosa che una shell leggerebbe diversamente, così che si possano incollare in una riga di comando
+help-show-link-targets = Stampa i link simbolici come percorso -> destinazione, con dove puntano così come lo dicono. Anche i campi {"{"}link_target{"}"} e {"{"}resolved{"}"} di --template e --format ndjson li hanno
+help-absolute-path = Stampa percorsi assoluti, unendo quelli relativi alla directory corrente
+help-canonicalize = Stampa i percorsi con ogni collegamento simbolico risolto, compresi quelli dei risultati
+help-unique-inodes = Lascia passare soltanto il primo dei risultati che sono collegamenti fisici allo stesso file, così che non venga stampato, contato né passato al comando due volte
+help-no-dedup = Lascia passare un risultato trovato di nuovo sotto un altro percorso, da radici di un workspace che si sovrappongono, link seguiti o --stdin, invece che solo la prima volta
+help-max-per-dir = Lascia passare al massimo N dei risultati in una stessa directory, come una node_modules o una directory di build che prenderebbe il sopravvento, e poi dice su stderr quanti altri ne aveva ciascuna
+help-rewrite = Riscrive i percorsi stampati con un s/REGEX/SOSTITUZIONE/[g] come quello di sed, ad es. 's#^/mnt/backup#//server/share#'. Si può indicare più volte
+help-hash = Stampa l'hash del contenuto di ogni file regolare trovato dopo il suo percorso, o dove il modello ha {"{"}hash{"}"}
+help-interactive = Scegli tra i risultati man mano che vengono trovati con una ricerca fuzzy, stampando la selezione: Tab ne seleziona più di uno, Invio conferma ed Esc annulla
+help-watch = Continua dopo la ricerca, stampando le voci create o spostate qui che corrispondono, fino a un'interruzione
+help-exec-on-new = Con --watch, esegue un comando della shell per le voci che compaiono e corrispondono, con {"{"}{"}"} sostituito dai loro percorsi, o i percorsi aggiunti alla fine, ad es. 'gzip {"{"}{"}"}'. I percorsi vengono raccolti finché per un momento non ne arriva uno nuovo
+help-exec-batch = Il massimo di percorsi dati a una singola esecuzione di --exec-on-new, che parte non appena ne ha tanti
+help-debounce = Con --watch, trattiene i risultati finché per questo tempo non ne arriva uno nuovo, ad es. 500ms, e riporta la raffica tutta insieme, con --exec-on-new eseguito una volta sola per essa
+help-coalesce-dir = Con --watch, riporta una volta sola i risultati di una raffica che stanno nella stessa directory, come quella directory, o come la directory creata nella raffica sotto cui stanno tutti
+help-alert-growth = Con --watch, avvisa su stderr quando un file corrispondente, o i file in una directory corrispondente, sono cresciuti di SIZE, o di SIZE entro DURATION, ad es. 100M o 10M/1m. I file vengono misurati dal primo cambiamento visto, o da zero se creati durante l'osservazione
+help-alert-command = Esegue un comando della shell per ogni avviso di --alert-growth invece di stamparlo, con {"{"}{"}"} sostituito dal percorso, o il percorso aggiunto alla fine
+help-watch-metadata = Con --watch, riporta anche su stderr quando cambiano i permessi o il proprietario di un risultato, ad es. per chmod o chown. All'inizio l'albero viene percorso un'altra volta, per sapere com'erano i risultati
+help-lint-query = Prima di cercare, avverte delle parti della ricerca che non possono trovare nulla o trovano meno di quanto sembri, come un nome che contiene una barra
+help-archives = Guarda anche dentro i file .zip, .tar e .tar.gz, confrontando i nomi dei loro membri e stampandoli come archivio.zip!percorso/interno. Dei membri si sa solo quanto conserva l'archivio, gli altri campi sono quelli dell'archivio
+help-deterministic = Stampa sempre lo stesso risultato per lo stesso albero: le directory vengono attraversate nell'ordine dei byte dei nomi, una radice dopo l'altra, gli orari sono stampati in UTC e --format ndjson omette quando è iniziata la ricerca e i percorsi assoluti a cui portano i link
+help-use-index = Cerca nell'indice della directory di partenza, o di una directory sopra di essa, invece di attraversarla. Il contenuto dipende dalle opzioni di `quaero index build`
+help-stdin = Confronta i percorsi letti da stdin invece di attraversare le directory, uno per riga o separati da byte NUL, ad es. da git ls-files o find -print0
+help-mdfind = Confronta i file che Spotlight trova per questa query sotto la directory di partenza invece di attraversare le directory, ad es. 'kMDItemContentType == "public.jpeg"' o semplici parole, perché gli altri filtri li restringano. Solo su macOS
+help-from-locate = Confronta i file che il database di locate ha per il target, o altrimenti per i pattern di --glob o le estensioni, sotto la directory di partenza invece di attraversare le directory, perché gli altri filtri li restringano. Ciò che è stato creato dopo l'ultimo updatedb non viene trovato
+help-threads = Quanti thread calcolano gli hash e cercano nel contenuto dei file, uno per core se omesso
+help-output = Scrive i risultati in questo file invece che su stdout, sostituendone il contenuto. Gli errori e --stats continuano ad andare al terminale
+help-also-output = Scrive anche ogni risultato in questo file, man mano che viene trovato e qualunque altra cosa si stampi. In CSV, TSV o NDJSON per un file .csv, .tsv o .ndjson/.jsonl e come percorsi semplici altrimenti. Si può indicare più volte
+help-also-count = Stampa anche il numero di risultati su stderr a ricerca finita
+help-report = Stampa un resoconto dei risultati invece dei risultati stessi
+help-buckets = Dimensioni da cui iniziano i gruppi di --report size-buckets, ad es. 4k,1M,100M
+help-top = Quante directory stampa --report top-dirs, o quanti file --report size-histogram
+help-top-depth = Quanti livelli sotto la directory di partenza si trovano le directory di --report top-dirs; 1 somma tutto ciò che sta sotto ciascuna delle sue sottodirectory
+help-group-by = Stampa quante voci trovate ci sono in ogni gruppo, e quanti byte occupano i file tra di esse, invece delle voci stesse: per estensione, tipo, directory o proprietario. --format csv, tsv o ndjson stampa i gruppi in quel formato
+help-bucket-by-age = Stampa i risultati in sezioni secondo quanto tempo fa sono stati modificati l'ultima volta, ognuna di queste età dando inizio a una, ad es. 1d,7d,30d, e dal più recente in ognuna se non ordinati. --format ndjson stampa un oggetto per sezione con i percorsi che contiene
+help-page-size = Stampa soltanto questo numero di risultati, la pagina scelta da --page, in un ordine che resta lo stesso da un'esecuzione all'altra: per percorso, o secondo --sort o --sort-by e poi per percorso. La pagina e il numero di pagine vanno su stderr, e gli id di --format ndjson contano dal primo risultato della prima pagina
+help-page = Quale pagina di --page-size risultati stampare, a partire da 1
+help-stats = Stampa un riepilogo della ricerca su stderr al termine
+help-timeout = Ferma la visita dopo questo tempo, ad es. 500ms o 10s, e prosegue con ciò che ha trovato finora, uscendo con stato 124
+help-checkpoint = Salva in FILE le directory che la ricerca ha finito ogni 30 secondi e quando viene interrotta, perché --resume riprenda da lì. Viene rimosso a ricerca finita
+help-resume = Riprende una ricerca interrotta da dove dice il checkpoint in FILE, tralasciando le directory che aveva finito, e continua a salvare in FILE a meno che --checkpoint non dica altrove. Le corrispondenze nelle directory a cui era a metà vengono stampate di nuovo
+help-lang = Lingua dei messaggi (ad es. en, it), altrimenti ricavata da LC_ALL, LC_MESSAGES o LANG
+help-no-config = Ignora il file di configurazione e QUAERO_OPTS, cercando soltanto con le impostazioni predefinite di quaero
+help-errors = Come scrivere gli errori su stderr: come testo, o come un oggetto JSON per riga per gli script. In ogni caso il codice di uscita è 0 se qualcosa corrisponde, 1 se niente corrisponde, 2 se la ricerca non è potuta partire e 3 se una parte non è riuscita ed è stata saltata
+help-bytes = Stampa le dimensioni come numeri di byte ovunque verrebbero abbreviate in 4.2M: in --template, con --human-readable, nei report e in `quaero du`
+help-help = Mostra l'aiuto
+help-version = Mostra la versione
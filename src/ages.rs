@@ -0,0 +1,129 @@
+//! `--bucket-by-age`: the matches printed in sections by how long ago they were last
+//! modified, for reviews of what to keep and what to clean up.
+
+use crate::output::{OutputFormat, Printer};
+use crate::pipeline::Match;
+use std::cmp::Reverse;
+use std::time::{Duration, SystemTime};
+
+/// Puts the matches in buckets by the time since they were last modified, newest first.
+pub struct AgeBuckets {
+    /// Where every bucket but the first starts, in increasing order
+    bounds: Vec<Duration>,
+    /// What the ages are counted back from
+    now: SystemTime,
+}
+
+impl AgeBuckets {
+    pub fn new(bounds: &[Duration]) -> Self {
+        let mut bounds = bounds.to_vec();
+        bounds.sort_unstable();
+        bounds.dedup();
+        bounds.retain(|bound| !bound.is_zero());
+
+        Self {
+            bounds,
+            now: SystemTime::now(),
+        }
+    }
+
+    fn bucket(&self, item: &Match) -> usize {
+        // Modified in the future, a file is as new as they come.
+        let age = item
+            .entry
+            .modified()
+            .and_then(|modified| self.now.duration_since(modified).ok())
+            .unwrap_or_default();
+        self.bounds.partition_point(|bound| *bound <= age)
+    }
+
+    fn range(&self, bucket: usize) -> String {
+        match (bucket.checked_sub(1), self.bounds.get(bucket)) {
+            (None, Some(end)) => format!("<{}", age(*end)),
+            (Some(start), Some(end)) => format!("{}-{}", age(self.bounds[start]), age(*end)),
+            (Some(start), None) => format!(">={}", age(self.bounds[start])),
+            (None, None) => tr!("age-any"),
+        }
+    }
+
+    /// Prints the matches a bucket at a time under a heading of their own, or as an
+    /// object a bucket in ndjson, leaving out the buckets nothing is in. Those of a
+    /// bucket come newest first unless `sorted` already.
+    pub fn print(
+        &self,
+        matches: &[Match],
+        sorted: bool,
+        format: Option<OutputFormat>,
+        printer: &mut Printer,
+    ) {
+        let mut buckets = vec![Vec::new(); self.bounds.len() + 1];
+        for item in matches {
+            buckets[self.bucket(item)].push(item);
+        }
+        if !sorted {
+            for items in &mut buckets {
+                items.sort_by_key(|item| (Reverse(item.entry.modified()), &item.entry.path));
+            }
+        }
+
+        let mut first = true;
+        for (bucket, items) in buckets.iter().enumerate() {
+            if items.is_empty() {
+                continue;
+            }
+            let range = self.range(bucket);
+
+            if format == Some(OutputFormat::Ndjson) {
+                let size = items
+                    .iter()
+                    .filter(|item| !item.entry.is_dir())
+                    .map(|item| item.entry.size())
+                    .sum::<u64>();
+                let paths = items
+                    .iter()
+                    .map(|item| quaero::clean_path(&item.entry.path))
+                    .collect::<Vec<_>>();
+                let record = serde_json::json!({
+                    "bucket": range,
+                    "count": items.len(),
+                    "size": size,
+                    "paths": paths,
+                });
+                printer.write(|out| writeln!(out, "{}", record));
+                continue;
+            }
+
+            let separator = !std::mem::take(&mut first);
+            printer.write(|out| {
+                if separator {
+                    writeln!(out)?;
+                }
+                let heading = tr!("age-bucket", "range" => range, "count" => items.len());
+                writeln!(out, "{}", heading)
+            });
+            for item in items {
+                printer.print(item);
+                if printer.failed() {
+                    return;
+                }
+            }
+        }
+    }
+}
+
+/// Spells `duration` as ages are given, in the largest unit it is a whole number of.
+fn age(duration: Duration) -> String {
+    let milliseconds = duration.as_millis();
+    let units = [
+        ("d", 24 * 60 * 60 * 1000),
+        ("h", 60 * 60 * 1000),
+        ("m", 60 * 1000),
+        ("s", 1000),
+    ];
+    for (unit, size) in units {
+        if milliseconds.is_multiple_of(size) {
+            return format!("{}{}", milliseconds / size, unit);
+        }
+    }
+    format!("{}ms", milliseconds)
+}
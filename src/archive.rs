@@ -0,0 +1,158 @@
+//! Listing what archives hold, for `--archives`. Every format is read by an
+//! [`ArchiveReader`], found for an archive by its name through [`reader_for`].
+
+use crate::{content, FileType};
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// What separates the path of an archive from the path of a member inside it.
+pub const SEPARATOR: char = '!';
+
+/// A file, directory or link stored in an archive.
+#[derive(Debug, Clone)]
+pub struct Member {
+    /// Path inside the archive, without a leading or trailing `/`
+    pub path: String,
+    pub file_type: FileType,
+    /// Size when extracted, in bytes
+    pub size: u64,
+    /// Permission bits, when the format keeps them
+    pub mode: Option<u32>,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+}
+
+impl Member {
+    /// The last component of its path.
+    pub fn name(&self) -> &str {
+        self.path.rsplit('/').next().unwrap_or(&self.path)
+    }
+}
+
+/// Reads the list of members of one archive format.
+pub trait ArchiveReader: Send + Sync {
+    /// Whether the file at `path` is an archive of this format, judging by its name.
+    fn reads(&self, path: &Path) -> bool;
+
+    fn members(&self, path: &Path) -> Result<Vec<Member>>;
+}
+
+fn has_suffix(path: &Path, suffixes: &[&str]) -> bool {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    suffixes.iter().any(|suffix| name.ends_with(suffix))
+}
+
+fn clean(path: &str) -> String {
+    path.trim_start_matches("./").trim_matches('/').to_owned()
+}
+
+pub struct Zip;
+
+impl ArchiveReader for Zip {
+    fn reads(&self, path: &Path) -> bool {
+        has_suffix(path, &[".zip", ".jar"])
+    }
+
+    fn members(&self, path: &Path) -> Result<Vec<Member>> {
+        let mut archive = zip::ZipArchive::new(BufReader::new(content::open(path)?))?;
+        let mut members = Vec::with_capacity(archive.len());
+        for index in 0..archive.len() {
+            let file = archive.by_index_raw(index)?;
+            let mode = file.unix_mode();
+            let file_type = match (file.is_dir(), file.is_symlink()) {
+                (true, _) => FileType::Directory,
+                (_, true) => FileType::SymLink,
+                _ if mode.is_some_and(|mode| mode & 0o111 != 0) => FileType::Executable,
+                _ => FileType::RegularFile,
+            };
+
+            members.push(Member {
+                path: clean(&file.name()?),
+                file_type,
+                size: file.size(),
+                mode: mode.map(|mode| mode & 0o7777),
+                uid: None,
+                gid: None,
+            });
+        }
+
+        Ok(members)
+    }
+}
+
+fn tar_members(reader: impl Read) -> Result<Vec<Member>> {
+    let mut archive = tar::Archive::new(reader);
+    let mut members = Vec::new();
+    for entry in archive.entries()? {
+        let entry = entry?;
+        let header = entry.header();
+        let mode = header.mode().ok();
+        let file_type = match header.entry_type() {
+            tar::EntryType::Directory => FileType::Directory,
+            tar::EntryType::Symlink => FileType::SymLink,
+            tar::EntryType::Char | tar::EntryType::Block | tar::EntryType::Fifo => {
+                FileType::Special
+            }
+            _ if mode.is_some_and(|mode| mode & 0o111 != 0) => FileType::Executable,
+            _ => FileType::RegularFile,
+        };
+
+        members.push(Member {
+            path: clean(&entry.path()?.to_string_lossy()),
+            file_type,
+            size: entry.size(),
+            mode: mode.map(|mode| mode & 0o7777),
+            uid: header.uid().ok().and_then(|uid| u32::try_from(uid).ok()),
+            gid: header.gid().ok().and_then(|gid| u32::try_from(gid).ok()),
+        });
+    }
+
+    Ok(members)
+}
+
+pub struct Tar;
+
+impl ArchiveReader for Tar {
+    fn reads(&self, path: &Path) -> bool {
+        has_suffix(path, &[".tar"])
+    }
+
+    fn members(&self, path: &Path) -> Result<Vec<Member>> {
+        tar_members(BufReader::new(content::open(path)?))
+    }
+}
+
+pub struct TarGz;
+
+impl ArchiveReader for TarGz {
+    fn reads(&self, path: &Path) -> bool {
+        has_suffix(path, &[".tar.gz", ".tgz"])
+    }
+
+    fn members(&self, path: &Path) -> Result<Vec<Member>> {
+        tar_members(GzDecoder::new(BufReader::new(content::open(path)?)))
+    }
+}
+
+/// Every format `--archives` looks into.
+pub const READERS: &[&dyn ArchiveReader] = &[&Zip, &Tar, &TarGz];
+
+/// The reader for the archive at `path`, if it is one.
+pub fn reader_for(path: &Path) -> Option<&'static dyn ArchiveReader> {
+    READERS.iter().copied().find(|reader| reader.reads(path))
+}
+
+/// The members of the archive at `path`, `None` if it isn't one of a known format.
+pub fn members(path: &Path) -> Option<Result<Vec<Member>>> {
+    let reader = reader_for(path)?;
+    Some(
+        reader
+            .members(path)
+            .with_context(|| path.display().to_string()),
+    )
+}
@@ -0,0 +1,105 @@
+//! `quaero audit`: the usual sweeps of a security audit, made in a single walk.
+
+use crate::{build_matcher, Filters, Search};
+use anyhow::Result;
+use quaero::perm::PermFilter;
+use quaero::{FileType, Matcher};
+use std::io::Write;
+use std::os::unix::ffi::OsStrExt;
+use std::sync::Arc;
+
+/// Entries anyone can write to, but for symbolic links, which all have every bit set.
+pub fn world_writable() -> Matcher {
+    Matcher::All(vec![
+        Matcher::Perm(PermFilter::all_of(0o002)),
+        Matcher::Not(Box::new(Matcher::Type(FileType::SymLink))),
+    ])
+}
+
+/// What an audit looks for, each kind with the name it is printed under.
+fn categories() -> Vec<(&'static str, Matcher)> {
+    let files = || {
+        Matcher::Any(vec![
+            Matcher::Type(FileType::RegularFile),
+            Matcher::Type(FileType::Executable),
+        ])
+    };
+    // Directories such as /tmp are there for everyone to write to, the sticky bit keeping
+    // them from removing what isn't theirs.
+    let shared = Matcher::All(vec![
+        Matcher::Type(FileType::Directory),
+        Matcher::Perm(PermFilter::all_of(0o1000)),
+    ]);
+
+    vec![
+        (
+            "setuid",
+            Matcher::All(vec![Matcher::Perm(PermFilter::all_of(0o4000)), files()]),
+        ),
+        (
+            "setgid",
+            Matcher::All(vec![Matcher::Perm(PermFilter::all_of(0o2000)), files()]),
+        ),
+        (
+            "world-writable",
+            Matcher::All(vec![world_writable(), Matcher::Not(Box::new(shared))]),
+        ),
+        (
+            "unowned",
+            Matcher::Any(vec![Matcher::NoUser, Matcher::NoGroup]),
+        ),
+    ]
+}
+
+/// Walks everything `filters` let through and prints, for every kind of entry an audit
+/// looks for, how many it found and their paths: files that run as their owner or
+/// their group, what anyone can write to but for the directories made for it, and
+/// entries whose owner or group doesn't exist.
+pub fn audit(filters: &Filters) -> Result<()> {
+    // What is in an archive can't do any harm until it is extracted.
+    if filters.archives {
+        return Err(anyhow::anyhow!(tr!("error-audit-archives")));
+    }
+
+    let mut categories = categories()
+        .into_iter()
+        .map(|(name, matcher)| (name, matcher.optimize(), Vec::new()))
+        .collect::<Vec<_>>();
+    let any = categories
+        .iter()
+        .map(|(_, matcher, _)| matcher.clone())
+        .collect();
+    let search = Search {
+        args: filters,
+        matcher: Matcher::All(vec![build_matcher(filters)?, Matcher::Any(any)]).optimize(),
+        stats: Arc::default(),
+        interrupt: Arc::default(),
+        checkpoint: None,
+    };
+
+    search.run(&mut |_, entry| {
+        for (_, matcher, found) in &mut categories {
+            if matcher.matches(&entry) {
+                found.push(entry.path.clone());
+            }
+        }
+        true
+    })?;
+
+    let mut out = std::io::stdout().lock();
+    for (index, (name, _, found)) in categories.iter_mut().enumerate() {
+        found.sort_by(|a, b| a.as_os_str().as_bytes().cmp(b.as_os_str().as_bytes()));
+        if index > 0 {
+            writeln!(out)?;
+        }
+        writeln!(
+            out,
+            "{}",
+            tr!("audit-heading", "category" => *name, "count" => found.len())
+        )?;
+        for path in found {
+            writeln!(out, "  {}", quaero::clean_path(path))?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,246 @@
+//! `quaero batch`: the named queries of a file, answered together by a single walk
+//! instead of one walk each.
+//!
+//! ```toml
+//! [[query]]
+//! name = "large-logs"
+//! expr = "ext:log and size:+100M"
+//! output = "large-logs.txt"
+//!
+//! [[query]]
+//! name = "sizes"
+//! expr = "type:file"
+//! report = "size-buckets"
+//! buckets = ["4k", "1M"]
+//! ```
+
+use crate::output::Printer;
+use crate::pipeline::Match;
+use crate::report::{self, Report, Summary};
+use crate::{build_matcher, Filters, Search};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use quaero::{expr, parse, Matcher};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct BatchFile {
+    #[serde(rename = "query", default)]
+    queries: Vec<QueryConfig>,
+}
+
+/// A query as the file gives it.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct QueryConfig {
+    name: String,
+    /// What to match, in the language of --expr
+    expr: String,
+    /// File to write the matches or the report to, stdout if left out
+    output: Option<PathBuf>,
+    /// Report to print instead of the matches, as with --report
+    report: Option<String>,
+    buckets: Option<Vec<String>>,
+    top: Option<usize>,
+    #[serde(rename = "top-depth")]
+    top_depth: Option<usize>,
+}
+
+struct Query {
+    name: String,
+    matcher: Matcher,
+    /// Where its matches go, `None` for stdout along with every other query that
+    /// doesn't say
+    printer: Option<Printer>,
+    summary: Option<Box<dyn Summary>>,
+}
+
+impl QueryConfig {
+    fn build(self, filters: &Filters) -> Result<Query> {
+        let matcher = expr::parse(&self.expr)?;
+        let matcher = match filters.full_path {
+            true => matcher.full_path(),
+            false => matcher,
+        };
+
+        if self.report.is_none()
+            && (self.buckets.is_some() || self.top.is_some() || self.top_depth.is_some())
+        {
+            return Err(anyhow::anyhow!(tr!("error-batch-report-options")));
+        }
+        let summary = match &self.report {
+            Some(name) => {
+                let report = Report::from_str(name, false).map_err(|_| {
+                    let reports = Report::value_variants()
+                        .iter()
+                        .filter_map(|report| report.to_possible_value())
+                        .map(|value| value.get_name().to_owned())
+                        .collect::<Vec<_>>();
+                    anyhow::anyhow!(tr!(
+                        "error-batch-unknown-report",
+                        "report" => name.as_str(),
+                        "reports" => reports.join(", "),
+                    ))
+                })?;
+                let buckets = self
+                    .buckets
+                    .iter()
+                    .flatten()
+                    .map(|bucket| parse::size(bucket))
+                    .collect::<Result<Vec<_>, _>>()?;
+                let top_depth = self.top_depth.unwrap_or(1);
+                if top_depth == 0 {
+                    return Err(anyhow::anyhow!(tr!("error-batch-top-depth")));
+                }
+
+                Some(report::summary(
+                    report,
+                    self.buckets.is_some().then_some(buckets.as_slice()),
+                    self.top.unwrap_or(10),
+                    top_depth,
+                ))
+            }
+            None => None,
+        };
+
+        let printer = match &self.output {
+            Some(path) => {
+                let mut printer =
+                    Printer::new(None, None, filters.workspace.is_some(), false).output(path)?;
+                if filters.deterministic {
+                    printer = printer.deterministic();
+                }
+                Some(printer)
+            }
+            None => None,
+        };
+
+        Ok(Query {
+            name: self.name,
+            matcher: matcher.optimize(),
+            printer,
+            summary,
+        })
+    }
+}
+
+/// Reads the queries of the file at `path`, checking that they can be told apart and
+/// don't write over each other.
+fn load(path: &Path, filters: &Filters) -> Result<Vec<Query>> {
+    let contents = std::fs::read_to_string(path).with_context(|| path.display().to_string())?;
+    let file: BatchFile = toml::from_str(&contents).with_context(|| path.display().to_string())?;
+
+    if file.queries.is_empty() {
+        return Err(anyhow::anyhow!(tr!(
+            "error-batch-empty",
+            "path" => path.display().to_string()
+        )));
+    }
+
+    let mut names = HashSet::new();
+    let mut outputs = HashSet::new();
+    let mut queries = Vec::with_capacity(file.queries.len());
+    for config in file.queries {
+        if !names.insert(config.name.clone()) {
+            return Err(anyhow::anyhow!(tr!(
+                "error-batch-duplicate-name",
+                "name" => config.name.as_str()
+            )));
+        }
+        if let Some(output) = config
+            .output
+            .clone()
+            .filter(|output| !outputs.insert(output.clone()))
+        {
+            return Err(anyhow::anyhow!(tr!(
+                "error-batch-duplicate-output",
+                "path" => output.display().to_string()
+            )));
+        }
+
+        let name = config.name.clone();
+        queries.push(
+            config
+                .build(filters)
+                .with_context(|| tr!("error-batch-query", "name" => name))?,
+        );
+    }
+
+    Ok(queries)
+}
+
+/// Walks once for every query in the file at `path`, within what `filters` narrow the
+/// search down to. Matches of the queries that don't have an output file of their own
+/// are printed as they are found, labeled with the name of the query, and reports
+/// once the walk is done.
+pub fn batch(path: &Path, filters: &Filters) -> Result<()> {
+    // Members of an archive are matched by the search as a whole, which can't tell
+    // which query they were for.
+    if filters.archives {
+        return Err(anyhow::anyhow!(tr!("error-batch-archives")));
+    }
+
+    let mut queries = load(path, filters)?;
+    let any = queries.iter().map(|query| query.matcher.clone()).collect();
+    let search = Search {
+        args: filters,
+        matcher: Matcher::All(vec![build_matcher(filters)?, Matcher::Any(any)]).optimize(),
+        stats: Arc::default(),
+        interrupt: Arc::default(),
+        checkpoint: None,
+    };
+
+    let mut stdout = Printer::new(None, None, false, false);
+    if filters.deterministic {
+        stdout = stdout.deterministic();
+    }
+
+    search.run(&mut |label, entry| {
+        for query in queries
+            .iter_mut()
+            .filter(|query| query.matcher.matches(&entry))
+        {
+            match (&mut query.summary, &mut query.printer) {
+                (Some(summary), _) => summary.add(&Match::new(None, entry.clone())),
+                (None, Some(printer)) => {
+                    printer.print(&Match::new(label.map(str::to_owned), entry.clone()));
+                }
+                (None, None) => stdout.print(&Match::new(Some(query.name.clone()), entry.clone())),
+            }
+        }
+
+        !stdout.failed()
+            && queries.iter().all(|query| {
+                query
+                    .printer
+                    .as_ref()
+                    .is_none_or(|printer| !printer.failed())
+            })
+    })?;
+
+    for query in &mut queries {
+        let Some(summary) = &query.summary else {
+            continue;
+        };
+        match &mut query.printer {
+            Some(printer) => printer.write(|out| summary.print(out)),
+            None => stdout.write(|out| {
+                writeln!(out, "[{}]", query.name)?;
+                summary.print(out)
+            }),
+        }
+    }
+
+    for query in &mut queries {
+        if let Some(printer) = &mut query.printer {
+            printer
+                .finish()
+                .with_context(|| tr!("error-batch-query", "name" => query.name.as_str()))?;
+        }
+    }
+    stdout.finish()
+}
@@ -0,0 +1,131 @@
+//! `quaero bench`: walks of a directory timed with every backend and a few numbers of
+//! threads, for picking the settings that walk it fastest and for catching a walk that
+//! got slower.
+//!
+//! Of the system calls, those counted are the ones a walk makes for each entry or
+//! directory: the stats of the entries it looks at, and a directory opened, read and
+//! closed for every one it lists. The statx calls the io-uring backend submits through
+//! its ring come on top, a few calls to io_uring_enter for each directory.
+
+use crate::capabilities::Capability;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use quaero::{Backend, IgnorePolicy, Matcher, WalkBuilder, WalkStats};
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// One walk of a directory, with what it took.
+struct Run {
+    elapsed: Duration,
+    stats: Arc<WalkStats>,
+}
+
+/// The numbers of threads tried unless told otherwise: powers of two up to one for
+/// every core, and that.
+fn default_threads() -> Vec<usize> {
+    let cores = std::thread::available_parallelism().map_or(1, |cores| cores.get());
+    let mut threads = std::iter::successors(Some(1), |threads| Some(threads * 2))
+        .take_while(|threads| *threads < cores)
+        .collect::<Vec<_>>();
+    threads.push(cores);
+    threads
+}
+
+fn builder(root: &Path, backend: Backend, stats: &Arc<WalkStats>) -> WalkBuilder {
+    WalkBuilder::new(root)
+        .backend(backend)
+        .policy(IgnorePolicy::from_unrestricted(3))
+        .stats(stats.clone())
+}
+
+/// Walks everything below `root`, its subdirectories split between `threads` threads
+/// that each take the next one once done with the last. Errors are only counted.
+fn walk(root: &Path, backend: Backend, threads: usize, matcher: Option<&Matcher>) -> Run {
+    let stats = Arc::new(WalkStats::default());
+    let started = Instant::now();
+
+    let subdirectories = builder(root, backend, &stats)
+        .max_depth(1)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.depth > 0 && entry.is_dir())
+        .map(|entry| entry.path)
+        .collect::<Vec<_>>();
+    let next = AtomicUsize::new(0);
+    std::thread::scope(|scope| {
+        for _ in 0..threads {
+            scope.spawn(|| {
+                while let Some(directory) = subdirectories.get(next.fetch_add(1, Ordering::Relaxed))
+                {
+                    let builder = builder(directory, backend, &stats);
+                    let builder = match matcher {
+                        Some(matcher) => builder.matcher(matcher.clone()),
+                        None => builder,
+                    };
+                    builder.build().for_each(drop);
+                }
+            });
+        }
+    });
+
+    Run {
+        elapsed: started.elapsed(),
+        stats,
+    }
+}
+
+/// Walks `root` `runs` times with every backend and each of `threads`, printing the
+/// fastest run of each. With an `extension` only the files with it are searched for,
+/// and the fast backends pass over the rest by name.
+pub fn bench(root: &Path, threads: &[usize], runs: usize, extension: Option<&str>) -> Result<()> {
+    // Anything but a directory is no walk at all.
+    std::fs::read_dir(root).with_context(|| root.display().to_string())?;
+    let matcher = extension.map(|extension| Matcher::Extension(vec![extension.to_owned()]));
+    let threads = match threads.is_empty() {
+        true => default_threads(),
+        false => threads.to_vec(),
+    };
+    let backends = Backend::value_variants()
+        .iter()
+        .copied()
+        .filter(|backend| {
+            *backend != Backend::IoUring || Capability::IoUring.check_for("quaero bench")
+        })
+        .collect::<Vec<_>>();
+
+    println!("{}", tr!("bench-heading"));
+    for backend in backends {
+        let name = backend
+            .to_possible_value()
+            .map_or_else(String::new, |value| value.get_name().to_owned());
+        for &threads in &threads {
+            let fastest = (0..runs)
+                .map(|_| walk(root, backend, threads, matcher.as_ref()))
+                .min_by_key(|run| run.elapsed)
+                .expect("there is at least one run");
+
+            let load = |counter: &std::sync::atomic::AtomicU64| counter.load(Ordering::Relaxed);
+            let (entries, directories, lookups) = (
+                load(&fastest.stats.entries),
+                load(&fastest.stats.directories),
+                load(&fastest.stats.lookups),
+            );
+            let seconds = fastest.elapsed.as_secs_f64();
+            println!(
+                "{:<8} {:>7} {:>10.1} {:>10} {:>12.0} {:>11} {:>10} {:>6}",
+                name,
+                threads,
+                seconds * 1000.0,
+                entries,
+                entries as f64 / seconds.max(f64::EPSILON),
+                directories,
+                lookups,
+                load(&fastest.stats.errors),
+            );
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,203 @@
+//! What the platform and this build support of the features some options rely on,
+//! for `quaero capabilities` and for those options to do without what is missing
+//! instead of failing.
+
+use clap::ValueEnum;
+use std::io::Write;
+use std::path::PathBuf;
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    /// When files were created, as statx reports it
+    BirthTime,
+    /// Extended attributes of files
+    Xattrs,
+    /// Copies sharing their extents with the original, on copy-on-write file systems
+    Reflinks,
+    /// Batched system calls through io_uring
+    IoUring,
+    /// File system events, for --watch
+    Inotify,
+    /// Reading files without updating their access times, with O_NOATIME
+    NoAtime,
+    /// `quaero self-update`, a feature of the build
+    SelfUpdate,
+}
+
+/// Something in the temporary directory to look at, removed again when dropped.
+#[cfg(target_os = "linux")]
+struct Scratch(PathBuf);
+
+#[cfg(target_os = "linux")]
+impl Scratch {
+    fn create(name: &str, contents: &[u8]) -> std::io::Result<Self> {
+        let path = std::env::temp_dir().join(format!(".quaero-{}-{}", name, std::process::id()));
+        std::fs::write(&path, contents)?;
+        Ok(Self(path))
+    }
+}
+
+#[cfg(target_os = "linux")]
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+fn birth_time() -> std::io::Result<()> {
+    std::fs::metadata(std::env::temp_dir())?
+        .created()
+        .map(|_| ())
+}
+
+#[cfg(target_os = "linux")]
+fn xattrs() -> std::io::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let path = std::ffi::CString::new(std::env::temp_dir().as_os_str().as_bytes())?;
+    match unsafe { libc::listxattr(path.as_ptr(), std::ptr::null_mut(), 0) } {
+        -1 => Err(std::io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn reflinks() -> std::io::Result<()> {
+    use std::os::fd::AsRawFd;
+
+    let original = Scratch::create("reflink", b"quaero")?;
+    let copy = Scratch::create("reflink-copy", b"")?;
+    let (original_file, copy_file) = (
+        std::fs::File::open(&original.0)?,
+        std::fs::OpenOptions::new().write(true).open(&copy.0)?,
+    );
+    match unsafe {
+        libc::ioctl(
+            copy_file.as_raw_fd(),
+            libc::FICLONE,
+            original_file.as_raw_fd(),
+        )
+    } {
+        -1 => Err(std::io::Error::last_os_error()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn io_uring() -> std::io::Result<()> {
+    // The struct io_uring_params the kernel fills in, left zeroed for the defaults.
+    let mut params = [0u32; 30];
+    let fd = unsafe { libc::syscall(libc::SYS_io_uring_setup, 1, params.as_mut_ptr()) };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    unsafe { libc::close(fd as libc::c_int) };
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn inotify() -> std::io::Result<()> {
+    let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC) };
+    if fd < 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+
+    unsafe { libc::close(fd) };
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+fn no_atime() -> std::io::Result<()> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    let scratch = Scratch::create("noatime", b"quaero")?;
+    std::fs::OpenOptions::new()
+        .read(true)
+        .custom_flags(libc::O_NOATIME)
+        .open(&scratch.0)
+        .map(drop)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn linux_only() -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        tr!("capability-linux-only"),
+    ))
+}
+
+impl Capability {
+    fn name(self) -> String {
+        self.to_possible_value()
+            .map(|value| value.get_name().to_owned())
+            .unwrap_or_default()
+    }
+
+    /// Checks whether it is there to use, returning why not otherwise.
+    pub fn detect(self) -> Result<(), String> {
+        let detected = match self {
+            Capability::SelfUpdate => match cfg!(feature = "self-update") {
+                true => Ok(()),
+                false => return Err(tr!("capability-not-built", "feature" => "self-update")),
+            },
+            Capability::BirthTime => birth_time(),
+            #[cfg(target_os = "linux")]
+            Capability::Xattrs => xattrs(),
+            #[cfg(target_os = "linux")]
+            Capability::Reflinks => reflinks(),
+            #[cfg(target_os = "linux")]
+            Capability::IoUring => io_uring(),
+            #[cfg(target_os = "linux")]
+            Capability::Inotify => inotify(),
+            #[cfg(target_os = "linux")]
+            Capability::NoAtime => no_atime(),
+            // The watcher picks whatever the platform has instead.
+            #[cfg(not(target_os = "linux"))]
+            Capability::Inotify => Ok(()),
+            #[cfg(not(target_os = "linux"))]
+            _ => linux_only(),
+        };
+
+        detected.map_err(|error| error.to_string())
+    }
+
+    /// Warns on stderr that `option` does without this capability, if it is missing.
+    /// Returns whether it is there.
+    pub fn check_for(self, option: &str) -> bool {
+        let Err(reason) = self.detect() else {
+            return true;
+        };
+
+        eprintln!(
+            "{}",
+            tr!(
+                "capability-warning",
+                "option" => option,
+                "capability" => self.name(),
+                "reason" => reason,
+            )
+        );
+        false
+    }
+}
+
+/// `quaero capabilities`: whether each capability is there, and why not if it isn't.
+/// The checks of the file system are made in the temporary directory.
+pub fn print(out: &mut dyn Write) -> std::io::Result<()> {
+    let width = Capability::value_variants()
+        .iter()
+        .map(|capability| capability.name().len())
+        .max()
+        .unwrap_or(0);
+
+    for capability in Capability::value_variants() {
+        let status = match capability.detect() {
+            Ok(()) => tr!("capability-available"),
+            Err(reason) => tr!("capability-missing", "reason" => reason),
+        };
+        writeln!(out, "{:<width$}  {}", capability.name(), status)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,82 @@
+//! `--chmod` and `--chown`: the permissions and the owners of the matched files set to
+//! what they should be.
+
+use crate::output::Printer;
+use anyhow::Result;
+use quaero::perm::ModeChange;
+use quaero::users::Owner;
+use quaero::Entry;
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+
+/// Gives the matched entries the permissions of `mode` and the owner of `owner`,
+/// printing those that weren't as asked already. Links keep their permissions, which
+/// mean nothing, but are given to the owner themselves rather than the file they point
+/// to. What can't be changed is reported and skipped, the rest is changed all the same.
+pub fn change(
+    matches: &[Entry],
+    mode: Option<&ModeChange>,
+    owner: Option<Owner>,
+    dry_run: bool,
+    printer: &mut Printer,
+) -> Result<()> {
+    let (mut changed, mut unchanged, mut failed) = (0, 0, 0);
+
+    for entry in matches {
+        let failed_for = |error: std::io::Error| {
+            let path = quaero::clean_path(&entry.path).into_owned();
+            crate::report_error(&anyhow::Error::from(error).context(path));
+        };
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(error) => {
+                failed_for(error);
+                failed += 1;
+                continue;
+            }
+        };
+        let bits = mode
+            .filter(|_| !entry.is_symlink())
+            .map(|mode| mode.apply(metadata.mode(), entry.is_dir()))
+            .filter(|bits| *bits != metadata.mode() & 0o7777);
+        let owner = owner.filter(|owner| {
+            owner.uid.is_some_and(|uid| uid != metadata.uid())
+                || owner.gid.is_some_and(|gid| gid != metadata.gid())
+        });
+        if bits.is_none() && owner.is_none() {
+            unchanged += 1;
+            continue;
+        }
+
+        if !dry_run {
+            // The owner first, as chown takes away the set-user-ID and set-group-ID bits.
+            let owned = owner.map_or(Ok(()), |owner| {
+                std::os::unix::fs::lchown(&entry.path, owner.uid, owner.gid)
+            });
+            let set = owned.and_then(|()| {
+                bits.map_or(Ok(()), |bits| {
+                    std::fs::set_permissions(&entry.path, std::fs::Permissions::from_mode(bits))
+                })
+            });
+            if let Err(error) = set {
+                failed_for(error);
+                failed += 1;
+                continue;
+            }
+        }
+        printer.write(|out| writeln!(out, "{}", quaero::clean_path(&entry.path)));
+        changed += 1;
+    }
+
+    eprintln!(
+        "{}",
+        tr!(
+            "change-summary",
+            "dry-run" => if dry_run { "yes" } else { "no" },
+            "files" => changed,
+            "unchanged" => unchanged,
+            "failed" => failed,
+        )
+    );
+
+    Ok(())
+}
@@ -0,0 +1,61 @@
+//! Where a depth-first walk got to, for a walk cut short to be resumed by another one
+//! instead of started over: the directories it got through, every entry below them
+//! yielded. A walk resumed from a checkpoint leaves them out, and lists the directories
+//! the last one was in the middle of again.
+
+use crate::filter::{Descend, DirFilter};
+use crate::Entry;
+use anyhow::Result;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+#[derive(Debug, Default)]
+pub struct Checkpoint {
+    /// The directories the walks resumed from got through
+    resumed: HashSet<PathBuf>,
+    /// The directories got through so far, in the order they were. Those below one are
+    /// dropped once it is got through, for there to only be a few of them however many
+    /// a walk goes through
+    done: Mutex<Vec<PathBuf>>,
+}
+
+impl Checkpoint {
+    /// Resumes from the `directories` a checkpoint saved.
+    pub fn resuming(directories: Vec<PathBuf>) -> Self {
+        Self {
+            resumed: directories.iter().cloned().collect(),
+            done: Mutex::new(directories),
+        }
+    }
+
+    /// The directories got through, to be saved for resuming from.
+    pub fn directories(&self) -> Vec<PathBuf> {
+        self.done.lock().expect("checkpoint lock poisoned").clone()
+    }
+
+    /// Whether a walk resumed from here leaves out `path`, having got through it.
+    pub fn is_done(&self, path: &Path) -> bool {
+        self.resumed.contains(path)
+    }
+
+    /// Records that every entry below `directory` was yielded.
+    pub(crate) fn finished(&self, directory: &Path) {
+        let mut done = self.done.lock().expect("checkpoint lock poisoned");
+        // Those below it were got through last, unless another walk got through others
+        // in between, which are only kept longer than they need to be.
+        while done.last().is_some_and(|last| last.starts_with(directory)) {
+            done.pop();
+        }
+        done.push(directory.to_owned());
+    }
+}
+
+impl DirFilter for Checkpoint {
+    fn descend(&self, directory: &Entry) -> Result<Descend> {
+        Ok(match self.is_done(&directory.path) {
+            true => Descend::Skip,
+            false => Descend::Yes,
+        })
+    }
+}
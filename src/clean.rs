@@ -0,0 +1,79 @@
+//! `quaero clean`: the old files of a directory removed, but for the newest few of
+//! every directory below it, as log and backup rotation asks.
+
+use crate::output::Printer;
+use crate::{build_matcher, delete, Filters, Search};
+use anyhow::Result;
+use globset::Glob;
+use quaero::{Entry, FileType};
+use std::collections::HashMap;
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// Removes the files below `directory` that `filters` and any of `patterns` match,
+/// keeping the `keep_latest` most recently modified of every directory. Of the others,
+/// only those last modified longer than `older_than` ago go, if it is given.
+pub fn clean(
+    filters: &mut Filters,
+    directory: &Path,
+    patterns: &[Glob],
+    older_than: Option<Duration>,
+    keep_latest: usize,
+    dry_run: bool,
+) -> Result<()> {
+    // Members of an archive can't be removed one by one.
+    if filters.archives {
+        return Err(anyhow::anyhow!(tr!("error-clean-archives")));
+    }
+
+    filters.start_directory = Some(directory.to_owned());
+    filters.globs.extend_from_slice(patterns);
+    let search = Search {
+        matcher: build_matcher(filters)?.optimize(),
+        args: &*filters,
+        stats: Arc::default(),
+        interrupt: Arc::default(),
+        checkpoint: None,
+    };
+
+    let mut directories: HashMap<PathBuf, Vec<(SystemTime, Entry)>> = HashMap::new();
+    search.run(&mut |_, entry| {
+        // Without a modification time to go by, a file is kept.
+        if let (false, Some(modified)) = (
+            matches!(entry.file_type(), FileType::Directory | FileType::Special),
+            entry.modified(),
+        ) {
+            let parent = entry.path.parent().unwrap_or(Path::new(""));
+            directories
+                .entry(parent.to_path_buf())
+                .or_default()
+                .push((modified, entry));
+        }
+        true
+    })?;
+
+    let cutoff = older_than.map(|age| SystemTime::now() - age);
+    let mut removed = directories
+        .into_values()
+        .flat_map(|mut files| {
+            files.sort_by(|(a, a_entry), (b, b_entry)| {
+                b.cmp(a).then_with(|| a_entry.path.cmp(&b_entry.path))
+            });
+            files.into_iter().skip(keep_latest)
+        })
+        .filter(|(modified, _)| cutoff.is_none_or(|cutoff| *modified < cutoff))
+        .map(|(_, entry)| entry)
+        .collect::<Vec<_>>();
+    removed.sort_by(|a, b| {
+        a.path
+            .as_os_str()
+            .as_bytes()
+            .cmp(b.path.as_os_str().as_bytes())
+    });
+
+    let mut printer = Printer::new(None, None, false, false);
+    delete::delete(&removed, false, dry_run, &mut printer)?;
+    printer.finish()
+}
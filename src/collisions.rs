@@ -0,0 +1,122 @@
+//! `quaero collisions`: the names of a directory that only differ in case or in how
+//! their accents are encoded, which file systems that ignore case or normalize names,
+//! as macOS and Windows do by default, can't keep apart.
+
+use crate::{build_matcher, Filters, Search};
+use anyhow::Result;
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use unicode_normalization::{is_nfc, is_nfd, UnicodeNormalization};
+
+/// How the names of a collision differ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Kind {
+    /// In case at least, e.g. README.md and Readme.md
+    Case,
+    /// Only in their Unicode normalization form, e.g. café with a precomposed é and with
+    /// an e followed by a combining accent
+    Normalization,
+}
+
+/// The name as a file system ignoring case and normalization forms sees it.
+fn folded(name: &str) -> String {
+    name.nfd()
+        .collect::<String>()
+        .to_lowercase()
+        .nfc()
+        .collect()
+}
+
+/// Which normalization form `name` is in, for names that look the same printed.
+fn form(name: &str) -> &'static str {
+    match (is_nfc(name), is_nfd(name)) {
+        (true, _) => "NFC",
+        (false, true) => "NFD",
+        (false, false) => "mixed",
+    }
+}
+
+/// Walks `directory`, or where `filters` say if not given, and prints every group of
+/// names of a directory that fold to the same one, by how they differ. Returns whether
+/// it found any.
+pub fn collisions(filters: &mut Filters, directory: Option<&Path>) -> Result<bool> {
+    if let Some(directory) = directory {
+        filters.start_directory = Some(directory.to_owned());
+    }
+    let search = Search {
+        matcher: build_matcher(filters)?.optimize(),
+        args: &*filters,
+        stats: Arc::default(),
+        interrupt: Arc::default(),
+        checkpoint: None,
+    };
+
+    // The names of every directory by what they fold to, for those that fold alike.
+    let mut directories: HashMap<PathBuf, HashMap<String, Vec<String>>> = HashMap::new();
+    search.run(&mut |_, entry| {
+        // What the root is called is up to the directory above it, which isn't walked.
+        // A name that isn't UTF-8 has no case or normalization form to fold.
+        if let (true, Some(name)) = (entry.depth > 0, entry.name.to_str()) {
+            let parent = entry.path.parent().unwrap_or(Path::new(""));
+            directories
+                .entry(parent.to_path_buf())
+                .or_default()
+                .entry(folded(name))
+                .or_default()
+                .push(name.to_owned());
+        }
+        true
+    })?;
+
+    let mut groups = Vec::new();
+    for (directory, names) in directories {
+        for mut names in names.into_values().filter(|names| names.len() > 1) {
+            names.sort_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+            let kind = match names.iter().all(|name| name.nfc().eq(names[0].nfc())) {
+                true => Kind::Normalization,
+                false => Kind::Case,
+            };
+            groups.push((kind, directory.clone(), names));
+        }
+    }
+    groups.sort();
+
+    let mut out = std::io::stdout().lock();
+    for kind in [Kind::Case, Kind::Normalization] {
+        let found = groups
+            .iter()
+            .filter(|(group_kind, ..)| *group_kind == kind)
+            .collect::<Vec<_>>();
+        if found.is_empty() {
+            continue;
+        }
+
+        if kind == Kind::Normalization && found.len() < groups.len() {
+            writeln!(out)?;
+        }
+        let kind_name = match kind {
+            Kind::Case => "case",
+            Kind::Normalization => "normalization",
+        };
+        writeln!(
+            out,
+            "{}",
+            tr!("collisions-heading", "kind" => kind_name, "count" => found.len())
+        )?;
+        for (_, directory, names) in found {
+            let names = names
+                .iter()
+                .map(|name| match kind {
+                    Kind::Case => name.clone(),
+                    Kind::Normalization => format!("{} ({})", name, form(name)),
+                })
+                .collect::<Vec<_>>();
+            let directory = quaero::clean_path(directory).into_owned();
+            writeln!(out, "  {}: {}", directory, names.join(", "))?;
+        }
+    }
+
+    Ok(!groups.is_empty())
+}
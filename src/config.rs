@@ -0,0 +1,300 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use toml::Spanned;
+
+/// Extensions of more than one part, unless the config file says otherwise.
+const COMPOUND_EXTENSIONS: &[&str] = &["tar.gz", "tar.bz2", "tar.xz", "tar.zst"];
+
+/// What `quaero config init` writes: every setting there is, commented out.
+const STARTER: &str = r#"# quaero configuration, see `quaero config check` after editing.
+
+# Extensions of more than one part, which the {stem} and {ext} fields of --template
+# split off whole. --extension matches extensions of several parts whatever this says.
+# compound-extensions = ["tar.gz", "tar.bz2", "tar.xz", "tar.zst"]
+
+# Defaults for every search, overridden by the command line and bypassed altogether
+# by --no-config. QUAERO_OPTS holds options to put in front of the ones given as well.
+#
+# [defaults]
+# # Names skipped by every search along with .git, .hg and .svn, unless -uuu
+# excludes = ["node_modules", "target"]
+# # As with -u, -uu and -uuu, when none of them is given
+# unrestricted = 1
+# # Threads hashing and searching the contents of files, as with --threads
+# threads = 4
+
+# A workspace is a named set of roots searched together with `quaero --workspace <name>`.
+#
+# [[workspaces.work.roots]]
+# path = "~/src/backend"
+# # Printed in front of its matches, the directory name if left out
+# label = "backend"
+# # Directories to avoid, relative to the root
+# excludes = ["target", "node_modules"]
+#
+# [[workspaces.work.roots]]
+# path = "~/src/frontend"
+"#;
+
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub workspaces: HashMap<Spanned<String>, Workspace>,
+
+    #[serde(rename = "compound-extensions")]
+    compound_extensions: Option<Vec<Spanned<String>>>,
+
+    pub defaults: Defaults,
+}
+
+/// What a search does unless the command line says otherwise.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Defaults {
+    /// Names skipped along with the default excludes
+    pub excludes: Vec<Spanned<String>>,
+    pub unrestricted: Option<Spanned<u8>>,
+    pub threads: Option<Spanned<usize>>,
+}
+
+/// Set by --no-config, after which the config file is never read.
+static BYPASSED: AtomicBool = AtomicBool::new(false);
+
+/// A named set of repository roots that are searched together.
+#[derive(Deserialize, Debug, Default)]
+#[serde(default, deny_unknown_fields)]
+pub struct Workspace {
+    pub roots: Vec<WorkspaceRoot>,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct WorkspaceRoot {
+    pub path: Spanned<PathBuf>,
+
+    /// Label printed in front of matches coming from this root, defaults to the root's name
+    pub label: Option<Spanned<String>>,
+
+    /// Directories to avoid, relative to the root
+    #[serde(default)]
+    pub excludes: Vec<Spanned<PathBuf>>,
+}
+
+impl WorkspaceRoot {
+    pub fn path(&self) -> PathBuf {
+        expand_home(self.path.get_ref())
+    }
+
+    pub fn label(&self) -> String {
+        self.label.as_ref().map_or_else(
+            || {
+                self.path().file_name().map_or_else(
+                    || self.path.get_ref().display().to_string(),
+                    |name| name.to_string_lossy().into_owned(),
+                )
+            },
+            |label| label.get_ref().clone(),
+        )
+    }
+
+    /// The excludes that actually exist on disk, resolved against the root.
+    pub fn excludes(&self) -> Vec<PathBuf> {
+        let root = self.path();
+        self.excludes
+            .iter()
+            .map(|exclude| root.join(expand_home(exclude.get_ref())))
+            .filter(|exclude| exclude.exists())
+            .collect()
+    }
+}
+
+/// Something `quaero config check` found wrong with a config file.
+#[derive(Debug)]
+pub struct Problem {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl Problem {
+    fn new(contents: &str, span: Range<usize>, message: String) -> Self {
+        let before = &contents[..span.start.min(contents.len())];
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            message,
+        }
+    }
+}
+
+impl Config {
+    pub fn location() -> Option<PathBuf> {
+        let config_home = std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+
+        Some(config_home.join("quaero").join("config.toml"))
+    }
+
+    /// `--no-config`: every search from now on goes as if there were no config file.
+    pub fn bypass() {
+        BYPASSED.store(true, Ordering::Relaxed);
+    }
+
+    pub fn load() -> Result<Self> {
+        if BYPASSED.load(Ordering::Relaxed) {
+            return Ok(Self::default());
+        }
+
+        let Some(location) = Self::location() else {
+            return Ok(Self::default());
+        };
+
+        if !location.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(&location)
+            .with_context(|| format!("failed to read {}", location.display()))?;
+
+        toml::from_str(&contents).with_context(|| format!("failed to parse {}", location.display()))
+    }
+
+    /// Extensions of more than one part, such as `tar.gz`.
+    pub fn compound_extensions(&self) -> Vec<String> {
+        match &self.compound_extensions {
+            Some(extensions) => extensions
+                .iter()
+                .map(|extension| extension.get_ref().clone())
+                .collect(),
+            None => COMPOUND_EXTENSIONS
+                .iter()
+                .map(|&extension| extension.to_owned())
+                .collect(),
+        }
+    }
+
+    /// Checks the config file at `location` against what it may hold, returning its
+    /// problems in the order they appear. A file that doesn't parse has just the one
+    /// problem, since nothing after it can be checked.
+    pub fn check(location: &Path) -> Result<Vec<Problem>> {
+        let contents = std::fs::read_to_string(location)
+            .with_context(|| format!("failed to read {}", location.display()))?;
+
+        let config: Config = match toml::from_str(&contents) {
+            Ok(config) => config,
+            Err(error) => {
+                let span = error.span().unwrap_or(0..0);
+                return Ok(vec![Problem::new(
+                    &contents,
+                    span,
+                    error.message().to_owned(),
+                )]);
+            }
+        };
+
+        let mut problems = Vec::new();
+        for extension in config.compound_extensions.iter().flatten() {
+            let parts = extension.get_ref().split('.').collect::<Vec<_>>();
+            if parts.len() < 2 || parts.iter().any(|part| part.is_empty()) {
+                let message = tr!(
+                    "config-not-compound",
+                    "extension" => extension.get_ref().as_str()
+                );
+                problems.push(Problem::new(&contents, extension.span(), message));
+            }
+        }
+
+        let defaults = &config.defaults;
+        for exclude in &defaults.excludes {
+            if exclude.get_ref().contains('/') || exclude.get_ref().is_empty() {
+                let message = tr!("config-exclude-not-name", "name" => exclude.get_ref().as_str());
+                problems.push(Problem::new(&contents, exclude.span(), message));
+            }
+        }
+        if let Some(level) = defaults
+            .unrestricted
+            .as_ref()
+            .filter(|level| *level.get_ref() > 3)
+        {
+            let message = tr!("config-unrestricted-range", "level" => *level.get_ref());
+            problems.push(Problem::new(&contents, level.span(), message));
+        }
+        if let Some(threads) = defaults
+            .threads
+            .as_ref()
+            .filter(|threads| *threads.get_ref() == 0)
+        {
+            problems.push(Problem::new(
+                &contents,
+                threads.span(),
+                tr!("config-no-threads"),
+            ));
+        }
+
+        for (name, workspace) in &config.workspaces {
+            if workspace.roots.is_empty() {
+                let message = tr!("config-no-roots", "name" => name.get_ref().as_str());
+                problems.push(Problem::new(&contents, name.span(), message));
+            }
+
+            let mut labels = HashSet::new();
+            for root in &workspace.roots {
+                let path = root.path();
+                if !path.is_dir() {
+                    let message = tr!("config-missing-root", "path" => path.display().to_string());
+                    problems.push(Problem::new(&contents, root.path.span(), message));
+                }
+
+                if !labels.insert(root.label()) {
+                    let span = root.label.as_ref().map_or(root.path.span(), Spanned::span);
+                    let message = tr!("config-duplicate-label", "label" => root.label());
+                    problems.push(Problem::new(&contents, span, message));
+                }
+
+                for exclude in &root.excludes {
+                    if exclude.get_ref().is_absolute() {
+                        let message = tr!(
+                            "config-absolute-exclude",
+                            "path" => exclude.get_ref().display().to_string()
+                        );
+                        problems.push(Problem::new(&contents, exclude.span(), message));
+                    }
+                }
+            }
+        }
+        problems.sort_by_key(|problem| (problem.line, problem.column));
+
+        Ok(problems)
+    }
+
+    /// Writes a commented starter config to `location`, unless there is one already
+    /// and `force` isn't set.
+    pub fn init(location: &Path, force: bool) -> Result<()> {
+        if location.exists() && !force {
+            return Err(anyhow::anyhow!(tr!(
+                "error-config-exists",
+                "path" => location.display().to_string()
+            )));
+        }
+
+        if let Some(parent) = location.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        std::fs::write(location, STARTER)
+            .with_context(|| format!("failed to write {}", location.display()))
+    }
+}
+
+fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), std::env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
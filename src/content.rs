@@ -0,0 +1,215 @@
+use crate::Entry;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use rayon::prelude::*;
+use regex::bytes::Regex;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// How much of a file is sniffed for NUL bytes to tell binary files apart.
+const BINARY_PROBE_SIZE: usize = 8 * 1024;
+
+/// Whether [`open`] leaves the access times of files alone.
+static NO_ATIME: AtomicBool = AtomicBool::new(false);
+
+/// Has [`open`], which whatever reads the contents of files opens them with, leave
+/// their access times as they were from now on, for `--no-atime`.
+pub fn preserve_atimes() {
+    NO_ATIME.store(true, Ordering::Relaxed);
+}
+
+/// Whether [`preserve_atimes`] was called.
+pub(crate) fn preserves_atimes() -> bool {
+    NO_ATIME.load(Ordering::Relaxed)
+}
+
+/// Opens the file at `path` for reading, without updating its access time once
+/// [`preserve_atimes`] was called. Only the owner of a file, or root, can open it so,
+/// and anyone else opens it as usual, as does every platform but Linux.
+pub fn open<T: AsRef<Path>>(path: T) -> std::io::Result<File> {
+    #[cfg(target_os = "linux")]
+    if preserves_atimes() {
+        use std::os::unix::fs::OpenOptionsExt;
+
+        let opened = std::fs::OpenOptions::new()
+            .read(true)
+            .custom_flags(libc::O_NOATIME)
+            .open(path.as_ref());
+        match opened {
+            Err(error) if error.raw_os_error() == Some(libc::EPERM) => {}
+            opened => return opened,
+        }
+    }
+    File::open(path)
+}
+
+pub struct ContentMatch {
+    pub line_number: usize,
+    pub line: String,
+}
+
+fn is_binary(file: &mut File) -> Result<bool> {
+    let mut probe = [0; BINARY_PROBE_SIZE];
+    let mut filled = 0;
+    while filled < probe.len() {
+        match file.read(&mut probe[filled..])? {
+            0 => break,
+            read => filled += read,
+        }
+    }
+    file.rewind()?;
+
+    Ok(probe[..filled].contains(&0))
+}
+
+/// Whether the file at `path` looks binary, having a NUL byte early on. An empty file
+/// is text.
+pub fn is_binary_file<T: AsRef<Path>>(path: T) -> Result<bool> {
+    is_binary(&mut open(path)?)
+}
+
+/// An encoding `--encoding` checks files are valid in.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Utf8,
+    /// Nothing but 7-bit ASCII, which is also valid UTF-8
+    Ascii,
+}
+
+impl Encoding {
+    /// Whether the whole of the file at `path` is valid in this encoding, read a block
+    /// at a time.
+    pub fn is_valid<T: AsRef<Path>>(self, path: T) -> Result<bool> {
+        let mut file = open(path)?;
+        let mut block = vec![0; 64 * 1024];
+        // The start of a character the last block ended in the middle of
+        let mut carried = 0;
+        loop {
+            let read = file.read(&mut block[carried..])?;
+            if read == 0 {
+                return Ok(carried == 0);
+            }
+
+            let filled = carried + read;
+            match self {
+                Encoding::Ascii if !block[..filled].is_ascii() => return Ok(false),
+                Encoding::Ascii => carried = 0,
+                Encoding::Utf8 => match std::str::from_utf8(&block[..filled]) {
+                    Ok(_) => carried = 0,
+                    // Cut short by the end of the block rather than invalid.
+                    Err(error) if error.error_len().is_none() => {
+                        let valid = error.valid_up_to();
+                        block.copy_within(valid..filled, 0);
+                        carried = filled - valid;
+                    }
+                    Err(_) => return Ok(false),
+                },
+            }
+        }
+    }
+}
+
+/// How many lines a text file has and how long the longest is.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LineStats {
+    /// The last one counting whether or not it ends in a newline
+    pub lines: u64,
+    /// In characters, without the line ending
+    pub longest: u64,
+}
+
+/// The [`LineStats`] of the file at `path`, or nothing for a binary file. Lengths count
+/// characters, taking the text for UTF-8.
+pub fn line_stats<T: AsRef<Path>>(path: T) -> Result<Option<LineStats>> {
+    let mut file = open(path)?;
+    if is_binary(&mut file)? {
+        return Ok(None);
+    }
+
+    let mut stats = LineStats::default();
+    let mut block = vec![0; 64 * 1024];
+    let mut length = 0;
+    // Whether the line so far ends in a carriage return, which isn't counted if a
+    // newline follows
+    let mut carriage_return = false;
+    loop {
+        let read = file.read(&mut block)?;
+        if read == 0 {
+            break;
+        }
+
+        for &byte in &block[..read] {
+            match byte {
+                b'\n' => {
+                    stats.lines += 1;
+                    stats.longest = stats.longest.max(length - carriage_return as u64);
+                    length = 0;
+                }
+                // Continuation bytes are part of the character they follow.
+                _ if byte & 0xc0 == 0x80 => {}
+                _ => length += 1,
+            }
+            carriage_return = byte == b'\r';
+        }
+    }
+    if length > 0 {
+        stats.lines += 1;
+        stats.longest = stats.longest.max(length);
+    }
+
+    Ok(Some(stats))
+}
+
+/// Returns every line of `path` matching `regex`, or nothing for binary files.
+pub fn search_file<T: AsRef<Path>>(path: T, regex: &Regex) -> Result<Vec<ContentMatch>> {
+    let mut file = open(path)?;
+    if is_binary(&mut file)? {
+        return Ok(Vec::new());
+    }
+
+    let mut reader = BufReader::new(file);
+    let mut matches = Vec::new();
+    let mut line = Vec::new();
+    let mut line_number = 0;
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line)? == 0 {
+            break;
+        }
+        line_number += 1;
+
+        let trimmed = line.strip_suffix(b"\n").unwrap_or(&line);
+        let trimmed = trimmed.strip_suffix(b"\r").unwrap_or(trimmed);
+        if regex.is_match(trimmed) {
+            matches.push(ContentMatch {
+                line_number,
+                line: String::from_utf8_lossy(trimmed).into_owned(),
+            });
+        }
+    }
+
+    Ok(matches)
+}
+
+/// Searches the contents of the regular files among `entries` in parallel, calling
+/// `report` with the index of each file that has at least one matching line, or that
+/// couldn't be read, for the caller to report and skip.
+pub fn search(
+    entries: &[&Entry],
+    regex: &Regex,
+    report: impl Fn(usize, Result<Vec<ContentMatch>>) + Sync,
+) {
+    entries
+        .par_iter()
+        .enumerate()
+        .filter(|(_, entry)| entry.is_file())
+        .for_each(|(index, entry)| match search_file(&entry.path, regex) {
+            Ok(matches) if matches.is_empty() => {}
+            found => report(
+                index,
+                found.with_context(|| entry.path.display().to_string()),
+            ),
+        });
+}
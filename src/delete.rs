@@ -0,0 +1,104 @@
+use crate::output::Printer;
+use anyhow::Result;
+use quaero::Entry;
+use std::collections::HashSet;
+use std::path::Path;
+
+#[derive(Default)]
+struct Summary {
+    files: usize,
+    directories: usize,
+    skipped: usize,
+}
+
+fn is_empty_after(directory: &Path, removed: &HashSet<&Path>) -> std::io::Result<bool> {
+    for child in std::fs::read_dir(directory)? {
+        if !removed.contains(child?.path().as_path()) {
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+impl Summary {
+    /// Reports what kept `path` from being removed, and goes on without it.
+    fn failed(&mut self, path: &Path, error: std::io::Error) {
+        let path = quaero::clean_path(path).into_owned();
+        crate::report_error(&anyhow::Error::from(error).context(path));
+        self.skipped += 1;
+    }
+}
+
+/// Removes the matched entries, children before their parents so that directories
+/// emptied along the way can be removed as well, printing them through `printer`. What
+/// can't be removed is reported and skipped, the rest is removed all the same.
+pub fn delete(
+    matches: &[Entry],
+    delete_dirs: bool,
+    dry_run: bool,
+    printer: &mut Printer,
+) -> Result<()> {
+    let mut summary = Summary::default();
+    let mut removed: HashSet<&Path> = HashSet::new();
+
+    for entry in matches.iter().rev() {
+        let path = entry.path.as_path();
+
+        if entry.is_dir() {
+            // The directory searched is left in place, however empty it ends up.
+            if !delete_dirs || entry.depth == 0 {
+                summary.skipped += 1;
+                continue;
+            }
+
+            match is_empty_after(path, &removed) {
+                Ok(true) => {}
+                Ok(false) => {
+                    eprintln!(
+                        "{}",
+                        tr!("delete-skipping-non-empty", "path" => quaero::clean_path(&entry.path).into_owned())
+                    );
+                    summary.skipped += 1;
+                    continue;
+                }
+                Err(error) => {
+                    summary.failed(&entry.path, error);
+                    continue;
+                }
+            }
+
+            if !dry_run {
+                if let Err(error) = std::fs::remove_dir(path) {
+                    summary.failed(&entry.path, error);
+                    continue;
+                }
+            }
+            summary.directories += 1;
+        } else {
+            if !dry_run {
+                if let Err(error) = std::fs::remove_file(path) {
+                    summary.failed(&entry.path, error);
+                    continue;
+                }
+            }
+            summary.files += 1;
+        }
+
+        printer.write(|out| writeln!(out, "{}", quaero::clean_path(&entry.path)));
+        removed.insert(path);
+    }
+
+    eprintln!(
+        "{}",
+        tr!(
+            "delete-summary",
+            "dry-run" => if dry_run { "yes" } else { "no" },
+            "files" => summary.files,
+            "directories" => summary.directories,
+            "skipped" => summary.skipped,
+        )
+    );
+
+    Ok(())
+}
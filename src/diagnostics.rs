@@ -0,0 +1,170 @@
+//! How errors are written to stderr, as text or with `--errors json` as a JSON object a
+//! line, and the exit code that says how a run went: whether anything matched, and
+//! whether it got through everything it was to look at.
+
+use clap::ValueEnum;
+use serde::Serialize;
+use std::io::Write;
+use std::process::ExitCode;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+/// Nothing matched, or with --quiet nothing was found.
+pub const NO_MATCHES: u8 = 1;
+/// The command line, the config or another error kept the run from going ahead at all.
+pub const USAGE: u8 = 2;
+/// Something couldn't be read or done, was reported and skipped.
+pub const PARTIAL: u8 = 3;
+
+/// How errors are written to stderr.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ErrorFormat {
+    /// The error and its causes after colons, as text
+    #[default]
+    Text,
+    /// A JSON object for every error: its level, error for one skipped and fatal for
+    /// one that ended the run, the message, the causes and the kind of I/O error
+    Json,
+}
+
+static FORMAT: OnceLock<ErrorFormat> = OnceLock::new();
+static REPORTED: AtomicU64 = AtomicU64::new(0);
+/// Whether errors go to stdout as events of `--format ndjson-events`
+static EVENTS: AtomicBool = AtomicBool::new(false);
+
+/// The error format asked for on the command line, looked for before the command line
+/// is parsed for its errors to be written in it too.
+pub fn requested_format() -> ErrorFormat {
+    let mut args = std::env::args_os().skip(1).take_while(|arg| arg != "--");
+    while let Some(arg) = args.next() {
+        let value = match arg.to_str().and_then(|arg| arg.strip_prefix("--errors=")) {
+            Some(value) => value.to_owned(),
+            None if arg == "--errors" => args
+                .next()
+                .map(|value| value.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            None => continue,
+        };
+        return ErrorFormat::from_str(&value, false).unwrap_or_default();
+    }
+    ErrorFormat::default()
+}
+
+pub fn init(format: ErrorFormat) {
+    let _ = FORMAT.set(format);
+}
+
+fn selected() -> ErrorFormat {
+    FORMAT.get().copied().unwrap_or_default()
+}
+
+/// `--format ndjson-events`: writes the errors from now on to stdout as `error` events,
+/// in line with the matches around them, instead of to stderr.
+pub fn as_events() {
+    EVENTS.store(true, Ordering::Relaxed);
+}
+
+/// How many errors were reported and skipped so far.
+pub fn reported() -> u64 {
+    REPORTED.load(Ordering::Relaxed)
+}
+
+/// The kind of the I/O error behind `error`, if one is, e.g. permission_denied.
+fn io_kind(error: &anyhow::Error) -> Option<String> {
+    let kind = error
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<std::io::Error>())?
+        .kind();
+    let mut snake = String::new();
+    for c in format!("{:?}", kind).chars() {
+        if c.is_uppercase() && !snake.is_empty() {
+            snake.push('_');
+        }
+        snake.push(c.to_ascii_lowercase());
+    }
+    Some(snake)
+}
+
+/// An error as an event of `--format ndjson-events`, its fields in the order the
+/// other events have theirs.
+#[derive(Serialize)]
+struct ErrorEvent<'a> {
+    event: &'static str,
+    level: &'a str,
+    message: &'a str,
+    causes: &'a [String],
+    kind: Option<&'a str>,
+}
+
+fn write(level: &str, message: String, causes: Vec<String>, kind: Option<String>) {
+    if EVENTS.load(Ordering::Relaxed) {
+        let event = ErrorEvent {
+            event: "error",
+            level,
+            message: &message,
+            causes: &causes,
+            kind: kind.as_deref(),
+        };
+        let event = serde_json::to_string(&event).expect("events serialize to JSON");
+        // Whoever reads the events going away is for the printer to find out.
+        let _ = writeln!(std::io::stdout().lock(), "{}", event);
+        return;
+    }
+
+    let line = match selected() {
+        ErrorFormat::Text => {
+            let chain = std::iter::once(message).chain(causes).collect::<Vec<_>>();
+            format!("quaero: {}", chain.join(": "))
+        }
+        ErrorFormat::Json => serde_json::json!({
+            "level": level,
+            "message": message,
+            "causes": causes,
+            "kind": kind,
+        })
+        .to_string(),
+    };
+    crate::progress::interject(|| eprintln!("{}", line));
+}
+
+/// Writes an error that was skipped, which makes the run a partial failure.
+pub fn report(error: &anyhow::Error) {
+    REPORTED.fetch_add(1, Ordering::Relaxed);
+    let causes = error.chain().skip(1).map(|cause| cause.to_string());
+    write("error", error.to_string(), causes.collect(), io_kind(error));
+}
+
+/// Writes the error that ended the run, for it to exit with [`USAGE`].
+pub fn fatal(error: &anyhow::Error) -> ExitCode {
+    let causes = error.chain().skip(1).map(|cause| cause.to_string());
+    write("fatal", error.to_string(), causes.collect(), io_kind(error));
+    ExitCode::from(USAGE)
+}
+
+/// Writes the error clap found in the command line and exits with [`USAGE`], unless it
+/// is only the help or the version to print.
+pub fn command_line(error: clap::Error) -> ! {
+    if !error.use_stderr() || selected() == ErrorFormat::Text {
+        error.exit();
+    }
+
+    let message = error.to_string();
+    let message = message.lines().next().unwrap_or_default();
+    let message = message.strip_prefix("error: ").unwrap_or(message);
+    write(
+        "fatal",
+        message.to_owned(),
+        Vec::new(),
+        Some("usage".to_owned()),
+    );
+    std::process::exit(USAGE.into());
+}
+
+/// The exit code of a run that went through, by whether it `found` anything.
+pub fn exit_code(found: bool) -> ExitCode {
+    match (reported(), found) {
+        (0, true) => ExitCode::SUCCESS,
+        (0, false) => ExitCode::from(NO_MATCHES),
+        _ => ExitCode::from(PARTIAL),
+    }
+}
@@ -0,0 +1,168 @@
+//! Directories read with getdents64 into a large buffer, for [`Backend::Fast`]: fewer
+//! system calls than through readdir, and the type of every entry without a stat.
+//!
+//! [`Backend::Fast`]: crate::walk::Backend::Fast
+
+use crate::content;
+use std::ffi::{CString, OsStr, OsString};
+use std::ops::Range;
+use std::os::fd::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+/// How much of a directory one system call reads, twice what glibc's readdir does.
+const BUFFER_SIZE: usize = 64 * 1024;
+
+/// Where the fields of a `struct linux_dirent64` are: after the inode (8 bytes) and the
+/// offset (8 bytes) come the length of the record, the type and the name.
+const RECORD_LENGTH: usize = 16;
+const TYPE: usize = 18;
+const NAME: usize = 19;
+
+/// An entry as the directory lists it.
+pub(crate) struct Dirent {
+    pub name: OsString,
+    /// The `DT_*` type, `DT_UNKNOWN` on file systems that don't say
+    pub kind: u8,
+}
+
+pub(crate) struct Dirents {
+    fd: OwnedFd,
+    buffer: Vec<u8>,
+    offset: usize,
+    done: bool,
+    /// What reading the directory failed with while passing entries over, for
+    /// [`next`](Iterator::next) to return
+    failed: Option<std::io::Error>,
+}
+
+impl Dirents {
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        let path = CString::new(path.as_os_str().as_bytes())?;
+        let open = |flags| unsafe { libc::open(path.as_ptr(), flags) };
+        let flags = libc::O_RDONLY | libc::O_DIRECTORY | libc::O_CLOEXEC;
+        let mut fd = match content::preserves_atimes() {
+            true => open(flags | libc::O_NOATIME),
+            false => -1,
+        };
+        // Only the owner of a directory, or root, can leave its access time alone.
+        if fd < 0 {
+            fd = open(flags);
+        }
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        Ok(Self {
+            fd: unsafe { OwnedFd::from_raw_fd(fd) },
+            // Left uninitialized, for the kernel to write to.
+            buffer: Vec::with_capacity(BUFFER_SIZE),
+            offset: 0,
+            done: false,
+            failed: None,
+        })
+    }
+
+    /// The directory itself, for the names it lists to be looked up in.
+    pub fn fd(&self) -> BorrowedFd<'_> {
+        self.fd.as_fd()
+    }
+
+    /// Reads the next batch of records, returning whether there were any left.
+    fn fill(&mut self) -> std::io::Result<bool> {
+        let read = unsafe {
+            libc::syscall(
+                libc::SYS_getdents64,
+                self.fd.as_raw_fd(),
+                self.buffer.as_mut_ptr(),
+                self.buffer.capacity(),
+            )
+        };
+        if read < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+
+        // The kernel wrote that much.
+        unsafe { self.buffer.set_len(read as usize) };
+        self.offset = 0;
+        Ok(read > 0)
+    }
+
+    /// The record up next, read in if need be, as where its name is in the buffer, its
+    /// type and its length, without moving past it.
+    fn current(&mut self) -> Option<std::io::Result<(Range<usize>, u8, usize)>> {
+        loop {
+            if self.offset >= self.buffer.len() {
+                if self.done {
+                    return None;
+                }
+                match self.fill() {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        self.done = true;
+                        return None;
+                    }
+                    Err(error) => {
+                        self.done = true;
+                        return Some(Err(error));
+                    }
+                }
+            }
+
+            let record = &self.buffer[self.offset..];
+            let length =
+                u16::from_ne_bytes([record[RECORD_LENGTH], record[RECORD_LENGTH + 1]]) as usize;
+            let kind = record[TYPE];
+            // The name is padded with NUL bytes up to the length of the record.
+            let name = &record[NAME..length];
+            let end = NAME
+                + name
+                    .iter()
+                    .position(|&byte| byte == 0)
+                    .unwrap_or(name.len());
+            let name = &record[NAME..end];
+
+            if name == b"." || name == b".." {
+                self.offset += length;
+                continue;
+            }
+            return Some(Ok((self.offset + NAME..self.offset + end, kind, length)));
+        }
+    }
+
+    /// Passes over the entries `skip` is true of, given their name and type where the
+    /// buffer has them, for those not to be copied out at all. The next entry is the
+    /// first it is false of.
+    pub fn skip_while(&mut self, mut skip: impl FnMut(&OsStr, u8) -> bool) {
+        while self.failed.is_none() {
+            match self.current() {
+                Some(Ok((name, kind, length))) => {
+                    if !skip(OsStr::from_bytes(&self.buffer[name]), kind) {
+                        return;
+                    }
+                    self.offset += length;
+                }
+                Some(Err(error)) => self.failed = Some(error),
+                None => return,
+            }
+        }
+    }
+}
+
+impl Iterator for Dirents {
+    type Item = std::io::Result<Dirent>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(error) = self.failed.take() {
+            return Some(Err(error));
+        }
+
+        let (name, kind, length) = match self.current()? {
+            Ok(record) => record,
+            Err(error) => return Some(Err(error)),
+        };
+        let name = OsStr::from_bytes(&self.buffer[name]).to_os_string();
+        self.offset += length;
+        Some(Ok(Dirent { name, kind }))
+    }
+}
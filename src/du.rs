@@ -0,0 +1,89 @@
+//! `quaero du`: how many bytes there are below each matched directory, in the files
+//! the walk finds there.
+
+use crate::{build_matcher, output, Filters, Search};
+use anyhow::Result;
+use quaero::Matcher;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::os::unix::fs::MetadataExt;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Walks everything `filters` let through and prints the size of every directory
+/// they match, followed by the total of them all. Sizes are those of the files, hard
+/// links to one counted once, and so leave out whatever an ignore file or --avoid
+/// keeps out of the walk. --depth only says how deep the directories to print are,
+/// as the files further down count all the same.
+pub fn du(filters: &mut Filters) -> Result<()> {
+    // Members of an archive are sized as the archive, which is counted already.
+    if filters.archives {
+        return Err(anyhow::anyhow!(tr!("error-du-archives")));
+    }
+
+    let depth = filters.depth.take().unwrap_or(usize::MAX);
+    let matcher = build_matcher(filters)?.optimize();
+    let search = Search {
+        args: filters,
+        matcher: Matcher::All(Vec::new()),
+        stats: Arc::default(),
+        interrupt: Arc::default(),
+        checkpoint: None,
+    };
+
+    let mut matched = Vec::new();
+    let mut totals: HashMap<PathBuf, u64> = HashMap::new();
+    let mut inodes = HashSet::new();
+    search.run(&mut |_, entry| {
+        if entry.is_dir() {
+            if entry.depth <= depth && matcher.matches(&entry) {
+                matched.push(PathBuf::from(&entry.path));
+            }
+            return true;
+        }
+
+        // One gone by now takes no room any more.
+        let Ok(metadata) = entry.metadata() else {
+            return true;
+        };
+        if metadata.nlink() > 1 && !inodes.insert((metadata.dev(), metadata.ino())) {
+            return true;
+        }
+        // Up to the root, which is as far up as anything was walked.
+        for directory in entry.path.ancestors().skip(1).take(entry.depth) {
+            *totals.entry(directory.to_path_buf()).or_default() += metadata.len();
+        }
+        true
+    })?;
+
+    matched.sort_by(|a, b| {
+        a.as_os_str()
+            .as_encoded_bytes()
+            .cmp(b.as_os_str().as_encoded_bytes())
+    });
+    let sizes = matched
+        .iter()
+        .map(|directory| output::size(totals.get(directory).copied().unwrap_or(0)))
+        .collect::<Vec<_>>();
+    let width = sizes.iter().map(String::len).max().unwrap_or(0);
+
+    // A directory below another that matched is already part of its total.
+    let matched_set = matched.iter().map(PathBuf::as_path).collect::<HashSet<_>>();
+    let total = matched
+        .iter()
+        .filter(|directory| {
+            !directory
+                .ancestors()
+                .skip(1)
+                .any(|ancestor| matched_set.contains(ancestor))
+        })
+        .map(|directory| totals.get(directory).copied().unwrap_or(0))
+        .sum::<u64>();
+
+    let mut out = std::io::stdout().lock();
+    for (directory, size) in matched.iter().zip(&sizes) {
+        writeln!(out, "{:>width$}  {}", size, directory.display())?;
+    }
+    writeln!(out, "{}", tr!("du-total", "size" => output::size(total)))?;
+    Ok(())
+}
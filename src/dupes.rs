@@ -0,0 +1,173 @@
+use crate::{report_error, Search};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use quaero::{content, Entry, FileType};
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::io::Read;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+/// How much of each file is hashed to tell most files of the same size apart, before
+/// the ones still alike are hashed in full.
+const PARTIAL_SIZE: u64 = 16 * 1024;
+
+/// Which file of a group of duplicates `--delete-dupes` keeps.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Keep {
+    /// The first one found
+    First,
+    /// The most recently modified one
+    Newest,
+    /// The least recently modified one
+    Oldest,
+}
+
+fn partial_hash(path: &Path) -> Result<blake3::Hash> {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_reader(content::open(path)?.take(PARTIAL_SIZE))?;
+    Ok(hasher.finalize())
+}
+
+fn full_hash(path: &Path) -> Result<blake3::Hash> {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update_reader(content::open(path)?)?;
+    Ok(hasher.finalize())
+}
+
+/// Splits every group further by the hash of its files, computed in parallel, keeping
+/// the parts with more than one file. Files that can't be read are reported and left
+/// out.
+fn split(
+    groups: Vec<Vec<Entry>>,
+    hash: impl Fn(&Path) -> Result<blake3::Hash> + Sync,
+) -> Vec<Vec<Entry>> {
+    groups
+        .into_par_iter()
+        .flat_map(|group| {
+            let hashed = group
+                .into_par_iter()
+                .filter_map(|entry| match hash(&entry.path) {
+                    Ok(hash) => Some((hash, entry)),
+                    Err(error) => {
+                        report_error(&error.context(entry.path.display().to_string()));
+                        None
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            // Files keep the order they were found in within each part.
+            let mut parts: Vec<(blake3::Hash, Vec<Entry>)> = Vec::new();
+            for (hash, entry) in hashed {
+                match parts.iter_mut().find(|(part, _)| *part == hash) {
+                    Some((_, part)) => part.push(entry),
+                    None => parts.push((hash, vec![entry])),
+                }
+            }
+
+            parts
+                .into_iter()
+                .map(|(_, part)| part)
+                .filter(|part| part.len() > 1)
+                .collect::<Vec<_>>()
+        })
+        .collect()
+}
+
+fn kept(group: &[Entry], keep: Keep) -> usize {
+    let modified = |index: &usize| {
+        let metadata = group[*index].metadata().ok();
+        metadata.map(|metadata| (metadata.mtime(), metadata.mtime_nsec()))
+    };
+
+    match keep {
+        Keep::First => 0,
+        Keep::Newest => (0..group.len()).rev().max_by_key(modified).unwrap_or(0),
+        Keep::Oldest => (0..group.len()).min_by_key(modified).unwrap_or(0),
+    }
+}
+
+/// `quaero dupes`: groups the matched regular files by size and then by the hash of
+/// their contents, printing each group of duplicates as its paths followed by a blank
+/// line, biggest files first.
+///
+/// Empty files are left out, and so are further hard links to a file already found.
+/// With `delete` all but one file of every group are removed, printing those instead.
+pub fn dupes(search: &Search, delete: Option<Keep>, dry_run: bool) -> Result<()> {
+    let mut inodes = HashSet::new();
+    let mut sizes: HashMap<u64, Vec<Entry>> = HashMap::new();
+    search.run(&mut |_, entry| {
+        let is_file = matches!(
+            entry.file_type(),
+            FileType::RegularFile | FileType::Executable
+        );
+        let Some((size, id)) = entry
+            .metadata()
+            .ok()
+            .filter(|_| is_file)
+            .map(|metadata| (metadata.len(), (metadata.dev(), metadata.ino())))
+        else {
+            return true;
+        };
+        if size > 0 && inodes.insert(id) {
+            sizes.entry(size).or_default().push(entry);
+        }
+        true
+    })?;
+
+    let (small, large): (Vec<_>, Vec<_>) = sizes
+        .into_values()
+        .filter(|group| group.len() > 1)
+        .partition(|group| group[0].size() <= PARTIAL_SIZE);
+    let mut groups = split(split(large, partial_hash), full_hash);
+    groups.extend(split(small, full_hash));
+    groups.sort_by(|a, b| {
+        b[0].size()
+            .cmp(&a[0].size())
+            .then_with(|| a[0].path.cmp(&b[0].path))
+    });
+
+    let (mut removed, mut freed) = (0, 0);
+    for group in &groups {
+        let Some(keep) = delete else {
+            for entry in group {
+                println!("{}", entry.path.display());
+            }
+            println!();
+            continue;
+        };
+
+        let kept = kept(group, keep);
+        for (index, entry) in group.iter().enumerate() {
+            if index == kept {
+                continue;
+            }
+
+            if !dry_run {
+                if let Err(error) =
+                    std::fs::remove_file(&entry.path).context(entry.path.display().to_string())
+                {
+                    report_error(&error);
+                    continue;
+                }
+            }
+            println!("{}", entry.path.display());
+            removed += 1;
+            freed += entry.size();
+        }
+    }
+
+    if delete.is_some() {
+        eprintln!(
+            "{}",
+            tr!(
+                "dupes-summary",
+                "dry-run" => if dry_run { "yes" } else { "no" },
+                "files" => removed,
+                "size" => freed,
+            )
+        );
+    }
+
+    Ok(())
+}
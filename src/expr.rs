@@ -0,0 +1,419 @@
+use crate::content::Encoding;
+use crate::matcher::{Globs, Matcher};
+use crate::parse::{self, ParseError};
+use crate::perm::ExecutableBy;
+use crate::script::Script;
+use crate::{users, FileType};
+use clap::ValueEnum;
+use std::path::Path;
+use std::sync::Arc;
+
+const EXAMPLE: &str = "'(ext:rs or ext:toml) and not path:target'";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Term(String),
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Open => write!(f, "'('"),
+            Token::Close => write!(f, "')'"),
+            Token::And => write!(f, "'and'"),
+            Token::Or => write!(f, "'or'"),
+            Token::Not => write!(f, "'not'"),
+            Token::Term(term) => write!(f, "'{}'", term),
+        }
+    }
+}
+
+/// A token along with the byte range of the expression it was read from.
+type Spanned = (std::ops::Range<usize>, Token);
+
+fn tokenize(expression: &str) -> Result<Vec<Spanned>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.char_indices().peekable();
+
+    while let Some(&(offset, c)) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push((offset..offset + 1, Token::Open));
+            }
+            ')' => {
+                chars.next();
+                tokens.push((offset..offset + 1, Token::Close));
+            }
+            _ => {
+                let mut word = String::new();
+                let mut quote = None;
+                let mut end = offset;
+                while let Some(&(at, c)) = chars.peek() {
+                    match quote {
+                        Some(q) if c == q => quote = None,
+                        Some(_) => word.push(c),
+                        None if c == '\'' || c == '"' => quote = Some(c),
+                        None if c.is_whitespace() || c == '(' || c == ')' => break,
+                        None => word.push(c),
+                    }
+                    chars.next();
+                    end = at + c.len_utf8();
+                }
+
+                if quote.is_some() {
+                    let span = offset..expression.len();
+                    return Err(ParseError::new(expression, span, "unterminated quote"));
+                }
+
+                let token = match word.as_str() {
+                    "and" | "&&" => Token::And,
+                    "or" | "||" => Token::Or,
+                    "not" | "!" => Token::Not,
+                    _ => Token::Term(word),
+                };
+                tokens.push((offset..end, token));
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    expression: &'a str,
+    tokens: Vec<Spanned>,
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(_, token)| token)
+    }
+
+    /// Where the next token is, or just past the end if there is none.
+    fn span(&self) -> std::ops::Range<usize> {
+        let end = self.expression.len();
+        self.tokens
+            .get(self.position)
+            .map_or(end..end + 1, |(span, _)| span.clone())
+    }
+
+    fn error(&self, span: std::ops::Range<usize>, message: impl Into<String>) -> ParseError {
+        ParseError::new(self.expression, span, message)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self
+            .tokens
+            .get(self.position)
+            .map(|(_, token)| token.clone());
+        self.position += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Matcher, ParseError> {
+        let mut alternatives = vec![self.parse_and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            alternatives.push(self.parse_and()?);
+        }
+
+        Ok(match alternatives.len() {
+            1 => alternatives.remove(0),
+            _ => Matcher::Any(alternatives),
+        })
+    }
+
+    /// Adjacent terms without an operator between them are implicitly and-ed.
+    fn parse_and(&mut self) -> Result<Matcher, ParseError> {
+        let mut conjuncts = vec![self.parse_unary()?];
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.next();
+                }
+                Some(Token::Open | Token::Not | Token::Term(_)) => {}
+                _ => break,
+            }
+            conjuncts.push(self.parse_unary()?);
+        }
+
+        Ok(match conjuncts.len() {
+            1 => conjuncts.remove(0),
+            _ => Matcher::All(conjuncts),
+        })
+    }
+
+    fn parse_unary(&mut self) -> Result<Matcher, ParseError> {
+        let span = self.span();
+        match self.next() {
+            Some(Token::Not) => Ok(Matcher::Not(Box::new(self.parse_unary()?))),
+            Some(Token::Open) => {
+                let matcher = self.parse_or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(matcher),
+                    _ => Err(self.error(span, "missing ')' for this '('")),
+                }
+            }
+            Some(Token::Term(term)) => {
+                let source = &self.expression[span.clone()];
+                parse_predicate(&term).map_err(|error| match source == term {
+                    true => error.within(self.expression, span.start),
+                    // Quotes moved the value around, so only the whole term can be shown.
+                    false => ParseError::new(self.expression, span, error.message()),
+                })
+            }
+            Some(token) => Err(self.error(span, format!("unexpected {}", token))),
+            None => Err(self
+                .error(span, "unexpected end of expression")
+                .example(EXAMPLE)),
+        }
+    }
+}
+
+fn parse_predicate(term: &str) -> Result<Matcher, ParseError> {
+    let Some((key, value)) = term.split_once(':') else {
+        return match term {
+            "nouser" => Ok(Matcher::NoUser),
+            "nogroup" => Ok(Matcher::NoGroup),
+            "broken" => Ok(Matcher::BrokenLink),
+            "sparse" => Ok(Matcher::Sparse),
+            "text" => Ok(Matcher::Text),
+            "binary" => Ok(Matcher::Binary),
+            "readable" => Ok(Matcher::Readable),
+            "writable" => Ok(Matcher::Writable),
+            "mine" => Ok(Matcher::Uid(users::effective_uid())),
+            _ => Err(
+                ParseError::new(term, 0..term.len(), "expected a key:value predicate")
+                    .example("name:main.rs, ext:rs or size:+1k"),
+            ),
+        };
+    };
+
+    // Errors in the value point into the term, past the key and the colon.
+    let offset = key.len() + 1;
+    let within = |error: ParseError| error.within(term, offset);
+    let matcher = match key {
+        "name" => Matcher::Name(value.to_owned()),
+        "ext" => Matcher::Extension(vec![value.to_owned()]),
+        "path" => Matcher::Path(value.to_owned()),
+        "fuzzy" => Matcher::Fuzzy(value.to_owned()),
+        "regex" => Matcher::Regex(parse::regex(value).map_err(within)?),
+        "path-name" => Matcher::PathName(value.to_owned()),
+        "path-fuzzy" => Matcher::PathFuzzy(value.to_owned()),
+        "path-regex" => Matcher::PathRegex(parse::regex(value).map_err(within)?),
+        "glob" | "path-glob" => {
+            let glob = parse::glob(value).map_err(within)?;
+            let globs = Globs::new(vec![glob]).map_err(|error| {
+                ParseError::new(term, 0..term.len(), error.to_string())
+            })?;
+            match key {
+                "glob" => Matcher::Glob(globs),
+                _ => Matcher::PathGlob(globs),
+            }
+        }
+        "type" => Matcher::Type(FileType::from_str(value, true).map_err(|_| {
+            ParseError::new(value, 0..value.len(), "unknown file type")
+                .example("dir, file, link, exec or special")
+                .within(term, offset)
+        })?),
+        "size" => Matcher::Size(parse::size_filter(value).map_err(within)?),
+        "allocated-size" => Matcher::AllocatedSize(parse::size_filter(value).map_err(within)?),
+        "perm" => Matcher::Perm(parse::perm_filter(value).map_err(within)?),
+        "executable-by" => Matcher::ExecutableBy(ExecutableBy::from_str(value, true).map_err(|_| {
+            ParseError::new(value, 0..value.len(), "unknown executor")
+                .example("me, owner or any")
+                .within(term, offset)
+        })?),
+        "nlink" => Matcher::Nlink(parse::number_filter(value).map_err(within)?),
+        "entries" => Matcher::Entries(parse::number_filter(value).map_err(within)?),
+        "inode" => Matcher::Inode(value.parse().map_err(|_| {
+            ParseError::new(value, 0..value.len().max(1), "expected an inode number")
+                .within(term, offset)
+        })?),
+        "depth" => Matcher::Depth(value.parse().map_err(|_| {
+            ParseError::new(value, 0..value.len().max(1), "expected a depth")
+                .within(term, offset)
+        })?),
+        "components" => Matcher::Components(parse::number_filter(value).map_err(within)?),
+        "below" => {
+            let (directory, levels) = parse::below(value).map_err(within)?;
+            Matcher::Below(directory, levels)
+        }
+        "same-file" => Matcher::SameFile(parse::same_file(Path::new(value)).map_err(within)?),
+        "modified-on" => Matcher::Modified(parse::day(value).map_err(within)?),
+        "modified-month" => Matcher::Modified(parse::month(value).map_err(within)?),
+        "mime" => Matcher::Mime(parse::mime(value).map_err(within)?),
+        "magic" => Matcher::Magic(parse::magic(value).map_err(within)?),
+        "lines" => Matcher::Lines(parse::number_filter(value).map_err(within)?),
+        "max-line-length" => Matcher::LineLength(parse::number_filter(value).map_err(within)?),
+        "encoding" => Matcher::Encoding(Encoding::from_str(value, true).map_err(|_| {
+            ParseError::new(value, 0..value.len().max(1), "unknown encoding")
+                .example("utf8 or ascii")
+                .within(term, offset)
+        })?),
+        "script" => Matcher::Script(Arc::new(Script::load(Path::new(value)).map_err(|error| {
+            ParseError::new(value, 0..value.len().max(1), format!("{:#}", error))
+                .within(term, offset)
+        })?)),
+        "has-xattr" => Matcher::HasXattr(value.to_owned()),
+        "xattr" => {
+            let (name, expected) = parse::xattr(value).map_err(within)?;
+            Matcher::Xattr(name, expected)
+        }
+        "links-to" => Matcher::LinksTo(parse::regex(value).map_err(within)?),
+        "owner" => Matcher::Uid(parse::user(value).map_err(within)?),
+        "group" => Matcher::Gid(parse::group(value).map_err(within)?),
+        "uid" => Matcher::Uid(id(value).map_err(within)?),
+        "gid" => Matcher::Gid(id(value).map_err(within)?),
+        _ => return Err(
+            ParseError::new(term, 0..key.len(), "unknown predicate").example(
+                "name, ext, path, fuzzy, regex, glob, path-name, path-fuzzy, path-regex, path-glob, type, size, allocated-size, perm, executable-by, nlink, entries, inode, components, below, same-file, modified-on, modified-month, mime, magic, encoding, lines, max-line-length, script, links-to, owner, group, uid or gid",
+            ),
+        ),
+    };
+
+    Ok(matcher)
+}
+
+fn id(value: &str) -> Result<u32, ParseError> {
+    value
+        .parse()
+        .map_err(|_| ParseError::new(value, 0..value.len().max(1), "expected a numeric id"))
+}
+
+/// Parses an expression such as `(ext:rs or ext:toml) and not path:target`.
+pub fn parse(expression: &str) -> Result<Matcher, ParseError> {
+    let mut parser = Parser {
+        expression,
+        tokens: tokenize(expression)?,
+        position: 0,
+    };
+
+    let matcher = parser.parse_or()?;
+    if let Some(token) = parser.peek() {
+        return Err(parser.error(parser.span(), format!("unexpected {}", token)));
+    }
+
+    Ok(matcher)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The shape of the tree `expression` parses to, its names and extensions as
+    /// leaves.
+    fn shape(expression: &str) -> String {
+        fn describe(matcher: &Matcher) -> String {
+            let list =
+                |matchers: &[Matcher]| matchers.iter().map(describe).collect::<Vec<_>>().join(", ");
+            match matcher {
+                Matcher::All(matchers) => format!("all({})", list(matchers)),
+                Matcher::Any(matchers) => format!("any({})", list(matchers)),
+                Matcher::Not(matcher) => format!("not({})", describe(matcher)),
+                Matcher::Name(name) => format!("name:{}", name),
+                Matcher::Extension(extensions) => format!("ext:{}", extensions.join(",")),
+                other => format!("{:?}", other),
+            }
+        }
+
+        describe(&parse(expression).unwrap())
+    }
+
+    /// Asserts that `expression` is rejected with `message`, pointing at `span` of it.
+    fn rejects(expression: &str, span: std::ops::Range<usize>, message: &str) {
+        let error = parse(expression).unwrap_err();
+        assert_eq!(
+            (error.span(), error.message()),
+            (span, message),
+            "{:?}",
+            expression
+        );
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(shape("name:a"), "name:a");
+        assert_eq!(
+            shape("name:a or name:b and name:c"),
+            "any(name:a, all(name:b, name:c))"
+        );
+        assert_eq!(
+            shape("name:a and name:b or name:c"),
+            "any(all(name:a, name:b), name:c)"
+        );
+        assert_eq!(
+            shape("(name:a or name:b) and name:c"),
+            "all(any(name:a, name:b), name:c)"
+        );
+        assert_eq!(shape("not name:a and name:b"), "all(not(name:a), name:b)");
+        assert_eq!(shape("not (name:a or name:b)"), "not(any(name:a, name:b))");
+        assert_eq!(shape("not not name:a"), "not(not(name:a))");
+        assert_eq!(shape("((name:a))"), "name:a");
+    }
+
+    #[test]
+    fn implicit_and() {
+        assert_eq!(
+            shape("name:a name:b or ext:c"),
+            "any(all(name:a, name:b), ext:c)"
+        );
+        assert_eq!(
+            shape("ext:rs (name:a or name:b)"),
+            "all(ext:rs, any(name:a, name:b))"
+        );
+        assert_eq!(shape("ext:rs ! name:a"), "all(ext:rs, not(name:a))");
+    }
+
+    #[test]
+    fn symbols_and_quotes() {
+        assert_eq!(
+            shape("name:a && name:b || ! name:c"),
+            "any(all(name:a, name:b), not(name:c))"
+        );
+        assert_eq!(shape("name:'a b'"), "name:a b");
+        assert_eq!(shape("name:\"or\""), "name:or");
+        assert_eq!(shape("(name:'x)')"), "name:x)");
+    }
+
+    #[test]
+    fn error_spans() {
+        rejects("", 0..1, "unexpected end of expression");
+        rejects("name:a or", 9..10, "unexpected end of expression");
+        rejects("not", 3..4, "unexpected end of expression");
+        rejects("(name:a", 0..1, "missing ')' for this '('");
+        rejects(
+            "ext:rs and (name:a or name:b",
+            11..12,
+            "missing ')' for this '('",
+        );
+        rejects("name:a)", 6..7, "unexpected ')'");
+        rejects("and name:a", 0..3, "unexpected 'and'");
+        rejects("name:a or or name:b", 10..12, "unexpected 'or'");
+        rejects("ext:rs name:'a", 7..14, "unterminated quote");
+        rejects("colour:red", 0..6, "unknown predicate");
+        rejects("ext:rs bogus", 7..12, "expected a key:value predicate");
+    }
+
+    #[test]
+    fn value_error_spans() {
+        rejects("size:4x", 6..7, "unknown size unit");
+        rejects("ext:rs and size:+4x", 18..19, "unknown size unit");
+        rejects("not type:nope", 9..13, "unknown file type");
+        rejects("(depth:x)", 7..8, "expected a depth");
+        rejects("below:tests:x", 12..13, "expected a number");
+
+        // Quotes move the value about, so the whole term is pointed at.
+        rejects("ext:rs size:'4x'", 7..16, "unknown size unit");
+    }
+}
@@ -0,0 +1,176 @@
+//! The two kinds of filters a [`Walk`](crate::Walk) applies: [`DirFilter`]s decide once
+//! per directory whether to walk into it, [`EntryFilter`]s decide which entries are
+//! yielded.
+
+use crate::{Entry, Matcher};
+use anyhow::{Context, Result};
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+/// What a [`DirFilter`] decides for a directory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Descend {
+    /// Walk into it
+    Yes,
+    /// Still yield the directory itself, but nothing below it
+    No,
+    /// Leave out the directory along with everything below it
+    Skip,
+}
+
+/// Decides for a directory found by a walk whether its contents are walked at all,
+/// which saves looking at every entry below it one by one.
+pub trait DirFilter: Send + Sync {
+    fn descend(&self, directory: &Entry) -> Result<Descend>;
+}
+
+/// Decides whether an entry found by a walk is yielded.
+pub trait EntryFilter: Send + Sync {
+    fn accepts(&self, entry: &Entry) -> bool;
+}
+
+/// Keeps a walk in the git repository its root is in: a directory below the root with
+/// a `.git` of its own, that of a nested repository or a submodule, is still yielded but
+/// not walked into.
+pub struct OneRepository;
+
+impl DirFilter for OneRepository {
+    fn descend(&self, directory: &Entry) -> Result<Descend> {
+        let nested = directory.depth > 0 && directory.path.join(".git").symlink_metadata().is_ok();
+        Ok(match nested {
+            true => Descend::No,
+            false => Descend::Yes,
+        })
+    }
+}
+
+/// The extensions of the directories macOS treats as single files.
+const BUNDLES: [&str; 5] = ["app", "framework", "bundle", "plugin", "kext"];
+
+/// Keeps a walk out of macOS bundles, applications, frameworks and plug-ins, which are
+/// still yielded as the single items Finder shows them as.
+pub struct Bundles;
+
+impl DirFilter for Bundles {
+    fn descend(&self, directory: &Entry) -> Result<Descend> {
+        let bundle = Path::new(&directory.name)
+            .extension()
+            .is_some_and(|extension| BUNDLES.iter().any(|bundle| extension == *bundle));
+        Ok(match bundle && directory.depth > 0 {
+            true => Descend::No,
+            false => Descend::Yes,
+        })
+    }
+}
+
+/// What a CACHEDIR.TAG starts with, from https://bford.info/cachedir/.
+const CACHEDIR_SIGNATURE: &[u8] = b"Signature: 8a477f597d28d172789f06886806bc55";
+
+/// Keeps a walk out of caches, the directories tagged by a CACHEDIR.TAG as cargo,
+/// ccache and others tag theirs, which are still yielded.
+pub struct CacheDirectories;
+
+impl DirFilter for CacheDirectories {
+    fn descend(&self, directory: &Entry) -> Result<Descend> {
+        use std::io::Read;
+
+        let tag = directory.path.join("CACHEDIR.TAG");
+        let mut signature = [0; CACHEDIR_SIGNATURE.len()];
+        let tagged = crate::content::open(tag)
+            .and_then(|mut tag| tag.read_exact(&mut signature))
+            .is_ok_and(|()| signature == CACHEDIR_SIGNATURE);
+        Ok(match tagged {
+            true => Descend::No,
+            false => Descend::Yes,
+        })
+    }
+}
+
+/// Keeps a walk out of the directories with any of some marker files in them, such as
+/// a `.nobackup`, which are still yielded.
+pub struct Marked {
+    markers: Vec<String>,
+}
+
+impl Marked {
+    pub fn new(markers: Vec<String>) -> Self {
+        Self { markers }
+    }
+}
+
+impl DirFilter for Marked {
+    fn descend(&self, directory: &Entry) -> Result<Descend> {
+        let directory = directory.path.as_path();
+        let marked = self
+            .markers
+            .iter()
+            .any(|marker| directory.join(marker).symlink_metadata().is_ok());
+        Ok(match marked {
+            true => Descend::No,
+            false => Descend::Yes,
+        })
+    }
+}
+
+/// Keeps a walk out of the directories `predicate` is true of, which are still yielded,
+/// as [`WalkBuilder::prune_if`](crate::WalkBuilder::prune_if) adds it.
+pub struct PruneIf<F>(pub F);
+
+impl<F: Fn(&Entry) -> bool + Send + Sync> DirFilter for PruneIf<F> {
+    fn descend(&self, directory: &Entry) -> Result<Descend> {
+        Ok(match (self.0)(directory) {
+            true => Descend::No,
+            false => Descend::Yes,
+        })
+    }
+}
+
+impl<F: Fn(&Entry) -> bool + Send + Sync> EntryFilter for F {
+    fn accepts(&self, entry: &Entry) -> bool {
+        self(entry)
+    }
+}
+
+impl EntryFilter for Matcher {
+    fn accepts(&self, entry: &Entry) -> bool {
+        self.matches(entry)
+    }
+}
+
+/// A matcher rules out the contents of a directory when no path below it can match,
+/// e.g. `not path:target` for a directory named `target`.
+impl DirFilter for Matcher {
+    fn descend(&self, directory: &Entry) -> Result<Descend> {
+        let path = directory.path.to_string_lossy();
+        let below = format!("{}/", path.trim_end_matches('/'));
+        Ok(match self.matches_below(&below) {
+            Some(false) => Descend::No,
+            _ => Descend::Yes,
+        })
+    }
+}
+
+/// Keeps a walk on the file system its root is on: directories other file systems are
+/// mounted on are still yielded, but not walked into. Like `find -xdev`.
+pub struct OneFileSystem {
+    device: u64,
+}
+
+impl OneFileSystem {
+    pub fn new<T: AsRef<Path>>(root: T) -> Result<Self> {
+        let root = root.as_ref();
+        let metadata = std::fs::metadata(root).with_context(|| root.display().to_string())?;
+        Ok(Self {
+            device: metadata.dev(),
+        })
+    }
+}
+
+impl DirFilter for OneFileSystem {
+    fn descend(&self, directory: &Entry) -> Result<Descend> {
+        Ok(match directory.metadata()?.dev() == self.device {
+            true => Descend::Yes,
+            false => Descend::No,
+        })
+    }
+}
@@ -0,0 +1,149 @@
+use crate::state::{self, StateLock};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const HOUR: u64 = 60 * 60;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+
+const SCHEMA_KIND: &str = "frecency";
+const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy)]
+pub struct FrecencyEntry {
+    pub count: u64,
+    pub last_used: u64,
+}
+
+impl FrecencyEntry {
+    /// Weights the number of times a path was picked by how recently that happened.
+    pub fn score(&self, now: u64) -> f64 {
+        let weight = match now.saturating_sub(self.last_used) {
+            age if age < HOUR => 4.0,
+            age if age < DAY => 2.0,
+            age if age < WEEK => 0.5,
+            _ => 0.25,
+        };
+
+        self.count as f64 * weight
+    }
+}
+
+#[derive(Default)]
+pub struct FrecencyStore {
+    entries: HashMap<PathBuf, FrecencyEntry>,
+}
+
+impl FrecencyStore {
+    pub fn location() -> Option<PathBuf> {
+        let data_home = std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| {
+                std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/share"))
+            })?;
+
+        Some(data_home.join("quaero").join("frecency"))
+    }
+
+    pub fn load() -> Result<Self> {
+        let Some(location) = Self::location() else {
+            return Ok(Self::default());
+        };
+
+        if !location.exists() {
+            return Ok(Self::default());
+        }
+
+        let contents = std::fs::read_to_string(&location)
+            .with_context(|| format!("failed to read {}", location.display()))?;
+
+        let (version, body) = state::parse_schema(&location, SCHEMA_KIND, &contents)?;
+        if version > SCHEMA_VERSION {
+            return Err(anyhow::anyhow!(
+                "{} was written by a newer quaero (schema v{}, supported up to v{})",
+                location.display(),
+                version,
+                SCHEMA_VERSION
+            ));
+        }
+
+        let mut entries = HashMap::new();
+        for line in body.lines() {
+            let mut fields = line.splitn(3, '\t');
+            let (Some(count), Some(last_used), Some(path)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+
+            let (Ok(count), Ok(last_used)) = (count.parse(), last_used.parse()) else {
+                continue;
+            };
+
+            entries.insert(PathBuf::from(path), FrecencyEntry { count, last_used });
+        }
+
+        Ok(Self { entries })
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let location = Self::location()
+            .ok_or_else(|| anyhow::anyhow!("could not determine where to store frecency data"))?;
+
+        if let Some(parent) = location.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let mut contents = state::schema_header(SCHEMA_KIND, SCHEMA_VERSION);
+        contents.push('\n');
+        for (path, entry) in &self.entries {
+            contents.push_str(&format!(
+                "{}\t{}\t{}\n",
+                entry.count,
+                entry.last_used,
+                path.display()
+            ));
+        }
+
+        state::write_atomically(&location, contents.as_bytes())
+    }
+
+    /// Loads, modifies and saves the store while holding its lock, so concurrent
+    /// invocations can't lose each other's updates.
+    pub fn update(modify: impl FnOnce(&mut Self)) -> Result<()> {
+        let location = Self::location()
+            .ok_or_else(|| anyhow::anyhow!("could not determine where to store frecency data"))?;
+        let _lock = StateLock::acquire(&location)?;
+
+        let mut store = Self::load()?;
+        modify(&mut store);
+        store.save()
+    }
+
+    pub fn mark_used<T: AsRef<Path>>(&mut self, path: T) {
+        let entry = self
+            .entries
+            .entry(path.as_ref().to_path_buf())
+            .or_insert(FrecencyEntry {
+                count: 0,
+                last_used: 0,
+            });
+
+        entry.count += 1;
+        entry.last_used = now();
+    }
+
+    pub fn score<T: AsRef<Path>>(&self, path: T) -> f64 {
+        self.entries
+            .get(path.as_ref())
+            .map_or(0.0, |entry| entry.score(now()))
+    }
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs())
+}
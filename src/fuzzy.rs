@@ -0,0 +1,99 @@
+//! Subsequence matching with fzf-like scoring, so `mnrs` finds `src/main.rs`.
+
+const SCORE_MATCH: i64 = 16;
+const BONUS_CONSECUTIVE: i64 = 8;
+const BONUS_BOUNDARY: i64 = 8;
+/// Matching right after a `/` means matching the start of a path component.
+const BONUS_COMPONENT: i64 = 10;
+const PENALTY_GAP_START: i64 = 3;
+const PENALTY_GAP_EXTENSION: i64 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FuzzyMatch {
+    /// Higher is better
+    pub score: i64,
+    /// Char indices of `candidate` matching the pattern, in order
+    pub positions: Vec<usize>,
+}
+
+fn bonus(previous: Option<char>, current: char) -> i64 {
+    match previous {
+        None | Some('/') => BONUS_COMPONENT,
+        Some('_' | '-' | '.' | ' ') => BONUS_BOUNDARY,
+        Some(previous) if previous.is_lowercase() && current.is_uppercase() => BONUS_BOUNDARY,
+        _ => 0,
+    }
+}
+
+/// Matches `pattern` against `candidate` as a subsequence, case-insensitively unless
+/// the pattern has uppercase letters in it.
+///
+/// The first occurrence of the pattern is found going forward and then shrunk going
+/// backward from where it ends, which finds a tight match in linear time.
+pub fn matches(pattern: &str, candidate: &str) -> Option<FuzzyMatch> {
+    let case_sensitive = pattern.chars().any(char::is_uppercase);
+    let fold = |c: char| match case_sensitive {
+        true => c,
+        false => c.to_lowercase().next().unwrap_or(c),
+    };
+
+    let pattern = pattern.chars().map(fold).collect::<Vec<_>>();
+    let original = candidate.chars().collect::<Vec<_>>();
+    let folded = original.iter().copied().map(fold).collect::<Vec<_>>();
+    if pattern.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+
+    let mut next = 0;
+    let mut end = None;
+    for (index, &c) in folded.iter().enumerate() {
+        if c == pattern[next] {
+            next += 1;
+            if next == pattern.len() {
+                end = Some(index);
+                break;
+            }
+        }
+    }
+    let end = end?;
+
+    let mut start = end;
+    let mut remaining = pattern.len();
+    for index in (0..=end).rev() {
+        if folded[index] == pattern[remaining - 1] {
+            remaining -= 1;
+            if remaining == 0 {
+                start = index;
+                break;
+            }
+        }
+    }
+
+    let mut score = 0;
+    let mut positions = Vec::with_capacity(pattern.len());
+    let mut next = 0;
+    let mut in_gap = false;
+    for index in start..=end {
+        if next < pattern.len() && folded[index] == pattern[next] {
+            score += SCORE_MATCH;
+            score += bonus(index.checked_sub(1).map(|i| original[i]), original[index]);
+            if index > 0 && positions.last() == Some(&(index - 1)) {
+                score += BONUS_CONSECUTIVE;
+            }
+            positions.push(index);
+            next += 1;
+            in_gap = false;
+        } else {
+            score -= match in_gap {
+                false => PENALTY_GAP_START,
+                true => PENALTY_GAP_EXTENSION,
+            };
+            in_gap = true;
+        }
+    }
+
+    Some(FuzzyMatch { score, positions })
+}
@@ -0,0 +1,109 @@
+//! What git considers changed in a repository, for `--git-status`. git itself is asked,
+//! so that whatever it is configured to ignore or to treat as unchanged counts the same.
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use std::collections::HashSet;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Arc;
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GitStatus {
+    /// Changed in the working tree since they were last staged
+    Modified,
+    /// Not tracked, and not ignored either
+    Untracked,
+    /// Changed in the index since the last commit
+    Staged,
+}
+
+impl GitStatus {
+    /// Whether an entry of `git status --porcelain`, with index status `index` and
+    /// working tree status `worktree`, has this status.
+    fn of(self, index: u8, worktree: u8) -> bool {
+        match self {
+            GitStatus::Untracked => index == b'?',
+            GitStatus::Staged => !matches!(index, b' ' | b'?' | b'!'),
+            GitStatus::Modified => !matches!(worktree, b' ' | b'?' | b'!'),
+        }
+    }
+}
+
+/// The files of a repository with any of some statuses, by device and inode so that
+/// however a walk spells their paths they are recognized.
+#[derive(Debug, Clone)]
+pub struct GitFiles {
+    pub statuses: Vec<GitStatus>,
+    files: Arc<HashSet<(u64, u64)>>,
+}
+
+impl GitFiles {
+    pub fn contains(&self, device: u64, inode: u64) -> bool {
+        self.files.contains(&(device, inode))
+    }
+}
+
+fn git(directory: &Path, args: &[&str]) -> Result<Vec<u8>> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(directory)
+        .args(args)
+        .output()
+        .context("git")?;
+    if !output.status.success() {
+        let message = String::from_utf8_lossy(&output.stderr);
+        return Err(anyhow::anyhow!("git: {}", message.trim()));
+    }
+
+    Ok(output.stdout)
+}
+
+/// The root of the repository `directory` is in, relative to the current directory as
+/// long as `directory` is.
+pub fn root(directory: &Path) -> Result<PathBuf> {
+    let up = git(directory, &["rev-parse", "--show-cdup"])?;
+    let up = Path::new(std::ffi::OsStr::from_bytes(up.trim_ascii_end()));
+    Ok(directory.join(up).components().collect())
+}
+
+/// Asks git for the files of the repository `directory` is in that have any of
+/// `statuses`. Files git reports deleted aren't there to be found.
+pub fn files(directory: &Path, statuses: &[GitStatus]) -> Result<GitFiles> {
+    let top = git(directory, &["rev-parse", "--show-toplevel"])?;
+    let top = PathBuf::from(std::ffi::OsStr::from_bytes(top.trim_ascii_end()));
+
+    // Renames are reported as the new file being added and the old one deleted.
+    let status = git(
+        &top,
+        &[
+            "status",
+            "--porcelain=v1",
+            "-z",
+            "--untracked-files=all",
+            "--no-renames",
+        ],
+    )?;
+
+    let mut files = HashSet::new();
+    for line in status.split(|byte| *byte == 0) {
+        let [index, worktree, b' ', path @ ..] = line else {
+            continue;
+        };
+        if !statuses.iter().any(|status| status.of(*index, *worktree)) {
+            continue;
+        }
+
+        let path = top.join(std::ffi::OsStr::from_bytes(path));
+        if let Ok(metadata) = path.symlink_metadata() {
+            files.insert((metadata.dev(), metadata.ino()));
+        }
+    }
+
+    Ok(GitFiles {
+        statuses: statuses.to_vec(),
+        files: Arc::new(files),
+    })
+}
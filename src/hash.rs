@@ -0,0 +1,142 @@
+use crate::pipeline::{Match, Stage};
+use crate::report_error;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use md5::Md5;
+use quaero::{content, FileType};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::sync::mpsc::{Receiver, Sender};
+
+/// How many matches may wait for their hash before the walk waits for the oldest.
+const WINDOW: usize = 256;
+/// Files are read through a buffer this big, however large they are.
+const BUFFER_SIZE: usize = 64 * 1024;
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Sha256,
+    Blake3,
+    Md5,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn digest<D: Digest>(file: &mut File) -> Result<String> {
+    let mut hasher = D::new();
+    let mut buffer = vec![0; BUFFER_SIZE];
+    loop {
+        match file.read(&mut buffer)? {
+            0 => break,
+            read => hasher.update(&buffer[..read]),
+        }
+    }
+
+    Ok(hex(&hasher.finalize()))
+}
+
+/// The hash of the contents of the file at `path`, in lowercase hex.
+pub fn hash_file(path: &Path, algorithm: HashAlgorithm) -> Result<String> {
+    let mut file = content::open(path).with_context(|| path.display().to_string())?;
+    match algorithm {
+        HashAlgorithm::Sha256 => digest::<Sha256>(&mut file),
+        HashAlgorithm::Md5 => digest::<Md5>(&mut file),
+        HashAlgorithm::Blake3 => {
+            let mut hasher = blake3::Hasher::new();
+            hasher
+                .update_reader(&mut file)
+                .with_context(|| path.display().to_string())?;
+            Ok(hasher.finalize().to_hex().to_string())
+        }
+    }
+}
+
+/// `--hash`: hashes the contents of every regular file among the matches, several at
+/// a time, passing them on in the order they came in.
+pub struct Hashes {
+    algorithm: HashAlgorithm,
+    /// Matches waiting for their hash, oldest first, by sequence number
+    window: VecDeque<(u64, Match)>,
+    /// Hashes of the matches in the window that are done, `None` when there is none
+    done: HashMap<u64, Option<String>>,
+    sender: Sender<(u64, Result<String>)>,
+    receiver: Receiver<(u64, Result<String>)>,
+    next: u64,
+}
+
+impl Hashes {
+    pub fn new(algorithm: HashAlgorithm) -> Self {
+        let (sender, receiver) = std::sync::mpsc::channel();
+        Self {
+            algorithm,
+            window: VecDeque::new(),
+            done: HashMap::new(),
+            sender,
+            receiver,
+            next: 0,
+        }
+    }
+
+    fn finished(&mut self, sequence: u64, hash: Result<String>) {
+        let hash = hash.map_err(|error| report_error(&error)).ok();
+        self.done.insert(sequence, hash);
+    }
+
+    /// Takes the oldest match off the window once its hash is done, waiting for it if
+    /// `wait` asks to.
+    fn pop(&mut self, wait: bool) -> Option<Match> {
+        let (sequence, _) = self.window.front()?;
+        let sequence = *sequence;
+
+        while let Ok((done, hash)) = self.receiver.try_recv() {
+            self.finished(done, hash);
+        }
+        while wait && !self.done.contains_key(&sequence) {
+            let (done, hash) = self.receiver.recv().expect("the sender is kept alive");
+            self.finished(done, hash);
+        }
+
+        let hash = self.done.remove(&sequence)?;
+        let (_, mut item) = self.window.pop_front()?;
+        item.hash = hash;
+        Some(item)
+    }
+}
+
+impl Stage for Hashes {
+    fn process(&mut self, item: Match) -> Option<Match> {
+        let sequence = self.next;
+        self.next += 1;
+
+        match item.entry.file_type() {
+            FileType::RegularFile | FileType::Executable => {
+                let (path, algorithm) = (item.entry.path.clone(), self.algorithm);
+                let sender = self.sender.clone();
+                rayon::spawn(move || {
+                    let _ = sender.send((sequence, hash_file(&path, algorithm)));
+                });
+            }
+            _ => {
+                self.done.insert(sequence, None);
+            }
+        }
+        self.window.push_back((sequence, item));
+
+        let full = self.window.len() > WINDOW;
+        self.pop(full)
+    }
+
+    fn finish(&mut self) -> Result<Vec<Match>> {
+        let mut passed = Vec::new();
+        while let Some(item) = self.pop(true) {
+            passed.push(item);
+        }
+
+        Ok(passed)
+    }
+}
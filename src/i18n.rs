@@ -0,0 +1,163 @@
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource};
+use std::sync::OnceLock;
+use unic_langid::LanguageIdentifier;
+
+const FALLBACK_LANGUAGE: &str = "en";
+const CATALOGS: [(&str, &str); 2] = [
+    ("en", include_str!("../locales/en.ftl")),
+    ("it", include_str!("../locales/it.ftl")),
+];
+
+struct Catalog {
+    language: &'static str,
+    selected: FluentBundle<FluentResource>,
+    fallback: FluentBundle<FluentResource>,
+}
+
+static CATALOG: OnceLock<Catalog> = OnceLock::new();
+
+/// Formats a localized message, e.g. `tr!("lock-held", "path" => path, "pid" => pid)`.
+macro_rules! tr {
+    ($id:expr) => {
+        $crate::i18n::message($id, None)
+    };
+    ($id:expr, $($key:literal => $value:expr),+ $(,)?) => {{
+        let mut args = fluent_bundle::FluentArgs::new();
+        $(args.set($key, $value);)+
+        $crate::i18n::message($id, Some(&args))
+    }};
+}
+
+fn bundle(language: &'static str) -> FluentBundle<FluentResource> {
+    let source = CATALOGS
+        .iter()
+        .find(|(name, _)| *name == language)
+        .map_or("", |(_, source)| source);
+    let resource = FluentResource::try_new(source.to_owned())
+        .expect("bundled message catalogs are valid fluent");
+
+    let identifier: LanguageIdentifier = language.parse().expect("valid language identifier");
+    let mut bundle = FluentBundle::new_concurrent(vec![identifier]);
+    bundle.set_use_isolating(false);
+    bundle
+        .add_resource(resource)
+        .expect("bundled message catalogs have no duplicate messages");
+
+    bundle
+}
+
+/// Maps `it_IT.UTF-8`, `it-IT` or `it` to one of the bundled catalogs.
+fn supported_language(locale: &str) -> Option<&'static str> {
+    let language = locale
+        .split(['_', '-', '.', '@'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+
+    CATALOGS
+        .iter()
+        .map(|(name, _)| *name)
+        .find(|name| *name == language)
+}
+
+/// The language requested with `--lang`, falling back to the usual locale variables.
+///
+/// This runs before clap parses the command line, because the help text itself is
+/// localized.
+pub fn requested_language() -> Option<String> {
+    let mut args = std::env::args_os().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--lang" {
+            return args
+                .next()
+                .map(|language| language.to_string_lossy().into_owned());
+        }
+        if let Some(language) = arg.to_str().and_then(|arg| arg.strip_prefix("--lang=")) {
+            return Some(language.to_owned());
+        }
+    }
+
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|name| std::env::var(name).ok())
+        .find(|value| !value.is_empty())
+}
+
+pub fn init(language: Option<&str>) {
+    let language = language
+        .and_then(supported_language)
+        .unwrap_or(FALLBACK_LANGUAGE);
+
+    let _ = CATALOG.set(Catalog {
+        language,
+        selected: bundle(language),
+        fallback: bundle(FALLBACK_LANGUAGE),
+    });
+}
+
+fn catalog() -> &'static Catalog {
+    CATALOG.get_or_init(|| Catalog {
+        language: FALLBACK_LANGUAGE,
+        selected: bundle(FALLBACK_LANGUAGE),
+        fallback: bundle(FALLBACK_LANGUAGE),
+    })
+}
+
+fn format(
+    bundle: &FluentBundle<FluentResource>,
+    id: &str,
+    args: Option<&FluentArgs>,
+) -> Option<String> {
+    let pattern = bundle.get_message(id)?.value()?;
+    let mut errors = Vec::new();
+    Some(
+        bundle
+            .format_pattern(pattern, args, &mut errors)
+            .into_owned(),
+    )
+}
+
+pub fn message(id: &str, args: Option<&FluentArgs>) -> String {
+    let catalog = catalog();
+    format(&catalog.selected, id, args)
+        .or_else(|| format(&catalog.fallback, id, args))
+        .unwrap_or_else(|| id.to_owned())
+}
+
+/// Replaces the help text of every argument and subcommand that has a translation in
+/// the selected language. English help comes straight from the doc comments.
+pub fn localize_command(mut command: clap::Command) -> clap::Command {
+    let catalog = catalog();
+    if catalog.language == FALLBACK_LANGUAGE {
+        return command;
+    }
+
+    // Building first makes the generated --help and --version flags visible as well.
+    command.build();
+    let ids = command
+        .get_arguments()
+        .map(|arg| arg.get_id().to_string())
+        .collect::<Vec<_>>();
+    for id in ids {
+        if let Some(help) = format(&catalog.selected, &format!("help-{}", id), None) {
+            command = command.mut_arg(id, |arg| arg.help(help));
+        }
+    }
+
+    let subcommands = command
+        .get_subcommands()
+        .map(|subcommand| subcommand.get_name().to_owned())
+        .collect::<Vec<_>>();
+    for name in subcommands {
+        command = command.mut_subcommand(name.clone(), |subcommand| {
+            let subcommand = localize_command(subcommand);
+            match format(&catalog.selected, &format!("about-{}", name), None) {
+                Some(about) => subcommand.about(about),
+                None => subcommand,
+            }
+        });
+    }
+
+    command
+}
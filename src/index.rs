@@ -0,0 +1,486 @@
+use crate::frecency::now;
+use crate::state::{self, StateLock};
+use crate::{is_vanished, report_error, Root, Search};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use quaero::filter::CacheDirectories;
+use quaero::{Entry, FileType, IgnorePolicy, WalkBuilder};
+use std::collections::HashMap;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+
+const SCHEMA_KIND: &str = "index";
+const SCHEMA_VERSION: u32 = 1;
+
+/// Indexes older than this are pointed out when searched.
+const STALE_AFTER: u64 = 24 * 60 * 60;
+
+/// An indexed entry, or the indexed root itself when `path` is empty.
+struct Record {
+    /// Relative to the root of the index
+    path: PathBuf,
+    file_type: FileType,
+    /// Modification time in nanoseconds, which tells `update` whether a directory
+    /// gained or lost entries
+    mtime: i64,
+}
+
+impl Record {
+    fn new(path: PathBuf, file_type: FileType, metadata: &std::fs::Metadata) -> Self {
+        Self {
+            path,
+            file_type,
+            mtime: metadata.mtime() * 1_000_000_000 + metadata.mtime_nsec(),
+        }
+    }
+}
+
+/// Everything below a directory as of the last `quaero index build` or `update`, in
+/// the order a walk finds it, for `--use-index` to search instead of walking.
+///
+/// What goes in is decided when the index is built, by its own `-u` and `--avoid`.
+pub struct Index {
+    /// Canonical path of the indexed directory
+    root: PathBuf,
+    unrestricted: u8,
+    avoids: Vec<PathBuf>,
+    /// When the index was last brought up to date, in seconds since the epoch
+    updated: u64,
+    records: Vec<Record>,
+}
+
+/// Where indexes live, one file per indexed directory.
+fn directory() -> Option<PathBuf> {
+    let cache_home = std::env::var_os("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".cache")))?;
+
+    Some(cache_home.join("quaero").join("index"))
+}
+
+/// The index file for the canonical path `root`, named after an FNV-1a hash of it.
+fn location(root: &Path) -> Result<PathBuf> {
+    let directory =
+        directory().ok_or_else(|| anyhow::anyhow!("could not determine where to store indexes"))?;
+
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in root.as_os_str().as_encoded_bytes() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    Ok(directory.join(format!("{:016x}", hash)))
+}
+
+fn type_name(file_type: FileType) -> String {
+    file_type
+        .to_possible_value()
+        .map_or_else(String::new, |value| value.get_name().to_owned())
+}
+
+impl Index {
+    /// Walks `root` and indexes everything the walk finds, reporting errors on the way.
+    pub fn build(root: &Path, unrestricted: u8, avoids: Vec<PathBuf>) -> Result<Self> {
+        let root = std::fs::canonicalize(root).with_context(|| root.display().to_string())?;
+        let metadata = std::fs::metadata(&root)?;
+
+        let mut index = Self {
+            records: vec![Record::new(PathBuf::new(), FileType::Directory, &metadata)],
+            root,
+            unrestricted,
+            avoids,
+            updated: now(),
+        };
+        for entry in index.walk_builder(&index.root).build() {
+            match entry {
+                // The root has its record already.
+                Ok(entry) if entry.depth == 0 => {}
+                Ok(entry) => match index.record(&entry) {
+                    Ok(record) => index.records.push(record),
+                    Err(error) if is_vanished(&error) => {}
+                    Err(error) => report_error(&error),
+                },
+                Err(error) => report_error(&error),
+            }
+        }
+
+        Ok(index)
+    }
+
+    /// Indexes keep their entries sorted, for searches of the same tree to find them in
+    /// the same order whenever the index was built.
+    fn walk_builder(&self, root: &Path) -> WalkBuilder {
+        let builder = WalkBuilder::new(root)
+            .sorted(true)
+            .policy(IgnorePolicy::from_unrestricted(self.unrestricted))
+            .avoids(self.avoids.clone());
+        match self.unrestricted < 3 {
+            true => builder.dir_filter(CacheDirectories),
+            false => builder,
+        }
+    }
+
+    fn record(&self, entry: &Entry) -> Result<Record> {
+        let path = entry
+            .path
+            .strip_prefix(&self.root)
+            .expect("walked entries are below the root");
+        let metadata = entry
+            .metadata()
+            .with_context(|| entry.path.display().to_string())?;
+
+        Ok(Record::new(path.to_path_buf(), entry.file_type(), metadata))
+    }
+
+    fn load(location: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(location)
+            .with_context(|| format!("failed to read {}", location.display()))?;
+
+        let (version, body) = state::parse_schema(location, SCHEMA_KIND, &contents)?;
+        if version > SCHEMA_VERSION {
+            return Err(anyhow::anyhow!(
+                "{} was written by a newer quaero (schema v{}, supported up to v{})",
+                location.display(),
+                version,
+                SCHEMA_VERSION
+            ));
+        }
+
+        let malformed = || anyhow::anyhow!("{} is malformed", location.display());
+        let (header, entries) = body.split_once("\n\n").ok_or_else(malformed)?;
+
+        let (mut root, mut unrestricted, mut avoids, mut updated) = (None, 0, Vec::new(), 0);
+        for line in header.lines() {
+            let (key, value) = line.split_once('\t').ok_or_else(malformed)?;
+            match key {
+                "root" => root = Some(state::unescape_path(value)),
+                "unrestricted" => unrestricted = value.parse().map_err(|_| malformed())?,
+                "avoid" => avoids.push(state::unescape_path(value)),
+                "updated" => updated = value.parse().map_err(|_| malformed())?,
+                _ => {}
+            }
+        }
+
+        let mut records = Vec::new();
+        for line in entries.lines() {
+            let mut fields = line.splitn(3, '\t');
+            let (Some(file_type), Some(mtime), Some(path)) =
+                (fields.next(), fields.next(), fields.next())
+            else {
+                continue;
+            };
+
+            let (Ok(file_type), Ok(mtime)) = (FileType::from_str(file_type, false), mtime.parse())
+            else {
+                continue;
+            };
+
+            records.push(Record {
+                path: state::unescape_path(path),
+                file_type,
+                mtime,
+            });
+        }
+
+        Ok(Self {
+            root: root.ok_or_else(malformed)?,
+            unrestricted,
+            avoids,
+            updated,
+            records,
+        })
+    }
+
+    /// Saves the index while holding its lock.
+    pub fn save(&self) -> Result<()> {
+        let location = location(&self.root)?;
+        if let Some(parent) = location.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let _lock = StateLock::acquire(&location)?;
+
+        let mut contents = state::schema_header(SCHEMA_KIND, SCHEMA_VERSION);
+        contents.push('\n');
+        contents.push_str(&format!("root\t{}\n", state::escape_path(&self.root)));
+        contents.push_str(&format!("unrestricted\t{}\n", self.unrestricted));
+        for avoid in &self.avoids {
+            contents.push_str(&format!("avoid\t{}\n", state::escape_path(avoid)));
+        }
+        contents.push_str(&format!("updated\t{}\n\n", self.updated));
+
+        for record in &self.records {
+            contents.push_str(&format!(
+                "{}\t{}\t{}\n",
+                type_name(record.file_type),
+                record.mtime,
+                state::escape_path(&record.path)
+            ));
+        }
+
+        state::write_atomically(&location, contents.as_bytes())
+    }
+
+    /// The index of `path` or of the closest directory above it that has one.
+    fn covering(path: &Path) -> Result<Option<Self>> {
+        let path = std::fs::canonicalize(path).with_context(|| path.display().to_string())?;
+        for ancestor in path.ancestors() {
+            let location = location(ancestor)?;
+            if !location.exists() {
+                continue;
+            }
+
+            let index = Self::load(&location)?;
+            if index.root == ancestor {
+                return Ok(Some(index));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Every index there is, in no particular order.
+    pub fn all() -> Result<Vec<Self>> {
+        let Some(directory) = directory() else {
+            return Ok(Vec::new());
+        };
+        if !directory.exists() {
+            return Ok(Vec::new());
+        }
+
+        let mut indexes = Vec::new();
+        for file in std::fs::read_dir(&directory)? {
+            let path = file?.path();
+            let is_lock_or_temporary = path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().contains('.'));
+            if !is_lock_or_temporary {
+                indexes.push(Self::load(&path)?);
+            }
+        }
+
+        Ok(indexes)
+    }
+
+    pub fn of(root: &Path) -> Result<Option<Self>> {
+        let root = std::fs::canonicalize(root).with_context(|| root.display().to_string())?;
+        let location = location(&root)?;
+        if !location.exists() {
+            return Ok(None);
+        }
+
+        Ok(Some(Self::load(&location)?).filter(|index| index.root == root))
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Number of entries below the root.
+    pub fn len(&self) -> usize {
+        self.records.len() - 1
+    }
+
+    /// Brings the index up to date by reading again only the directories whose
+    /// modification time changed, and whatever appeared below them. Returns how many
+    /// directories were read.
+    ///
+    /// Changes to the ignore files of directories that didn't change otherwise go
+    /// unnoticed until the index is built again.
+    pub fn update(&mut self) -> Result<usize> {
+        let mut children: HashMap<PathBuf, Vec<Record>> = HashMap::new();
+        let mut directories = HashMap::new();
+        for record in std::mem::take(&mut self.records) {
+            if record.file_type == FileType::Directory {
+                directories.insert(record.path.clone(), record.mtime);
+            }
+            if let Some(parent) = record.path.parent() {
+                children
+                    .entry(parent.to_path_buf())
+                    .or_default()
+                    .push(record);
+            }
+        }
+
+        let builder = self.walk_builder(&self.root);
+        let mut update = Update {
+            index: self,
+            builder,
+            children,
+            directories,
+            records: Vec::new(),
+            read: 0,
+        };
+        update.visit(PathBuf::new())?;
+
+        let Update { records, read, .. } = update;
+        self.records = records;
+        self.updated = now();
+        Ok(read)
+    }
+}
+
+struct Update<'a> {
+    index: &'a Index,
+    builder: WalkBuilder,
+    children: HashMap<PathBuf, Vec<Record>>,
+    /// Modification times of the directories in the index
+    directories: HashMap<PathBuf, i64>,
+    records: Vec<Record>,
+    read: usize,
+}
+
+impl Update<'_> {
+    /// Records the directory at `path` and everything below it. A directory that
+    /// vanished in the meantime is left out.
+    fn visit(&mut self, path: PathBuf) -> Result<()> {
+        let full = self.index.root.join(&path);
+        let metadata = match std::fs::symlink_metadata(&full) {
+            Ok(metadata) => metadata,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(error) => {
+                return Err(anyhow::Error::from(error).context(full.display().to_string()))
+            }
+        };
+        let record = Record::new(path.clone(), FileType::Directory, &metadata);
+        let unchanged = self.directories.get(&path) == Some(&record.mtime);
+        self.records.push(record);
+
+        let children = match unchanged {
+            true => self.children.remove(&path).unwrap_or_default(),
+            false => {
+                self.read += 1;
+                self.read_directory(&full)?
+            }
+        };
+
+        for child in children {
+            let is_directory = child.file_type == FileType::Directory;
+            let path = child.path.clone();
+            if is_directory {
+                self.visit(path)?;
+            } else {
+                self.records.push(child);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The children of `directory` that a walk of the index root would record.
+    fn read_directory(&mut self, directory: &Path) -> Result<Vec<Record>> {
+        let files = match std::fs::read_dir(directory) {
+            Ok(files) => files,
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(error) => {
+                return Err(anyhow::Error::from(error).context(directory.display().to_string()))
+            }
+        };
+
+        let mut children = Vec::new();
+        for file in files {
+            let admitted = file
+                .map_err(anyhow::Error::from)
+                .and_then(|file| self.builder.admit(file.path()))
+                .and_then(|entry| entry.map(|entry| self.index.record(&entry)).transpose());
+            match admitted {
+                Ok(Some(record)) => children.push(record),
+                Ok(None) => {}
+                Err(error) if is_vanished(&error) => {}
+                Err(error) => report_error(&error),
+            }
+        }
+        children.sort_by(|a, b| {
+            a.path
+                .as_os_str()
+                .as_bytes()
+                .cmp(b.path.as_os_str().as_bytes())
+        });
+
+        Ok(children)
+    }
+}
+
+/// `--use-index`: searches the roots through their indexes instead of walking them.
+/// Every indexed entry that could match is looked at on disk, and left out if it is
+/// gone by now.
+pub fn run(
+    search: &Search,
+    roots: &[Root],
+    on_match: &mut impl FnMut(Option<&str>, Entry) -> bool,
+) -> Result<()> {
+    let args = search.args;
+    let max_depth = args.depth.unwrap_or(usize::MAX);
+
+    for root in roots {
+        let index = Index::covering(&root.path)?.ok_or_else(|| {
+            anyhow::anyhow!(tr!("error-no-index", "path" => root.path.display().to_string()))
+        })?;
+
+        let age = now().saturating_sub(index.updated);
+        if age > STALE_AFTER {
+            eprintln!(
+                "{}",
+                tr!(
+                    "index-stale",
+                    "root" => index.root.display().to_string(),
+                    "hours" => age / (60 * 60),
+                )
+            );
+        }
+
+        let below = std::fs::canonicalize(&root.path)?;
+        let mut avoids = Vec::new();
+        for avoid in args.avoids.iter().flatten().chain(&root.excludes) {
+            avoids.push(std::fs::canonicalize(avoid)?);
+        }
+
+        for record in &index.records {
+            if search.interrupt.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+
+            let full = index.root.join(&record.path);
+            // The root is at depth 0, as when walking.
+            let Ok(relative) = full.strip_prefix(&below) else {
+                continue;
+            };
+            let depth = relative.components().count();
+            if depth > max_depth || avoids.iter().any(|avoid| full.starts_with(avoid)) {
+                continue;
+            }
+            search.stats.entries.fetch_add(1, Ordering::Relaxed);
+
+            let path = match relative.as_os_str().is_empty() {
+                true => root.path.clone(),
+                false => root.path.join(relative),
+            };
+            let name = path.file_name().unwrap_or_default();
+            let decided = search.matcher.matches_path(&path, name, record.file_type);
+            if decided == Some(false) {
+                continue;
+            }
+
+            let mut entry = match Entry::from_path(&path) {
+                Ok(entry) => entry,
+                Err(error) if is_vanished(&error) => {
+                    search.stats.vanished.fetch_add(1, Ordering::Relaxed);
+                    continue;
+                }
+                Err(error) => {
+                    search.stats.errors.fetch_add(1, Ordering::Relaxed);
+                    report_error(&error.context(path.display().to_string()));
+                    continue;
+                }
+            };
+            entry.depth = depth;
+
+            if search.matcher.matches(&entry) && !on_match(root.label.as_deref(), entry) {
+                return Ok(());
+            }
+        }
+    }
+
+    Ok(())
+}
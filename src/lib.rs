@@ -0,0 +1,566 @@
+//! Traversal and matching behind the `quaero` command line tool.
+//!
+//! ```no_run
+//! use quaero::{Matcher, WalkBuilder};
+//!
+//! let walk = WalkBuilder::new("src")
+//!     .matcher(Matcher::Extension(vec!["rs".to_owned()]))
+//!     .build();
+//!
+//! for entry in walk {
+//!     println!("{}", entry?.path.display());
+//! }
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+//!
+//! Where a walk goes is up to the embedder as well, apart from what it matches:
+//!
+//! ```no_run
+//! use quaero::WalkBuilder;
+//!
+//! // Not into the directories that ask to be left out of builds.
+//! let walk = WalkBuilder::new(".")
+//!     .prune_if(|directory| directory.path.join(".skipbuild").exists())
+//!     .build();
+//! # drop(walk);
+//! ```
+
+pub mod archive;
+pub mod checkpoint;
+pub mod content;
+#[cfg(target_os = "linux")]
+mod dirents;
+pub mod expr;
+pub mod filter;
+pub mod fuzzy;
+pub mod git;
+pub mod magic;
+pub mod matcher;
+mod mode;
+pub mod mounts;
+pub mod number;
+pub mod parse;
+pub mod period;
+pub mod perm;
+pub mod policy;
+pub mod script;
+#[cfg(target_os = "linux")]
+mod uring;
+pub mod users;
+mod walk;
+pub mod xattr;
+
+use anyhow::Result;
+use clap::ValueEnum;
+use std::borrow::Cow;
+use std::ffi::{OsStr, OsString};
+use std::fs::{DirEntry, Metadata};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::os::unix::fs::{MetadataExt, PermissionsExt};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use std::time::SystemTime;
+
+pub use matcher::Matcher;
+pub use policy::IgnorePolicy;
+pub use walk::{Backend, Follow, Walk, WalkBuilder, WalkStats};
+
+/// Whether `error` only says that the path is already gone again, which is common for
+/// short-lived files and not worth reporting.
+pub fn is_vanished(error: &anyhow::Error) -> bool {
+    error
+        .downcast_ref::<std::io::Error>()
+        .is_some_and(|error| error.kind() == std::io::ErrorKind::NotFound)
+}
+
+/// Prints `message` on stderr if `QUAERO_DEBUG` is set, for what is otherwise passed
+/// over silently.
+pub fn debug(message: std::fmt::Arguments) {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    if *ENABLED
+        .get_or_init(|| std::env::var_os("QUAERO_DEBUG").is_some_and(|value| !value.is_empty()))
+    {
+        eprintln!("quaero: debug: {}", message);
+    }
+}
+
+/// Whether the file `name` ends with `extension` after a dot and a non-empty stem,
+/// ignoring case so that `jpg` matches `photo.JPG`.
+pub(crate) fn has_extension(name: &[u8], extension: &str) -> bool {
+    let Some(split) = name.len().checked_sub(extension.len()) else {
+        return false;
+    };
+
+    split > 1
+        && name[split..].eq_ignore_ascii_case(extension.as_bytes())
+        && name[..split].ends_with(b".")
+}
+
+/// Spells `path` the cleanest way, without looking at the file system: without `.`
+/// components, the leading `./` included, doubled or trailing slashes, and with every
+/// `..` taking out the component before it. What is left of nothing is `.`.
+///
+/// Only fit for printing, or for writing out for another program to read: a `..` after
+/// a symbolic link leads elsewhere than where the lexical path does.
+pub fn cleaned_path(path: &Path) -> Cow<'_, Path> {
+    let bytes = path.as_os_str().as_bytes();
+    let relative = bytes.strip_prefix(b"/");
+    let absolute = relative.is_some();
+    let relative = relative.unwrap_or(bytes);
+    if !relative.is_empty()
+        && relative
+            .split(|byte| *byte == b'/')
+            .all(|component| !matches!(component, b"" | b"." | b".."))
+    {
+        return Cow::Borrowed(path);
+    }
+
+    let mut components: Vec<&[u8]> = Vec::new();
+    for component in bytes.split(|byte| *byte == b'/') {
+        match component {
+            b"" | b"." => {}
+            b".." => match components.last() {
+                Some(&last) if last != b".." => {
+                    components.pop();
+                }
+                // There is nothing above the root.
+                _ if absolute => {}
+                _ => components.push(b".."),
+            },
+            component => components.push(component),
+        }
+    }
+
+    let joined = components.join(&b'/');
+    let cleaned = match (absolute, joined.is_empty()) {
+        (true, _) => [b"/".as_slice(), &joined].concat(),
+        (false, true) => b".".to_vec(),
+        (false, false) => joined,
+    };
+    Cow::Owned(PathBuf::from(OsString::from_vec(cleaned)))
+}
+
+/// [`cleaned_path`] as text, a name that isn't UTF-8 spelled with replacement
+/// characters.
+pub fn clean_path(path: &Path) -> Cow<'_, str> {
+    match cleaned_path(path) {
+        Cow::Borrowed(path) => path.to_string_lossy(),
+        Cow::Owned(path) => Cow::Owned(path.to_string_lossy().into_owned()),
+    }
+}
+
+/// Quotes `path` for a POSIX shell to read it back as it is, if it has anything the
+/// shell would take for something else: in single quotes, or in `$'...'` with escapes
+/// when there are control characters, which can't be typed in single quotes, or bytes
+/// that aren't UTF-8.
+pub fn shell_quote(path: &OsStr) -> Cow<'_, str> {
+    let Some(text) = path.to_str() else {
+        return Cow::Owned(escape_quote(path.as_bytes()));
+    };
+
+    let safe = |c: char| c.is_alphanumeric() || "/._-+,:@%=".contains(c);
+    if !text.is_empty() && text.chars().all(safe) {
+        return Cow::Borrowed(text);
+    }
+
+    if !text.chars().any(char::is_control) {
+        return Cow::Owned(format!("'{}'", text.replace('\'', r"'\''")));
+    }
+
+    Cow::Owned(escape_quote(path.as_bytes()))
+}
+
+/// `bytes` in `$'...'`, with escapes for what can't be typed as it is.
+fn escape_quote(bytes: &[u8]) -> String {
+    let mut quoted = String::with_capacity(bytes.len() + 3);
+    quoted.push_str("$'");
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '\n' => quoted.push_str(r"\n"),
+                '\t' => quoted.push_str(r"\t"),
+                '\r' => quoted.push_str(r"\r"),
+                '\'' | '\\' => {
+                    quoted.push('\\');
+                    quoted.push(c);
+                }
+                c if c.is_control() => {
+                    let mut bytes = [0; 4];
+                    for byte in c.encode_utf8(&mut bytes).bytes() {
+                        quoted.push_str(&format!("\\x{:02x}", byte));
+                    }
+                }
+                c => quoted.push(c),
+            }
+        }
+        for byte in chunk.invalid() {
+            quoted.push_str(&format!("\\x{:02x}", byte));
+        }
+    }
+    quoted.push('\'');
+    quoted
+}
+
+/// Splits a file name into its stem and its extension, the longest of the `compound`
+/// extensions such as `tar.gz` it ends with, or else the part after the last dot. Names
+/// with nothing before the dot, such as `.bashrc`, have no extension.
+pub fn split_extension<'a>(name: &'a str, compound: &[String]) -> (&'a str, Option<&'a str>) {
+    let compound = compound
+        .iter()
+        .filter(|extension| has_extension(name.as_bytes(), extension))
+        .max_by_key(|extension| extension.len());
+    if let Some(extension) = compound {
+        let split = name.len() - extension.len();
+        return (&name[..split - 1], Some(&name[split..]));
+    }
+
+    match name.rsplit_once('.') {
+        Some((stem, extension)) if !stem.is_empty() => (stem, Some(extension)),
+        _ => (name, None),
+    }
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FileType {
+    #[clap(name = "dir")]
+    Directory,
+
+    #[clap(name = "file")]
+    RegularFile,
+
+    #[clap(name = "link")]
+    SymLink,
+
+    #[clap(name = "exec")]
+    Executable,
+
+    /// Named pipes, sockets and devices
+    #[clap(name = "special")]
+    Special,
+}
+
+impl FileType {
+    /// Classifies an entry from its `lstat` metadata. Only regular files are
+    /// executables: links have every permission bit set whatever they point to.
+    pub fn from_metadata(metadata: &Metadata) -> Self {
+        let file_type = metadata.file_type();
+
+        let executable_mask = 0o111;
+        let is_executable = metadata.permissions().mode() & executable_mask != 0;
+
+        if file_type.is_dir() {
+            FileType::Directory
+        } else if file_type.is_symlink() {
+            FileType::SymLink
+        } else if file_type.is_file() && is_executable {
+            FileType::Executable
+        } else if file_type.is_file() {
+            FileType::RegularFile
+        } else {
+            FileType::Special
+        }
+    }
+}
+
+impl TryFrom<&DirEntry> for FileType {
+    type Error = anyhow::Error;
+
+    fn try_from(entry: &DirEntry) -> Result<Self> {
+        Ok(Self::from_metadata(&entry.metadata()?))
+    }
+}
+
+/// The type of an entry by what its directory lists it as, which tells regular files
+/// apart from directories, links and special files but not executables from the rest.
+fn listed_type(file_type: std::fs::FileType) -> FileType {
+    if file_type.is_dir() {
+        FileType::Directory
+    } else if file_type.is_symlink() {
+        FileType::SymLink
+    } else if file_type.is_file() {
+        FileType::RegularFile
+    } else {
+        FileType::Special
+    }
+}
+
+/// A single file system entry produced by a [`Walk`].
+///
+/// Its metadata is only looked up once something asks for it, and so is its type where
+/// its directory lists it as a regular file, for only the mode tells executables.
+#[derive(Clone)]
+pub struct Entry {
+    /// The type, a regular file being either kind until the metadata says which
+    listed: FileType,
+    /// The name as the file system has it, which need not be UTF-8
+    pub name: OsString,
+    pub path: PathBuf,
+    metadata: OnceLock<Metadata>,
+    /// How many directories below its root the entry is, its children being at depth 1
+    pub depth: usize,
+}
+
+impl TryFrom<DirEntry> for Entry {
+    type Error = anyhow::Error;
+
+    fn try_from(entry: DirEntry) -> Result<Self> {
+        // The type as the directory lists it, which takes no stat where it lists any.
+        let file_type = entry.file_type()?;
+
+        Ok(Self::listed(
+            entry.path(),
+            entry.file_name(),
+            listed_type(file_type),
+        ))
+    }
+}
+
+impl Entry {
+    /// The entry at `path` called `name`, of the type its directory lists it as, for
+    /// its metadata to be looked up when it is asked for.
+    pub fn listed(path: PathBuf, name: OsString, file_type: FileType) -> Self {
+        Self {
+            listed: file_type,
+            name,
+            path,
+            metadata: OnceLock::new(),
+            depth: 0,
+        }
+    }
+
+    /// The entry at `path` called `name`, of `file_type` and with `metadata` it is
+    /// known by already, e.g. as the member of an archive is by that of the archive.
+    pub fn with_metadata(
+        path: PathBuf,
+        name: OsString,
+        file_type: FileType,
+        metadata: Metadata,
+    ) -> Self {
+        Self {
+            listed: file_type,
+            name,
+            path,
+            metadata: OnceLock::from(metadata),
+            depth: 0,
+        }
+    }
+
+    /// Builds the entry for `path` without walking to it, e.g. for paths read from
+    /// another program. Symbolic links are not followed.
+    pub fn from_path<T: AsRef<Path>>(path: T) -> Result<Self> {
+        let path = path.as_ref();
+        let metadata = std::fs::symlink_metadata(path)?;
+
+        Ok(Self::with_metadata(
+            path.to_owned(),
+            path.file_name().unwrap_or_default().to_owned(),
+            FileType::from_metadata(&metadata),
+            metadata,
+        ))
+    }
+
+    /// The metadata of the entry, looked up with an `lstat` the first time it is asked
+    /// for, which fails if the entry is gone by then. That of a link followed is what it
+    /// leads to.
+    pub fn metadata(&self) -> std::io::Result<&Metadata> {
+        if let Some(metadata) = self.metadata.get() {
+            return Ok(metadata);
+        }
+
+        let metadata = std::fs::symlink_metadata(&self.path)?;
+        Ok(self.metadata.get_or_init(|| metadata))
+    }
+
+    /// The type of the entry. Telling an executable from another regular file looks up
+    /// the metadata; one that can't be looked up any more is taken for a regular file.
+    pub fn file_type(&self) -> FileType {
+        match self.listed {
+            FileType::RegularFile => self
+                .metadata()
+                .map_or(FileType::RegularFile, FileType::from_metadata),
+            file_type => file_type,
+        }
+    }
+
+    /// Whether the entry is a directory, known without its metadata.
+    pub fn is_dir(&self) -> bool {
+        self.listed == FileType::Directory
+    }
+
+    /// Whether the entry is a regular file, executable or not, known without its
+    /// metadata.
+    pub fn is_file(&self) -> bool {
+        matches!(self.listed, FileType::RegularFile | FileType::Executable)
+    }
+
+    /// Whether the entry is a symbolic link, one not followed, known without its
+    /// metadata.
+    pub fn is_symlink(&self) -> bool {
+        self.listed == FileType::SymLink
+    }
+
+    /// The entry looked at as what it links to, if it is a symbolic link that leads
+    /// anywhere. A broken link stays a link.
+    pub fn followed(mut self) -> Self {
+        if self.listed == FileType::SymLink {
+            if let Ok(metadata) = std::fs::metadata(&self.path) {
+                self.listed = FileType::from_metadata(&metadata);
+                self.metadata = OnceLock::from(metadata);
+            }
+        }
+        self
+    }
+
+    /// What the entry points to if it is a symbolic link, as the link says, relative or
+    /// not. Links that were followed are what they point to.
+    pub fn link_target(&self) -> Option<PathBuf> {
+        match self.listed {
+            FileType::SymLink => std::fs::read_link(&self.path).ok(),
+            _ => None,
+        }
+    }
+
+    /// The path of what the entry points to, with every link on the way resolved, if it
+    /// is a symbolic link that leads anywhere.
+    pub fn resolved(&self) -> Option<PathBuf> {
+        match self.listed {
+            FileType::SymLink => std::fs::canonicalize(&self.path).ok(),
+            _ => None,
+        }
+    }
+
+    /// The size of the entry in bytes, 0 for one gone by the time it is asked for.
+    pub fn size(&self) -> u64 {
+        self.metadata().map_or(0, Metadata::len)
+    }
+
+    /// When the entry was last modified, if it can still be told.
+    pub fn modified(&self) -> Option<SystemTime> {
+        self.metadata().ok()?.modified().ok()
+    }
+
+    /// How many bytes are allocated on disk for the entry, which the holes of a sparse
+    /// file leave out and the last block of any other rounds up. 0 for one gone.
+    pub fn allocated_size(&self) -> u64 {
+        // st_blocks is counted in units of 512 bytes whatever the block size.
+        self.metadata()
+            .map_or(0, |metadata| metadata.blocks() * 512)
+    }
+
+    /// Whether the entry is a file with less than half of its size allocated, as is a
+    /// disk image or a database with room left to grow. Files of less than a block are
+    /// left out, as file systems may keep them in the inode with none allocated.
+    pub fn is_sparse(&self) -> bool {
+        let size = self.size();
+        self.is_file() && size > 4096 && self.allocated_size() < size / 2
+    }
+
+    /// How many entries are directly in the entry, `.` and `..` left out, if it is a
+    /// directory that can be read.
+    pub fn entry_count(&self) -> Option<u64> {
+        if !self.is_dir() {
+            return None;
+        }
+        let entries = std::fs::read_dir(&self.path).ok()?;
+        Some(entries.count() as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cleaned(path: &str) -> String {
+        cleaned_path(Path::new(path)).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn cleaned_paths() {
+        assert!(matches!(cleaned_path(Path::new("a/b")), Cow::Borrowed(_)));
+        assert_eq!(cleaned("a/b"), "a/b");
+        assert_eq!(cleaned("./a"), "a");
+        assert_eq!(cleaned("a//b/"), "a/b");
+        assert_eq!(cleaned("a/./b/."), "a/b");
+        assert_eq!(cleaned("a/../b"), "b");
+        assert_eq!(cleaned("a/.."), ".");
+        assert_eq!(cleaned("."), ".");
+        assert_eq!(cleaned(""), ".");
+        assert_eq!(cleaned("../a"), "../a");
+        assert_eq!(cleaned("../../a/.."), "../..");
+        assert_eq!(cleaned("a/../../b"), "../b");
+    }
+
+    #[test]
+    fn cleaned_absolute_paths() {
+        assert_eq!(cleaned("/"), "/");
+        assert_eq!(cleaned("//a/b/"), "/a/b");
+        assert_eq!(cleaned("/.."), "/");
+        assert_eq!(cleaned("/a/../.."), "/");
+        assert_eq!(cleaned("/a/./b/../c"), "/a/c");
+    }
+
+    #[test]
+    fn cleaned_bytes() {
+        let path = |bytes: &[u8]| PathBuf::from(OsStr::from_bytes(bytes));
+        assert_eq!(cleaned_path(&path(b"a/\xff/../b")), path(b"a/b"));
+        assert_eq!(cleaned_path(&path(b"./a/\xff/")), path(b"a/\xff"));
+    }
+
+    fn quoted(text: &str) -> String {
+        shell_quote(OsStr::new(text)).into_owned()
+    }
+
+    #[test]
+    fn shell_quotes() {
+        assert!(matches!(shell_quote(OsStr::new("a/b")), Cow::Borrowed(_)));
+        assert_eq!(quoted("src/main.rs"), "src/main.rs");
+        assert_eq!(quoted("-x+y,z:@%=1"), "-x+y,z:@%=1");
+        assert_eq!(quoted("été.txt"), "été.txt");
+        assert_eq!(quoted(""), "''");
+        assert_eq!(quoted("a b"), "'a b'");
+        assert_eq!(quoted("$HOME"), "'$HOME'");
+        assert_eq!(quoted("*.rs"), "'*.rs'");
+        assert_eq!(quoted("~"), "'~'");
+        assert_eq!(quoted("it's"), r"'it'\''s'");
+    }
+
+    #[test]
+    fn shell_quote_escapes() {
+        assert_eq!(quoted("a\nb"), r"$'a\nb'");
+        assert_eq!(quoted("tab\t\r"), r"$'tab\t\r'");
+        assert_eq!(quoted("it's\n"), r"$'it\'s\n'");
+        assert_eq!(quoted("a\\b\n"), r"$'a\\b\n'");
+        assert_eq!(quoted("\x01"), r"$'\x01'");
+        assert_eq!(quoted("\u{85}"), r"$'\xc2\x85'");
+        assert_eq!(shell_quote(OsStr::from_bytes(b"x\xff y")), r"$'x\xff y'");
+    }
+
+    #[test]
+    fn split_extensions() {
+        let compound = ["tar.gz".to_owned()];
+        assert_eq!(split_extension("main.rs", &compound), ("main", Some("rs")));
+        assert_eq!(
+            split_extension("a.tar.gz", &compound),
+            ("a", Some("tar.gz"))
+        );
+        assert_eq!(
+            split_extension("a.TAR.GZ", &compound),
+            ("a", Some("TAR.GZ"))
+        );
+        assert_eq!(split_extension("a.tar.gz", &[]), ("a.tar", Some("gz")));
+        assert_eq!(split_extension("a.b.c", &compound), ("a.b", Some("c")));
+        assert_eq!(split_extension("x.", &compound), ("x", Some("")));
+        assert_eq!(split_extension("Makefile", &compound), ("Makefile", None));
+        assert_eq!(split_extension(".bashrc", &compound), (".bashrc", None));
+
+        // A compound extension needs a stem in front of it, as any other does.
+        assert_eq!(split_extension("tar.gz", &compound), ("tar", Some("gz")));
+        assert_eq!(split_extension(".tar.gz", &compound), (".tar", Some("gz")));
+
+        let compound = ["gz".to_owned(), "tar.gz".to_owned()];
+        assert_eq!(
+            split_extension("a.tar.gz", &compound),
+            ("a", Some("tar.gz"))
+        );
+    }
+}
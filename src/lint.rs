@@ -0,0 +1,132 @@
+use crate::{Root, Search};
+use quaero::Matcher;
+
+/// Characters with a meaning of their own in a regular expression.
+const REGEX_META: &[char] = &[
+    '\\', '.', '+', '*', '?', '(', ')', '|', '[', ']', '{', '}', '^', '$',
+];
+
+/// The matchers an entry has to pass, looking into conjunctions and disjunctions but
+/// not below a `not`, where a matcher that never matches turns into one that always
+/// does.
+fn required(matcher: &Matcher, found: &mut Vec<Matcher>) {
+    match matcher {
+        Matcher::All(matchers) | Matcher::Any(matchers) => {
+            for matcher in matchers {
+                required(matcher, found);
+            }
+        }
+        Matcher::Counted(matcher, _) => required(matcher, found),
+        Matcher::Adaptive(adaptive) => {
+            for matcher in adaptive.matchers() {
+                required(matcher, found);
+            }
+        }
+        Matcher::Not(_) => {}
+        matcher => found.push(matcher.clone()),
+    }
+}
+
+/// The literal start of a regular expression anchored with `^`, `None` if it isn't
+/// anchored or the anchor only applies to one of several alternatives.
+fn anchored_prefix(regex: &str) -> Option<&str> {
+    let rest = regex.strip_prefix('^')?;
+    if regex.contains('|') {
+        return None;
+    }
+
+    let end = rest.find(REGEX_META).unwrap_or(rest.len());
+    // A quantifier after the last literal character makes that one optional.
+    let end = match rest[end..].starts_with(['*', '?', '{']) {
+        true => rest[..end]
+            .char_indices()
+            .last()
+            .map_or(0, |(index, _)| index),
+        false => end,
+    };
+    Some(&rest[..end])
+}
+
+/// What every path found below `root` starts with.
+fn path_prefix(root: &Root) -> String {
+    format!("{}/", root.path.display().to_string().trim_end_matches('/'))
+}
+
+fn check_matcher(matcher: &Matcher, roots: &[Root], warnings: &mut Vec<String>) {
+    match matcher {
+        Matcher::Name(name) if name.contains('/') => {
+            warnings.push(tr!("lint-name-slash", "name" => name.as_str()));
+        }
+        Matcher::Fuzzy(pattern) if pattern.contains('/') => {
+            warnings.push(tr!("lint-fuzzy-slash", "pattern" => pattern.as_str()));
+        }
+        Matcher::Regex(regex) => check_regex(regex.as_str(), false, roots, warnings),
+        Matcher::PathRegex(regex) => check_regex(regex.as_str(), true, roots, warnings),
+        Matcher::Regexes(regexes) | Matcher::PathRegexes(regexes) => {
+            let full_path = matches!(matcher, Matcher::PathRegexes(_));
+            for pattern in regexes.patterns() {
+                check_regex(pattern, full_path, roots, warnings);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn check_regex(pattern: &str, full_path: bool, roots: &[Root], warnings: &mut Vec<String>) {
+    if pattern.ends_with("/$") || pattern.ends_with("/\\z") {
+        warnings.push(tr!("lint-regex-trailing-slash", "regex" => pattern));
+    }
+
+    // A regex matched against names has no root to start with.
+    let Some(literal) = anchored_prefix(pattern).filter(|_| full_path) else {
+        return;
+    };
+    for root in roots {
+        let prefix = path_prefix(root);
+        if !prefix.starts_with(literal) && !literal.starts_with(&prefix) {
+            warnings.push(tr!(
+                "lint-regex-anchored",
+                "regex" => pattern,
+                "prefix" => prefix,
+            ));
+        }
+    }
+}
+
+/// `--lint-query`: looks for parts of the search that make it find less than it
+/// seems to ask for, or nothing at all, returning a warning for each.
+pub fn lint(search: &Search, roots: &[Root]) -> Vec<String> {
+    let mut warnings = Vec::new();
+
+    if search.args.depth == Some(0) {
+        warnings.push(tr!("lint-depth-zero"));
+    }
+
+    let mut matchers = Vec::new();
+    required(&search.matcher, &mut matchers);
+    for matcher in &matchers {
+        check_matcher(matcher, roots, &mut warnings);
+    }
+
+    let canonical_roots = roots
+        .iter()
+        .filter_map(|root| std::fs::canonicalize(&root.path).ok())
+        .collect::<Vec<_>>();
+    for avoid in search.args.avoids.iter().flatten() {
+        let path = avoid.display().to_string();
+        match std::fs::canonicalize(avoid) {
+            Err(_) => warnings.push(tr!("lint-avoid-missing", "path" => path)),
+            Ok(avoid) if !canonical_roots.iter().any(|root| avoid.starts_with(root)) => {
+                warnings.push(tr!("lint-avoid-outside", "path" => path));
+            }
+            Ok(_) => {}
+        }
+    }
+
+    for root in roots.iter().filter(|root| !root.path.is_dir()) {
+        let path = root.path.display().to_string();
+        warnings.push(tr!("lint-root-missing", "path" => path));
+    }
+
+    warnings
+}
@@ -0,0 +1,134 @@
+//! Telling what a file holds from its first bytes, whatever it is named.
+
+use crate::content;
+use std::io::Read;
+use std::path::Path;
+
+/// How much of a file is read to sniff it, enough for the `ustar` marker of tar files.
+const HEADER_SIZE: usize = 512;
+
+/// A kind of file recognized by the bytes it starts with.
+#[derive(Debug, PartialEq, Eq)]
+pub struct Kind {
+    /// Short name, as given to `--magic`
+    pub name: &'static str,
+    pub mime: &'static str,
+    /// Bytes found at an offset into the file, all of them for the file to be this kind
+    signature: &'static [(usize, &'static [u8])],
+}
+
+const fn kind(
+    name: &'static str,
+    mime: &'static str,
+    signature: &'static [(usize, &'static [u8])],
+) -> Kind {
+    Kind {
+        name,
+        mime,
+        signature,
+    }
+}
+
+/// Every kind there is, the more specific signatures before the ones they start with.
+pub const KINDS: &[Kind] = &[
+    kind("png", "image/png", &[(0, b"\x89PNG\r\n\x1a\n")]),
+    kind("jpeg", "image/jpeg", &[(0, b"\xff\xd8\xff")]),
+    kind("gif", "image/gif", &[(0, b"GIF8")]),
+    kind("webp", "image/webp", &[(0, b"RIFF"), (8, b"WEBP")]),
+    kind("tiff", "image/tiff", &[(0, b"II*\0")]),
+    kind("tiff", "image/tiff", &[(0, b"MM\0*")]),
+    kind("ico", "image/x-icon", &[(0, b"\0\0\x01\0")]),
+    kind("bmp", "image/bmp", &[(0, b"BM")]),
+    kind("wav", "audio/wav", &[(0, b"RIFF"), (8, b"WAVE")]),
+    kind("avi", "video/x-msvideo", &[(0, b"RIFF"), (8, b"AVI ")]),
+    kind("mp3", "audio/mpeg", &[(0, b"ID3")]),
+    kind("flac", "audio/flac", &[(0, b"fLaC")]),
+    kind("ogg", "audio/ogg", &[(0, b"OggS")]),
+    kind("mp4", "video/mp4", &[(4, b"ftyp")]),
+    kind("mkv", "video/x-matroska", &[(0, b"\x1a\x45\xdf\xa3")]),
+    kind("pdf", "application/pdf", &[(0, b"%PDF-")]),
+    kind("zip", "application/zip", &[(0, b"PK\x03\x04")]),
+    kind("zip", "application/zip", &[(0, b"PK\x05\x06")]),
+    kind("gzip", "application/gzip", &[(0, b"\x1f\x8b")]),
+    kind("bzip2", "application/x-bzip2", &[(0, b"BZh")]),
+    kind("xz", "application/x-xz", &[(0, b"\xfd7zXZ\0")]),
+    kind("zstd", "application/zstd", &[(0, b"\x28\xb5\x2f\xfd")]),
+    kind(
+        "7z",
+        "application/x-7z-compressed",
+        &[(0, b"7z\xbc\xaf\x27\x1c")],
+    ),
+    kind("tar", "application/x-tar", &[(257, b"ustar")]),
+    kind(
+        "sqlite",
+        "application/vnd.sqlite3",
+        &[(0, b"SQLite format 3\0")],
+    ),
+    kind("wasm", "application/wasm", &[(0, b"\0asm")]),
+    kind("elf", "application/x-elf", &[(0, b"\x7fELF")]),
+    kind(
+        "macho",
+        "application/x-mach-binary",
+        &[(0, b"\xfe\xed\xfa\xce")],
+    ),
+    kind(
+        "macho",
+        "application/x-mach-binary",
+        &[(0, b"\xfe\xed\xfa\xcf")],
+    ),
+    kind(
+        "macho",
+        "application/x-mach-binary",
+        &[(0, b"\xce\xfa\xed\xfe")],
+    ),
+    kind(
+        "macho",
+        "application/x-mach-binary",
+        &[(0, b"\xcf\xfa\xed\xfe")],
+    ),
+    kind(
+        "pe",
+        "application/vnd.microsoft.portable-executable",
+        &[(0, b"MZ")],
+    ),
+    kind("script", "text/x-script", &[(0, b"#!")]),
+];
+
+/// Whether some kind is called `name`.
+pub fn is_known(name: &str) -> bool {
+    KINDS.iter().any(|kind| kind.name == name)
+}
+
+/// The kind of file `header`, the first bytes of a file, belongs to.
+pub fn identify(header: &[u8]) -> Option<&'static Kind> {
+    KINDS.iter().find(|kind| {
+        kind.signature.iter().all(|(offset, bytes)| {
+            header
+                .get(*offset..offset + bytes.len())
+                .is_some_and(|found| found == *bytes)
+        })
+    })
+}
+
+/// The kind of the file at `path`, `None` if it is of none or can't be read.
+pub fn sniff(path: &Path) -> Option<&'static Kind> {
+    let mut header = Vec::with_capacity(HEADER_SIZE);
+    content::open(path)
+        .ok()?
+        .take(HEADER_SIZE as u64)
+        .read_to_end(&mut header)
+        .ok()?;
+
+    identify(&header)
+}
+
+/// Whether `mime` is matched by `pattern`, either a whole MIME type such as
+/// `image/png` or all the types under one such as `image/*`.
+pub fn mime_matches(pattern: &str, mime: &str) -> bool {
+    match pattern.strip_suffix("/*") {
+        Some(top_level) => mime
+            .split_once('/')
+            .is_some_and(|(found, _)| top_level == "*" || found == top_level),
+        None => pattern == mime,
+    }
+}
@@ -1,214 +1,2805 @@
-use anyhow::Result;
-use clap::{Parser, ValueEnum};
-use regex::Regex;
-use std::ffi::OsStr;
-use std::fs::DirEntry;
-use std::os::unix::fs::PermissionsExt;
-use std::path::{Path, PathBuf};
+#[macro_use]
+mod i18n;
 
-#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq, Hash)]
-enum FileType {
-    #[clap(name = "dir")]
-    Directory,
+mod ages;
+mod audit;
+mod batch;
+mod bench;
+mod capabilities;
+mod change;
+mod clean;
+mod collisions;
+mod config;
+mod delete;
+mod diagnostics;
+mod du;
+mod dupes;
+mod frecency;
+mod hash;
+mod index;
+mod lint;
+mod output;
+mod picker;
+mod pipeline;
+mod progress;
+mod rename;
+mod replace;
+mod report;
+mod resume;
+#[cfg(feature = "self-update")]
+mod self_update;
+mod sort;
+mod state;
+mod template;
+mod touch;
+mod transfer;
+mod tree;
+mod watch;
 
-    #[clap(name = "file")]
-    RegularFile,
+use ages::AgeBuckets;
+use anyhow::{Context, Result};
+use capabilities::Capability;
+use clap::builder::{PathBufValueParser, TypedValueParser};
+use clap::{ArgAction, Args, CommandFactory, FromArgMatches, Parser, Subcommand};
+use config::Config;
+use diagnostics::ErrorFormat;
+use dupes::Keep;
+use frecency::FrecencyStore;
+use globset::Glob;
+use hash::{HashAlgorithm, Hashes};
+use ignore::gitignore::Gitignore;
+use index::Index;
+use output::{labeled, FanOut, OutputFormat, Printer, Totals};
+use picker::Picked;
+use pipeline::{
+    Absolute, Canonical, Dedup, FilterCommand, Match, PerDirectory, Physical, PipeThrough,
+    Pipeline, Rewrite, UniqueInodes,
+};
+use progress::Progress;
+use quaero::checkpoint::Checkpoint;
+use quaero::filter::{Bundles, CacheDirectories, Marked, OneFileSystem, OneRepository};
+use quaero::git::{self, GitStatus};
+use quaero::matcher::{FileId, Globs, MatchCounter};
+use quaero::number::NumberFilter;
+use quaero::parse::Patterns;
+use quaero::period::Period;
+use quaero::perm::{ExecutableBy, ModeChange, PermFilter};
+use quaero::script::Script;
+use quaero::users::{self, Owner};
+use quaero::{archive, content, expr, mounts, parse};
+use quaero::{
+    is_vanished, Backend, Entry, FileType, Follow, IgnorePolicy, Matcher, WalkBuilder, WalkStats,
+};
+use regex::{Regex, RegexSet};
+use report::{GroupBy, Groups, Report, Summary};
+use resume::Checkpointer;
+use sort::SortKey;
+use std::ffi::{OsStr, OsString};
+use std::io::{BufRead, IsTerminal, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Component, Path, PathBuf};
+use std::process::{ExitCode, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use template::Template;
+use transfer::Transfer;
 
-    #[clap(name = "link")]
-    SymLink,
+/// Any of the patterns of the files of --patterns-from or --exclude-from, matched
+/// against the name or with `full_path` the whole path.
+fn pattern_matcher(files: &[Patterns], full_path: bool) -> Result<Matcher> {
+    let globs = files.iter().flat_map(|patterns| patterns.globs.clone());
+    let regexes = files.iter().flat_map(|patterns| &patterns.regexes);
 
-    #[clap(name = "exec")]
-    Executable,
+    let mut any = Vec::new();
+    let globs = Globs::new(globs.collect())?;
+    if !globs.globs().is_empty() {
+        any.push(match full_path {
+            true => Matcher::PathGlob(globs),
+            false => Matcher::Glob(globs),
+        });
+    }
+    let regexes = RegexSet::new(regexes.map(Regex::as_str))?;
+    if !regexes.is_empty() {
+        any.push(match full_path {
+            true => Matcher::PathRegexes(regexes),
+            false => Matcher::Regexes(regexes),
+        });
+    }
+
+    Ok(match any.len() {
+        1 => any.remove(0),
+        _ => Matcher::Any(any),
+    })
+}
+
+/// Warns about the options `args` has that go without a capability this system lacks,
+/// and has files read without their access times changed for --no-atime if it can.
+fn check_capabilities(args: &Filters) {
+    // Without them nothing has any, and the search goes on to find nothing.
+    if args.has_xattr.is_some() {
+        Capability::Xattrs.check_for("--has-xattr");
+    } else if args.xattr.is_some() {
+        Capability::Xattrs.check_for("--xattr");
+    }
+    // Without it the walk goes on as with the fast backend.
+    if args.backend == Backend::IoUring {
+        Capability::IoUring.check_for("--backend io-uring");
+    }
+    if args.no_atime && Capability::NoAtime.check_for("--no-atime") {
+        content::preserve_atimes();
+    }
+}
+
+fn build_matcher(args: &Filters) -> Result<Matcher> {
+    let mut matchers = Vec::new();
+
+    if let Some(target) = &args.target {
+        matchers.push(match args.full_path {
+            true => Matcher::PathName(target.clone()),
+            false => Matcher::Name(target.clone()),
+        });
+    }
+    if let Some(file_type) = args.file_type {
+        matchers.push(Matcher::Type(file_type));
+    }
+    if let Some(extensions) = &args.extensions {
+        matchers.push(Matcher::Extension(extensions.clone()));
+    }
+    match args.regexes.as_slice() {
+        [] => {}
+        [regex] => matchers.push(match args.full_path {
+            true => Matcher::PathRegex(regex.clone()),
+            false => Matcher::Regex(regex.clone()),
+        }),
+        regexes => {
+            let regexes = RegexSet::new(regexes.iter().map(Regex::as_str))?;
+            matchers.push(match args.full_path {
+                true => Matcher::PathRegexes(regexes),
+                false => Matcher::Regexes(regexes),
+            });
+        }
+    }
+    if !args.globs.is_empty() {
+        let globs = Globs::new(args.globs.clone())?;
+        matchers.push(match args.full_path {
+            true => Matcher::PathGlob(globs),
+            false => Matcher::Glob(globs),
+        });
+    }
+    if !args.patterns_from.is_empty() {
+        matchers.push(pattern_matcher(&args.patterns_from, args.full_path)?);
+    }
+    if !args.exclude_from.is_empty() {
+        let excluded = pattern_matcher(&args.exclude_from, args.full_path)?;
+        matchers.push(Matcher::Not(Box::new(excluded)));
+    }
+    if let Some(size) = args.size {
+        matchers.push(Matcher::Size(size));
+    }
+    if let Some(size) = args.allocated_size {
+        matchers.push(Matcher::AllocatedSize(size));
+    }
+    if args.sparse {
+        matchers.push(Matcher::Sparse);
+    }
+    if let Some(perm) = args.perm {
+        matchers.push(Matcher::Perm(perm));
+    }
+    for (wanted, bits) in [
+        (args.setuid, 0o4000),
+        (args.setgid, 0o2000),
+        (args.sticky, 0o1000),
+    ] {
+        if wanted {
+            matchers.push(Matcher::Perm(PermFilter::all_of(bits)));
+        }
+    }
+    if args.world_writable {
+        matchers.push(audit::world_writable());
+    }
+    if let Some(by) = args.executable_by {
+        matchers.push(Matcher::ExecutableBy(by));
+    }
+    if args.readable {
+        matchers.push(Matcher::Readable);
+    }
+    if args.writable {
+        matchers.push(Matcher::Writable);
+    }
+    if let Some(nlink) = args.nlink {
+        matchers.push(Matcher::Nlink(nlink));
+    }
+    if let Some(entries) = args.entries {
+        matchers.push(Matcher::Entries(entries));
+    }
+    if let Some(inode) = args.inode {
+        matchers.push(Matcher::Inode(inode));
+    }
+    if let Some(depth) = args.exact_depth {
+        matchers.push(Matcher::Depth(depth));
+    }
+    if let Some(count) = args.path_components {
+        matchers.push(Matcher::Components(count));
+    }
+    if let Some((directory, levels)) = &args.below {
+        matchers.push(Matcher::Below(directory.clone(), *levels));
+    }
+    if let Some(file) = &args.same_file {
+        matchers.push(Matcher::SameFile(file.clone()));
+    }
+    if !args.git_status.is_empty() {
+        let directory = args.start_directory.as_deref().unwrap_or(Path::new("."));
+        matchers.push(Matcher::GitStatus(git::files(directory, &args.git_status)?));
+    }
+    for period in [args.modified_on, args.modified_month]
+        .into_iter()
+        .flatten()
+    {
+        matchers.push(Matcher::Modified(period));
+    }
+    if let Some(uid) = args.owner.or(args.uid) {
+        matchers.push(Matcher::Uid(uid));
+    }
+    if args.mine {
+        matchers.push(Matcher::Uid(users::effective_uid()));
+    }
+    if let Some(gid) = args.group.or(args.gid) {
+        matchers.push(Matcher::Gid(gid));
+    }
+    if args.nouser {
+        matchers.push(Matcher::NoUser);
+    }
+    if args.nogroup {
+        matchers.push(Matcher::NoGroup);
+    }
+    if let Some(mime) = &args.mime {
+        matchers.push(Matcher::Mime(mime.clone()));
+    }
+    if let Some(name) = &args.magic {
+        matchers.push(Matcher::Magic(name.clone()));
+    }
+    if args.text {
+        matchers.push(Matcher::Text);
+    }
+    if args.binary {
+        matchers.push(Matcher::Binary);
+    }
+    if let Some(encoding) = args.encoding {
+        matchers.push(Matcher::Encoding(encoding));
+    }
+    if let Some(lines) = args.lines {
+        matchers.push(Matcher::Lines(lines));
+    }
+    if let Some(length) = args.max_line_length {
+        matchers.push(Matcher::LineLength(length));
+    }
+    if args.broken {
+        matchers.push(Matcher::BrokenLink);
+    }
+    if let Some(regex) = &args.links_to {
+        matchers.push(Matcher::LinksTo(regex.clone()));
+    }
+    if let Some(name) = &args.has_xattr {
+        matchers.push(Matcher::HasXattr(name.clone()));
+    }
+    if let Some((name, value)) = &args.xattr {
+        matchers.push(Matcher::Xattr(name.clone(), value.clone()));
+    }
+    if let Some(path) = &args.filter_script {
+        matchers.push(Matcher::Script(Arc::new(Script::load(path)?)));
+    }
+    if let Some(pattern) = &args.fuzzy {
+        matchers.push(match args.full_path {
+            true => Matcher::PathFuzzy(pattern.clone()),
+            false => Matcher::Fuzzy(pattern.clone()),
+        });
+    }
+    if let Some(expression) = &args.expression {
+        matchers.push(match args.full_path {
+            true => expression.clone().full_path(),
+            false => expression.clone(),
+        });
+    }
+
+    Ok(Matcher::All(matchers))
+}
+
+/// The subcommands that search, taking the filters a search does and the options of
+/// QUAERO_OPTS after their name.
+const SEARCH_SUBCOMMANDS: &[&str] = &["dupes", "du", "clean", "audit", "collisions", "batch"];
+
+#[derive(Subcommand)]
+enum Command {
+    /// Record that a path was picked from the results, feeding the frecency store
+    MarkUsed {
+        /// Path that was picked
+        path: PathBuf,
+    },
+
+    /// Find the matched files with the same contents, printing each group of duplicates
+    Dupes {
+        #[command(flatten)]
+        filters: Box<Filters>,
+
+        /// Remove all but one file of every group, the one chosen by --keep
+        #[clap(name = "delete-dupes", long)]
+        delete_dupes: bool,
+
+        /// Which file of every group --delete-dupes keeps
+        #[clap(
+            name = "keep",
+            long,
+            value_enum,
+            default_value = "first",
+            requires = "delete-dupes"
+        )]
+        keep: Keep,
+
+        /// Only report what --delete-dupes would remove
+        #[clap(name = "dry-run", long, requires = "delete-dupes")]
+        dry_run: bool,
+    },
+
+    /// Print how many bytes the files below each matched directory add up to, and the
+    /// total of them all
+    Du {
+        #[command(flatten)]
+        filters: Box<Filters>,
+    },
+
+    /// Remove the matched files below a directory that are older than --older-than, but
+    /// for the --keep-latest newest of every directory, however old
+    Clean {
+        /// Directory to remove the old files of
+        #[clap(name = "directory", conflicts_with_all = ["from", "workspace", "repo"])]
+        directory: PathBuf,
+
+        /// Only remove the files last modified longer ago than this, a number followed
+        /// by s, m, h, d or w, e.g. 30d
+        #[clap(name = "older-than", long, value_parser = parse::age, required_unless_present = "keep-latest")]
+        older_than: Option<Duration>,
+
+        /// Glob the names of the files to remove match, such as '*.log'. Given more than
+        /// once, any of them has to match
+        #[clap(name = "pattern", long, value_parser = parse::glob)]
+        patterns: Vec<Glob>,
+
+        /// How many of the most recently modified matched files of every directory to
+        /// keep, whatever --older-than says
+        #[clap(name = "keep-latest", long, default_value_t = 0)]
+        keep_latest: usize,
+
+        /// Only report what would be removed
+        #[clap(name = "dry-run", long)]
+        dry_run: bool,
+
+        #[command(flatten)]
+        filters: Box<Filters>,
+    },
+
+    /// Look for what a security audit asks about: set-user-ID and set-group-ID files,
+    /// what anyone can write to and files nobody owns, printing every kind found apart
+    Audit {
+        #[command(flatten)]
+        filters: Box<Filters>,
+    },
+
+    /// Print the names of every directory that differ only in case or in their Unicode
+    /// normalization, which macOS and Windows file systems take for the same name
+    Collisions {
+        /// Directory to look in, the current one if not given
+        #[clap(name = "collisions-directory", value_name = "DIRECTORY", conflicts_with_all = ["from", "workspace", "repo"])]
+        directory: Option<PathBuf>,
+
+        #[command(flatten)]
+        filters: Box<Filters>,
+    },
+
+    /// Run the named queries of a file together, walking once for all of them
+    Batch {
+        /// TOML file with a [[query]] table for every query: its name, an expr in the
+        /// language of --expr and optionally an output file, a report and the buckets,
+        /// top and top-depth of the report
+        #[clap(name = "queries")]
+        queries: PathBuf,
+
+        #[command(flatten)]
+        filters: Box<Filters>,
+    },
+
+    /// Take back the renames of a --rename, last first, leaving those whose entry has
+    /// moved on since
+    UndoRename {
+        /// The log of the renames, as --rename reported it, the latest if left out
+        #[clap(name = "log")]
+        log: Option<PathBuf>,
+    },
+
+    /// Maintain the indexes that --use-index searches instead of walking
+    Index {
+        #[command(subcommand)]
+        command: IndexCommand,
+    },
+
+    /// Check the config file or write a starter one
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+
+    /// Print which of the features some options rely on the platform and this build
+    /// support, and why not where they don't
+    Capabilities,
+
+    /// Time walks of a directory with every backend and a few numbers of threads,
+    /// printing the fastest run of each with how many entries it went through a second
+    /// and the directories and stat calls that took
+    Bench {
+        /// Directory to walk, everything below it as with -uuu
+        #[clap(name = "bench-directory", value_name = "DIRECTORY")]
+        directory: PathBuf,
+
+        /// Numbers of threads to split the walk between, each walking the next
+        /// subdirectory of the directory. Powers of two up to one for every core if left
+        /// out
+        #[clap(
+            name = "bench-threads",
+            long = "threads",
+            value_name = "THREADS",
+            value_delimiter = ',',
+            value_parser = clap::value_parser!(u32).range(1..)
+        )]
+        threads: Vec<u32>,
+
+        /// How many times to walk with each backend and number of threads, of which the
+        /// fastest counts
+        #[clap(name = "runs", long, default_value_t = 3, value_parser = clap::value_parser!(u32).range(1..))]
+        runs: u32,
+
+        /// Time a search for the files with this extension rather than a walk of every
+        /// entry, where the fast backends rule the rest out without a stat
+        #[clap(name = "bench-extension", long = "extension", value_name = "EXTENSION")]
+        extension: Option<String>,
+    },
+
+    /// Print a script completing the options, subcommands and their values in a shell
+    Completions {
+        /// Shell to complete in
+        #[clap(name = "shell", value_enum)]
+        shell: clap_complete::Shell,
+    },
+
+    /// Print the man page, generated from the options and subcommands of this build
+    Man {
+        /// Write a page for quaero and one for every subcommand to this directory
+        /// instead, named quaero.1, quaero-dupes.1 and so on
+        #[clap(name = "out-dir", long)]
+        out_dir: Option<PathBuf>,
+    },
+
+    /// Replace this binary with the latest release from GitHub
+    #[cfg(feature = "self-update")]
+    SelfUpdate {
+        /// Only report whether a newer release is available
+        #[clap(long)]
+        check: bool,
+    },
+}
+
+impl Command {
+    /// The filters of a subcommand that searches, one of [`SEARCH_SUBCOMMANDS`].
+    fn filters(&mut self) -> Option<&mut Filters> {
+        match self {
+            Command::Dupes { filters, .. }
+            | Command::Du { filters }
+            | Command::Clean { filters, .. }
+            | Command::Audit { filters }
+            | Command::Collisions { filters, .. }
+            | Command::Batch { filters, .. } => Some(filters),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+enum IndexCommand {
+    /// Walk a directory and index everything below it, replacing any index it had
+    Build {
+        /// Directory to index
+        root: PathBuf,
+
+        /// Index more: -u ignores .gitignore/.ignore/.quaeroignore files, -uu also takes
+        /// hidden files and -uuu doesn't skip the default excludes (.git, .hg, .svn) or
+        /// walk past the caches tagged by a CACHEDIR.TAG either
+        #[clap(name = "unrestricted", long, short, action = ArgAction::Count)]
+        unrestricted: u8,
+
+        /// Directories to leave out of the index
+        #[clap(name = "avoid", long, short, num_args = 0.., value_delimiter = ' ')]
+        avoids: Vec<PathBuf>,
+    },
+
+    /// Bring indexes up to date, reading again only the directories that changed
+    Update {
+        /// Indexed directory to update, every index if left out
+        root: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+enum ConfigCommand {
+    /// Check a config file for settings that don't exist or don't make sense, printing
+    /// where each problem is
+    Check {
+        /// Config file to check, the one quaero reads if left out
+        file: Option<PathBuf>,
+    },
+
+    /// Write a starter config file, with every setting commented out
+    Init {
+        /// Overwrite the config file if there is one already
+        #[clap(long)]
+        force: bool,
+    },
+}
+
+// What to search and what to look for, shared by every command that searches. Not a
+// doc comment, which clap would take for the about text of the commands.
+#[derive(Args)]
+struct Filters {
+    /// Target to find
+    target: Option<String>,
+
+    /// Match the target, --regex, --glob, --fuzzy and the regex:, glob: and fuzzy:
+    /// predicates of --expr against the whole path instead of the name, a target matching the last
+    /// components of the path such as src/main.rs
+    #[clap(name = "full-path", long, short = 'p')]
+    full_path: bool,
+
+    /// Directory from where to start searching
+    #[clap(name = "from", long, short)]
+    start_directory: Option<PathBuf>,
+
+    /// Search every root of a workspace defined in the config file
+    #[clap(name = "workspace", long, short, conflicts_with = "from")]
+    workspace: Option<String>,
+
+    /// File type to look for
+    #[clap(name = "type", long, short, value_enum)]
+    file_type: Option<FileType>,
+
+    /// Directories to avoid
+    #[clap(name = "avoid", long, short, num_args = 0.., value_delimiter = ' ')]
+    avoids: Option<Vec<PathBuf>>,
+
+    /// Extension to look for, such as rs or tar.gz, in any case and with or without its
+    /// leading dot
+    #[clap(name = "extension", long, short, num_args = 0.., value_delimiter = ' ')]
+    extensions: Option<Vec<String>>,
+
+    /// How deep to walk: 0 for the starting directory alone, 1 for what is in it too, 2
+    /// for what is in those directories as well and so on. The starting directory is
+    /// always an entry of its own, at depth 0, for the filters to match like any other
+    #[clap(name = "depth", long, short, visible_alias = "max-depth")]
+    depth: Option<usize>,
+
+    /// Only match the entries exactly this deep, as --depth counts, walking no deeper
+    #[clap(name = "exact-depth", long)]
+    exact_depth: Option<usize>,
+
+    /// Paths of a number of components as they are printed, `.` and `..` left out:
+    /// exactly N, more than +N or less than -N, e.g. 3 for src/walk/mod.rs
+    #[clap(
+        name = "path-components",
+        long,
+        value_name = "N",
+        allow_hyphen_values = true
+    )]
+    path_components: Option<NumberFilter>,
+
+    /// Entries N levels below a directory of this name, its children being 1 below it:
+    /// exactly N, more than +N or less than -N, e.g. tests:2 for tests/unit/parse.rs
+    #[clap(name = "below", long, value_name = "NAME:N", allow_hyphen_values = true, value_parser = parse::below)]
+    below: Option<(String, NumberFilter)>,
+
+    /// Also skip what the ignore list at this path matches, in gitignore syntax whatever
+    /// -u says, its patterns anchored at the current directory. Can be given more than
+    /// once
+    #[clap(name = "ignore-file", long, conflicts_with = "use-index")]
+    ignore_files: Vec<PathBuf>,
+
+    /// Walk breadth-first, finding every match at one depth before any deeper down, e.g.
+    /// for the shallowest matches to come first with --interactive
+    #[clap(name = "bfs", long)]
+    bfs: bool,
+
+    /// How directories are read: through the standard library, or on Linux with large
+    /// getdents64 batches, which pays off for searches by name. io-uring, experimental,
+    /// also stats each directory's entries at once, for network and FUSE file systems
+    #[clap(name = "backend", long, value_enum, default_value_t = Backend::Std)]
+    backend: Backend,
+
+    /// Read files, for their contents, hashes and types, without updating their access
+    /// times, and on Linux list directories with the fast backends without updating
+    /// theirs. Searches by metadata alone never open files. Only for the files one owns,
+    /// others are read as usual
+    #[clap(name = "no-atime", long)]
+    no_atime: bool,
+
+    /// Never follow symbolic links: match them as links and don't walk into them. The
+    /// default
+    #[clap(name = "no-follow", long, short = 'P', overrides_with_all = ["follow-roots", "follow"])]
+    no_follow: bool,
+
+    /// Follow the symbolic links given as roots or read by --stdin, but none found by
+    /// walking
+    #[clap(name = "follow-roots", long, short = 'H', overrides_with_all = ["no-follow", "follow"])]
+    follow_roots: bool,
+
+    /// Follow every symbolic link: match it by the type, size and times of what it
+    /// leads to, and walk into it if that is a directory. Broken links stay links
+    #[clap(name = "follow", long, short = 'L', overrides_with_all = ["no-follow", "follow-roots"])]
+    follow: bool,
+
+    /// Don't walk into directories other file systems are mounted on, so that a search
+    /// of / stays off network and FUSE mounts
+    #[clap(name = "one-file-system", long, short = 'x')]
+    one_file_system: bool,
+
+    /// Pass over the mount points of network file systems, NFS, SMB, sshfs and the like,
+    /// without so much as looking at them, for a dead mount not to hang the search
+    #[clap(name = "skip-network-fs", long)]
+    skip_network_fs: bool,
+
+    /// Pass over the mount points of file systems of these types, comma-separated as
+    /// /proc/mounts names them, e.g. nfs,cifs,fuse.sshfs; fuse stands for every FUSE
+    /// file system
+    #[clap(
+        name = "exclude-fstype",
+        long,
+        value_name = "TYPES",
+        value_delimiter = ','
+    )]
+    exclude_fstypes: Vec<String>,
+
+    /// List macOS bundles, the directories named .app, .framework, .bundle, .plugin or
+    /// .kext, without walking into them, as the single files Finder shows them as
+    #[clap(name = "skip-bundles", long)]
+    skip_bundles: bool,
+
+    /// Don't walk into the directories with a file of this name in them, e.g.
+    /// .nobackup, but still list them. Given more than once, any of the names marks a
+    /// directory
+    #[clap(name = "skip-marked", long, value_name = "FILE")]
+    skip_marked: Vec<String>,
+
+    /// Search the whole git repository the current directory is in, from its root, but
+    /// not the repositories and submodules nested in it
+    #[clap(name = "repo", long, conflicts_with_all = ["from", "workspace"])]
+    repo: bool,
+
+    /// Walk into the repositories and submodules nested in the one of --repo as well
+    #[clap(name = "descend-into-repos", long, requires = "repo")]
+    descend_into_repos: bool,
+
+    /// Regular expression to match against the name, see --full-path. Given more than
+    /// once, any of them has to match
+    #[clap(name = "regex", long, short, value_parser = parse::regex)]
+    regexes: Vec<Regex>,
+
+    /// Glob to match against the name, such as '*.yml', see --full-path. Given more
+    /// than once, any of them has to match
+    #[clap(name = "glob", long, short = 'g', value_parser = parse::glob)]
+    globs: Vec<Glob>,
+
+    /// File of patterns to match against the name, one a line: globs, or regular
+    /// expressions after regex:, blank lines and # comments left out. Any of them has
+    /// to match, see --full-path. Given more than once, any of those of every file
+    #[clap(name = "patterns-from", long, value_name = "FILE", value_parser = parse::patterns)]
+    patterns_from: Vec<Patterns>,
+
+    /// File of patterns, as for --patterns-from, whose matches are left out along with
+    /// everything below the directories among them
+    #[clap(name = "exclude-from", long, value_name = "FILE", value_parser = parse::patterns)]
+    exclude_from: Vec<Patterns>,
+
+    /// Size in bytes, optionally with a k, M, G or T suffix: exactly N, more than +N or
+    /// less than -N
+    #[clap(name = "size", long, allow_hyphen_values = true, value_parser = parse::size_filter)]
+    size: Option<NumberFilter>,
+
+    /// Bytes allocated on disk, as --size: less than the size where a file has holes,
+    /// more where its last block isn't full
+    #[clap(name = "allocated-size", long, allow_hyphen_values = true, value_parser = parse::size_filter)]
+    allocated_size: Option<NumberFilter>,
+
+    /// Sparse files, those with less than half of their size allocated on disk, such
+    /// as disk images and databases
+    #[clap(name = "sparse", long)]
+    sparse: bool,
+
+    /// Permission bits to look for: exactly MODE, all of -MODE or any of /MODE, where
+    /// MODE is either octal (644) or symbolic (u+w,g-x)
+    #[clap(name = "perm", long, allow_hyphen_values = true)]
+    perm: Option<PermFilter>,
+
+    /// Entries with the set-user-ID bit, which run as their owner. Short for --perm -4000
+    #[clap(name = "setuid", long)]
+    setuid: bool,
+
+    /// Entries with the set-group-ID bit, which run as their group or have what is
+    /// created in them belong to it. Short for --perm -2000
+    #[clap(name = "setgid", long)]
+    setgid: bool,
+
+    /// Entries with the sticky bit, directories where only the owner of a file can
+    /// remove it. Short for --perm -1000
+    #[clap(name = "sticky", long)]
+    sticky: bool,
+
+    /// Entries anyone can write to, --perm -o+w but for symbolic links, whose bits mean
+    /// nothing
+    #[clap(name = "world-writable", long)]
+    world_writable: bool,
+
+    /// Only match executables that these can run: me, as access(2) decides for the
+    /// effective user, the owner of the file or any user at all
+    #[clap(name = "executable-by", long, value_enum)]
+    executable_by: Option<ExecutableBy>,
+
+    /// Entries the user running quaero can read, or what they link to, as access(2)
+    /// decides for the effective user and groups, ACLs and read-only mounts included
+    #[clap(name = "readable", long)]
+    readable: bool,
+
+    /// Entries the user running quaero can write to, or what they link to, as access(2)
+    /// decides, e.g. --writable -f /etc for the files one can actually edit there
+    #[clap(name = "writable", long)]
+    writable: bool,
+
+    /// Number of hard links: exactly N, more than +N or less than -N
+    #[clap(
+        name = "nlink",
+        long,
+        visible_alias = "links",
+        allow_hyphen_values = true
+    )]
+    nlink: Option<NumberFilter>,
+
+    /// Directories with a number of entries directly in them: exactly N, more than +N or
+    /// less than -N, e.g. --entries +10000 for those that slow everything listing them
+    #[clap(name = "entries", long, allow_hyphen_values = true)]
+    entries: Option<NumberFilter>,
+
+    /// Inode number of the entry, on whatever device
+    #[clap(name = "inode", long)]
+    inode: Option<u64>,
+
+    /// Hard links to the file at PATH, or to the file it points to: the entries with
+    /// its device and inode
+    #[clap(name = "same-file", long, value_name = "PATH", value_parser = PathBufValueParser::new().try_map(|path| parse::same_file(&path)))]
+    same_file: Option<FileId>,
+
+    /// Files git reports with this status in the repository the search starts in:
+    /// modified in the working tree, untracked or staged. Given more than once, any of
+    /// them
+    #[clap(name = "git-status", long, value_enum, conflicts_with = "workspace")]
+    git_status: Vec<GitStatus>,
+
+    /// Last modified on a day, given as YYYY-MM-DD, from midnight to midnight in the
+    /// local time zone
+    #[clap(name = "modified-on", long, value_parser = parse::day)]
+    modified_on: Option<Period>,
+
+    /// Last modified in a month, given as YYYY-MM, in the local time zone
+    #[clap(name = "modified-month", long, value_parser = parse::month)]
+    modified_month: Option<Period>,
+
+    /// Owner of the entry, by user name
+    #[clap(name = "owner", long, value_parser = parse::user)]
+    owner: Option<u32>,
+
+    /// Group of the entry, by group name
+    #[clap(name = "group", long, value_parser = parse::group)]
+    group: Option<u32>,
+
+    /// Owner of the entry, by numeric user id
+    #[clap(name = "uid", long, conflicts_with = "owner")]
+    uid: Option<u32>,
+
+    /// Entries owned by the user running quaero, its effective user id
+    #[clap(name = "mine", long, conflicts_with_all = ["owner", "uid"])]
+    mine: bool,
+
+    /// Group of the entry, by numeric group id
+    #[clap(name = "gid", long, conflicts_with = "group")]
+    gid: Option<u32>,
+
+    /// Entries whose owner doesn't exist in the users database
+    #[clap(name = "nouser", long)]
+    nouser: bool,
+
+    /// Entries whose group doesn't exist in the group database
+    #[clap(name = "nogroup", long)]
+    nogroup: bool,
+
+    /// Regular files whose contents, judging by their first bytes, are of a MIME type
+    /// such as 'image/png' or of any type under one such as 'image/*'
+    #[clap(name = "mime", long, value_parser = parse::mime)]
+    mime: Option<String>,
+
+    /// Regular files whose contents, judging by their first bytes, are of a kind such as
+    /// png, pdf, zip, tar or elf, however they are named
+    #[clap(name = "magic", long, value_parser = parse::magic)]
+    magic: Option<String>,
+
+    /// Regular files without a NUL byte in their first 8 KiB, as content searches take
+    /// for text
+    #[clap(name = "text", long, conflicts_with = "binary")]
+    text: bool,
+
+    /// Regular files with a NUL byte in their first 8 KiB
+    #[clap(name = "binary", long)]
+    binary: bool,
+
+    /// Regular files valid throughout in an encoding. --expr 'not encoding:utf8' finds
+    /// the sources that would break a tool expecting UTF-8
+    #[clap(name = "encoding", long, value_enum)]
+    encoding: Option<content::Encoding>,
+
+    /// Text files of N lines, more than +N or less than -N, e.g. +2000 for the sources
+    /// worth splitting up
+    #[clap(name = "lines", long, allow_hyphen_values = true)]
+    lines: Option<NumberFilter>,
+
+    /// Text files whose longest line is N characters long, longer with +N or shorter
+    /// with -N
+    #[clap(name = "max-line-length", long, allow_hyphen_values = true)]
+    max_line_length: Option<NumberFilter>,
+
+    /// Symbolic links whose target doesn't exist
+    #[clap(name = "broken", long)]
+    broken: bool,
+
+    /// Symbolic links whose target, with every link on the way resolved, matches a
+    /// regular expression
+    #[clap(name = "links-to", long, value_parser = parse::regex)]
+    links_to: Option<Regex>,
+
+    /// Entries with an extended attribute of this name, such as user.comment or
+    /// com.apple.quarantine. Those of a symbolic link are its own
+    #[clap(name = "has-xattr", long, value_name = "NAME")]
+    has_xattr: Option<String>,
+
+    /// Entries whose extended attribute NAME has exactly the value VALUE
+    #[clap(name = "xattr", long, value_name = "NAME=VALUE", value_parser = parse::xattr)]
+    xattr: Option<(String, String)>,
+
+    /// Entries a Lua script lets through: it returns a function, called with a table of
+    /// the path, name, type, size, mode, times and more of each entry, that returns
+    /// whether it matches
+    #[clap(name = "filter-script", long, value_name = "FILE")]
+    filter_script: Option<PathBuf>,
+
+    /// Characters that appear in order in the name, not necessarily next to each other,
+    /// e.g. 'mnrs' for main.rs. See --sort score and --full-path
+    #[clap(name = "fuzzy", long)]
+    fuzzy: Option<String>,
+
+    /// Match an expression of key:value predicates combined with and, or, not and
+    /// parentheses, e.g. '(ext:rs or ext:toml) and not path:target and size:+1k'
+    #[clap(name = "expr", long, value_parser = expr::parse)]
+    expression: Option<Matcher>,
+
+    /// Search more: -u ignores .gitignore/.ignore/.quaeroignore files, -uu also shows
+    /// hidden files and -uuu doesn't skip the default excludes (.git, .hg, .svn) or walk
+    /// past the caches tagged by a CACHEDIR.TAG either
+    #[clap(name = "unrestricted", long, short, action = ArgAction::Count)]
+    unrestricted: u8,
+
+    /// Before searching, warn about parts of the search that can't match anything or
+    /// match less than they seem to, such as a name with a slash in it
+    #[clap(name = "lint-query", long)]
+    lint_query: bool,
+
+    /// Look into .zip, .tar and .tar.gz files as well, matching the names of their
+    /// members and printing them as archive.zip!inner/path. Members only have what the
+    /// archive keeps of them, their other fields are the archive's
+    #[clap(name = "archives", long, conflicts_with = "use-index")]
+    archives: bool,
+
+    /// Print the same output for the same tree every time: directories are walked in
+    /// the byte order of their names, one root after the other, times are printed in
+    /// UTC and --format ndjson leaves out when the scan started and the absolute paths
+    /// links resolve to
+    #[clap(name = "deterministic", long)]
+    deterministic: bool,
+
+    /// Search the index of the starting directory, or of a directory above it, instead
+    /// of walking. What it holds follows the options of `quaero index build`
+    #[clap(name = "use-index", long, conflicts_with = "bfs")]
+    use_index: bool,
+
+    /// Match the paths read from stdin instead of walking, one per line or separated by
+    /// NUL bytes, e.g. from git ls-files or find -print0
+    #[clap(
+        name = "stdin",
+        long,
+        conflicts_with_all = ["from", "workspace", "use-index", "depth", "exact-depth", "bfs", "avoid", "ignore-file", "one-file-system", "skip-network-fs", "exclude-fstype", "unrestricted"]
+    )]
+    stdin: bool,
+
+    /// Match the files Spotlight finds for this query below the starting directory
+    /// instead of walking, e.g. 'kMDItemContentType == "public.jpeg"' or plain words,
+    /// for the other filters to narrow down. Only on macOS
+    #[clap(
+        name = "mdfind",
+        long,
+        value_name = "QUERY",
+        conflicts_with_all = ["stdin", "workspace", "use-index", "depth", "exact-depth", "bfs", "avoid", "ignore-file", "one-file-system", "skip-network-fs", "exclude-fstype", "unrestricted"]
+    )]
+    mdfind: Option<String>,
+
+    /// Match the files the locate database has for the target, or else the --glob
+    /// patterns or the extensions, below the starting directory instead of walking, for
+    /// the other filters to narrow down. What was made since updatedb last ran is missed
+    #[clap(
+        name = "from-locate",
+        long,
+        conflicts_with_all = ["mdfind", "stdin", "workspace", "use-index", "depth", "exact-depth", "bfs", "avoid", "ignore-file", "one-file-system", "skip-network-fs", "exclude-fstype", "unrestricted"]
+    )]
+    from_locate: bool,
+
+    /// How many threads hash and search the contents of files, one for every core if
+    /// left out
+    #[clap(
+        name = "threads",
+        long,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    threads: Option<usize>,
+
+    /// Names skipped along with the default excludes, from the config file
+    #[clap(skip)]
+    excludes: Vec<String>,
+
+    /// The lists of --ignore-file, once read
+    #[clap(skip)]
+    ignore_lists: Vec<Gitignore>,
+}
+
+#[derive(Parser)]
+#[command(
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    args_override_self = true,
+    group(clap::ArgGroup::new("action").multiple(true))
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    #[command(flatten)]
+    filters: Filters,
+
+    /// Remove matched files instead of printing them
+    #[clap(name = "delete", long, group = "action", conflicts_with_all = ["archives", "follow"])]
+    delete: bool,
+
+    /// Copy the matched files into this directory instead of printing them, skipping
+    /// directories and whatever a file is already in the way of
+    #[clap(name = "copy-to", long, value_name = "DIR", groups = ["action", "transfer"], conflicts_with_all = ["delete", "move-to", "touch", "chmod", "chown", "archives", "content", "sort", "sort-by", "count", "quiet", "template", "format", "interactive", "watch", "report", "page-size"])]
+    copy_to: Option<PathBuf>,
+
+    /// Move the matched files into this directory instead of printing them, skipping
+    /// directories and whatever a file is already in the way of
+    #[clap(name = "move-to", long, value_name = "DIR", groups = ["action", "transfer"], conflicts_with_all = ["delete", "touch", "chmod", "chown", "archives", "content", "sort", "sort-by", "count", "quiet", "template", "format", "interactive", "watch", "report", "page-size"])]
+    move_to: Option<PathBuf>,
+
+    /// Set the access and modification times of the matched files to now, or to --mtime,
+    /// instead of printing them. Links are touched themselves
+    #[clap(name = "touch", long, group = "action", conflicts_with_all = ["delete", "chmod", "chown", "archives", "content", "sort", "sort-by", "count", "quiet", "template", "format", "interactive", "watch", "report", "page-size"])]
+    touch: bool,
+
+    /// The time --touch sets: now, seconds since the epoch after an @, or a day as
+    /// YYYY-MM-DD followed by a time as HH:MM or HH:MM:SS after a T, in the local time
+    /// zone
+    #[clap(name = "mtime", long, value_name = "TIMESTAMP", requires = "touch", value_parser = parse::timestamp)]
+    mtime: Option<SystemTime>,
+
+    /// Give the matched entries these permissions instead of printing them: octal, or
+    /// symbolic as chmod takes them, e.g. go-w or u=rw,a+X. Links are left as they are
+    #[clap(name = "chmod", long, value_name = "MODE", group = "action", value_parser = parse::mode_change, conflicts_with_all = ["delete", "archives", "content", "sort", "sort-by", "count", "quiet", "template", "format", "interactive", "watch", "report", "page-size"])]
+    chmod: Option<ModeChange>,
+
+    /// Give the matched entries to this user, and this group after a colon, instead of
+    /// printing them: USER, USER:GROUP or :GROUP. Links are given away themselves
+    #[clap(name = "chown", long, value_name = "USER[:GROUP]", group = "action", value_parser = parse::owner, conflicts_with_all = ["delete", "archives", "content", "sort", "sort-by", "count", "quiet", "template", "format", "interactive", "watch", "report", "page-size"])]
+    chown: Option<Owner>,
+
+    /// Replace what a regular expression matches in the matched text files, written
+    /// OLD->NEW, where $1 or ${name} in NEW stand for what a group matched, e.g.
+    /// '(\w+)_old->${1}_new'. Files are rewritten whole and renamed into place, those
+    /// links point to for links followed, and files with other hard links are skipped;
+    /// with --dry-run a diff shows what would change
+    #[clap(name = "replace", long, value_name = "OLD->NEW", group = "action", value_parser = parse::replacement, conflicts_with_all = ["delete", "touch", "chmod", "chown", "archives", "content", "sort", "sort-by", "count", "quiet", "template", "format", "interactive", "watch", "report", "page-size"])]
+    replace: Option<(regex::bytes::Regex, String)>,
+
+    /// Rename the matched entries by a substitution on their names, written as sed
+    /// writes them, e.g. 's/(.+)\.jpeg$/$1.jpg/', with the g flag for every match and i
+    /// to ignore case. A name is left as it is rather than take the place of another;
+    /// `quaero undo-rename` takes the renames back
+    #[clap(name = "rename", long, value_name = "s/REGEX/REPLACEMENT/", group = "action", value_parser = rename::substitution, conflicts_with_all = ["delete", "touch", "chmod", "chown", "replace", "archives", "content", "sort", "sort-by", "count", "quiet", "template", "format", "interactive", "watch", "report", "page-size"])]
+    rename: Option<rename::Substitution>,
+
+    /// Keep the path of every file below the root it was found in, in the directory of
+    /// --copy-to or --move-to, instead of putting them all side by side
+    #[clap(name = "preserve-structure", long, requires = "transfer")]
+    preserve_structure: bool,
+
+    /// Search the contents of matched files, printing path:line:text for each hit
+    #[clap(name = "content", long, conflicts_with_all = ["delete", "archives"])]
+    content: Option<regex::bytes::Regex>,
+
+    /// Also remove matched directories, as long as they are empty
+    #[clap(name = "delete-dirs", long, requires = "delete")]
+    delete_dirs: bool,
+
+    /// Only report what --delete, --copy-to, --move-to, --touch, --chmod, --chown,
+    /// --replace or --rename would do
+    #[clap(name = "dry-run", long, requires = "action")]
+    dry_run: bool,
+
+    /// Print the matches ordered by this key instead of as they are found
+    #[clap(name = "sort", long, value_enum, conflicts_with_all = ["delete", "content"])]
+    sort: Option<SortKey>,
+
+    /// Print the matches ordered by what this template renders for each, with runs of
+    /// digits compared as numbers, e.g. '{ext}/{size}'. Takes the fields of --template
+    #[clap(name = "sort-by", long, conflicts_with_all = ["delete", "content", "sort", "count", "quiet", "interactive", "watch", "report"])]
+    sort_by: Option<Template>,
+
+    /// Only print the number of matches
+    #[clap(name = "count", long, short, conflicts_with_all = ["delete", "content", "sort"])]
+    count: bool,
+
+    /// Print nothing, exit with 0 if anything matches and 1 otherwise
+    #[clap(name = "quiet", long, short, conflicts_with_all = ["delete", "content", "sort", "count"])]
+    quiet: bool,
+
+    /// Print each match with this template instead of just its path, e.g.
+    /// '{path}\t{size:h}\t{mtime:%Y-%m-%d}'. Fields: path, name, stem, ext, parent, type,
+    /// size, allocated, perm, nlink, entries, inode, device, uid, gid, user, group, mtime,
+    /// atime, ctime, depth, link_target, resolved, label and hash
+    #[clap(name = "template", long, conflicts_with_all = ["delete", "content", "count", "quiet"])]
+    template: Option<Template>,
+
+    /// Print the sizes and times of --template that don't say how as 4.2M or 1.3G and
+    /// as 2 hours ago. --format csv, tsv and ndjson always have bytes and ISO 8601 times
+    #[clap(name = "human-readable", long, requires = "template")]
+    human_readable: bool,
+
+    /// Print every time of --template in ISO 8601, e.g. 2026-10-14T09:48:22+02:00,
+    /// whatever it or --human-readable says, for scripts to parse
+    #[clap(name = "iso-time", long, requires = "template")]
+    iso_time: bool,
+
+    /// Print the matches in a structured format, with the path, name, type, size, mtime
+    /// and permissions of each
+    #[clap(name = "format", long, visible_alias = "output-format", value_enum, conflicts_with_all = ["delete", "content", "count", "quiet", "template"])]
+    format: Option<OutputFormat>,
+
+    /// Pipe the paths of the matches through a shell command, one per line, keeping the
+    /// paths it prints back
+    #[clap(name = "pipe-through", long)]
+    pipe_through: Option<String>,
+
+    /// Only keep the matches a shell command exits with status 0 for, run once for each
+    /// with {} replaced by its path, or the path added at the end, e.g. 'head -c2 {} |
+    /// grep -q PK'. What the command prints is discarded
+    #[clap(name = "filter-cmd", long, value_name = "COMMAND", conflicts_with_all = ["interactive", "watch"])]
+    filter_cmd: Option<String>,
+
+    /// How many --filter-cmd commands run at once, one for every core if not given
+    #[clap(
+        name = "jobs",
+        long,
+        short = 'j',
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        requires = "filter-cmd"
+    )]
+    jobs: Option<usize>,
+
+    /// Print paths as they were traversed, through any symbolic link on the way (default)
+    #[clap(name = "logical", long, overrides_with = "physical")]
+    logical: bool,
+
+    /// Print paths with the symbolic links leading to them resolved
+    #[clap(name = "physical", long, overrides_with = "logical")]
+    physical: bool,
+
+    /// Make links to the files of the paths printed to a terminal, OSC 8 hyperlinks that
+    /// iTerm2, WezTerm, kitty, GNOME Terminal and others open on a click. Terminals not
+    /// known to support them get plain paths
+    #[clap(name = "hyperlink", long, conflicts_with_all = ["format", "template", "output"])]
+    hyperlink: bool,
+
+    /// Quote the paths with spaces, quotes, control characters or anything else a shell
+    /// would read otherwise, so that they can be pasted into a command line
+    #[clap(name = "quote", long, conflicts_with_all = ["format", "template"])]
+    quote: bool,
+
+    /// Print symbolic links as path -> target, with where they point as they say. The
+    /// {link_target} and {resolved} fields of --template and --format ndjson have it too
+    #[clap(name = "show-link-targets", long, conflicts_with_all = ["format", "template"])]
+    show_link_targets: bool,
+
+    /// Print absolute paths, the relative ones joined to the current directory
+    #[clap(name = "absolute-path", long, conflicts_with = "canonicalize")]
+    absolute_path: bool,
+
+    /// Print paths with every symbolic link resolved, those of the matches included
+    #[clap(name = "canonicalize", long, conflicts_with = "physical")]
+    canonicalize: bool,
+
+    /// Pass on only the first of the matches that are hard links to the same file, so
+    /// that it is neither printed, counted nor piped through twice
+    #[clap(name = "unique-inodes", long, conflicts_with_all = ["archives", "watch"])]
+    unique_inodes: bool,
+
+    /// Pass on a match found again under another path, by roots of a workspace that
+    /// overlap, links followed or --stdin, rather than only the first time
+    #[clap(name = "no-dedup", long)]
+    no_dedup: bool,
+
+    /// Pass on at most N of the matches in any one directory, such as a node_modules or a
+    /// build directory that would take over the output, and then say on stderr how many
+    /// more each had
+    #[clap(name = "max-per-dir", long, value_name = "N", conflicts_with_all = ["interactive", "watch"])]
+    max_per_dir: Option<usize>,
+
+    /// Rewrite the printed paths with a sed-style s/REGEX/REPLACEMENT/[g], e.g.
+    /// 's#^/mnt/backup#//server/share#'. Can be given more than once
+    #[clap(name = "rewrite", long, conflicts_with_all = ["delete", "content"])]
+    rewrites: Vec<Rewrite>,
+
+    /// Print the hash of the contents of every matched regular file after its path, or
+    /// where the template has {hash}
+    #[clap(name = "hash", long, value_enum, conflicts_with_all = ["delete", "content", "count", "quiet", "archives"])]
+    hash: Option<HashAlgorithm>,
+
+    /// Pick from the matches as they are found with a fuzzy finder, printing the
+    /// selection: Tab selects several, Enter accepts and Esc aborts
+    #[clap(name = "interactive", long, short, conflicts_with_all = ["delete", "content", "sort", "count", "quiet", "format", "pipe-through", "rewrite", "physical", "hash"])]
+    interactive: bool,
+
+    /// Keep running after the search, printing the entries that are created or moved in
+    /// and match, until interrupted
+    #[clap(name = "watch", long, conflicts_with_all = ["delete", "content", "sort", "count", "quiet", "interactive", "pipe-through", "hash", "stats", "archives", "stdin"])]
+    watch: bool,
+
+    /// With --watch, run a shell command for the entries that appear and match, with {}
+    /// replaced by their paths, or the paths added at the end, e.g. 'gzip {}'. The paths
+    /// are batched until no new one has come in for a moment
+    #[clap(name = "exec-on-new", long, value_name = "COMMAND", requires = "watch")]
+    exec_on_new: Option<String>,
+
+    /// The most paths one run of --exec-on-new is given, the batch running as soon as
+    /// it has that many
+    #[clap(name = "exec-batch", long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..), requires = "exec-on-new")]
+    exec_batch: Option<u32>,
+
+    /// With --watch, hold the matches back until none new has come in for this long,
+    /// e.g. 500ms, and report the burst at once, --exec-on-new running for it once
+    #[clap(name = "debounce", long, value_name = "DURATION", value_parser = parse::age, requires = "watch")]
+    debounce: Option<Duration>,
+
+    /// With --watch, report the matches of a burst that are in the same directory once,
+    /// as that directory, or as the directory made in the burst they are all below
+    #[clap(name = "coalesce-dir", long, requires = "watch")]
+    coalesce_dir: bool,
+
+    /// With --watch, alert on stderr once a matched file, or the files in a matched
+    /// directory, have grown by SIZE, or by SIZE within DURATION, e.g. 100M or 10M/1m.
+    /// Files are measured from the first change seen of them, or from nothing if made
+    /// while watching
+    #[clap(name = "alert-growth", long, value_name = "SIZE[/DURATION]", value_parser = parse::growth, requires = "watch")]
+    alert_growth: Option<(u64, Option<Duration>)>,
+
+    /// Run a shell command for every --alert-growth alert instead of printing it, with
+    /// {} replaced by the path, or the path added at the end
+    #[clap(
+        name = "alert-command",
+        long,
+        value_name = "COMMAND",
+        requires = "alert-growth"
+    )]
+    alert_command: Option<String>,
+
+    /// With --watch, also report on stderr when the permissions or the owner of a match
+    /// change, e.g. by chmod or chown. The tree is walked once more at the start, for
+    /// what the matches are like to begin with
+    #[clap(name = "watch-metadata", long, requires = "watch")]
+    watch_metadata: bool,
+
+    /// Write the matches to this file instead of stdout, replacing what it held. Errors
+    /// and --stats still go to the terminal
+    #[clap(name = "output", long, short, conflicts_with_all = ["quiet", "interactive", "watch"])]
+    output: Option<PathBuf>,
+
+    /// Also write every match to this file, as it is found and whatever else is printed.
+    /// Written as CSV, TSV or NDJSON for a .csv, .tsv or .ndjson/.jsonl file and as
+    /// plain paths otherwise. Can be given more than once
+    #[clap(name = "also-output", long, conflicts_with_all = ["quiet", "interactive", "watch"])]
+    also_outputs: Vec<PathBuf>,
+
+    /// Also print the number of matches on stderr once the search is done
+    #[clap(name = "also-count", long, conflicts_with_all = ["count", "quiet", "interactive", "watch"])]
+    also_count: bool,
+
+    /// Print a report on the matches instead of the matches themselves
+    #[clap(name = "report", long, value_enum, conflicts_with_all = ["delete", "content", "sort", "count", "quiet", "template", "format", "interactive", "watch"])]
+    report: Option<Report>,
+
+    /// Sizes where the buckets of --report size-buckets start, e.g. 4k,1M,100M
+    #[clap(
+        name = "buckets",
+        long,
+        value_delimiter = ',',
+        value_parser = parse::size,
+        requires = "report"
+    )]
+    buckets: Option<Vec<u64>>,
+
+    /// How many directories --report top-dirs prints, or files --report size-histogram
+    #[clap(name = "top", long, default_value_t = 10, requires = "report")]
+    top: usize,
+
+    /// How many levels below the starting directory the directories of --report
+    /// top-dirs are, 1 adding up everything under each of its subdirectories
+    #[clap(
+        name = "top-depth",
+        long,
+        default_value_t = 1,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        requires = "report"
+    )]
+    top_depth: usize,
+
+    /// Print how many matches there are in each group, and how many bytes the files
+    /// among them take, instead of the matches themselves: by extension, type,
+    /// directory or owner. --format csv, tsv or ndjson prints the groups in it
+    #[clap(name = "group-by", long, value_enum, value_name = "KEY", conflicts_with_all = ["delete", "content", "sort", "count", "quiet", "template", "interactive", "watch", "report"])]
+    group_by: Option<GroupBy>,
+
+    /// Print the matches in sections by how long ago they were last modified, each of
+    /// these ages starting one, e.g. 1d,7d,30d, and newest first in each unless sorted.
+    /// --format ndjson prints an object a section with the paths in it
+    #[clap(
+        name = "bucket-by-age",
+        long,
+        value_name = "AGES",
+        value_delimiter = ',',
+        value_parser = parse::age,
+        conflicts_with_all = ["delete", "content", "count", "quiet", "interactive", "watch", "report", "group-by"]
+    )]
+    bucket_by_age: Option<Vec<Duration>>,
+
+    /// Print only this many matches, the page chosen by --page, in an order that is the
+    /// same from one run to the next: by path, or by --sort or --sort-by and then by
+    /// path. The page and the number of pages go to stderr, and the ids of --format
+    /// ndjson count from the first match of the first page
+    #[clap(
+        name = "page-size",
+        long,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        conflicts_with_all = ["delete", "content", "count", "quiet", "interactive", "watch", "report"]
+    )]
+    page_size: Option<usize>,
+
+    /// Which page of --page-size matches to print, from 1
+    #[clap(
+        name = "page",
+        long,
+        default_value_t = 1,
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        requires = "page-size"
+    )]
+    page: usize,
+
+    /// Print a summary of the search on stderr once it is done
+    #[clap(name = "stats", long)]
+    stats: bool,
+
+    /// Stop the walk once it has run this long, e.g. 500ms or 10s, and go on with what
+    /// it found so far, exiting with status 124
+    #[clap(name = "timeout", long, value_name = "DURATION", value_parser = parse::age, conflicts_with_all = ["interactive", "watch"])]
+    timeout: Option<Duration>,
+
+    /// Save the directories the search got through to FILE every 30 seconds and once it
+    /// is cut short, for --resume to go on from there. Removed once the search is done
+    #[clap(name = "checkpoint", long, value_name = "FILE", conflicts_with_all = ["interactive", "watch", "bfs", "workspace", "stdin", "mdfind", "from-locate", "use-index", "sort", "sort-by", "page-size", "count", "report", "group-by", "bucket-by-age", "content", "pipe-through", "filter-cmd", "action"])]
+    checkpoint: Option<PathBuf>,
+
+    /// Go on with a search cut short from where the checkpoint in FILE says it got to,
+    /// leaving out the directories it got through, and keep saving to FILE unless
+    /// --checkpoint says where else. Matches in the directories it was in the middle of
+    /// are printed again
+    #[clap(name = "resume", long, value_name = "FILE", conflicts_with_all = ["interactive", "watch", "bfs", "workspace", "stdin", "mdfind", "from-locate", "use-index", "sort", "sort-by", "page-size", "count", "report", "group-by", "bucket-by-age", "content", "pipe-through", "filter-cmd", "action"])]
+    resume: Option<PathBuf>,
+
+    /// Language of the messages (e.g. en, it), otherwise taken from LC_ALL, LC_MESSAGES or LANG
+    #[clap(name = "lang", long, global = true)]
+    lang: Option<String>,
+
+    /// Ignore the config file and QUAERO_OPTS, searching with the defaults of quaero
+    /// alone
+    #[clap(name = "no-config", long, global = true)]
+    no_config: bool,
+
+    /// How to write errors to stderr: as text, or as a JSON object a line for scripts.
+    /// Either way the exit code is 0 if anything matched, 1 if nothing did, 2 if the
+    /// search couldn't run and 3 if some of it failed and was skipped
+    #[clap(name = "errors", long, value_enum, value_name = "FORMAT", global = true, default_value_t = ErrorFormat::Text)]
+    errors: ErrorFormat,
+
+    /// Print sizes as numbers of bytes wherever they would be shortened to 4.2M: in
+    /// --template, with --human-readable, in reports and by `quaero du`
+    #[clap(name = "bytes", long, global = true)]
+    bytes: bool,
 }
 
-impl TryFrom<&DirEntry> for FileType {
-    type Error = anyhow::Error;
+/// A directory the search starts from.
+struct Root {
+    /// Printed in front of the matches from this root, in workspace mode
+    label: Option<String>,
+    path: PathBuf,
+    excludes: Vec<PathBuf>,
+}
+
+/// What every walk of one search shares.
+struct Search<'a> {
+    args: &'a Filters,
+    matcher: Matcher,
+    stats: Arc<WalkStats>,
+    /// Set to stop every walk early
+    interrupt: Arc<AtomicBool>,
+    /// `--checkpoint`: where the walk got to
+    checkpoint: Option<Arc<Checkpoint>>,
+}
+
+impl Search<'_> {
+    fn policy(&self) -> IgnorePolicy {
+        self.args.ignore_lists.iter().cloned().fold(
+            IgnorePolicy::from_unrestricted(self.args.unrestricted)
+                .excluding(self.args.excludes.iter().cloned()),
+            IgnorePolicy::ignore_list,
+        )
+    }
+
+    fn follow(&self) -> Follow {
+        match (self.args.follow, self.args.follow_roots) {
+            (true, _) => Follow::Always,
+            (false, true) => Follow::Roots,
+            (false, false) => Follow::Never,
+        }
+    }
+
+    fn walk_builder<T: AsRef<Path>>(&self, root: T) -> WalkBuilder {
+        let builder = WalkBuilder::new(&root)
+            .stats(self.stats.clone())
+            .interrupt(self.interrupt.clone())
+            .avoids(self.args.avoids.clone().unwrap_or_default())
+            .max_depth(
+                self.args
+                    .depth
+                    .unwrap_or(usize::MAX)
+                    .min(self.args.exact_depth.unwrap_or(usize::MAX)),
+            )
+            .policy(self.policy())
+            .follow(self.follow())
+            .backend(self.args.backend)
+            .sorted(self.args.deterministic)
+            .breadth_first(self.args.bfs);
+        // A root that can't be looked at fails the walk anyway.
+        let builder = match self.args.one_file_system {
+            true => match OneFileSystem::new(root.as_ref()) {
+                Ok(filter) => builder.dir_filter(filter),
+                Err(_) => builder,
+            },
+            false => builder,
+        };
+        let mut fs_types = self.args.exclude_fstypes.clone();
+        if self.args.skip_network_fs {
+            fs_types.extend(mounts::NETWORK.iter().map(|fs_type| fs_type.to_string()));
+        }
+        let builder = match fs_types.is_empty() {
+            true => builder,
+            false => match mounts::mount_points(&fs_types) {
+                Ok(points) => builder.skip_mount_points(points),
+                Err(error) => {
+                    eprintln!(
+                        "{}",
+                        tr!("mounts-unavailable", "reason" => format!("{:#}", error))
+                    );
+                    builder
+                }
+            },
+        };
+        let builder = match self.args.skip_bundles {
+            true => builder.dir_filter(Bundles),
+            false => builder,
+        };
+        let builder = match self.args.skip_marked.is_empty() {
+            true => builder,
+            false => builder.dir_filter(Marked::new(self.args.skip_marked.clone())),
+        };
+        // Caches go with the default excludes.
+        let builder = match self.args.unrestricted < 3 {
+            true => builder.dir_filter(CacheDirectories),
+            false => builder,
+        };
+        let builder = match self.args.repo && !self.args.descend_into_repos {
+            true => builder.dir_filter(OneRepository),
+            false => builder,
+        };
+        // Its errors came up building the matcher already.
+        let builder = match pattern_matcher(&self.args.exclude_from, self.args.full_path) {
+            Ok(excluded) if !self.args.exclude_from.is_empty() => {
+                builder.prune_if(move |directory| excluded.matches(directory))
+            }
+            _ => builder,
+        };
+        let builder = match &self.checkpoint {
+            Some(checkpoint) => builder.checkpoint(checkpoint.clone()),
+            None => builder,
+        };
+
+        if !self.args.archives {
+            return builder.matcher(self.matcher.clone());
+        }
+
+        // Archives are yielded whatever they are named, for their members to be matched.
+        let matcher = self.matcher.clone();
+        builder
+            .dir_filter(self.matcher.clone())
+            .entry_filter(move |entry: &Entry| {
+                matcher.matches(entry) || archive::reader_for(&entry.path).is_some()
+            })
+    }
 
-    fn try_from(entry: &DirEntry) -> Result<Self> {
-        let file_type = entry.file_type()?;
+    /// Hands `entry` to `on_match`, and with --archives the members that match of the
+    /// archive it may be. Returns false as soon as `on_match` does.
+    fn emit(
+        &self,
+        label: Option<&str>,
+        entry: Entry,
+        on_match: &mut impl FnMut(Option<&str>, Entry) -> bool,
+    ) -> bool {
+        // Anything else only got here by matching, archives whether they match or not.
+        if !self.args.archives || archive::reader_for(&entry.path).is_none() {
+            return on_match(label, entry);
+        }
 
-        let is_dir = file_type.is_dir();
-        let is_file = file_type.is_file();
-        let is_symlink = file_type.is_symlink();
+        // Members go by the metadata of their archive.
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata.clone(),
+            Err(_) => return on_match(label, entry),
+        };
+        let members = match entry.file_type() {
+            FileType::RegularFile | FileType::Executable => archive::members(&entry.path)
+                .into_iter()
+                .flat_map(|members| {
+                    members.unwrap_or_else(|error| {
+                        report_error(&error);
+                        Vec::new()
+                    })
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        let matched = members
+            .iter()
+            .filter_map(|member| {
+                let mut path = entry.path.clone().into_os_string();
+                path.push(archive::SEPARATOR.to_string());
+                path.push(&member.path);
+                let path = PathBuf::from(path);
+                self.matcher.matches_member(&path, member).then(|| {
+                    let name = member.name().into();
+                    let mut member_entry =
+                        Entry::with_metadata(path, name, member.file_type, metadata.clone());
+                    member_entry.depth = entry.depth + member.path.matches('/').count() + 1;
+                    member_entry
+                })
+            })
+            .collect::<Vec<_>>();
 
-        let permissions = entry.metadata()?.permissions();
-        let executable_mask = 0o111;
-        let is_executable = permissions.mode() & executable_mask != 0;
+        if self.matcher.matches(&entry) && !on_match(label, entry) {
+            return false;
+        }
+        matched.into_iter().all(|member| on_match(label, member))
+    }
 
-        let file_type = match (is_dir, is_file, is_symlink) {
-            (true, ..) => FileType::Directory,
-            (_, true, _) if !is_executable => FileType::RegularFile,
-            (.., true) if !is_executable => FileType::SymLink,
-            (false, ..) if is_executable => FileType::Executable,
-            _ => unreachable!(
-                "a file can be either one of these: directory, regular file, symbolic link"
-            ),
+    /// The roots to search, from --from or from the workspace in the config file. They
+    /// are spelled cleanly, without `.` components, doubled or trailing slashes, for the
+    /// paths found below them to be printed the same however they were given.
+    fn roots(&self) -> Result<Vec<Root>> {
+        let Some(name) = &self.args.workspace else {
+            let start_directory = self.args.start_directory.as_deref();
+            return Ok(vec![Root {
+                label: None,
+                path: start_directory
+                    .unwrap_or(Path::new("."))
+                    .components()
+                    .collect(),
+                excludes: Vec::new(),
+            }]);
         };
 
-        Ok(file_type)
+        let config = Config::load()?;
+        let workspace = config.workspaces.get(name.as_str()).ok_or_else(|| {
+            anyhow::anyhow!(tr!("error-unknown-workspace", "name" => name.as_str()))
+        })?;
+
+        Ok(workspace
+            .roots
+            .iter()
+            .map(|root| Root {
+                label: Some(root.label()),
+                path: root.path().components().collect(),
+                excludes: root.excludes(),
+            })
+            .collect())
     }
-}
 
-struct ParsedEntry {
-    file_type: FileType,
-    name: String,
-    path: String,
-}
+    fn root_builder(&self, root: &Root) -> WalkBuilder {
+        self.walk_builder(&root.path).avoids(root.excludes.clone())
+    }
+
+    /// Walks every root in parallel, handing every match to `on_match` on the calling
+    /// thread.
+    fn parallel(&self, roots: &[Root], on_match: &mut impl FnMut(Option<&str>, Entry) -> bool) {
+        let (sender, receiver) = std::sync::mpsc::channel();
 
-impl TryFrom<DirEntry> for ParsedEntry {
-    type Error = anyhow::Error;
+        std::thread::scope(|scope| {
+            let handles = roots
+                .iter()
+                .map(|root| {
+                    let sender = sender.clone();
+                    scope.spawn(move || {
+                        for entry in self.root_builder(root).build() {
+                            if sender.send((root.label.as_deref(), entry)).is_err() {
+                                break;
+                            }
+                        }
+                    })
+                })
+                .collect::<Vec<_>>();
+            drop(sender);
 
-    fn try_from(entry: DirEntry) -> Result<Self> {
-        let file_type = FileType::try_from(&entry)?;
+            for (label, entry) in receiver {
+                match entry {
+                    Ok(entry) => {
+                        if !self.emit(label, entry, on_match) {
+                            self.interrupt.store(true, Ordering::Relaxed);
+                            break;
+                        }
+                    }
+                    Err(error) => report_error(&error),
+                }
+            }
 
-        Ok(Self {
-            file_type,
-            name: entry.file_name().into_string().unwrap(),
-            path: entry.path().into_os_string().into_string().unwrap(),
+            for handle in handles {
+                handle.join().expect("workspace search thread panicked");
+            }
         })
     }
-}
 
-fn walk_directory<T: AsRef<Path>>(
-    directory: T,
-    avoids: &Option<Vec<PathBuf>>,
-    depth: usize,
-    callback: &impl Fn(&ParsedEntry),
-) -> Result<()> {
-    if depth <= 0 {
-        return Ok(());
+    /// `--stdin`: matches the paths in stdin, split on NUL bytes if the first of it has
+    /// any and on newlines otherwise.
+    fn read_stdin(&self, on_match: &mut impl FnMut(Option<&str>, Entry) -> bool) -> Result<()> {
+        let mut stdin = std::io::stdin().lock();
+        let delimiter = match stdin.fill_buf()?.contains(&0) {
+            true => 0,
+            false => b'\n',
+        };
+        self.match_paths(stdin, delimiter, None, on_match)
+    }
+
+    /// `--mdfind`: matches the paths Spotlight finds for `query` below the starting
+    /// directory, which its index answers without a walk.
+    fn read_mdfind(
+        &self,
+        query: &str,
+        on_match: &mut impl FnMut(Option<&str>, Entry) -> bool,
+    ) -> Result<()> {
+        if !cfg!(target_os = "macos") {
+            return Err(anyhow::anyhow!(tr!("error-mdfind-platform")));
+        }
+
+        let mut mdfind = std::process::Command::new("mdfind")
+            .arg("-0")
+            .arg("-onlyin")
+            .arg(
+                self.args
+                    .start_directory
+                    .as_deref()
+                    .unwrap_or(Path::new(".")),
+            )
+            .arg(query)
+            .stdout(Stdio::piped())
+            .spawn()
+            .context("mdfind")?;
+        let paths = std::io::BufReader::new(mdfind.stdout.take().expect("stdout is piped"));
+        let matched = self.match_paths(paths, 0, None, on_match);
+
+        // Stopped early, it goes by the closed pipe.
+        let status = mdfind.wait().context("mdfind")?;
+        if status.code().is_some_and(|code| code != 0) {
+            return Err(anyhow::anyhow!("mdfind: {}", status));
+        }
+        matched
+    }
+
+    /// `--from-locate`: matches the paths the locate database has below the starting
+    /// directory for the target, or else the globs or the extensions. Locate is asked for
+    /// more than matches, ignoring case and the other filters, which narrow them down.
+    fn read_locate(&self, on_match: &mut impl FnMut(Option<&str>, Entry) -> bool) -> Result<()> {
+        let patterns: Vec<String> = match (&self.args.target, &self.args.extensions) {
+            (Some(target), _) => vec![target.clone()],
+            // Those of --full-path are for the path below the starting directory.
+            _ if !self.args.globs.is_empty() && !self.args.full_path => self
+                .args
+                .globs
+                .iter()
+                .map(|glob| glob.glob().to_owned())
+                .collect(),
+            (None, Some(extensions)) if !extensions.is_empty() => extensions
+                .iter()
+                .map(|extension| format!("*.{}", extension.trim_start_matches('.')))
+                .collect(),
+            _ => return Err(anyhow::anyhow!(tr!("error-locate-pattern"))),
+        };
+
+        let start = self
+            .args
+            .start_directory
+            .as_deref()
+            .unwrap_or(Path::new("."));
+        let below = std::fs::canonicalize(start).with_context(|| start.display().to_string())?;
+
+        let mut locate = std::process::Command::new("locate");
+        // Only what still exists, with the patterns for the names unless for the paths.
+        locate.arg("-0").arg("-e").arg("-i");
+        if !self.args.full_path {
+            locate.arg("-b");
+        }
+        let mut locate = locate
+            .arg("--")
+            .args(&patterns)
+            .stdout(Stdio::piped())
+            .spawn()
+            .context("locate")?;
+        let paths = std::io::BufReader::new(locate.stdout.take().expect("stdout is piped"));
+        let matched = self.match_paths(paths, 0, Some(&below), on_match);
+
+        // It exits with 1 for nothing found, and stopped early goes by the closed pipe.
+        let status = locate.wait().context("locate")?;
+        if status.code().is_some_and(|code| code > 1) {
+            return Err(anyhow::anyhow!("locate: {}", status));
+        }
+        matched
     }
 
-    'outer: for entry in std::fs::read_dir(directory)? {
-        let entry = ParsedEntry::try_from(entry?)?;
+    /// Matches the paths of `paths`, separated by `delimiter` and left out unless
+    /// `below` the directory given, and those they lead to unless links aren't followed.
+    fn match_paths(
+        &self,
+        paths: impl BufRead,
+        delimiter: u8,
+        below: Option<&Path>,
+        on_match: &mut impl FnMut(Option<&str>, Entry) -> bool,
+    ) -> Result<()> {
+        for path in paths.split(delimiter) {
+            let path = path?;
+            if path.is_empty() {
+                continue;
+            }
 
-        if let Some(excludes) = avoids {
-            for exclude in excludes {
-                let lhs = std::fs::canonicalize(&entry.path)?;
-                let rhs = std::fs::canonicalize(exclude)?;
-                if lhs.starts_with(rhs) {
-                    continue 'outer;
+            let path = Path::new(OsStr::from_bytes(&path));
+            if below.is_some_and(|below| !path.starts_with(below)) {
+                continue;
+            }
+            self.stats.entries.fetch_add(1, Ordering::Relaxed);
+            let mut entry = match Entry::from_path(path) {
+                Ok(entry) if self.follow() != Follow::Never => entry.followed(),
+                Ok(entry) => entry,
+                Err(error) => {
+                    self.stats.errors.fetch_add(1, Ordering::Relaxed);
+                    report_error(&error.context(path.display().to_string()));
+                    continue;
                 }
+            };
+            entry.depth = path
+                .components()
+                .filter(|component| matches!(component, Component::Normal(_)))
+                .count();
+
+            let archive = self.args.archives && archive::reader_for(path).is_some();
+            if (archive || self.matcher.matches(&entry)) && !self.emit(None, entry, on_match) {
+                break;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Runs the search, calling `on_match` until it returns false.
+    fn run(&self, on_match: &mut impl FnMut(Option<&str>, Entry) -> bool) -> Result<()> {
+        if self.args.stdin {
+            return self.read_stdin(on_match);
+        }
+        if let Some(query) = &self.args.mdfind {
+            return self.read_mdfind(query, on_match);
+        }
+        if self.args.from_locate {
+            return self.read_locate(on_match);
+        }
+
+        let roots = self.roots()?;
+        if self.args.lint_query {
+            for warning in lint::lint(self, &roots) {
+                eprintln!("{}", tr!("lint-warning", "message" => warning));
             }
         }
 
-        callback(&entry);
-        if entry.file_type == FileType::Directory {
-            walk_directory(entry.path, avoids, depth - 1, callback)?;
+        if self.args.use_index {
+            return index::run(self, &roots, on_match);
+        }
+
+        if self.args.workspace.is_some() && !self.args.deterministic {
+            self.parallel(&roots, on_match);
+            return Ok(());
+        }
+
+        for root in &roots {
+            for entry in self.root_builder(root).build() {
+                match entry {
+                    Ok(entry) => {
+                        if !self.emit(root.label.as_deref(), entry, on_match) {
+                            return Ok(());
+                        }
+                    }
+                    Err(error) => report_error(&error),
+                }
+            }
         }
+
+        Ok(())
     }
+}
 
-    Ok(())
+/// Errors while walking are reported and skipped, like find does, and make the exit
+/// code that of a partial failure.
+pub(crate) fn report_error(error: &anyhow::Error) {
+    diagnostics::report(error);
 }
 
-enum SearchMode {
-    Target,
-    Type,
-    TargetAndType,
-    Extension,
-    Regex,
+fn print_stats(stats: &WalkStats, matches: u64, matched_size: u64, elapsed: Duration) {
+    eprintln!(
+        "{}",
+        tr!(
+            "stats-summary",
+            "entries" => stats.entries.load(Ordering::Relaxed),
+            "directories" => stats.directories.load(Ordering::Relaxed),
+            "matches" => matches,
+            "size" => matched_size,
+            "errors" => stats.errors.load(Ordering::Relaxed),
+            "vanished" => stats.vanished.load(Ordering::Relaxed),
+            "elapsed" => format!("{:.3}", elapsed.as_secs_f64()),
+        )
+    );
 }
 
-fn match_target(target: &Option<String>, entry: &ParsedEntry) {
-    if entry.name == target.as_deref().unwrap() {
-        println!("{}", entry.path);
+/// Reports how many entries each filter was asked about and how many it let through,
+/// in the order they ended up being evaluated in.
+fn print_filter_stats(matcher: &Matcher, counters: &[(String, Arc<MatchCounter>)]) {
+    if counters.is_empty() {
+        return;
     }
-}
 
-fn match_type(target_type: &Option<FileType>, entry: &ParsedEntry) {
-    if entry.file_type == target_type.unwrap() {
-        println!("{}", entry.path);
+    let order = match matcher {
+        Matcher::Adaptive(adaptive) => adaptive.order(),
+        _ => (0..counters.len()).collect(),
+    };
+
+    eprintln!("{}", tr!("stats-filters"));
+    for (filter, counter) in order.iter().map(|index| &counters[*index]) {
+        let evaluated = counter.evaluated.load(Ordering::Relaxed);
+        let matched = counter.matched.load(Ordering::Relaxed);
+        let percent = match evaluated {
+            0 => 0.0,
+            _ => matched as f64 * 100.0 / evaluated as f64,
+        };
+
+        eprintln!(
+            "{}",
+            tr!(
+                "stats-filter",
+                "filter" => filter.as_str(),
+                "evaluated" => evaluated,
+                "matched" => matched,
+                "rejected" => evaluated - matched,
+                "percent" => format!("{:.1}", percent),
+            )
+        );
     }
 }
 
-fn match_extensions(extensions: &Option<Vec<String>>, entry: &ParsedEntry) {
-    let target_extension = Path::new(&entry.path).extension().and_then(OsStr::to_str);
-    if let Some(target_extension) = target_extension {
-        for extension in extensions.as_deref().unwrap() {
-            if extension == target_extension {
-                println!("{}", entry.path);
+fn interactive(search: &Search, printer: &mut Printer) -> Result<ExitCode> {
+    let (sender, receiver) = std::sync::mpsc::channel();
+
+    let picked = std::thread::scope(|scope| {
+        let walk = scope.spawn(move || {
+            search.run(&mut |label, entry| {
+                let label = label.map(str::to_owned);
+                sender.send(Match::new(label, entry)).is_ok()
+            })
+        });
+
+        let picked = picker::pick(receiver);
+        search.interrupt.store(true, Ordering::Relaxed);
+        walk.join().expect("search thread panicked")?;
+        picked
+    })?;
+
+    match picked {
+        Picked::Aborted => Ok(ExitCode::from(130)),
+        Picked::Selection(selection) if selection.is_empty() => Ok(ExitCode::FAILURE),
+        Picked::Selection(selection) => {
+            for item in &selection {
+                printer.print(item);
             }
+            printer.finish()?;
+            Ok(ExitCode::SUCCESS)
         }
     }
 }
 
-fn match_regex(regex: &Option<Regex>, entry: &ParsedEntry) {
-    let path = &entry.path;
-    if regex.as_ref().unwrap().is_match(path) {
-        println!("{}", path);
+fn mark_used(path: &Path) -> Result<()> {
+    let path = std::fs::canonicalize(path)?;
+
+    FrecencyStore::update(|store| store.mark_used(path))
+}
+
+/// Writes out what `quaero completions` or `quaero man` generated, in one go as the
+/// generators panic on a closed pipe. Whoever closed it has seen all they wanted.
+fn print_generated(generated: &[u8]) -> Result<()> {
+    match std::io::stdout().write_all(generated) {
+        Err(error) if error.kind() != std::io::ErrorKind::BrokenPipe => Err(error.into()),
+        _ => Ok(()),
     }
 }
 
-fn match_target_and_type(
-    target: &Option<String>,
-    target_type: &Option<FileType>,
-    entry: &ParsedEntry,
-) {
-    if entry.name == *target.as_deref().unwrap() && entry.file_type == target_type.unwrap() {
-        println!("{}", entry.path);
+fn man(out_dir: Option<&Path>) -> Result<()> {
+    let command = i18n::localize_command(Cli::command());
+    let Some(out_dir) = out_dir else {
+        let mut page = Vec::new();
+        clap_mangen::Man::new(command).render(&mut page)?;
+        return print_generated(&page);
+    };
+
+    std::fs::create_dir_all(out_dir).with_context(|| out_dir.display().to_string())?;
+    clap_mangen::generate_to(command, out_dir).with_context(|| out_dir.display().to_string())
+}
+
+fn config(command: &ConfigCommand) -> Result<ExitCode> {
+    let location = match command {
+        ConfigCommand::Check { file: Some(file) } => file.clone(),
+        _ => Config::location().ok_or_else(|| anyhow::anyhow!(tr!("error-no-config-location")))?,
+    };
+    let path = location.display().to_string();
+
+    match command {
+        ConfigCommand::Check { .. } => {
+            let problems = Config::check(&location)?;
+            for problem in &problems {
+                println!(
+                    "{}:{}:{}: {}",
+                    path, problem.line, problem.column, problem.message
+                );
+            }
+
+            if !problems.is_empty() {
+                return Ok(ExitCode::FAILURE);
+            }
+            eprintln!("{}", tr!("config-ok", "path" => path));
+        }
+        ConfigCommand::Init { force } => {
+            Config::init(&location, *force)?;
+            eprintln!("{}", tr!("config-written", "path" => path));
+        }
     }
+
+    Ok(ExitCode::SUCCESS)
 }
 
-#[derive(Parser)]
-#[command(version, about, long_about = None)]
-struct Cli {
-    /// Target to find
-    target: Option<String>,
+fn index(command: &IndexCommand) -> Result<()> {
+    match command {
+        IndexCommand::Build {
+            root,
+            unrestricted,
+            avoids,
+        } => {
+            let index = Index::build(root, *unrestricted, avoids.clone())?;
+            index.save()?;
+            println!(
+                "{}",
+                tr!(
+                    "index-built",
+                    "root" => index.root().display().to_string(),
+                    "entries" => index.len(),
+                )
+            );
+        }
+        IndexCommand::Update { root } => {
+            let indexes = match root {
+                Some(root) => vec![Index::of(root)?.ok_or_else(|| {
+                    anyhow::anyhow!(tr!("error-no-index", "path" => root.display().to_string()))
+                })?],
+                None => Index::all()?,
+            };
 
-    /// Directory from where to start searching
-    #[clap(name = "from", long, short)]
-    start_directory: Option<String>,
+            for mut index in indexes {
+                let read = index.update()?;
+                index.save()?;
+                println!(
+                    "{}",
+                    tr!(
+                        "index-updated",
+                        "root" => index.root().display().to_string(),
+                        "entries" => index.len(),
+                        "directories" => read,
+                    )
+                );
+            }
+        }
+    }
 
-    /// File type to look for
-    #[clap(name = "type", long, short, value_enum)]
-    file_type: Option<FileType>,
+    Ok(())
+}
 
-    /// Directories to avoid
-    #[clap(name = "avoid", long, short, num_args = 0.., value_delimiter = ' ')]
-    avoids: Option<Vec<PathBuf>>,
+/// The option of `command` or of any of its subcommands called `long`, e.g. --from.
+fn find_option<'a>(command: &'a clap::Command, long: &str) -> Option<&'a clap::Arg> {
+    command
+        .get_arguments()
+        .find(|arg| arg.get_long() == Some(long))
+        .or_else(|| {
+            command
+                .get_subcommands()
+                .find_map(|subcommand| find_option(subcommand, long))
+        })
+}
 
-    /// Extension to look for (without .)
-    #[clap(name = "extension", long, short, num_args = 0.., value_delimiter = ' ')]
-    extensions: Option<Vec<String>>,
+/// The command line as it was typed, but for the option `rendered` as clap writes it
+/// in an error, such as `--from <DIR>`, given long, short or with its value attached.
+/// `None` if it isn't an option or wasn't found there.
+fn without_option(command: &clap::Command, rendered: &str) -> Option<String> {
+    let long = rendered.split_whitespace().next()?.strip_prefix("--")?;
+    let arg = find_option(command, long)?;
+    let long = format!("--{}", long);
+    let short = arg.get_short().map(|short| format!("-{}", short));
+    let takes_values = arg
+        .get_num_args()
+        .map_or(arg.get_action().takes_values(), |range| {
+            range.takes_values()
+        });
+    let many = arg
+        .get_num_args()
+        .is_some_and(|range| range.max_values() > 1);
 
-    /// How many nested subdirectories to walk into
-    #[clap(name = "depth", long, short)]
-    depth: Option<usize>,
+    let (mut kept, mut found) = (Vec::new(), false);
+    let mut tokens = std::env::args_os().skip(1).peekable();
+    while let Some(token) = tokens.next() {
+        if token == "--" {
+            kept.push(token);
+            kept.extend(tokens.by_ref());
+            break;
+        }
+
+        let bytes = token.as_bytes();
+        let exact = bytes == long.as_bytes()
+            || short
+                .as_ref()
+                .is_some_and(|short| bytes == short.as_bytes());
+        let attached = takes_values
+            && (bytes.starts_with(format!("{}=", long).as_bytes())
+                || short
+                    .as_ref()
+                    .is_some_and(|short| bytes.starts_with(short.as_bytes())));
+        if exact && takes_values {
+            tokens.next();
+            while many
+                && tokens
+                    .peek()
+                    .is_some_and(|next| !next.as_bytes().starts_with(b"-"))
+            {
+                tokens.next();
+            }
+        }
+        match exact || attached {
+            true => found = true,
+            false => kept.push(token),
+        }
+    }
 
-    #[clap(name = "regex", long, short)]
-    regex: Option<Regex>,
+    found.then(|| {
+        std::iter::once(command.get_name().to_owned())
+            .chain(
+                kept.iter()
+                    .map(|token| quaero::shell_quote(token).into_owned()),
+            )
+            .collect::<Vec<_>>()
+            .join(" ")
+    })
 }
 
-fn deduce_search_mode(args: &Cli) -> Result<SearchMode> {
-    match (&args.target, args.file_type, &args.extensions, &args.regex) {
-        (Some(_), None, ..) => return Ok(SearchMode::Target),
-        (None, Some(_), ..) => return Ok(SearchMode::Type),
-        (.., Some(_), _) => return Ok(SearchMode::Extension),
-        (.., Some(_)) => return Ok(SearchMode::Regex),
-        (Some(_), Some(_), ..) => return Ok(SearchMode::TargetAndType),
-        _ => {
-            return Err(anyhow::anyhow!(
-                "Either a target to find or a file type to search must be specified"
-            ))
+/// Adds to an error about options that can't be used together the command lines
+/// without each of them, for the closest invocation that would run.
+fn suggest_invocations(mut error: clap::Error, command: &clap::Command) -> clap::Error {
+    use clap::error::{ContextKind, ContextValue, ErrorKind};
+
+    if error.kind() != ErrorKind::ArgumentConflict {
+        return error;
+    }
+    let mut conflicting = Vec::new();
+    for kind in [ContextKind::InvalidArg, ContextKind::PriorArg] {
+        match error.get(kind) {
+            Some(ContextValue::String(arg)) => conflicting.push(arg.clone()),
+            Some(ContextValue::Strings(args)) => conflicting.extend(args.iter().cloned()),
+            _ => {}
         }
     }
+
+    let mut command = command.clone();
+    command.build();
+    let suggestions = conflicting
+        .iter()
+        .filter_map(|arg| {
+            let line = without_option(&command, arg)?;
+            let option = arg.split_whitespace().next().unwrap_or(arg);
+            Some(tr!("conflict-without", "option" => option, "command" => line).into())
+        })
+        .collect::<Vec<_>>();
+    if !suggestions.is_empty() {
+        error.insert(
+            ContextKind::Suggested,
+            ContextValue::StyledStrs(suggestions),
+        );
+    }
+    error
 }
 
-fn main() -> Result<()> {
-    let args = Cli::parse();
+/// The command line, with the options in QUAERO_OPTS put in front of the ones given so
+/// that those override them. They go after the name of a subcommand that searches, and
+/// aren't used at all with other subcommands or --no-config.
+fn arguments() -> Vec<OsString> {
+    let mut args = std::env::args_os().collect::<Vec<_>>();
+    let bypassed = args
+        .iter()
+        .skip(1)
+        .take_while(|arg| *arg != "--")
+        .any(|arg| arg == "--no-config");
+    let Some(options) = std::env::var_os("QUAERO_OPTS").filter(|_| !bypassed) else {
+        return args;
+    };
 
-    let search_mode = deduce_search_mode(&args)?;
-    let dispatcher = |entry: &ParsedEntry| match search_mode {
-        SearchMode::Target => match_target(&args.target, entry),
-        SearchMode::Type => match_type(&args.file_type, entry),
-        SearchMode::Extension => match_extensions(&args.extensions, entry),
-        SearchMode::Regex => match_regex(&args.regex, entry),
-        SearchMode::TargetAndType => match_target_and_type(&args.target, &args.file_type, entry),
+    let at = match args.get(1).and_then(|arg| arg.to_str()) {
+        Some(name) if SEARCH_SUBCOMMANDS.contains(&name) => 2,
+        Some("help") => return args,
+        Some(name) if Cli::command().find_subcommand(name).is_some() => return args,
+        _ => 1,
     };
+    let options = options
+        .as_bytes()
+        .split(|byte| byte.is_ascii_whitespace())
+        .filter(|option| !option.is_empty())
+        .map(|option| OsStr::from_bytes(option).to_owned())
+        .collect::<Vec<_>>();
+    args.splice(at..at, options);
+    args
+}
 
-    let start_directory = args.start_directory.unwrap_or(".".to_owned());
-    let depth = args.depth.unwrap_or(std::usize::MAX);
-    walk_directory(start_directory, &args.avoids, depth, &dispatcher)?;
+/// Fills in what the command line left to the defaults of the config file, and reads
+/// the lists of --ignore-file.
+fn apply_defaults(filters: &mut Filters, config: &Config) -> Result<()> {
+    let defaults = &config.defaults;
+    filters.excludes = defaults
+        .excludes
+        .iter()
+        .map(|exclude| exclude.get_ref().clone())
+        .collect();
+    if filters.unrestricted == 0 {
+        if let Some(level) = &defaults.unrestricted {
+            filters.unrestricted = *level.get_ref();
+        }
+    }
+    if filters.threads.is_none() {
+        filters.threads = defaults.threads.as_ref().map(|threads| *threads.get_ref());
+    }
 
+    let directory = std::env::current_dir()?;
+    filters.ignore_lists = filters
+        .ignore_files
+        .iter()
+        .map(|path| IgnorePolicy::read_list(&directory, path))
+        .collect::<Result<_>>()?;
     Ok(())
 }
+
+fn main() -> ExitCode {
+    i18n::init(i18n::requested_language().as_deref());
+    diagnostics::init(diagnostics::requested_format());
+    run().unwrap_or_else(|error| diagnostics::fatal(&error))
+}
+
+fn run() -> Result<ExitCode> {
+    let command = i18n::localize_command(Cli::command());
+    let matches = command
+        .clone()
+        .try_get_matches_from(arguments())
+        .map_err(|error| suggest_invocations(error, &command))
+        .unwrap_or_else(|error| diagnostics::command_line(error));
+    let mut args =
+        Cli::from_arg_matches(&matches).unwrap_or_else(|error| diagnostics::command_line(error));
+
+    if args.no_config {
+        Config::bypass();
+    }
+    if args.bytes {
+        output::raw_sizes();
+    }
+    // The other subcommands don't search, and `quaero config` has to get by with a
+    // config file that doesn't parse.
+    let filters = match &mut args.command {
+        Some(command) => command.filters(),
+        None => Some(&mut args.filters),
+    };
+    let settings = match filters {
+        Some(filters) => {
+            let settings = Config::load()?;
+            apply_defaults(filters, &settings)?;
+            check_capabilities(filters);
+            if filters.repo {
+                let root = git::root(Path::new("."))?;
+                filters.start_directory = Some(root);
+            }
+            if let Some(threads) = filters.threads {
+                rayon::ThreadPoolBuilder::new()
+                    .num_threads(threads)
+                    .build_global()?;
+            }
+            settings
+        }
+        None => Config::default(),
+    };
+
+    match &mut args.command {
+        Some(Command::MarkUsed { path }) => {
+            mark_used(path)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Dupes {
+            filters,
+            delete_dupes,
+            keep,
+            dry_run,
+        }) => {
+            let search = Search {
+                args: filters,
+                matcher: build_matcher(filters)?.optimize(),
+                stats: Arc::default(),
+                interrupt: Arc::default(),
+                checkpoint: None,
+            };
+            dupes::dupes(&search, delete_dupes.then_some(*keep), *dry_run)?;
+            return Ok(diagnostics::exit_code(true));
+        }
+        Some(Command::Du { filters }) => {
+            du::du(filters)?;
+            return Ok(diagnostics::exit_code(true));
+        }
+        Some(Command::Clean {
+            directory,
+            older_than,
+            patterns,
+            keep_latest,
+            dry_run,
+            filters,
+        }) => {
+            clean::clean(
+                filters,
+                directory,
+                patterns,
+                *older_than,
+                *keep_latest,
+                *dry_run,
+            )?;
+            return Ok(diagnostics::exit_code(true));
+        }
+        Some(Command::Audit { filters }) => {
+            audit::audit(filters)?;
+            return Ok(diagnostics::exit_code(true));
+        }
+        Some(Command::Collisions { directory, filters }) => {
+            let found = collisions::collisions(filters, directory.as_deref())?;
+            return Ok(diagnostics::exit_code(found));
+        }
+        Some(Command::Batch { queries, filters }) => {
+            batch::batch(queries, filters)?;
+            return Ok(diagnostics::exit_code(true));
+        }
+        Some(Command::UndoRename { log }) => {
+            rename::undo(log.as_deref())?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Index { command }) => {
+            index(command)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Config { command }) => return config(command),
+        Some(Command::Capabilities) => {
+            capabilities::print(&mut std::io::stdout().lock())?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Bench {
+            directory,
+            threads,
+            runs,
+            extension,
+        }) => {
+            let threads = threads.iter().map(|threads| *threads as usize);
+            bench::bench(
+                directory,
+                &threads.collect::<Vec<_>>(),
+                *runs as usize,
+                extension.as_deref(),
+            )?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Completions { shell }) => {
+            let mut command = i18n::localize_command(Cli::command());
+            let name = command.get_name().to_owned();
+            let mut script = Vec::new();
+            clap_complete::generate(*shell, &mut command, name, &mut script);
+            print_generated(&script)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Man { out_dir }) => {
+            man(out_dir.as_deref())?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        #[cfg(feature = "self-update")]
+        Some(Command::SelfUpdate { check }) => {
+            self_update::self_update(*check)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        None => {}
+    }
+
+    let matcher = build_matcher(&args.filters)?;
+    let has_filter = !matches!(&matcher, Matcher::All(matchers) if matchers.is_empty())
+        || args.content.is_some();
+
+    if args.delete && !has_filter {
+        return Err(anyhow::anyhow!(tr!("error-delete-without-filter")));
+    }
+
+    if !has_filter {
+        return Err(anyhow::anyhow!(tr!("error-no-filter")));
+    }
+
+    if args.watch && args.format == Some(OutputFormat::Tree) {
+        return Err(anyhow::anyhow!(tr!("error-watch-tree")));
+    }
+    if (args.checkpoint.is_some() || args.resume.is_some())
+        && args.format == Some(OutputFormat::Tree)
+    {
+        return Err(anyhow::anyhow!(tr!("error-checkpoint-tree")));
+    }
+    if args.format == Some(OutputFormat::NdjsonEvents)
+        && (args.group_by.is_some() || args.bucket_by_age.is_some())
+    {
+        return Err(anyhow::anyhow!(tr!("error-events-sections")));
+    }
+    if args.bucket_by_age.is_some() && !matches!(args.format, None | Some(OutputFormat::Ndjson)) {
+        return Err(anyhow::anyhow!(tr!("error-bucket-format")));
+    }
+
+    if args.sort == Some(SortKey::Score) && args.filters.fuzzy.is_none() {
+        return Err(anyhow::anyhow!(tr!("error-score-without-fuzzy")));
+    }
+
+    // With --stats every filter keeps count of what it accepts and rejects.
+    let mut counters = Vec::new();
+    let matcher = match matcher {
+        Matcher::All(matchers) if args.stats => Matcher::All(
+            matchers
+                .into_iter()
+                .map(|matcher| {
+                    let counter = Arc::new(MatchCounter::default());
+                    counters.push((matcher.to_string(), counter.clone()));
+                    Matcher::Counted(Box::new(matcher), counter)
+                })
+                .collect(),
+        ),
+        matcher => matcher,
+    }
+    .optimize();
+
+    let started = Instant::now();
+    let search = Search {
+        args: &args.filters,
+        matcher,
+        stats: Arc::default(),
+        interrupt: Arc::default(),
+        checkpoint: match &args.resume {
+            Some(path) => Some(Arc::new(resume::load(path)?)),
+            None => args.checkpoint.is_some().then(Arc::default),
+        },
+    };
+    let mut matched_size = 0;
+
+    let configured = |template: Template| match template.splits_extensions() {
+        true => template.compound_extensions(settings.compound_extensions()),
+        false => template,
+    };
+    // Sorting goes by what the fields are, not by how long ago.
+    let template = args
+        .template
+        .clone()
+        .map(configured)
+        .map(|template| match args.human_readable {
+            true => template.human_readable(),
+            false => template,
+        })
+        .map(|template| match args.iso_time {
+            true => template.iso_times(),
+            false => template,
+        });
+    let sort_by = args.sort_by.clone().map(configured);
+    let mut printer = Printer::new(
+        template,
+        args.format,
+        args.filters.workspace.is_some(),
+        args.hash.is_some(),
+    );
+    if args.filters.deterministic {
+        printer = printer.deterministic();
+    }
+    if let Some(path) = &args.output {
+        printer = printer.output(path)?;
+    }
+    // What is printed is out for good before the checkpoint says its directory is done.
+    if args.watch || search.checkpoint.is_some() {
+        printer = printer.line_buffered();
+    }
+    if args.hyperlink {
+        printer = printer.hyperlinks();
+    }
+    if args.quote {
+        printer = printer.quoted();
+    }
+    if args.show_link_targets {
+        printer = printer.link_targets();
+    }
+    // The groups of --group-by have columns of their own.
+    if args.group_by.is_none() && args.bucket_by_age.is_none() {
+        printer.header();
+    }
+    // Errors written to a file of their own would be out of order with the rest.
+    if args.format == Some(OutputFormat::NdjsonEvents) && args.output.is_none() {
+        diagnostics::as_events();
+    }
+
+    if args.interactive {
+        return interactive(&search, &mut printer);
+    }
+
+    let mut also = FanOut::new(
+        &args.also_outputs,
+        args.filters.workspace.is_some(),
+        args.hash.is_some(),
+        args.filters.deterministic,
+    )?;
+
+    let mut pipeline = Pipeline::default();
+    // One walk of a tree finds every path once; --watch finds them again on purpose.
+    let overlapping = args.filters.workspace.is_some()
+        || search.follow() == Follow::Always
+        || args.filters.stdin
+        || args.filters.mdfind.is_some()
+        || args.filters.from_locate;
+    if overlapping && !args.no_dedup && !args.watch {
+        pipeline.push(Dedup::default());
+    }
+    if let Some(command) = &args.filter_cmd {
+        let jobs = args.jobs.unwrap_or_else(|| {
+            std::thread::available_parallelism().map_or(1, std::num::NonZeroUsize::get)
+        });
+        pipeline.push(FilterCommand::new(command.clone(), jobs));
+    }
+    if args.unique_inodes {
+        pipeline.push(UniqueInodes::default());
+    }
+    if let Some(max) = args.max_per_dir {
+        pipeline.push(PerDirectory::new(max));
+    }
+    if let Some(command) = &args.pipe_through {
+        pipeline.push(PipeThrough::new(command.clone()));
+    }
+    if args.physical {
+        pipeline.push(Physical::default());
+    }
+    if args.absolute_path {
+        pipeline.push(Absolute::new()?);
+    }
+    if args.canonicalize {
+        pipeline.push(Canonical);
+    }
+    if let Some(algorithm) = args.hash {
+        pipeline.push(Hashes::new(algorithm));
+    }
+    for rewrite in &args.rewrites {
+        pipeline.push(rewrite.clone());
+    }
+
+    let mut summary = args
+        .report
+        .map(|report| report::summary(report, args.buckets.as_deref(), args.top, args.top_depth))
+        .or_else(|| {
+            let compound = settings.compound_extensions();
+            let groups = args
+                .group_by
+                .map(|by| Groups::new(by, args.format, compound))?;
+            Some(Box::new(groups) as Box<dyn Summary>)
+        });
+    let mut matches = Vec::new();
+    let mut count = 0;
+    let tree = args.format == Some(OutputFormat::Tree);
+    let sorted = args.sort.is_some() || sort_by.is_some();
+    let buckets = args.bucket_by_age.as_deref().map(AgeBuckets::new);
+    let ordered = sorted || args.page_size.is_some() || buckets.is_some();
+    let transfer = match (&args.copy_to, &args.move_to) {
+        (Some(to), _) => Some((Transfer::Copy, to)),
+        (None, Some(to)) => Some((Transfer::Move, to)),
+        (None, None) => None,
+    };
+    let act = args.delete
+        || transfer.is_some()
+        || args.touch
+        || args.chmod.is_some()
+        || args.chown.is_some()
+        || args.replace.is_some()
+        || args.rename.is_some();
+    let collect = act || args.content.is_some() || ordered || tree;
+    // Only a terminal has anyone watching it.
+    let progress =
+        (std::io::stdout().is_terminal() && std::io::stderr().is_terminal() && !args.quiet)
+            .then(|| Progress::start(search.stats.clone()));
+    // Returns whether the search should keep going.
+    let mut emit = |item: Match| {
+        count += 1;
+        if let Some(progress) = &progress {
+            progress.found();
+        }
+        if !item.entry.is_dir() {
+            matched_size += item.entry.size();
+        }
+
+        if args.quiet {
+            return false;
+        }
+
+        also.print(&item);
+        if let Some(summary) = &mut summary {
+            summary.add(&item);
+        } else if collect {
+            matches.push(item);
+        } else if !args.count {
+            progress::interject(|| printer.print(&item));
+        }
+
+        !printer.failed() && !also.failed()
+    };
+    let mut on_match = |label: Option<&str>, entry: Entry| {
+        let label = label.map(str::to_owned);
+        match pipeline.process(Match::new(label, entry)) {
+            Some(item) => emit(item),
+            None => true,
+        }
+    };
+
+    // Ctrl-C ends the walk instead of the process, for what was found so far to be
+    // written out; a second one doesn't wait for that.
+    let interrupted = Arc::new(AtomicBool::new(false));
+    if !args.watch {
+        let (interrupted, interrupt) = (interrupted.clone(), search.interrupt.clone());
+        ctrlc::set_handler(move || {
+            if interrupted.swap(true, Ordering::Relaxed) {
+                std::process::exit(130);
+            }
+            interrupt.store(true, Ordering::Relaxed);
+        })?;
+    }
+
+    // The walk is cut short the same way, and the rest goes on with what it found.
+    let timed_out = Arc::new(AtomicBool::new(false));
+    if let Some(timeout) = args.timeout {
+        let (timed_out, interrupt) = (timed_out.clone(), search.interrupt.clone());
+        std::thread::spawn(move || {
+            std::thread::sleep(timeout);
+            timed_out.store(true, Ordering::Relaxed);
+            interrupt.store(true, Ordering::Relaxed);
+        });
+    }
+
+    let checkpointer = search.checkpoint.as_ref().map(|checkpoint| {
+        let path = args.checkpoint.clone().or_else(|| args.resume.clone());
+        Checkpointer::start(path.expect("one is given"), checkpoint.clone())
+    });
+    search.run(&mut on_match)?;
+    if let Some(progress) = &progress {
+        progress.finish();
+    }
+    if let Some(checkpointer) = checkpointer {
+        checkpointer
+            .finish(!interrupted.load(Ordering::Relaxed) && !timed_out.load(Ordering::Relaxed))?;
+    }
+    // Whatever stopped the walk first says how it ended.
+    let timed_out = timed_out.load(Ordering::Relaxed) && !interrupted.load(Ordering::Relaxed);
+    let done = |count: u64| match timed_out {
+        true => ExitCode::from(124),
+        false => diagnostics::exit_code(count > 0),
+    };
+    let totals = |count: u64, matched_size: u64, status: &'static str| Totals {
+        matches: count,
+        size: matched_size,
+        entries: search.stats.entries.load(Ordering::Relaxed),
+        directories: search.stats.directories.load(Ordering::Relaxed),
+        errors: diagnostics::reported(),
+        vanished: search.stats.vanished.load(Ordering::Relaxed),
+        elapsed_ms: (!args.filters.deterministic).then(|| started.elapsed().as_millis()),
+        status,
+    };
+    if interrupted.load(Ordering::Relaxed) {
+        also.finish()?;
+        printer.summary(&totals(count, matched_size, "interrupted"));
+        printer.finish()?;
+        eprintln!(
+            "{}",
+            tr!(
+                "interrupted",
+                "entries" => search.stats.entries.load(Ordering::Relaxed),
+                "matches" => count,
+            )
+        );
+        return Ok(ExitCode::from(130));
+    }
+    if args.watch {
+        let options = watch::Options {
+            exec_on_new: args.exec_on_new.clone(),
+            exec_batch: args.exec_batch.map(|most| most as usize),
+            debounce: args.debounce,
+            coalesce_dir: args.coalesce_dir,
+            alert_growth: args.alert_growth,
+            alert_command: args.alert_command.clone(),
+            metadata: args.watch_metadata,
+        };
+        return watch::watch(&search, options, on_match).map(|()| ExitCode::SUCCESS);
+    }
+
+    for item in pipeline.finish()? {
+        if !emit(item) {
+            break;
+        }
+    }
+
+    if timed_out {
+        eprintln!(
+            "{}",
+            tr!(
+                "timed-out",
+                "entries" => search.stats.entries.load(Ordering::Relaxed),
+                "matches" => count,
+            )
+        );
+    }
+    also.finish()?;
+    if args.also_count {
+        eprintln!("{}", tr!("also-count", "count" => count));
+    }
+
+    if args.stats {
+        print_stats(&search.stats, count, matched_size, started.elapsed());
+        print_filter_stats(&search.matcher, &counters);
+    }
+
+    // A match is all --quiet asks about, however long the rest would have taken.
+    if args.quiet {
+        return Ok(match count {
+            0 => done(count),
+            _ => diagnostics::exit_code(true),
+        });
+    }
+
+    if args.count {
+        printer.write(|out| writeln!(out, "{}", count));
+        printer.finish()?;
+        return Ok(done(count));
+    }
+
+    if let Some(summary) = &summary {
+        printer.write(|out| summary.print(out));
+        printer.finish()?;
+        return Ok(done(count));
+    }
+
+    // Matches that sort the same are left in the order they were found in otherwise,
+    // which can change from one run to the next.
+    if args.page_size.is_some() {
+        matches.sort_by(|a, b| {
+            let (a_path, b_path) = (a.entry.path.as_os_str(), b.entry.path.as_os_str());
+            (a_path.as_bytes(), &a.label).cmp(&(b_path.as_bytes(), &b.label))
+        });
+    }
+    if let Some(key) = args.sort {
+        let fuzzy = args.filters.fuzzy.as_deref();
+        sort::sort(&mut matches, key, fuzzy, args.filters.full_path, |item| {
+            &item.entry
+        })?;
+    }
+    if let Some(template) = &sort_by {
+        sort::sort_by_template(&mut matches, template);
+    }
+    if let Some(size) = args.page_size {
+        let pages = matches.len().div_ceil(size);
+        let start = (args.page - 1).saturating_mul(size).min(matches.len());
+        let end = start.saturating_add(size).min(matches.len());
+        eprintln!(
+            "{}",
+            tr!(
+                "page-summary",
+                "page" => args.page,
+                "pages" => pages,
+                "matches" => matches.len(),
+            )
+        );
+        matches.truncate(end);
+        matches.drain(..start);
+        printer.skip(start as u64);
+    }
+
+    if let Some(buckets) = &buckets {
+        buckets.print(&matches, sorted, args.format, &mut printer);
+    } else if ordered && !tree {
+        for item in &matches {
+            printer.print(item);
+            if printer.failed() {
+                break;
+            }
+        }
+    }
+
+    if tree {
+        printer.write(|out| tree::print(&matches, ordered, out));
+    }
+
+    if let Some(regex) = &args.content {
+        let entries = matches.iter().map(|item| &item.entry).collect::<Vec<_>>();
+        // Hits come in from several threads at once.
+        let printer = std::sync::Mutex::new(&mut printer);
+        let print = |index: usize, hits: Result<Vec<content::ContentMatch>>| {
+            let hits = match hits {
+                Ok(hits) => hits,
+                Err(error) => return report_error(&error),
+            };
+            let Match { label, entry, .. } = &matches[index];
+            let path = labeled(label.as_deref(), &entry.path);
+            printer.lock().expect("printer lock poisoned").write(|out| {
+                for hit in hits {
+                    writeln!(out, "{}:{}:{}", path, hit.line_number, hit.line)?;
+                }
+                Ok(())
+            });
+        };
+
+        // Files are searched in parallel, so they are done in any order.
+        if args.filters.deterministic {
+            let found = std::sync::Mutex::new(Vec::new());
+            content::search(&entries, regex, |index, hits| {
+                found
+                    .lock()
+                    .expect("hits lock poisoned")
+                    .push((index, hits));
+            });
+            let mut found = found.into_inner().expect("hits lock poisoned");
+            found.sort_by_key(|(index, _)| *index);
+            for (index, hits) in found {
+                print(index, hits);
+            }
+        } else {
+            content::search(&entries, regex, print);
+        }
+    }
+
+    if act {
+        let entries = matches
+            .into_iter()
+            .map(|item| item.entry)
+            .collect::<Vec<_>>();
+        match transfer {
+            _ if args.delete => {
+                delete::delete(&entries, args.delete_dirs, args.dry_run, &mut printer)?
+            }
+            Some((transfer, to)) => transfer::transfer(
+                &entries,
+                transfer,
+                to,
+                args.preserve_structure,
+                args.dry_run,
+                &mut printer,
+            )?,
+            None => {
+                if let Some(substitution) = &args.rename {
+                    rename::rename(&entries, substitution, args.dry_run, &mut printer)?;
+                } else if let Some((regex, with)) = &args.replace {
+                    replace::replace(&entries, regex, with, args.dry_run, &mut printer)?;
+                } else if args.touch {
+                    let time = args.mtime.unwrap_or_else(SystemTime::now);
+                    touch::touch(&entries, time, args.dry_run, &mut printer)?;
+                } else {
+                    change::change(
+                        &entries,
+                        args.chmod.as_ref(),
+                        args.chown,
+                        args.dry_run,
+                        &mut printer,
+                    )?;
+                }
+            }
+        }
+    }
+
+    let status = match timed_out {
+        true => "timed_out",
+        false => "complete",
+    };
+    printer.summary(&totals(count, matched_size, status));
+    printer.finish()?;
+    Ok(done(count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn search_subcommands() {
+        let command = Cli::command();
+        let searching = command
+            .get_subcommands()
+            .filter(|subcommand| {
+                subcommand
+                    .get_arguments()
+                    .any(|argument| argument.get_id() == "from")
+            })
+            .map(|subcommand| subcommand.get_name())
+            .collect::<Vec<_>>();
+        assert_eq!(searching, SEARCH_SUBCOMMANDS);
+    }
+}
@@ -0,0 +1,884 @@
+use crate::archive::Member;
+use crate::content::{self, Encoding};
+use crate::git::GitFiles;
+use crate::number::NumberFilter;
+use crate::period::{Period, Unit};
+use crate::perm::{self, ExecutableBy, PermFilter};
+use crate::script::Script;
+use crate::{fuzzy, magic, users, xattr, Entry, FileType};
+use clap::ValueEnum;
+use globset::{Glob, GlobSet};
+use regex::{Regex, RegexSet};
+use std::borrow::Cow;
+use std::ffi::OsStr;
+use std::fs::Metadata;
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, RwLock};
+
+/// How many entries an [`Adaptive`] matcher sees between two reorderings.
+const REORDER_INTERVAL: u64 = 1024;
+
+/// How many entries a [`Matcher::Counted`] was asked about, and how many it accepted.
+#[derive(Debug, Default)]
+pub struct MatchCounter {
+    pub evaluated: AtomicU64,
+    pub matched: AtomicU64,
+}
+
+/// Glob patterns matched all at once, kept along with the set for writing them back.
+#[derive(Debug, Clone)]
+pub struct Globs {
+    globs: Vec<Glob>,
+    set: GlobSet,
+}
+
+impl Globs {
+    pub fn new(globs: Vec<Glob>) -> Result<Self, globset::Error> {
+        let mut builder = GlobSet::builder();
+        for glob in &globs {
+            builder.add(glob.clone());
+        }
+
+        Ok(Self {
+            set: builder.build()?,
+            globs,
+        })
+    }
+
+    pub fn globs(&self) -> &[Glob] {
+        &self.globs
+    }
+
+    /// Whether any of the globs matches `path`, by the bytes it has.
+    pub fn is_match(&self, path: impl AsRef<Path>) -> bool {
+        self.set.is_match(path)
+    }
+}
+
+/// A file as its device and inode tell it apart from every other, whatever path it is
+/// reached by, along with the path it was looked up by.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileId {
+    pub path: PathBuf,
+    pub device: u64,
+    pub inode: u64,
+}
+
+/// A predicate over a single entry. Command line flags and `--expr` expressions are
+/// both turned into a tree of these.
+#[derive(Debug, Clone)]
+pub enum Matcher {
+    Name(String),
+    Type(FileType),
+    Extension(Vec<String>),
+    /// The regular expression matches the name
+    Regex(Regex),
+    Path(String),
+    /// The pattern is a subsequence of the name, see [`fuzzy`](crate::fuzzy)
+    Fuzzy(String),
+    /// Any of the regular expressions matches the name
+    Regexes(RegexSet),
+    /// Any of the globs matches the name
+    Glob(Globs),
+    /// The path ends with these components, e.g. `src/main.rs`
+    PathName(String),
+    /// The regular expression matches the whole path
+    PathRegex(Regex),
+    /// The pattern is a subsequence of the whole path
+    PathFuzzy(String),
+    PathRegexes(RegexSet),
+    /// Any of the globs matches the whole path, `*` stopping at a `/` and `**` not
+    PathGlob(Globs),
+    Size(NumberFilter),
+    /// The bytes allocated on disk, see [`Entry::allocated_size`]
+    AllocatedSize(NumberFilter),
+    /// See [`Entry::is_sparse`]
+    Sparse,
+    Perm(PermFilter),
+    /// Regular files that can be executed by whom it says
+    ExecutableBy(ExecutableBy),
+    /// Entries the user running quaero can read, see [`perm::accessible`]
+    Readable,
+    /// Entries the user running quaero can write to
+    Writable,
+    Nlink(NumberFilter),
+    /// Directories with a number of entries directly in them, see [`Entry::entry_count`]
+    Entries(NumberFilter),
+    Inode(u64),
+    /// How many directories below its root the entry is, see [`Entry::depth`]
+    Depth(usize),
+    /// Paths of a number of components, `.` and `..` left out
+    Components(NumberFilter),
+    /// Entries some levels below a directory of a name, its children being 1 below it
+    Below(String, NumberFilter),
+    /// Hard links to the same file, see [`FileId`]
+    SameFile(FileId),
+    /// Files git reports with any of some statuses, see [`git::files`](crate::git::files)
+    GitStatus(GitFiles),
+    /// Last modified within a day or month of the calendar
+    Modified(Period),
+    Uid(u32),
+    Gid(u32),
+    NoUser,
+    NoGroup,
+    /// Regular files whose contents are of a MIME type, see [`magic::mime_matches`]
+    Mime(String),
+    /// Regular files whose contents are of a [`magic::Kind`], by name
+    Magic(String),
+    /// Regular files without a NUL byte early on, see [`content::is_binary_file`]
+    Text,
+    /// Regular files with a NUL byte early on
+    Binary,
+    /// Regular files valid throughout in an encoding
+    Encoding(Encoding),
+    /// Text files with a number of lines, see [`content::line_stats`]
+    Lines(NumberFilter),
+    /// Text files whose longest line is of a length, in characters
+    LineLength(NumberFilter),
+    /// Entries with an extended attribute of this name, see [`xattr::value`]
+    HasXattr(String),
+    /// Entries whose extended attribute of the first name has the second as its value
+    Xattr(String, String),
+    /// Entries a Lua script says match, see [`Script`]
+    Script(Arc<Script>),
+    /// Symbolic links whose target doesn't exist
+    BrokenLink,
+    /// Symbolic links whose target, resolved, matches
+    LinksTo(Regex),
+    Not(Box<Matcher>),
+    All(Vec<Matcher>),
+    Any(Vec<Matcher>),
+    /// Matches like the inner matcher, keeping count of its verdicts
+    Counted(Box<Matcher>, Arc<MatchCounter>),
+    /// Matches like [`Matcher::All`], see [`Matcher::optimize`]
+    Adaptive(Arc<Adaptive>),
+}
+
+/// A conjunction that keeps reordering its matchers while it runs, so that the ones
+/// rejecting the most entries for their cost go first.
+#[derive(Debug)]
+pub struct Adaptive {
+    matchers: Vec<Matcher>,
+    counters: Vec<MatchCounter>,
+    /// Indices into `matchers`, in evaluation order
+    order: RwLock<Vec<usize>>,
+    evaluated: AtomicU64,
+}
+
+impl Adaptive {
+    fn new(matchers: Vec<Matcher>) -> Self {
+        let mut order = (0..matchers.len()).collect::<Vec<_>>();
+        order.sort_by_key(|index| matchers[*index].cost());
+
+        Self {
+            counters: matchers.iter().map(|_| MatchCounter::default()).collect(),
+            matchers,
+            order: RwLock::new(order),
+            evaluated: AtomicU64::new(0),
+        }
+    }
+
+    /// The matchers it was built from, in the order they were given.
+    pub fn matchers(&self) -> &[Matcher] {
+        &self.matchers
+    }
+
+    /// Indices of the matchers it was built from, in the order they are evaluated.
+    pub fn order(&self) -> Vec<usize> {
+        self.order.read().expect("order lock poisoned").clone()
+    }
+
+    fn matches(&self, entry: &Entry) -> bool {
+        if self.evaluated.fetch_add(1, Ordering::Relaxed) % REORDER_INTERVAL == REORDER_INTERVAL - 1
+        {
+            self.reorder();
+        }
+
+        let order = self.order.read().expect("order lock poisoned");
+        order.iter().all(|index| {
+            let counter = &self.counters[*index];
+            counter.evaluated.fetch_add(1, Ordering::Relaxed);
+            let matched = self.matchers[*index].matches(entry);
+            if matched {
+                counter.matched.fetch_add(1, Ordering::Relaxed);
+            }
+            matched
+        })
+    }
+
+    /// Sorts the matchers by their cost per rejected entry. A matcher only sees what the
+    /// ones before it let through, which is close enough for telling them apart.
+    fn reorder(&self) {
+        // Others are matching with the current order; it is just as good for a while.
+        let Ok(mut order) = self.order.try_write() else {
+            return;
+        };
+
+        let rank = |index: usize| {
+            let counter = &self.counters[index];
+            let evaluated = counter.evaluated.load(Ordering::Relaxed);
+            let rejected = evaluated - counter.matched.load(Ordering::Relaxed);
+            let rejects = (rejected + 1) as f64 / (evaluated + 2) as f64;
+            self.matchers[index].cost() as f64 / rejects
+        };
+        order.sort_by(|a, b| rank(*a).total_cmp(&rank(*b)));
+    }
+}
+
+impl Matcher {
+    /// Rough cost of evaluating the matcher once, relative to comparing a name. Filters
+    /// on the path alone come first, then those reading metadata, then those looking
+    /// up the user and group databases.
+    pub fn cost(&self) -> u32 {
+        match self {
+            Matcher::Name(_) | Matcher::Type(_) | Matcher::Depth(_) => 1,
+            Matcher::Extension(_) => 2,
+            Matcher::Path(_)
+            | Matcher::PathName(_)
+            | Matcher::Components(_)
+            | Matcher::Below(..) => 3,
+            Matcher::Regex(_)
+            | Matcher::PathRegex(_)
+            | Matcher::Regexes(_)
+            | Matcher::PathRegexes(_)
+            | Matcher::Glob(_)
+            | Matcher::PathGlob(_) => 8,
+            Matcher::Fuzzy(_) | Matcher::PathFuzzy(_) => 12,
+            Matcher::Size(_)
+            | Matcher::AllocatedSize(_)
+            | Matcher::Sparse
+            | Matcher::Perm(_)
+            | Matcher::Nlink(_)
+            | Matcher::Inode(_)
+            | Matcher::SameFile(_)
+            | Matcher::GitStatus(_)
+            | Matcher::Modified(_)
+            | Matcher::Uid(_)
+            | Matcher::Gid(_) => 16,
+            Matcher::ExecutableBy(ExecutableBy::Me) | Matcher::Readable | Matcher::Writable => 24,
+            Matcher::ExecutableBy(_) => 16,
+            Matcher::NoUser
+            | Matcher::NoGroup
+            | Matcher::HasXattr(_)
+            | Matcher::Xattr(..)
+            | Matcher::BrokenLink
+            | Matcher::LinksTo(_) => 24,
+            Matcher::Mime(_)
+            | Matcher::Magic(_)
+            | Matcher::Text
+            | Matcher::Binary
+            | Matcher::Entries(_)
+            | Matcher::Script(_) => 64,
+            // Reads the whole file.
+            Matcher::Encoding(_) | Matcher::Lines(_) | Matcher::LineLength(_) => 128,
+            Matcher::Not(matcher) | Matcher::Counted(matcher, _) => matcher.cost(),
+            Matcher::All(matchers) | Matcher::Any(matchers) => {
+                matchers.iter().map(Matcher::cost).sum()
+            }
+            Matcher::Adaptive(adaptive) => adaptive.matchers.iter().map(Matcher::cost).sum(),
+        }
+    }
+
+    /// Rearranges the matcher to match the same entries with less work: the operands of
+    /// every `and` and `or` are sorted cheapest first, and a top-level `and` goes on to
+    /// reorder itself by what it sees each operand reject.
+    pub fn optimize(self) -> Matcher {
+        match self {
+            // The operands keep their positions, which `Adaptive::order` refers to.
+            Matcher::All(matchers) if matchers.len() > 1 => {
+                let matchers = matchers.into_iter().map(Matcher::sorted).collect();
+                Matcher::Adaptive(Arc::new(Adaptive::new(matchers)))
+            }
+            matcher => matcher.sorted(),
+        }
+    }
+
+    /// The matcher with `regex:`, `glob:` and `fuzzy:` matching the whole path instead of the
+    /// name, for `--full-path`. `name:` and `path:` say what they match already.
+    pub fn full_path(self) -> Matcher {
+        let full_path = |matchers: Vec<Matcher>| {
+            matchers
+                .into_iter()
+                .map(Matcher::full_path)
+                .collect::<Vec<_>>()
+        };
+
+        match self {
+            Matcher::Regex(regex) => Matcher::PathRegex(regex),
+            Matcher::Fuzzy(pattern) => Matcher::PathFuzzy(pattern),
+            Matcher::Regexes(regexes) => Matcher::PathRegexes(regexes),
+            Matcher::Glob(globs) => Matcher::PathGlob(globs),
+            Matcher::Not(matcher) => Matcher::Not(Box::new(matcher.full_path())),
+            Matcher::Counted(matcher, counter) => {
+                Matcher::Counted(Box::new(matcher.full_path()), counter)
+            }
+            Matcher::All(matchers) => Matcher::All(full_path(matchers)),
+            Matcher::Any(matchers) => Matcher::Any(full_path(matchers)),
+            matcher => matcher,
+        }
+    }
+
+    fn sorted(self) -> Matcher {
+        let sort = |matchers: Vec<Matcher>| {
+            let mut matchers = matchers
+                .into_iter()
+                .map(Matcher::sorted)
+                .collect::<Vec<_>>();
+            matchers.sort_by_key(Matcher::cost);
+            matchers
+        };
+
+        match self {
+            Matcher::Not(matcher) => Matcher::Not(Box::new(matcher.sorted())),
+            Matcher::Counted(matcher, counter) => {
+                Matcher::Counted(Box::new(matcher.sorted()), counter)
+            }
+            Matcher::All(matchers) => Matcher::All(sort(matchers)),
+            Matcher::Any(matchers) => Matcher::Any(sort(matchers)),
+            matcher => matcher,
+        }
+    }
+
+    /// What the matcher says about an entry known only by its path, name and type,
+    /// `None` if that depends on its metadata. This lets an index rule out entries
+    /// without a `stat`; an executable may have become a regular file since it was
+    /// indexed or the other way around, so only the directory and link types count.
+    ///
+    /// Names and paths are compared by their bytes, but for regular expressions and
+    /// fuzzy patterns, which see one that isn't UTF-8 with replacement characters.
+    pub fn matches_path(&self, path: &Path, name: &OsStr, file_type: FileType) -> Option<bool> {
+        let bytes = path.as_os_str().as_bytes();
+        let all = |matchers: &[Matcher]| {
+            let mut decided = Some(true);
+            for matcher in matchers {
+                match matcher.matches_path(path, name, file_type) {
+                    Some(false) => return Some(false),
+                    Some(true) => {}
+                    None => decided = None,
+                }
+            }
+            decided
+        };
+
+        match self {
+            Matcher::Name(target) => Some(name == target.as_str()),
+            Matcher::Type(target) => match (target, file_type) {
+                (
+                    FileType::RegularFile | FileType::Executable,
+                    FileType::RegularFile | FileType::Executable,
+                ) => None,
+                (target, file_type) => Some(*target == file_type),
+            },
+            Matcher::Extension(extensions) => Some(has_extension(bytes, extensions)),
+            Matcher::Regex(regex) => Some(regex.is_match(&name.to_string_lossy())),
+            Matcher::Path(fragment) => Some(contains(bytes, fragment.as_bytes())),
+            Matcher::Components(count) => Some(count.matches(components(bytes).count() as u64)),
+            Matcher::Below(directory, levels) => Some(is_below(bytes, directory, *levels)),
+            Matcher::Fuzzy(pattern) => {
+                Some(fuzzy::matches(pattern, &name.to_string_lossy()).is_some())
+            }
+            Matcher::PathName(components) => Some(ends_with_components(bytes, components)),
+            Matcher::PathRegex(regex) => Some(regex.is_match(&path.to_string_lossy())),
+            Matcher::PathFuzzy(pattern) => {
+                Some(fuzzy::matches(pattern, &path.to_string_lossy()).is_some())
+            }
+            Matcher::Regexes(regexes) => Some(regexes.is_match(&name.to_string_lossy())),
+            Matcher::PathRegexes(regexes) => Some(regexes.is_match(&path.to_string_lossy())),
+            Matcher::Glob(globs) => Some(globs.is_match(name)),
+            Matcher::PathGlob(globs) => Some(globs.is_match(path)),
+            Matcher::Size(_)
+            | Matcher::AllocatedSize(_)
+            | Matcher::Perm(_)
+            | Matcher::Nlink(_)
+            | Matcher::Inode(_)
+            | Matcher::Depth(_)
+            | Matcher::SameFile(_)
+            | Matcher::GitStatus(_)
+            | Matcher::Modified(_)
+            | Matcher::Uid(_)
+            | Matcher::Gid(_)
+            | Matcher::NoUser
+            | Matcher::NoGroup
+            | Matcher::HasXattr(_)
+            | Matcher::Xattr(..)
+            | Matcher::Readable
+            | Matcher::Writable
+            | Matcher::Script(_) => None,
+            // Anything but a file is no kind of file.
+            Matcher::Mime(_)
+            | Matcher::Magic(_)
+            | Matcher::Text
+            | Matcher::Binary
+            | Matcher::Encoding(_)
+            | Matcher::Lines(_)
+            | Matcher::LineLength(_)
+            | Matcher::ExecutableBy(_)
+            | Matcher::Sparse => match file_type {
+                FileType::RegularFile | FileType::Executable => None,
+                _ => Some(false),
+            },
+            Matcher::Entries(_) => match file_type {
+                FileType::Directory => None,
+                _ => Some(false),
+            },
+            // Indexes built before links were told apart from executables have them as
+            // either.
+            Matcher::BrokenLink | Matcher::LinksTo(_) => match file_type {
+                FileType::SymLink | FileType::Executable => None,
+                _ => Some(false),
+            },
+            Matcher::Not(matcher) => matcher
+                .matches_path(path, name, file_type)
+                .map(|matched| !matched),
+            Matcher::All(matchers) => all(matchers),
+            Matcher::Any(matchers) => {
+                let mut decided = Some(false);
+                for matcher in matchers {
+                    match matcher.matches_path(path, name, file_type) {
+                        Some(true) => return Some(true),
+                        Some(false) => {}
+                        None => decided = None,
+                    }
+                }
+                decided
+            }
+            Matcher::Counted(matcher, _) => matcher.matches_path(path, name, file_type),
+            Matcher::Adaptive(adaptive) => all(&adaptive.matchers),
+        }
+    }
+
+    /// What the matcher says about every path starting with `prefix`, `None` if it
+    /// depends on the rest of the path. Only `path:` and `path-fuzzy:` can tell, as a path
+    /// containing a fragment or subsequence keeps it however it goes on.
+    pub fn matches_below(&self, prefix: &str) -> Option<bool> {
+        let all = |matchers: &[Matcher]| {
+            let mut decided = Some(true);
+            for matcher in matchers {
+                match matcher.matches_below(prefix) {
+                    Some(false) => return Some(false),
+                    Some(true) => {}
+                    None => decided = None,
+                }
+            }
+            decided
+        };
+
+        match self {
+            Matcher::Path(fragment) => prefix.contains(fragment.as_str()).then_some(true),
+            Matcher::PathFuzzy(pattern) => fuzzy::matches(pattern, prefix).map(|_| true),
+            Matcher::Not(matcher) => matcher.matches_below(prefix).map(|matched| !matched),
+            Matcher::All(matchers) => all(matchers),
+            Matcher::Any(matchers) => {
+                let mut decided = Some(false);
+                for matcher in matchers {
+                    match matcher.matches_below(prefix) {
+                        Some(true) => return Some(true),
+                        Some(false) => {}
+                        None => decided = None,
+                    }
+                }
+                decided
+            }
+            Matcher::Counted(matcher, _) => matcher.matches_below(prefix),
+            Matcher::Adaptive(adaptive) => all(&adaptive.matchers),
+            _ => None,
+        }
+    }
+
+    /// Whether [`matches_path`](Self::matches_path) looks at more of the path than its
+    /// last component, which the name can't stand in for without it.
+    pub fn looks_at_path(&self) -> bool {
+        match self {
+            Matcher::Path(_)
+            | Matcher::Components(_)
+            | Matcher::Below(..)
+            | Matcher::PathName(_)
+            | Matcher::PathRegex(_)
+            | Matcher::PathFuzzy(_)
+            | Matcher::PathRegexes(_)
+            | Matcher::PathGlob(_) => true,
+            Matcher::Not(matcher) | Matcher::Counted(matcher, _) => matcher.looks_at_path(),
+            Matcher::All(matchers) | Matcher::Any(matchers) => {
+                matchers.iter().any(Matcher::looks_at_path)
+            }
+            Matcher::Adaptive(adaptive) => adaptive.matchers.iter().any(Matcher::looks_at_path),
+            _ => false,
+        }
+    }
+
+    /// Whether a member of an archive matches, `path` being the archive's path and the
+    /// member's joined by [`archive::SEPARATOR`](crate::archive::SEPARATOR). What the
+    /// archive doesn't keep of the member, or only its contents could tell, doesn't
+    /// match.
+    pub fn matches_member(&self, path: &Path, member: &Member) -> bool {
+        let name = OsStr::new(member.name());
+        match self {
+            Matcher::Size(size) => size.matches(member.size),
+            Matcher::Perm(perm) => member.mode.is_some_and(|mode| perm.matches(mode)),
+            // Nobody is about to run a member, so only its bits can tell.
+            Matcher::ExecutableBy(ExecutableBy::Me) => false,
+            // Nor to write to one, or read it but through its archive.
+            Matcher::Readable | Matcher::Writable => false,
+            Matcher::ExecutableBy(by) => {
+                member.file_type == FileType::Executable
+                    && member.mode.is_some_and(|mode| by.matches(path, mode))
+            }
+            // The inode of a member is the archive's, and so is the space it takes.
+            Matcher::AllocatedSize(_)
+            | Matcher::Sparse
+            | Matcher::Nlink(_)
+            | Matcher::Entries(_)
+            | Matcher::Inode(_)
+            | Matcher::SameFile(_)
+            | Matcher::GitStatus(_)
+            | Matcher::HasXattr(_)
+            | Matcher::Xattr(..)
+            | Matcher::Mime(_)
+            | Matcher::Magic(_)
+            | Matcher::Text
+            | Matcher::Binary
+            | Matcher::Encoding(_)
+            | Matcher::Lines(_)
+            | Matcher::LineLength(_)
+            | Matcher::Script(_)
+            | Matcher::BrokenLink
+            | Matcher::LinksTo(_) => false,
+            Matcher::Uid(uid) => member.uid == Some(*uid),
+            Matcher::Gid(gid) => member.gid == Some(*gid),
+            Matcher::NoUser => member.uid.is_some_and(|uid| !users::has_user(uid)),
+            Matcher::NoGroup => member.gid.is_some_and(|gid| !users::has_group(gid)),
+            Matcher::Type(file_type) => member.file_type == *file_type,
+            Matcher::Not(matcher) => !matcher.matches_member(path, member),
+            Matcher::All(matchers) => matchers
+                .iter()
+                .all(|matcher| matcher.matches_member(path, member)),
+            Matcher::Any(matchers) => matchers
+                .iter()
+                .any(|matcher| matcher.matches_member(path, member)),
+            Matcher::Counted(matcher, _) => matcher.matches_member(path, member),
+            Matcher::Adaptive(adaptive) => adaptive
+                .matchers
+                .iter()
+                .all(|matcher| matcher.matches_member(path, member)),
+            matcher => matcher
+                .matches_path(path, name, member.file_type)
+                .unwrap_or(false),
+        }
+    }
+
+    pub fn matches(&self, entry: &Entry) -> bool {
+        // An entry gone by the time its metadata is looked up matches nothing that
+        // needs it.
+        let with_metadata =
+            |matches: &dyn Fn(&Metadata) -> bool| entry.metadata().is_ok_and(matches);
+
+        match self {
+            Matcher::Name(_)
+            | Matcher::Extension(_)
+            | Matcher::Regex(_)
+            | Matcher::Path(_)
+            | Matcher::Components(_)
+            | Matcher::Below(..)
+            | Matcher::Fuzzy(_)
+            | Matcher::PathName(_)
+            | Matcher::PathRegex(_)
+            | Matcher::PathFuzzy(_)
+            | Matcher::Regexes(_)
+            | Matcher::PathRegexes(_)
+            | Matcher::Glob(_)
+            | Matcher::PathGlob(_) => {
+                self.matches_path(&entry.path, &entry.name, entry.listed) == Some(true)
+            }
+            // Only the two kinds of regular files take their metadata to tell apart.
+            Matcher::Type(target @ (FileType::RegularFile | FileType::Executable)) => {
+                entry.is_file() && entry.file_type() == *target
+            }
+            Matcher::Type(target) => entry.listed == *target,
+            Matcher::Size(size) => with_metadata(&|metadata| size.matches(metadata.len())),
+            Matcher::AllocatedSize(size) => size.matches(entry.allocated_size()),
+            Matcher::Sparse => entry.is_sparse(),
+            Matcher::Perm(perm) => with_metadata(&|metadata| perm.matches(metadata.mode())),
+            Matcher::ExecutableBy(by) => {
+                entry.file_type() == FileType::Executable
+                    && with_metadata(&|metadata| by.matches(&entry.path, metadata.mode()))
+            }
+            Matcher::Readable => perm::accessible(&entry.path, libc::R_OK),
+            Matcher::Writable => perm::accessible(&entry.path, libc::W_OK),
+            Matcher::Nlink(nlink) => with_metadata(&|metadata| nlink.matches(metadata.nlink())),
+            Matcher::Entries(entries) => entry
+                .entry_count()
+                .is_some_and(|count| entries.matches(count)),
+            Matcher::Inode(inode) => with_metadata(&|metadata| metadata.ino() == *inode),
+            Matcher::Depth(depth) => entry.depth == *depth,
+            Matcher::SameFile(file) => with_metadata(&|metadata| {
+                metadata.ino() == file.inode && metadata.dev() == file.device
+            }),
+            Matcher::GitStatus(files) => {
+                with_metadata(&|metadata| files.contains(metadata.dev(), metadata.ino()))
+            }
+            Matcher::Modified(period) => with_metadata(&|metadata| {
+                metadata
+                    .modified()
+                    .is_ok_and(|modified| period.contains(modified))
+            }),
+            Matcher::Uid(uid) => with_metadata(&|metadata| metadata.uid() == *uid),
+            Matcher::Gid(gid) => with_metadata(&|metadata| metadata.gid() == *gid),
+            Matcher::NoUser => with_metadata(&|metadata| !users::has_user(metadata.uid())),
+            Matcher::NoGroup => with_metadata(&|metadata| !users::has_group(metadata.gid())),
+            Matcher::Mime(pattern) => {
+                sniff(entry).is_some_and(|kind| magic::mime_matches(pattern, kind.mime))
+            }
+            Matcher::Magic(name) => sniff(entry).is_some_and(|kind| kind.name == name),
+            Matcher::Text => {
+                entry.is_file() && content::is_binary_file(&entry.path).is_ok_and(|binary| !binary)
+            }
+            Matcher::Binary => {
+                entry.is_file() && content::is_binary_file(&entry.path).is_ok_and(|binary| binary)
+            }
+            Matcher::Encoding(encoding) => {
+                entry.is_file() && encoding.is_valid(&entry.path).is_ok_and(|valid| valid)
+            }
+            Matcher::Lines(lines) => {
+                line_stats(entry).is_some_and(|stats| lines.matches(stats.lines))
+            }
+            Matcher::LineLength(length) => {
+                line_stats(entry).is_some_and(|stats| length.matches(stats.longest))
+            }
+            Matcher::Script(script) => script.matches(entry),
+            Matcher::HasXattr(name) => xattr::value(&entry.path, name).is_some(),
+            Matcher::Xattr(name, value) => {
+                xattr::value(&entry.path, name).is_some_and(|found| found == value.as_bytes())
+            }
+            Matcher::BrokenLink => entry.is_symlink() && std::fs::metadata(&entry.path).is_err(),
+            Matcher::LinksTo(regex) => {
+                link_target(entry).is_some_and(|target| regex.is_match(&target.to_string_lossy()))
+            }
+            Matcher::Not(matcher) => !matcher.matches(entry),
+            Matcher::All(matchers) => matchers.iter().all(|matcher| matcher.matches(entry)),
+            Matcher::Any(matchers) => matchers.iter().any(|matcher| matcher.matches(entry)),
+            Matcher::Counted(matcher, counter) => {
+                counter.evaluated.fetch_add(1, Ordering::Relaxed);
+                let matched = matcher.matches(entry);
+                if matched {
+                    counter.matched.fetch_add(1, Ordering::Relaxed);
+                }
+                matched
+            }
+            Matcher::Adaptive(adaptive) => adaptive.matches(entry),
+        }
+    }
+}
+
+/// Whether the file name in `path` ends with one of `extensions`, after a dot and a
+/// non-empty stem, in any case. Extensions may have several parts, `tar.gz` matching
+/// `backup.TAR.GZ` but not `backup.gz`, and may be given with their leading dot.
+fn has_extension(path: &[u8], extensions: &[String]) -> bool {
+    let name = path.rsplit(|byte| *byte == b'/').next().unwrap_or(path);
+    extensions.iter().any(|extension| {
+        crate::has_extension(name, extension.strip_prefix('.').unwrap_or(extension))
+    })
+}
+
+/// Whether `fragment` is anywhere in `path`.
+fn contains(path: &[u8], fragment: &[u8]) -> bool {
+    fragment.is_empty()
+        || path
+            .windows(fragment.len())
+            .any(|window| window == fragment)
+}
+
+/// Whether `path` ends with the whole of `components`, not just part of the first one.
+fn ends_with_components(path: &[u8], components: &str) -> bool {
+    path.strip_suffix(components.as_bytes())
+        .is_some_and(|rest| rest.is_empty() || rest.ends_with(b"/"))
+}
+
+/// The components of `path` that name something, without `.`, `..` and empty ones.
+fn components(path: &[u8]) -> impl Iterator<Item = &[u8]> {
+    path.split(|byte| *byte == b'/')
+        .filter(|component| !matches!(*component, b"" | b"." | b".."))
+}
+
+/// Whether `path` is as many `levels` below a directory named `directory` as it says,
+/// any of them if it has a few.
+fn is_below(path: &[u8], directory: &str, levels: NumberFilter) -> bool {
+    let components = components(path).collect::<Vec<_>>();
+    components
+        .iter()
+        .rev()
+        .enumerate()
+        .skip(1)
+        .any(|(distance, component)| {
+            *component == directory.as_bytes() && levels.matches(distance as u64)
+        })
+}
+
+/// Where a symbolic link points, with every link on the way resolved. The target of
+/// a broken link is only resolved as far as it exists.
+fn link_target(entry: &Entry) -> Option<PathBuf> {
+    if !entry.is_symlink() {
+        return None;
+    }
+
+    let path = entry.path.as_path();
+    std::fs::canonicalize(path).ok().or_else(|| {
+        let target = std::fs::read_link(path).ok()?;
+        let parent = path.parent().unwrap_or(Path::new("."));
+        let parent = std::fs::canonicalize(parent).unwrap_or_else(|_| parent.to_path_buf());
+        Some(parent.join(target))
+    })
+}
+
+fn line_stats(entry: &Entry) -> Option<content::LineStats> {
+    match entry.is_file() {
+        true => content::line_stats(&entry.path).ok().flatten(),
+        false => None,
+    }
+}
+
+/// The kind of a regular file, from its first bytes.
+fn sniff(entry: &Entry) -> Option<&'static magic::Kind> {
+    match entry.is_file() {
+        true => magic::sniff(&entry.path),
+        false => None,
+    }
+}
+
+/// Quotes `value` if the `--expr` tokenizer would otherwise split it.
+fn quoted(value: &str) -> Cow<'_, str> {
+    if !value.contains([' ', '\t', '(', ')', '\'', '"']) {
+        return Cow::Borrowed(value);
+    }
+
+    match value.contains('\'') {
+        true => Cow::Owned(format!("\"{}\"", value)),
+        false => Cow::Owned(format!("'{}'", value)),
+    }
+}
+
+fn join(f: &mut std::fmt::Formatter<'_>, matchers: &[Matcher], operator: &str) -> std::fmt::Result {
+    write!(f, "(")?;
+    for (index, matcher) in matchers.iter().enumerate() {
+        if index > 0 {
+            write!(f, " {} ", operator)?;
+        }
+        write!(f, "{}", matcher)?;
+    }
+    write!(f, ")")
+}
+
+/// Writes `patterns` as `key:` predicates, joined by `or` if there are several.
+fn alternatives(
+    f: &mut std::fmt::Formatter<'_>,
+    key: &str,
+    patterns: impl IntoIterator<Item = impl AsRef<str>>,
+) -> std::fmt::Result {
+    let patterns = patterns
+        .into_iter()
+        .map(|pattern| format!("{}:{}", key, quoted(pattern.as_ref())))
+        .collect::<Vec<_>>();
+    match patterns.as_slice() {
+        [pattern] => write!(f, "{}", pattern),
+        patterns => write!(f, "({})", patterns.join(" or ")),
+    }
+}
+
+/// Writes the matcher back in `--expr` syntax.
+impl std::fmt::Display for Matcher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Matcher::Name(name) => write!(f, "name:{}", quoted(name)),
+            Matcher::Type(file_type) => match file_type.to_possible_value() {
+                Some(value) => write!(f, "type:{}", value.get_name()),
+                None => write!(f, "type:{:?}", file_type),
+            },
+            Matcher::Extension(extensions) if extensions.len() == 1 => {
+                write!(f, "ext:{}", extensions[0])
+            }
+            Matcher::Extension(extensions) => {
+                let alternatives = extensions
+                    .iter()
+                    .map(|extension| Matcher::Extension(vec![extension.clone()]))
+                    .collect::<Vec<_>>();
+                join(f, &alternatives, "or")
+            }
+            Matcher::Regex(regex) => write!(f, "regex:{}", quoted(regex.as_str())),
+            Matcher::Path(fragment) => write!(f, "path:{}", quoted(fragment)),
+            Matcher::Fuzzy(pattern) => write!(f, "fuzzy:{}", quoted(pattern)),
+            Matcher::PathName(components) => write!(f, "path-name:{}", quoted(components)),
+            Matcher::PathRegex(regex) => write!(f, "path-regex:{}", quoted(regex.as_str())),
+            Matcher::PathFuzzy(pattern) => write!(f, "path-fuzzy:{}", quoted(pattern)),
+            Matcher::Regexes(regexes) => alternatives(f, "regex", regexes.patterns()),
+            Matcher::PathRegexes(regexes) => alternatives(f, "path-regex", regexes.patterns()),
+            Matcher::Glob(globs) => alternatives(f, "glob", globs.globs().iter().map(Glob::glob)),
+            Matcher::PathGlob(globs) => {
+                alternatives(f, "path-glob", globs.globs().iter().map(Glob::glob))
+            }
+            Matcher::Size(size) => write!(f, "size:{}", size),
+            Matcher::AllocatedSize(size) => write!(f, "allocated-size:{}", size),
+            Matcher::Sparse => write!(f, "sparse"),
+            Matcher::Perm(perm) => write!(f, "perm:{}", perm),
+            Matcher::ExecutableBy(by) => match by.to_possible_value() {
+                Some(value) => write!(f, "executable-by:{}", value.get_name()),
+                None => write!(f, "executable-by:{:?}", by),
+            },
+            Matcher::Readable => write!(f, "readable"),
+            Matcher::Writable => write!(f, "writable"),
+            Matcher::Nlink(nlink) => write!(f, "nlink:{}", nlink),
+            Matcher::Entries(entries) => write!(f, "entries:{}", entries),
+            Matcher::Inode(inode) => write!(f, "inode:{}", inode),
+            Matcher::Depth(depth) => write!(f, "depth:{}", depth),
+            Matcher::Components(count) => write!(f, "components:{}", count),
+            Matcher::Below(directory, levels) => {
+                write!(f, "below:{}", quoted(&format!("{}:{}", directory, levels)))
+            }
+            Matcher::SameFile(file) => {
+                write!(f, "same-file:{}", quoted(&file.path.to_string_lossy()))
+            }
+            Matcher::GitStatus(files) => {
+                let statuses = files
+                    .statuses
+                    .iter()
+                    .filter_map(|status| status.to_possible_value())
+                    .map(|value| value.get_name().to_owned())
+                    .collect::<Vec<_>>();
+                write!(f, "git-status:{}", statuses.join(","))
+            }
+            Matcher::Modified(period) => match period.unit {
+                Unit::Day => write!(f, "modified-on:{}", period),
+                Unit::Month => write!(f, "modified-month:{}", period),
+            },
+            Matcher::Uid(uid) => write!(f, "uid:{}", uid),
+            Matcher::Gid(gid) => write!(f, "gid:{}", gid),
+            Matcher::NoUser => write!(f, "nouser"),
+            Matcher::NoGroup => write!(f, "nogroup"),
+            Matcher::Mime(pattern) => write!(f, "mime:{}", quoted(pattern)),
+            Matcher::Magic(name) => write!(f, "magic:{}", name),
+            Matcher::Text => write!(f, "text"),
+            Matcher::Binary => write!(f, "binary"),
+            Matcher::Lines(lines) => write!(f, "lines:{}", lines),
+            Matcher::LineLength(length) => write!(f, "max-line-length:{}", length),
+            Matcher::Encoding(encoding) => match encoding.to_possible_value() {
+                Some(value) => write!(f, "encoding:{}", value.get_name()),
+                None => write!(f, "encoding:{:?}", encoding),
+            },
+            Matcher::Script(script) => {
+                write!(f, "script:{}", quoted(&script.path.to_string_lossy()))
+            }
+            Matcher::HasXattr(name) => write!(f, "has-xattr:{}", quoted(name)),
+            Matcher::Xattr(name, value) => {
+                write!(f, "xattr:{}", quoted(&format!("{}={}", name, value)))
+            }
+            Matcher::BrokenLink => write!(f, "broken"),
+            Matcher::LinksTo(regex) => write!(f, "links-to:{}", quoted(regex.as_str())),
+            Matcher::Not(matcher) => write!(f, "not {}", matcher),
+            Matcher::All(matchers) => join(f, matchers, "and"),
+            Matcher::Any(matchers) => join(f, matchers, "or"),
+            Matcher::Counted(matcher, _) => write!(f, "{}", matcher),
+            Matcher::Adaptive(adaptive) => join(f, &adaptive.matchers, "and"),
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! Permission modes as `-perm` and `--chmod` take them: octal such as `644`, or
+//! chmod-style symbolic clauses such as `u+w`, `go-rwx` or `a=r,u+x`.
+
+use crate::parse::ParseError;
+use std::ops::Range;
+
+const USER: u32 = 0o4700;
+const GROUP: u32 = 0o2070;
+const OTHER: u32 = 0o1007;
+const ALL: u32 = USER | GROUP | OTHER;
+
+/// The bits of an octal `mode`, or of symbolic clauses applied to no bits at all.
+pub(crate) fn parse(mode: &str) -> Result<u32, ParseError> {
+    if mode.is_empty() {
+        return Err(ParseError::new(mode, 0..1, "empty permission mode"));
+    }
+
+    if mode.bytes().all(|byte| byte.is_ascii_digit()) {
+        return match u32::from_str_radix(mode, 8) {
+            Ok(bits) if bits <= 0o7777 => Ok(bits),
+            _ => Err(ParseError::new(
+                mode,
+                0..mode.len(),
+                "invalid octal permission mode",
+            )),
+        };
+    }
+
+    apply_symbolic(0, mode, true)
+}
+
+/// Applies the comma separated symbolic clauses of `mode` to `bits`, where `X` is an
+/// execute bit only if `executable`.
+pub(crate) fn apply_symbolic(
+    mut bits: u32,
+    mode: &str,
+    executable: bool,
+) -> Result<u32, ParseError> {
+    let mut offset = 0;
+    for clause in mode.split(',') {
+        bits =
+            apply_clause(bits, clause, executable).map_err(|error| error.within(mode, offset))?;
+        offset += clause.len() + 1;
+    }
+
+    Ok(bits)
+}
+
+/// Applies a single symbolic clause such as `u+w`, `go-rx` or `a=r` to `bits`.
+fn apply_clause(mut bits: u32, clause: &str, executable: bool) -> Result<u32, ParseError> {
+    let invalid = |span: Range<usize>, message: &str| ParseError::new(clause, span, message);
+
+    let operators_start = clause
+        .find(['+', '-', '='])
+        .ok_or_else(|| invalid(0..clause.len().max(1), "expected +, - or ="))?;
+    let (who, mut actions) = clause.split_at(operators_start);
+
+    let mut who_mask = 0;
+    for (index, class) in who.char_indices() {
+        who_mask |= match class {
+            'u' => USER,
+            'g' => GROUP,
+            'o' => OTHER,
+            'a' => ALL,
+            _ => return Err(invalid(index..index + 1, "expected u, g, o or a")),
+        };
+    }
+    if who_mask == 0 {
+        who_mask = ALL;
+    }
+
+    while let Some(operator) = actions.chars().next() {
+        let start = clause.len() - actions.len();
+        let perms_end = actions[1..]
+            .find(['+', '-', '='])
+            .map_or(actions.len(), |index| index + 1);
+        let perms = &actions[1..perms_end];
+        actions = &actions[perms_end..];
+
+        let mut perm_bits = 0;
+        for (index, perm) in perms.char_indices() {
+            let at = start + 1 + index;
+            perm_bits |= match perm {
+                'r' => 0o444,
+                'w' => 0o222,
+                'x' => 0o111,
+                'X' if executable => 0o111,
+                'X' => 0,
+                's' => 0o6000,
+                't' => 0o1000,
+                _ => {
+                    return Err(invalid(
+                        at..at + perm.len_utf8(),
+                        "expected r, w, x, s or t",
+                    ))
+                }
+            };
+        }
+        let perm_bits = perm_bits & who_mask;
+
+        match operator {
+            '+' => bits |= perm_bits,
+            '-' => bits &= !perm_bits,
+            _ => bits = (bits & !who_mask) | perm_bits,
+        }
+    }
+
+    Ok(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::perm_filter;
+    use crate::perm::{PermFilter, PermKind};
+
+    /// Asserts that `value` is rejected with `message`, pointing at `span` of it.
+    fn rejects(value: &str, span: Range<usize>, message: &str) {
+        let error = perm_filter(value).unwrap_err();
+        assert_eq!(
+            (error.span(), error.message()),
+            (span, message),
+            "{:?}",
+            value
+        );
+    }
+
+    #[test]
+    fn octal() {
+        assert_eq!(parse("644"), Ok(0o644));
+        assert_eq!(parse("0"), Ok(0));
+        assert_eq!(parse("4755"), Ok(0o4755));
+        assert_eq!(parse("7777"), Ok(0o7777));
+    }
+
+    #[test]
+    fn kinds() {
+        let filter = |kind, mode| PermFilter { kind, mode };
+        assert_eq!(perm_filter("644"), Ok(filter(PermKind::Exact, 0o644)));
+        assert_eq!(perm_filter("-644"), Ok(filter(PermKind::All, 0o644)));
+        assert_eq!(perm_filter("/022"), Ok(filter(PermKind::Any, 0o022)));
+        assert_eq!(perm_filter("-u+x"), Ok(filter(PermKind::All, 0o100)));
+        assert_eq!(perm_filter("/g+w,o+w"), Ok(filter(PermKind::Any, 0o022)));
+    }
+
+    #[test]
+    fn symbolic() {
+        assert_eq!(parse("u+w"), Ok(0o200));
+        assert_eq!(parse("a=r,u+x"), Ok(0o544));
+        assert_eq!(parse("+x"), Ok(0o111));
+        assert_eq!(parse("u+s,o+t"), Ok(0o5000));
+        assert_eq!(apply_symbolic(0o777, "go-rwx", false), Ok(0o700));
+        assert_eq!(apply_symbolic(0o640, "a=r", false), Ok(0o444));
+        assert_eq!(apply_symbolic(0o600, "u=rw-w+x", false), Ok(0o500));
+    }
+
+    #[test]
+    fn conditional_execute() {
+        assert_eq!(apply_symbolic(0o644, "a+X", false), Ok(0o644));
+        assert_eq!(apply_symbolic(0o644, "a+X", true), Ok(0o755));
+    }
+
+    #[test]
+    fn error_spans() {
+        rejects("", 0..1, "empty permission mode");
+        rejects("-", 1..2, "empty permission mode");
+        rejects("999", 0..3, "invalid octal permission mode");
+        rejects("-17777", 1..6, "invalid octal permission mode");
+        rejects("u", 0..1, "expected +, - or =");
+        rejects("z+w", 0..1, "expected u, g, o or a");
+        rejects("u+q", 2..3, "expected r, w, x, s or t");
+        rejects("/u+w,go-q", 8..9, "expected r, w, x, s or t");
+        rejects("u+w,,o+r", 4..5, "expected +, - or =");
+    }
+}
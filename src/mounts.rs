@@ -0,0 +1,110 @@
+//! Where file systems of some types are mounted, for a walk to pass over them without
+//! looking, as a dead network mount hangs whatever looks at it. Read from /proc/mounts
+//! on Linux and from getmntinfo on macOS.
+
+use anyhow::Result;
+use std::path::PathBuf;
+
+/// The types of the network file systems, for `--skip-network-fs`.
+pub const NETWORK: &[&str] = &[
+    "nfs",
+    "nfs4",
+    "cifs",
+    "smb3",
+    "smbfs",
+    "afpfs",
+    "webdav",
+    "davfs",
+    "9p",
+    "afs",
+    "ceph",
+    "glusterfs",
+    "lustre",
+    "fuse.sshfs",
+    "fuse.rclone",
+    "fuse.s3fs",
+];
+
+/// Whether `fstype` is one of `types`, in which `fuse` stands for every FUSE file
+/// system.
+fn is_one_of(fstype: &str, types: &[String]) -> bool {
+    types
+        .iter()
+        .any(|wanted| fstype == wanted || (wanted == "fuse" && fstype.starts_with("fuse.")))
+}
+
+/// A field of /proc/mounts, where spaces, tabs, newlines and backslashes are written in
+/// octal.
+#[cfg(target_os = "linux")]
+fn unescape(field: &str) -> PathBuf {
+    use std::os::unix::ffi::OsStringExt;
+
+    let bytes = field.as_bytes();
+    let mut unescaped = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let octal = bytes
+            .get(i + 1..i + 4)
+            .and_then(|digits| std::str::from_utf8(digits).ok())
+            .and_then(|digits| u8::from_str_radix(digits, 8).ok());
+        match (bytes[i], octal) {
+            (b'\\', Some(byte)) => {
+                unescaped.push(byte);
+                i += 4;
+            }
+            (byte, _) => {
+                unescaped.push(byte);
+                i += 1;
+            }
+        }
+    }
+    PathBuf::from(std::ffi::OsString::from_vec(unescaped))
+}
+
+/// The mount points of the file systems of any of `types`, e.g. `nfs` or `fuse.sshfs`.
+#[cfg(target_os = "linux")]
+pub fn mount_points(types: &[String]) -> Result<Vec<PathBuf>> {
+    use anyhow::Context;
+
+    let mounts = std::fs::read_to_string("/proc/mounts").context("/proc/mounts")?;
+    Ok(mounts
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(' ');
+            let (_, point, fstype) = (fields.next()?, fields.next()?, fields.next()?);
+            is_one_of(fstype, types).then(|| unescape(point))
+        })
+        .collect())
+}
+
+/// The mount points of the file systems of any of `types`, e.g. `nfs` or `smbfs`.
+#[cfg(target_os = "macos")]
+pub fn mount_points(types: &[String]) -> Result<Vec<PathBuf>> {
+    use std::ffi::CStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let mut mounts: *mut libc::statfs = std::ptr::null_mut();
+    // Without waiting on the file systems, which is the point. The list belongs to libc.
+    let count = unsafe { libc::getmntinfo(&mut mounts, libc::MNT_NOWAIT) };
+    if count <= 0 {
+        return Err(std::io::Error::last_os_error().into());
+    }
+
+    let mounts = unsafe { std::slice::from_raw_parts(mounts, count as usize) };
+    Ok(mounts
+        .iter()
+        .filter_map(|mount| {
+            let fstype = unsafe { CStr::from_ptr(mount.f_fstypename.as_ptr()) };
+            let point = unsafe { CStr::from_ptr(mount.f_mntonname.as_ptr()) };
+            is_one_of(&fstype.to_string_lossy(), types)
+                .then(|| PathBuf::from(std::ffi::OsStr::from_bytes(point.to_bytes())))
+        })
+        .collect())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+pub fn mount_points(_types: &[String]) -> Result<Vec<PathBuf>> {
+    Err(anyhow::anyhow!(
+        "the mounted file systems can't be listed on this platform"
+    ))
+}
@@ -0,0 +1,56 @@
+use crate::parse::{self, ParseError};
+use std::str::FromStr;
+
+/// A find-style numeric argument: `N` is exactly N, `+N` more than N and `-N` less than N.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumberFilter {
+    Exactly(u64),
+    MoreThan(u64),
+    LessThan(u64),
+}
+
+impl NumberFilter {
+    pub fn matches(&self, value: u64) -> bool {
+        match *self {
+            NumberFilter::Exactly(n) => value == n,
+            NumberFilter::MoreThan(n) => value > n,
+            NumberFilter::LessThan(n) => value < n,
+        }
+    }
+}
+
+impl std::fmt::Display for NumberFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NumberFilter::Exactly(n) => write!(f, "{}", n),
+            NumberFilter::MoreThan(n) => write!(f, "+{}", n),
+            NumberFilter::LessThan(n) => write!(f, "-{}", n),
+        }
+    }
+}
+
+impl FromStr for NumberFilter {
+    type Err = ParseError;
+
+    fn from_str(value: &str) -> Result<Self, ParseError> {
+        parse::number_filter(value)
+    }
+}
+
+/// Formats `bytes` in the units [`parse::size`] accepts, e.g. `512B`, `4.0K` or `1.2G`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [char; 4] = ['K', 'M', 'G', 'T'];
+
+    if bytes < 1 << 10 {
+        return format!("{}B", bytes);
+    }
+
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+
+    format!("{:.1}{}", value, UNITS[unit])
+}
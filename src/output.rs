@@ -0,0 +1,580 @@
+use crate::pipeline::Match;
+use crate::template::Template;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use clap::ValueEnum;
+use quaero::number::format_size;
+use quaero::{perm, Entry};
+use serde::Serialize;
+use std::borrow::Cow;
+use std::fs::File;
+use std::io::{BufWriter, IsTerminal, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant, SystemTime};
+
+/// How much output is kept before it is written, when it doesn't go to a terminal.
+const BUFFER_SIZE: usize = 64 * 1024;
+/// How long buffered output may wait to be written, for whoever reads it as it comes.
+const FLUSH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Structured alternatives to printing one path per line.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Comma separated values with a header row, quoted as in RFC 4180
+    Csv,
+    /// Tab separated values with a header row, escaping tabs and newlines in fields
+    Tsv,
+    /// One JSON object per line, numbered with an id that increases within a scan
+    Ndjson,
+    /// One JSON object per line for every event of the run, as its `event` says: a
+    /// `begin` first, a `match` as ndjson has it for every match, an `error` for every
+    /// error skipped and a `summary` last, for programs that drive quaero to follow it
+    NdjsonEvents,
+    /// An indented tree under each root, showing only the directories leading to matches
+    Tree,
+}
+
+impl OutputFormat {
+    /// The format a file is written in going by its extension, `None` for plain paths.
+    pub fn for_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_string_lossy().to_ascii_lowercase();
+        match extension.as_str() {
+            "csv" => Some(OutputFormat::Csv),
+            "tsv" => Some(OutputFormat::Tsv),
+            "ndjson" | "jsonl" => Some(OutputFormat::Ndjson),
+            _ => None,
+        }
+    }
+}
+
+/// The version of the events of `--format ndjson-events`, which only goes up for changes
+/// that could break whoever reads them: fields are only ever added otherwise.
+pub const EVENTS_VERSION: u32 = 1;
+
+const COLUMNS: [&str; 6] = ["path", "name", "type", "size", "mtime", "permissions"];
+
+impl OutputFormat {
+    fn separator(self) -> char {
+        match self {
+            OutputFormat::Tsv => '\t',
+            _ => ',',
+        }
+    }
+
+    fn escape(self, field: &str) -> Cow<'_, str> {
+        match self {
+            OutputFormat::Csv if field.contains([',', '"', '\n', '\r']) => {
+                Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+            }
+            OutputFormat::Tsv if field.contains(['\\', '\t', '\n', '\r']) => Cow::Owned(
+                field
+                    .replace('\\', "\\\\")
+                    .replace('\t', "\\t")
+                    .replace('\n', "\\n")
+                    .replace('\r', "\\r"),
+            ),
+            _ => Cow::Borrowed(field),
+        }
+    }
+
+    pub fn row<'a>(self, fields: impl IntoIterator<Item = &'a str>) -> String {
+        fields
+            .into_iter()
+            .map(|field| self.escape(field))
+            .collect::<Vec<_>>()
+            .join(&self.separator().to_string())
+    }
+}
+
+/// `path` as printed, cleaned up by [`quaero::clean_path`] and after the label of the
+/// workspace root it was found under.
+pub fn labeled(label: Option<&str>, path: &Path) -> String {
+    let path = quaero::clean_path(path);
+    match label {
+        Some(label) => format!("[{}] {}", label, path),
+        None => path.into_owned(),
+    }
+}
+
+fn lossy(path: PathBuf) -> String {
+    path.to_string_lossy().into_owned()
+}
+
+/// `--bytes`: sizes printed as numbers of bytes wherever they would be shortened.
+static RAW_SIZES: AtomicBool = AtomicBool::new(false);
+
+pub fn raw_sizes() {
+    RAW_SIZES.store(true, Ordering::Relaxed);
+}
+
+/// A size as printed for people to read, such as 4.2M, or in bytes with `--bytes`.
+pub fn size(bytes: u64) -> String {
+    match RAW_SIZES.load(Ordering::Relaxed) {
+        true => bytes.to_string(),
+        false => format_size(bytes),
+    }
+}
+
+fn columns(entry: &Entry, utc: bool) -> [String; 6] {
+    // What the metadata tells is left empty for an entry gone by now.
+    let metadata = entry.metadata().ok();
+    let mtime = metadata
+        .and_then(|metadata| {
+            DateTime::from_timestamp(metadata.mtime(), metadata.mtime_nsec() as u32)
+        })
+        .map(|time| match utc {
+            true => time.to_rfc3339(),
+            false => time.with_timezone(&Local).to_rfc3339(),
+        })
+        .unwrap_or_default();
+    let file_type = entry
+        .file_type()
+        .to_possible_value()
+        .map(|value| value.get_name().to_owned())
+        .unwrap_or_default();
+
+    [
+        quaero::clean_path(&entry.path).into_owned(),
+        entry.name.to_string_lossy().into_owned(),
+        file_type,
+        metadata.map_or_else(String::new, |metadata| metadata.len().to_string()),
+        mtime,
+        metadata.map_or_else(String::new, |metadata| perm::symbolic(metadata.mode())),
+    ]
+}
+
+/// A match as printed by `--format ndjson`.
+#[derive(Serialize)]
+struct Record<'a> {
+    /// Increases by one for every match printed in the same scan, starting at 1
+    id: u64,
+    /// When the scan started, in milliseconds since the Unix epoch, to tell scans apart
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scan_epoch: Option<u128>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    label: Option<&'a str>,
+    path: &'a str,
+    name: &'a str,
+    #[serde(rename = "type")]
+    file_type: &'a str,
+    size: u64,
+    mtime: &'a str,
+    permissions: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hash: Option<&'a str>,
+    /// What a symbolic link points to, as it says
+    #[serde(skip_serializing_if = "Option::is_none")]
+    link_target: Option<String>,
+    /// What a symbolic link points to, with every link on the way resolved, unless it is
+    /// broken or the output is to be the same wherever the tree is
+    #[serde(skip_serializing_if = "Option::is_none")]
+    resolved: Option<String>,
+}
+
+/// An event of `--format ndjson-events`, tagged with what it is as `event`.
+#[derive(Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum Event<'a> {
+    Begin {
+        version: u32,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        scan_epoch: Option<u128>,
+    },
+    Match(Record<'a>),
+    Summary(&'a Totals),
+}
+
+/// How a run went, for the `summary` event that ends `--format ndjson-events`.
+#[derive(Serialize)]
+pub struct Totals {
+    pub matches: u64,
+    /// The bytes the files among the matches take
+    pub size: u64,
+    pub entries: u64,
+    pub directories: u64,
+    /// The errors reported and skipped on the way
+    pub errors: u64,
+    pub vanished: u64,
+    /// `None` with `--deterministic`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub elapsed_ms: Option<u128>,
+    /// `complete`, `interrupted` or `timed_out`
+    pub status: &'static str,
+}
+
+/// Whether the terminal is one of those known to make links of OSC 8 escapes, which the
+/// others may print as garbage.
+fn supports_hyperlinks() -> bool {
+    let var = |name: &str| std::env::var(name).unwrap_or_default();
+    if var("TERM") == "dumb" {
+        return false;
+    }
+
+    matches!(
+        var("TERM_PROGRAM").as_str(),
+        "iTerm.app" | "WezTerm" | "vscode" | "ghostty" | "Hyper"
+    ) || var("VTE_VERSION")
+        .parse::<u32>()
+        .is_ok_and(|version| version >= 5000)
+        || ["kitty", "foot", "alacritty", "wezterm"]
+            .iter()
+            .any(|name| var("TERM").contains(name))
+        || ["WT_SESSION", "KONSOLE_VERSION", "DOMTERM"]
+            .iter()
+            .any(|name| std::env::var_os(name).is_some())
+}
+
+/// What `--hyperlink` makes links to the files from.
+struct Hyperlinks {
+    host: String,
+    /// What relative paths are relative to
+    current: PathBuf,
+}
+
+impl Hyperlinks {
+    fn new() -> Option<Self> {
+        let mut host = [0u8; 256];
+        let host = match unsafe { libc::gethostname(host.as_mut_ptr().cast(), host.len()) } {
+            0 => {
+                let end = host
+                    .iter()
+                    .position(|byte| *byte == 0)
+                    .unwrap_or(host.len());
+                String::from_utf8_lossy(&host[..end]).into_owned()
+            }
+            _ => String::new(),
+        };
+
+        Some(Self {
+            host,
+            current: std::env::current_dir().ok()?,
+        })
+    }
+
+    /// `text` as a link to the file at `path`, its bytes percent-encoded in the URL
+    /// unless they are safe there as they are.
+    fn link(&self, path: &Path, text: &str) -> String {
+        let absolute = self.current.join(path).components().collect::<PathBuf>();
+        let mut url = format!("file://{}", self.host);
+        for byte in absolute.as_os_str().as_encoded_bytes() {
+            match byte {
+                b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' => {
+                    url.push(*byte as char)
+                }
+                byte => url.push_str(&format!("%{:02X}", byte)),
+            }
+        }
+
+        format!("\x1b]8;;{}\x1b\\{}\x1b]8;;\x1b\\", url, text)
+    }
+}
+
+/// Prints matches as plain paths, through a `--template` or in a structured format.
+pub struct Printer {
+    template: Option<Template>,
+    format: Option<OutputFormat>,
+    /// Whether matches come from a workspace, adding a label column to structured formats
+    labels: bool,
+    /// Whether matches are hashed, adding a hash column to structured formats and to
+    /// plain paths
+    hashes: bool,
+    /// `None` with `--deterministic`
+    scan_epoch: Option<u128>,
+    /// Whether ndjson records have where links resolve to, an absolute path that
+    /// `--deterministic` leaves out as it depends on where the tree is
+    resolved: bool,
+    /// Whether times are printed in UTC rather than in the local time zone
+    utc: bool,
+    printed: u64,
+    out: Box<dyn Write + Send>,
+    /// Whether every line is written right away, rather than once the buffer fills up
+    /// or [`FLUSH_INTERVAL`] passes
+    line_buffered: bool,
+    flushed: Instant,
+    /// The first error writing to `out`, after which nothing more is written
+    failed: Option<std::io::Error>,
+    /// What plain paths link to, with --hyperlink on a terminal that supports it
+    hyperlinks: Option<Hyperlinks>,
+    /// `--quote`: plain paths are quoted for the shell
+    quote: bool,
+    /// `--show-link-targets`: plain paths of symbolic links are followed by what they
+    /// point to
+    link_targets: bool,
+}
+
+impl Printer {
+    pub fn new(
+        template: Option<Template>,
+        format: Option<OutputFormat>,
+        labels: bool,
+        hashes: bool,
+    ) -> Self {
+        Self {
+            template,
+            format,
+            labels,
+            hashes,
+            scan_epoch: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .ok()
+                .map(|elapsed| elapsed.as_millis()),
+            resolved: true,
+            utc: false,
+            printed: 0,
+            out: Box::new(BufWriter::with_capacity(BUFFER_SIZE, std::io::stdout())),
+            // Whoever reads events follows them as they come.
+            line_buffered: std::io::stdout().is_terminal()
+                || format == Some(OutputFormat::NdjsonEvents),
+            flushed: Instant::now(),
+            failed: None,
+            hyperlinks: None,
+            quote: false,
+            link_targets: false,
+        }
+    }
+
+    /// `--quote`: quotes the plain paths that need it for a shell to take them as they
+    /// are, for them to be pasted into a command line.
+    pub fn quoted(mut self) -> Self {
+        self.quote = true;
+        self
+    }
+
+    /// `--show-link-targets`: prints the plain paths of symbolic links as `path ->
+    /// target`, as `ls -l` does.
+    pub fn link_targets(mut self) -> Self {
+        self.link_targets = true;
+        self
+    }
+
+    /// `--hyperlink`: makes links to the files of the plain paths printed, if they go to
+    /// a terminal that supports them.
+    pub fn hyperlinks(mut self) -> Self {
+        if std::io::stdout().is_terminal() && supports_hyperlinks() {
+            self.hyperlinks = Hyperlinks::new();
+        }
+        self
+    }
+
+    /// Writes every line right away, for matches that come in one at a time.
+    pub fn line_buffered(mut self) -> Self {
+        self.line_buffered = true;
+        self
+    }
+
+    /// `--output`: writes to a file at `path`, created or truncated, instead of stdout.
+    pub fn output(mut self, path: &Path) -> Result<Self> {
+        let file = File::create(path).with_context(|| path.display().to_string())?;
+        self.out = Box::new(BufWriter::with_capacity(BUFFER_SIZE, file));
+        self.line_buffered = false;
+        self.hyperlinks = None;
+        Ok(self)
+    }
+
+    /// Writes through `write` to where the matches go, unless writing there failed
+    /// already.
+    pub fn write(&mut self, write: impl FnOnce(&mut dyn Write) -> std::io::Result<()>) {
+        if self.failed.is_some() {
+            return;
+        }
+
+        let mut written = write(&mut self.out);
+        if written.is_ok() && (self.line_buffered || self.flushed.elapsed() >= FLUSH_INTERVAL) {
+            written = self.out.flush();
+            self.flushed = Instant::now();
+        }
+        if let Err(error) = written {
+            self.failed = Some(error);
+        }
+    }
+
+    /// Whether writing failed, after which there is no point finding more matches.
+    pub fn failed(&self) -> bool {
+        self.failed.is_some()
+    }
+
+    /// Flushes what is left to write, failing if any of it couldn't be written. Whoever
+    /// reads the output going away early isn't a failure, as with `quaero ... | head`.
+    pub fn finish(&mut self) -> Result<()> {
+        self.write(|out| out.flush());
+        match self.failed.take() {
+            Some(error) if error.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+            Some(error) => Err(error).context(tr!("error-output")),
+            None => Ok(()),
+        }
+    }
+
+    /// `--deterministic`: prints nothing that changes from one run to the next, or from
+    /// one copy of the tree to another, as the time a scan started, the local time zone
+    /// or the absolute paths links resolve to.
+    pub fn deterministic(mut self) -> Self {
+        self.scan_epoch = None;
+        self.resolved = false;
+        self.utc = true;
+        self.template = self.template.map(Template::utc);
+        self
+    }
+
+    /// Numbers the matches printed from now on as if `matches` had been printed before
+    /// them, for the pages after the first.
+    pub fn skip(&mut self, matches: u64) {
+        self.printed += matches;
+    }
+
+    /// Prints the header row of structured formats, or the `begin` event, before any
+    /// match.
+    pub fn header(&mut self) {
+        match self.format {
+            Some(format @ (OutputFormat::Csv | OutputFormat::Tsv)) => {
+                let label = self.labels.then_some("label");
+                let hash = self.hashes.then_some("hash");
+                let row = format.row(label.into_iter().chain(COLUMNS).chain(hash));
+                self.write(|out| writeln!(out, "{}", row));
+            }
+            Some(OutputFormat::NdjsonEvents) => self.event(&Event::Begin {
+                version: EVENTS_VERSION,
+                scan_epoch: self.scan_epoch,
+            }),
+            _ => {}
+        }
+    }
+
+    /// Prints the `summary` event that ends `--format ndjson-events`, and nothing in
+    /// other formats.
+    pub fn summary(&mut self, totals: &Totals) {
+        if self.format == Some(OutputFormat::NdjsonEvents) {
+            self.event(&Event::Summary(totals));
+        }
+    }
+
+    fn event(&mut self, event: &Event) {
+        let event = serde_json::to_string(event).expect("events serialize to JSON");
+        self.write(|out| writeln!(out, "{}", event));
+    }
+
+    pub fn print(&mut self, item: &Match) {
+        let (label, entry) = (item.label.as_deref(), &item.entry);
+        let hash = self
+            .hashes
+            .then(|| item.hash.as_deref().unwrap_or_default());
+        self.printed += 1;
+
+        if let Some(format) = self.format {
+            let columns = columns(entry, self.utc);
+            if matches!(format, OutputFormat::Ndjson | OutputFormat::NdjsonEvents) {
+                let [path, name, file_type, _, mtime, permissions] = &columns;
+                let record = Record {
+                    id: self.printed,
+                    scan_epoch: self.scan_epoch,
+                    label: label.filter(|_| self.labels),
+                    path,
+                    name,
+                    file_type,
+                    size: entry.size(),
+                    mtime,
+                    permissions,
+                    hash: item.hash.as_deref(),
+                    link_target: entry.link_target().map(lossy),
+                    resolved: self.resolved.then(|| entry.resolved()).flatten().map(lossy),
+                };
+                if format == OutputFormat::NdjsonEvents {
+                    self.event(&Event::Match(record));
+                    return;
+                }
+                let record = serde_json::to_string(&record).expect("records serialize to JSON");
+                self.write(|out| writeln!(out, "{}", record));
+                return;
+            }
+
+            let label = self.labels.then(|| label.unwrap_or_default());
+            let fields = label
+                .into_iter()
+                .chain(columns.iter().map(String::as_str))
+                .chain(hash);
+            let row = format.row(fields);
+            self.write(|out| writeln!(out, "{}", row));
+            return;
+        }
+
+        // Paths go out with the bytes they have, for whatever reads them to find the
+        // files by, UTF-8 or not.
+        let path = || {
+            let cleaned = quaero::cleaned_path(&entry.path);
+            let mut path = match self.quote {
+                true => quaero::shell_quote(cleaned.as_os_str())
+                    .into_owned()
+                    .into_bytes(),
+                false => cleaned.as_os_str().as_bytes().to_vec(),
+            };
+            if let Some(hyperlinks) = &self.hyperlinks {
+                let text = String::from_utf8_lossy(&path);
+                path = hyperlinks.link(&entry.path, &text).into_bytes();
+            }
+            if let Some(label) = label {
+                path = [format!("[{}] ", label).as_bytes(), &path].concat();
+            }
+            if let Some(target) = entry.link_target().filter(|_| self.link_targets) {
+                path.extend_from_slice(b" -> ");
+                path.extend_from_slice(target.as_os_str().as_bytes());
+            }
+            path
+        };
+        let line = match (&self.template, &item.hash) {
+            (Some(template), _) => template.render(item).into_bytes(),
+            (None, Some(hash)) => [path(), format!("\t{}", hash).into_bytes()].concat(),
+            (None, None) => path(),
+        };
+        self.write(|out| {
+            out.write_all(&line)?;
+            writeln!(out)
+        });
+    }
+}
+
+/// `--also-output`: files every match is written to on top of the main output, each in
+/// the format its extension calls for, so that one walk feeds all of them.
+#[derive(Default)]
+pub struct FanOut {
+    outputs: Vec<(PathBuf, Printer)>,
+}
+
+impl FanOut {
+    pub fn new(paths: &[PathBuf], labels: bool, hashes: bool, deterministic: bool) -> Result<Self> {
+        let mut outputs = Vec::with_capacity(paths.len());
+        for path in paths {
+            let mut printer =
+                Printer::new(None, OutputFormat::for_path(path), labels, hashes).output(path)?;
+            if deterministic {
+                printer = printer.deterministic();
+            }
+            printer.header();
+            outputs.push((path.clone(), printer));
+        }
+
+        Ok(Self { outputs })
+    }
+
+    pub fn print(&mut self, item: &Match) {
+        for (_, printer) in &mut self.outputs {
+            printer.print(item);
+        }
+    }
+
+    pub fn failed(&self) -> bool {
+        self.outputs.iter().any(|(_, printer)| printer.failed())
+    }
+
+    /// Flushes every file, failing on the first that couldn't be written in full.
+    pub fn finish(&mut self) -> Result<()> {
+        for (path, printer) in &mut self.outputs {
+            printer
+                .finish()
+                .with_context(|| path.display().to_string())?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,862 @@
+//! Parsers for the values filters take, shared by the command line, `--expr` and the
+//! queries of `quaero batch`. Their errors point at the part of the value that is wrong
+//! and show what a valid one looks like.
+
+use crate::magic;
+use crate::matcher::FileId;
+use crate::mode;
+use crate::number::NumberFilter;
+use crate::period::Period;
+use crate::perm::{ModeChange, PermFilter, PermKind};
+use crate::users::{self, Owner};
+use chrono::{Local, NaiveDate, NaiveTime, TimeZone};
+use globset::{Glob, GlobBuilder};
+use regex::Regex;
+use std::ops::Range;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+const SIZE_EXAMPLE: &str = "512, 4k or 1G";
+const NUMBER_EXAMPLE: &str = "3, +3 or -3";
+const PERM_EXAMPLE: &str = "644, -u+x or /g+w,o+w";
+const DAY_EXAMPLE: &str = "2024-06-01";
+const MONTH_EXAMPLE: &str = "2024-06";
+const MODE_CHANGE_EXAMPLE: &str = "644, go-w or u=rw,a+X";
+const OWNER_EXAMPLE: &str = "root, root:wheel or :0";
+const AGE_EXAMPLE: &str = "500ms, 90s, 12h, 30d or 2w";
+const XATTR_EXAMPLE: &str = "user.comment=draft";
+const BELOW_EXAMPLE: &str = "tests:2, tests:+0 or src:-3";
+const REPLACEMENT_EXAMPLE: &str = "colour->color or (\\w+)_old->${1}_new";
+const GROWTH_EXAMPLE: &str = "100M or 10M/1m";
+const TIMESTAMP_EXAMPLE: &str = "now, 2024-06-01, 2024-06-01T12:30:00 or @1717245000";
+
+/// A value that failed to parse, with the span of it that is wrong.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    value: String,
+    span: Range<usize>,
+    message: String,
+    example: Option<&'static str>,
+}
+
+impl ParseError {
+    pub fn new(value: &str, span: Range<usize>, message: impl Into<String>) -> Self {
+        Self {
+            value: value.to_owned(),
+            span,
+            message: message.into(),
+            example: None,
+        }
+    }
+
+    /// Adds an example of a valid value to the error.
+    pub fn example(mut self, example: &'static str) -> Self {
+        self.example = Some(example);
+        self
+    }
+
+    /// The same error, for a value that is part of the larger `value` at `offset`.
+    pub fn within(mut self, value: &str, offset: usize) -> Self {
+        self.value = value.to_owned();
+        self.span = self.span.start + offset..self.span.end + offset;
+        self
+    }
+
+    /// Byte range of the value that is wrong.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+/// Writes the message, then the value with the wrong part underlined.
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)?;
+        if self.value.is_empty() {
+            return Ok(());
+        }
+
+        let start = self.value[..self.span.start.min(self.value.len())]
+            .chars()
+            .count();
+        let width = self
+            .value
+            .get(self.span.clone())
+            .map_or(1, |wrong| wrong.chars().count().max(1));
+        write!(
+            f,
+            "\n    {}\n    {}{}",
+            self.value,
+            " ".repeat(start),
+            "^".repeat(width)
+        )?;
+
+        if let Some(example) = self.example {
+            write!(f, "\n  e.g. {}", example)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// Splits a find-style `+` or `-` prefix off `value`.
+fn comparison(value: &str) -> (Option<char>, &str) {
+    match value.chars().next() {
+        Some(sign @ ('+' | '-')) => (Some(sign), &value[1..]),
+        _ => (None, value),
+    }
+}
+
+fn number_filter_with(
+    value: &str,
+    parse: impl Fn(&str) -> Result<u64, ParseError>,
+) -> Result<NumberFilter, ParseError> {
+    let (sign, number) = comparison(value);
+    let offset = value.len() - number.len();
+    let number = parse(number).map_err(|error| error.within(value, offset))?;
+
+    Ok(match sign {
+        Some('+') => NumberFilter::MoreThan(number),
+        Some(_) => NumberFilter::LessThan(number),
+        None => NumberFilter::Exactly(number),
+    })
+}
+
+fn number(value: &str) -> Result<u64, ParseError> {
+    let digits = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    if digits < value.len() || value.is_empty() {
+        let span = digits..value.len().max(digits + 1);
+        return Err(ParseError::new(value, span, "expected a number").example(NUMBER_EXAMPLE));
+    }
+
+    value
+        .parse()
+        .map_err(|_| ParseError::new(value, 0..value.len(), "number too large"))
+}
+
+/// A find-style number: `N` is exactly N, `+N` more than N and `-N` less than N.
+pub fn number_filter(value: &str) -> Result<NumberFilter, ParseError> {
+    number_filter_with(value, number).map_err(|error| error.example(NUMBER_EXAMPLE))
+}
+
+/// A size with an optional binary unit suffix (`k`, `M`, `G`, `T`), in bytes.
+pub fn size(value: &str) -> Result<u64, ParseError> {
+    let invalid = |span: Range<usize>, message: &str| {
+        ParseError::new(value, span, message).example(SIZE_EXAMPLE)
+    };
+
+    let split = value
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(value.len());
+    let (digits, unit) = value.split_at(split);
+    if digits.is_empty() {
+        return Err(invalid(0..value.len().max(1), "expected a size"));
+    }
+    let number: u64 = digits
+        .parse()
+        .map_err(|_| invalid(0..split, "size too large"))?;
+
+    let multiplier: u64 = match unit {
+        "" | "b" | "B" => 1,
+        "k" | "K" => 1 << 10,
+        "m" | "M" => 1 << 20,
+        "g" | "G" => 1 << 30,
+        "t" | "T" => 1 << 40,
+        _ => return Err(invalid(split..value.len(), "unknown size unit")),
+    };
+
+    number
+        .checked_mul(multiplier)
+        .ok_or_else(|| invalid(0..value.len(), "size too large"))
+}
+
+/// Like [`number_filter`], with the number given as a [`size`] such as `+1k`.
+pub fn size_filter(value: &str) -> Result<NumberFilter, ParseError> {
+    number_filter_with(value, size)
+}
+
+/// A find-style `-perm` argument: exactly `MODE`, all bits of `-MODE` or any bit of
+/// `/MODE`, where `MODE` is either octal or symbolic.
+pub fn perm_filter(value: &str) -> Result<PermFilter, ParseError> {
+    let (kind, mode) = match value.chars().next() {
+        Some('-') => (PermKind::All, &value[1..]),
+        Some('/') => (PermKind::Any, &value[1..]),
+        _ => (PermKind::Exact, value),
+    };
+    let offset = value.len() - mode.len();
+
+    let mode =
+        mode::parse(mode).map_err(|error| error.within(value, offset).example(PERM_EXAMPLE))?;
+    Ok(PermFilter { kind, mode })
+}
+
+/// A chmod-style mode to set: octal, or symbolic clauses such as `go-w` or `u=rw,a+X`
+/// applied to the bits each file has.
+pub fn mode_change(value: &str) -> Result<ModeChange, ParseError> {
+    let bits = mode::parse(value).map_err(|error| error.example(MODE_CHANGE_EXAMPLE))?;
+    Ok(match value.bytes().all(|byte| byte.is_ascii_digit()) {
+        true => ModeChange::Octal(bits),
+        false => ModeChange::Symbolic(value.to_owned()),
+    })
+}
+
+/// What `--replace` does: a regular expression, the first `->` and what replaces it,
+/// where `$1` or `${name}` stand for what a group matched.
+pub fn replacement(value: &str) -> Result<(regex::bytes::Regex, String), ParseError> {
+    let Some((pattern, with)) = value.split_once("->") else {
+        return Err(
+            ParseError::new(value, 0..value.len().max(1), "expected OLD->NEW")
+                .example(REPLACEMENT_EXAMPLE),
+        );
+    };
+    if pattern.is_empty() {
+        return Err(
+            ParseError::new(value, 0..2, "expected a regular expression before ->")
+                .example(REPLACEMENT_EXAMPLE),
+        );
+    }
+
+    // Checked as text for its errors to point into it, then taken to bytes, as the
+    // files may not all be UTF-8.
+    regex(pattern).map_err(|error| error.within(value, 0).example(REPLACEMENT_EXAMPLE))?;
+    let regex = regex::bytes::Regex::new(pattern)
+        .map_err(|error| ParseError::new(value, 0..pattern.len(), error.to_string()))?;
+    Ok((regex, with.to_owned()))
+}
+
+/// A regular expression.
+pub fn regex(value: &str) -> Result<Regex, ParseError> {
+    Regex::new(value).map_err(|error| {
+        let regex::Error::Syntax(syntax) = &error else {
+            return ParseError::new(value, 0..value.len(), error.to_string());
+        };
+
+        // The syntax error is a diagram of its own: the pattern, a line of carets under
+        // the span and the message, which fits the single line patterns only.
+        let lines = syntax.lines().collect::<Vec<_>>();
+        let message = lines.last().unwrap_or(&"").trim_start_matches("error: ");
+        let (indent, carets) = match lines.as_slice() {
+            [_, pattern, carets, ..] if pattern.ends_with(value) => {
+                (pattern.len() - value.len(), *carets)
+            }
+            _ => return ParseError::new(value, 0..value.len(), message),
+        };
+        let column = (carets.len() - carets.trim_start().len()).saturating_sub(indent);
+        let carets = carets.trim();
+        let byte = |column: usize| {
+            value
+                .char_indices()
+                .nth(column)
+                .map_or(value.len(), |(byte, _)| byte)
+        };
+
+        let span = byte(column)..byte(column + carets.chars().count());
+        ParseError::new(value, span, message)
+    })
+}
+
+/// The numbers of a date such as `2024-06-01`, `count` of them separated by `-`, each
+/// with the span it was found at.
+fn date_fields(value: &str, count: usize) -> Result<Vec<(u32, Range<usize>)>, ParseError> {
+    let mut fields = Vec::with_capacity(count);
+    let mut start = 0;
+    for part in value.split('-') {
+        let span = start..start + part.len();
+        start = span.end + 1;
+        if fields.len() == count {
+            return Err(ParseError::new(
+                value,
+                span.start - 1..value.len(),
+                "unexpected text after the date",
+            ));
+        }
+        if part.is_empty() || !part.bytes().all(|byte| byte.is_ascii_digit()) {
+            let span = span.start..span.end.max(span.start + 1);
+            return Err(ParseError::new(value, span, "expected a number"));
+        }
+
+        let number = part
+            .parse()
+            .map_err(|_| ParseError::new(value, span.clone(), "number too large"))?;
+        fields.push((number, span));
+    }
+
+    match fields.len() < count {
+        true => Err(ParseError::new(
+            value,
+            value.len()..value.len() + 1,
+            "expected a '-' and more of the date",
+        )),
+        false => Ok(fields),
+    }
+}
+
+fn year(value: &str, (year, span): &(u32, Range<usize>)) -> Result<i32, ParseError> {
+    i32::try_from(*year).map_err(|_| ParseError::new(value, span.clone(), "no such year"))
+}
+
+fn month_number(value: &str, (month, span): &(u32, Range<usize>)) -> Result<u32, ParseError> {
+    match (1..=12).contains(month) {
+        true => Ok(*month),
+        false => Err(ParseError::new(value, span.clone(), "no such month")),
+    }
+}
+
+/// A day of the calendar, as `YYYY-MM-DD`.
+pub fn day(value: &str) -> Result<Period, ParseError> {
+    let date = || {
+        let fields = date_fields(value, 3)?;
+        let (year, month) = (year(value, &fields[0])?, month_number(value, &fields[1])?);
+        let (day, span) = &fields[2];
+        NaiveDate::from_ymd_opt(year, month, *day)
+            .ok_or_else(|| ParseError::new(value, span.clone(), "no such day in that month"))
+    };
+
+    date()
+        .map(Period::day)
+        .map_err(|error| error.example(DAY_EXAMPLE))
+}
+
+/// A month of the calendar, as `YYYY-MM`.
+pub fn month(value: &str) -> Result<Period, ParseError> {
+    let period = || {
+        let fields = date_fields(value, 2)?;
+        let (year, month) = (year(value, &fields[0])?, month_number(value, &fields[1])?);
+        Period::month(year, month)
+            .ok_or_else(|| ParseError::new(value, fields[0].1.clone(), "no such year"))
+    };
+
+    period().map_err(|error| error.example(MONTH_EXAMPLE))
+}
+
+/// A length of time, a number followed by a unit: `ms`, `s`, `m`, `h`, `d` or `w`.
+pub fn age(value: &str) -> Result<Duration, ParseError> {
+    let invalid = |span: Range<usize>, message: &str| {
+        ParseError::new(value, span, message).example(AGE_EXAMPLE)
+    };
+
+    let digits = value.bytes().take_while(u8::is_ascii_digit).count();
+    if digits == 0 {
+        return Err(invalid(0..value.len().max(1), "expected a number"));
+    }
+    let milliseconds = match &value[digits..] {
+        "ms" => 1,
+        "s" => 1000,
+        "m" => 60 * 1000,
+        "h" => 60 * 60 * 1000,
+        "d" => 24 * 60 * 60 * 1000,
+        "w" => 7 * 24 * 60 * 60 * 1000,
+        "" => {
+            return Err(invalid(
+                digits..digits + 1,
+                "expected a unit: ms, s, m, h, d or w",
+            ))
+        }
+        _ => return Err(invalid(digits..value.len(), "expected ms, s, m, h, d or w")),
+    };
+
+    value[..digits]
+        .parse::<u64>()
+        .ok()
+        .and_then(|number| number.checked_mul(milliseconds))
+        .map(Duration::from_millis)
+        .ok_or_else(|| invalid(0..digits, "number too large"))
+}
+
+/// How much an entry may grow, as a [`size`], and optionally within how long, as an
+/// [`age`] after a `/`.
+pub fn growth(value: &str) -> Result<(u64, Option<Duration>), ParseError> {
+    let (bytes, within) = match value.split_once('/') {
+        Some((bytes, within)) => (bytes, Some(within)),
+        None => (value, None),
+    };
+    let bytes = size(bytes).map_err(|error| error.within(value, 0).example(GROWTH_EXAMPLE))?;
+    let within = within
+        .map(|within| {
+            age(within).map_err(|error| {
+                error
+                    .within(value, value.len() - within.len())
+                    .example(GROWTH_EXAMPLE)
+            })
+        })
+        .transpose()?;
+    Ok((bytes, within))
+}
+
+/// An instant: `now`, seconds since the epoch after an `@`, or a day as `YYYY-MM-DD`
+/// followed by a time as `HH:MM` or `HH:MM:SS` after a `T` or a space, midnight if
+/// left out, in the local time zone.
+pub fn timestamp(value: &str) -> Result<SystemTime, ParseError> {
+    let instant = || {
+        if value == "now" {
+            return Ok(SystemTime::now());
+        }
+        if let Some(seconds) = value.strip_prefix('@') {
+            return seconds
+                .parse()
+                .map(|seconds| SystemTime::UNIX_EPOCH + Duration::from_secs(seconds))
+                .map_err(|_| ParseError::new(value, 1..value.len().max(2), "expected seconds"));
+        }
+
+        let (written, time) = value.split_once(['T', ' ']).unwrap_or((value, ""));
+        let fields = date_fields(written, 3).map_err(|error| error.within(value, 0))?;
+        let (year, month) = (year(value, &fields[0])?, month_number(value, &fields[1])?);
+        let (day, span) = &fields[2];
+        let date = NaiveDate::from_ymd_opt(year, month, *day)
+            .ok_or_else(|| ParseError::new(value, span.clone(), "no such day in that month"))?;
+
+        let time_span = written.len() + 1..value.len().max(written.len() + 2);
+        let time = match time {
+            "" if written.len() == value.len() => NaiveTime::MIN,
+            time => NaiveTime::parse_from_str(time, "%H:%M:%S")
+                .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M"))
+                .map_err(|_| ParseError::new(value, time_span.clone(), "expected a time"))?,
+        };
+        Local
+            .from_local_datetime(&date.and_time(time))
+            .earliest()
+            .map(SystemTime::from)
+            .ok_or_else(|| ParseError::new(value, time_span, "no such time in the local time zone"))
+    };
+
+    instant().map_err(|error| error.example(TIMESTAMP_EXAMPLE))
+}
+
+/// A glob pattern such as `*.rs`, where `*` and `?` never match a `/`.
+pub fn glob(value: &str) -> Result<Glob, ParseError> {
+    GlobBuilder::new(value)
+        .literal_separator(true)
+        .build()
+        .map_err(|error| {
+            ParseError::new(value, 0..value.len().max(1), error.kind().to_string())
+                .example("*.rs, test_*.py or src/**/*.{c,h}")
+        })
+}
+
+/// A path to a file, told apart by its device and inode. A symbolic link stands for
+/// the file it points to.
+pub fn same_file(value: &Path) -> Result<FileId, ParseError> {
+    let metadata = std::fs::metadata(value).map_err(|error| {
+        let value = value.to_string_lossy();
+        ParseError::new(&value, 0..value.len().max(1), error.to_string())
+    })?;
+
+    Ok(FileId {
+        path: value.to_owned(),
+        device: metadata.dev(),
+        inode: metadata.ino(),
+    })
+}
+
+/// A user name or a numeric uid.
+pub fn user(value: &str) -> Result<u32, ParseError> {
+    users::parse_user(value)
+        .ok_or_else(|| ParseError::new(value, 0..value.len(), "no such user").example("root or 0"))
+}
+
+/// A chown-style owner: `USER`, `USER:GROUP` or `:GROUP`, by name or by number.
+pub fn owner(value: &str) -> Result<Owner, ParseError> {
+    let (user, group) = value.split_once(':').unwrap_or((value, ""));
+    let group_span = user.len() + 1..value.len().max(user.len() + 2);
+    if user.is_empty() && group.is_empty() {
+        return Err(
+            ParseError::new(value, 0..value.len().max(1), "expected a user or a group")
+                .example(OWNER_EXAMPLE),
+        );
+    }
+
+    let uid = match user {
+        "" => None,
+        user => Some(users::parse_user(user).ok_or_else(|| {
+            ParseError::new(value, 0..user.len(), "no such user").example(OWNER_EXAMPLE)
+        })?),
+    };
+    let gid = match group {
+        "" => None,
+        group => Some(users::parse_group(group).ok_or_else(|| {
+            ParseError::new(value, group_span, "no such group").example(OWNER_EXAMPLE)
+        })?),
+    };
+    Ok(Owner { uid, gid })
+}
+
+/// An extended attribute and the value it should have, as `NAME=VALUE`.
+pub fn xattr(value: &str) -> Result<(String, String), ParseError> {
+    match value.split_once('=') {
+        Some(("", _)) => {
+            Err(ParseError::new(value, 0..1, "expected a name").example(XATTR_EXAMPLE))
+        }
+        Some((name, expected)) => Ok((name.to_owned(), expected.to_owned())),
+        None => Err(ParseError::new(
+            value,
+            value.len()..value.len() + 1,
+            "expected a '=' and the value",
+        )
+        .example(XATTR_EXAMPLE)),
+    }
+}
+
+/// The name of a directory and how many levels below it, find-style, as `NAME:N`.
+pub fn below(value: &str) -> Result<(String, NumberFilter), ParseError> {
+    let invalid = |span: Range<usize>, message: &str| {
+        ParseError::new(value, span, message).example(BELOW_EXAMPLE)
+    };
+
+    let Some((directory, levels)) = value.rsplit_once(':') else {
+        return Err(invalid(
+            value.len()..value.len() + 1,
+            "expected a ':' and how many levels below",
+        ));
+    };
+    if directory.is_empty() {
+        return Err(invalid(0..1, "expected the name of a directory"));
+    }
+    if let Some(slash) = directory.find('/') {
+        return Err(invalid(slash..slash + 1, "expected a name, without a '/'"));
+    }
+    let levels = number_filter(levels).map_err(|error| error.within(value, directory.len() + 1))?;
+    Ok((directory.to_owned(), levels))
+}
+
+/// A group name or a numeric gid.
+pub fn group(value: &str) -> Result<u32, ParseError> {
+    users::parse_group(value).ok_or_else(|| {
+        ParseError::new(value, 0..value.len(), "no such group").example("wheel or 0")
+    })
+}
+
+/// A MIME type, or all the types under one such as `image/*`.
+pub fn mime(value: &str) -> Result<String, ParseError> {
+    let invalid = |span: Range<usize>, message: &str| {
+        ParseError::new(value, span, message).example("image/png, image/* or application/pdf")
+    };
+
+    let Some((top_level, subtype)) = value.split_once('/') else {
+        return Err(invalid(0..value.len().max(1), "expected a type/subtype"));
+    };
+    if top_level.is_empty() || top_level == "*" && subtype != "*" {
+        return Err(invalid(
+            0..top_level.len().max(1),
+            "expected a type such as image",
+        ));
+    }
+    if subtype.is_empty() || subtype.contains('/') {
+        let start = top_level.len() + 1;
+        return Err(invalid(
+            start..value.len().max(start + 1),
+            "expected a subtype such as png",
+        ));
+    }
+
+    Ok(value.to_ascii_lowercase())
+}
+
+/// The name of a [`magic::Kind`].
+pub fn magic(value: &str) -> Result<String, ParseError> {
+    let name = value.to_ascii_lowercase();
+    match magic::is_known(&name) {
+        true => Ok(name),
+        false => Err(
+            ParseError::new(value, 0..value.len().max(1), "unknown kind of file")
+                .example("png, jpeg, pdf, zip, gzip, tar, elf or script"),
+        ),
+    }
+}
+
+/// The patterns of a file, one a line: globs, or regular expressions after `regex:`.
+#[derive(Debug, Clone, Default)]
+pub struct Patterns {
+    pub globs: Vec<Glob>,
+    pub regexes: Vec<Regex>,
+}
+
+/// The patterns in the file at `value`, one a line, such as a list another tool wrote
+/// or one too long for the command line. A line is a glob, or with `glob:` or `regex:`
+/// in front says which; blank lines and those starting with `#` are left out.
+pub fn patterns(value: &str) -> Result<Patterns, ParseError> {
+    let contents = std::fs::read_to_string(value)
+        .map_err(|error| ParseError::new(value, 0..value.len().max(1), error.to_string()))?;
+
+    let mut patterns = Patterns::default();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.trim_end_matches('\r');
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        // Errors point into the line, which is shown along with where it is from.
+        let at_line = |error: ParseError| {
+            let message = format!("{}:{}: {}", value, number + 1, error.message());
+            ParseError::new(line, error.span(), message)
+        };
+        match line.strip_prefix("regex:") {
+            Some(pattern) => patterns
+                .regexes
+                .push(regex(pattern).map_err(|error| at_line(error.within(line, 6)))?),
+            None => {
+                let pattern = line.strip_prefix("glob:").unwrap_or(line);
+                let offset = line.len() - pattern.len();
+                patterns
+                    .globs
+                    .push(glob(pattern).map_err(|error| at_line(error.within(line, offset)))?);
+            }
+        }
+    }
+
+    Ok(patterns)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Asserts that `parse` rejects `value` with `message`, pointing at `span` of it.
+    fn rejects<T: std::fmt::Debug>(
+        parse: impl Fn(&str) -> Result<T, ParseError>,
+        value: &str,
+        span: Range<usize>,
+        message: &str,
+    ) {
+        let error = parse(value).unwrap_err();
+        assert_eq!(
+            (error.span(), error.message()),
+            (span, message),
+            "{:?}",
+            value
+        );
+    }
+
+    #[test]
+    fn sizes() {
+        assert_eq!(size("0"), Ok(0));
+        assert_eq!(size("512"), Ok(512));
+        assert_eq!(size("10b"), Ok(10));
+        assert_eq!(size("4k"), Ok(4096));
+        assert_eq!(size("3M"), Ok(3 << 20));
+        assert_eq!(size("1G"), Ok(1 << 30));
+        assert_eq!(size("2t"), Ok(2 << 40));
+        assert_eq!(size_filter("+1k"), Ok(NumberFilter::MoreThan(1024)));
+        assert_eq!(size_filter("-2"), Ok(NumberFilter::LessThan(2)));
+    }
+
+    #[test]
+    fn size_error_spans() {
+        rejects(size, "", 0..1, "expected a size");
+        rejects(size, "k", 0..1, "expected a size");
+        rejects(size, "4x", 1..2, "unknown size unit");
+        rejects(size, "4kb", 1..3, "unknown size unit");
+        rejects(size, "99999999999999999999", 0..20, "size too large");
+        rejects(size, "17179869184G", 0..12, "size too large");
+        rejects(size_filter, "+4x", 2..3, "unknown size unit");
+    }
+
+    #[test]
+    fn ages() {
+        assert_eq!(age("500ms"), Ok(Duration::from_millis(500)));
+        assert_eq!(age("90s"), Ok(Duration::from_secs(90)));
+        assert_eq!(age("2m"), Ok(Duration::from_secs(120)));
+        assert_eq!(age("12h"), Ok(Duration::from_secs(12 * 60 * 60)));
+        assert_eq!(age("30d"), Ok(Duration::from_secs(30 * 24 * 60 * 60)));
+        assert_eq!(age("2w"), Ok(Duration::from_secs(14 * 24 * 60 * 60)));
+        assert_eq!(age("0s"), Ok(Duration::ZERO));
+    }
+
+    #[test]
+    fn age_error_spans() {
+        rejects(age, "", 0..1, "expected a number");
+        rejects(age, "ms", 0..2, "expected a number");
+        rejects(age, "5", 1..2, "expected a unit: ms, s, m, h, d or w");
+        rejects(age, "5y", 1..2, "expected ms, s, m, h, d or w");
+        rejects(age, "5 s", 1..3, "expected ms, s, m, h, d or w");
+        rejects(age, "99999999999999999999d", 0..20, "number too large");
+        rejects(age, "18446744073709551615w", 0..20, "number too large");
+    }
+
+    #[test]
+    fn growths() {
+        assert_eq!(growth("100M"), Ok((100 << 20, None)));
+        assert_eq!(
+            growth("10k/1m"),
+            Ok((10 << 10, Some(Duration::from_secs(60))))
+        );
+        rejects(growth, "/1m", 0..1, "expected a size");
+        rejects(growth, "10x/1m", 2..3, "unknown size unit");
+        rejects(growth, "10M/", 4..5, "expected a number");
+        rejects(growth, "10M/5y", 5..6, "expected ms, s, m, h, d or w");
+    }
+
+    #[test]
+    fn timestamps() {
+        assert_eq!(timestamp("@0"), Ok(SystemTime::UNIX_EPOCH));
+        assert_eq!(
+            timestamp("@1717245000"),
+            Ok(SystemTime::UNIX_EPOCH + Duration::from_secs(1717245000))
+        );
+        assert!(timestamp("now").is_ok());
+
+        let midnight = timestamp("2024-06-01").unwrap();
+        assert_eq!(timestamp("2024-06-01T00:00"), Ok(midnight));
+        assert_eq!(
+            timestamp("2024-06-01 12:30:15"),
+            Ok(midnight + Duration::from_secs(12 * 60 * 60 + 30 * 60 + 15))
+        );
+    }
+
+    #[test]
+    fn timestamp_error_spans() {
+        rejects(timestamp, "@", 1..2, "expected seconds");
+        rejects(timestamp, "@1x", 1..3, "expected seconds");
+        rejects(timestamp, "2024-13-01", 5..7, "no such month");
+        rejects(timestamp, "2024-02-30", 8..10, "no such day in that month");
+        rejects(
+            timestamp,
+            "2024-06",
+            7..8,
+            "expected a '-' and more of the date",
+        );
+        rejects(timestamp, "2024-06-01T", 11..12, "expected a time");
+        rejects(timestamp, "2024-06-01T25:00", 11..16, "expected a time");
+        rejects(timestamp, "yesterday", 0..9, "expected a number");
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(
+            date_fields("2024-06-01", 3),
+            Ok(vec![(2024, 0..4), (6, 5..7), (1, 8..10)])
+        );
+        assert_eq!(date_fields("2024-6", 2), Ok(vec![(2024, 0..4), (6, 5..6)]));
+        assert_eq!(
+            day("2024-02-29"),
+            Ok(Period::day(NaiveDate::from_ymd_opt(2024, 2, 29).unwrap()))
+        );
+        assert_eq!(month("2024-06"), Ok(Period::month(2024, 6).unwrap()));
+    }
+
+    #[test]
+    fn date_error_spans() {
+        let fields = |count| move |value: &str| date_fields(value, count);
+        rejects(fields(3), "", 0..1, "expected a number");
+        rejects(fields(3), "2024--01", 5..6, "expected a number");
+        rejects(fields(2), "2024-0x", 5..7, "expected a number");
+        rejects(
+            fields(3),
+            "2024-06",
+            7..8,
+            "expected a '-' and more of the date",
+        );
+        rejects(
+            fields(3),
+            "2024-06-01-02",
+            10..13,
+            "unexpected text after the date",
+        );
+        rejects(fields(2), "99999999999-01", 0..11, "number too large");
+        rejects(day, "2023-02-29", 8..10, "no such day in that month");
+        rejects(month, "2024-00", 5..7, "no such month");
+    }
+
+    #[test]
+    fn belows() {
+        assert_eq!(
+            below("tests:2"),
+            Ok(("tests".to_owned(), NumberFilter::Exactly(2)))
+        );
+        assert_eq!(
+            below("src:-3"),
+            Ok(("src".to_owned(), NumberFilter::LessThan(3)))
+        );
+        assert_eq!(
+            below("a:b:+0"),
+            Ok(("a:b".to_owned(), NumberFilter::MoreThan(0)))
+        );
+    }
+
+    #[test]
+    fn below_error_spans() {
+        rejects(
+            below,
+            "tests",
+            5..6,
+            "expected a ':' and how many levels below",
+        );
+        rejects(below, ":2", 0..1, "expected the name of a directory");
+        rejects(below, "a/b:1", 1..2, "expected a name, without a '/'");
+        rejects(below, "tests:x", 6..7, "expected a number");
+        rejects(below, "tests:", 6..7, "expected a number");
+        rejects(below, "tests:+2x", 8..9, "expected a number");
+    }
+
+    #[test]
+    fn owners() {
+        let owner_of = |uid, gid| Ok(Owner { uid, gid });
+        assert_eq!(owner("0"), owner_of(Some(0), None));
+        assert_eq!(owner("0:"), owner_of(Some(0), None));
+        assert_eq!(owner(":0"), owner_of(None, Some(0)));
+        assert_eq!(owner("0:0"), owner_of(Some(0), Some(0)));
+    }
+
+    #[test]
+    fn owner_error_spans() {
+        rejects(owner, "", 0..1, "expected a user or a group");
+        rejects(owner, ":", 0..1, "expected a user or a group");
+        rejects(owner, "no-such-user", 0..12, "no such user");
+        rejects(owner, "no-such-user:0", 0..12, "no such user");
+        rejects(owner, "0:no-such-group", 2..15, "no such group");
+    }
+
+    #[test]
+    fn mimes() {
+        assert_eq!(mime("image/png"), Ok("image/png".to_owned()));
+        assert_eq!(mime("Image/PNG"), Ok("image/png".to_owned()));
+        assert_eq!(mime("image/*"), Ok("image/*".to_owned()));
+        assert_eq!(mime("*/*"), Ok("*/*".to_owned()));
+    }
+
+    #[test]
+    fn mime_error_spans() {
+        rejects(mime, "", 0..1, "expected a type/subtype");
+        rejects(mime, "image", 0..5, "expected a type/subtype");
+        rejects(mime, "/png", 0..1, "expected a type such as image");
+        rejects(mime, "*/png", 0..1, "expected a type such as image");
+        rejects(mime, "image/", 6..7, "expected a subtype such as png");
+        rejects(mime, "a/b/c", 2..5, "expected a subtype such as png");
+    }
+
+    #[test]
+    fn error_rendering() {
+        assert_eq!(
+            size("4x").unwrap_err().to_string(),
+            "unknown size unit\n    4x\n     ^\n  e.g. 512, 4k or 1G"
+        );
+        assert_eq!(
+            ParseError::new("", 0..1, "expected a size").to_string(),
+            "expected a size"
+        );
+
+        // Past the end, the caret goes right after the value.
+        assert_eq!(
+            ParseError::new("a", 1..2, "expected more").to_string(),
+            "expected more\n    a\n     ^"
+        );
+
+        // Columns and widths are counted in characters, not bytes.
+        assert_eq!(
+            ParseError::new("äöxy", 4..6, "wrong").to_string(),
+            "wrong\n    äöxy\n      ^^"
+        );
+
+        let error = ParseError::new("2", 0..1, "wrong").within("tests:2", 6);
+        assert_eq!(error.span(), 6..7);
+        assert_eq!(error.to_string(), "wrong\n    tests:2\n          ^");
+    }
+}
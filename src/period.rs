@@ -0,0 +1,77 @@
+//! Calendar days and months, for `--modified-on` and `--modified-month`. A period runs
+//! from midnight to midnight in the local time zone, however long that turns out to be
+//! around a daylight saving change.
+
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, TimeDelta, TimeZone};
+use std::time::SystemTime;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Day,
+    Month,
+}
+
+/// A day or a month of the calendar, with the instants it starts and ends at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Period {
+    pub unit: Unit,
+    /// The first day of the period
+    pub first: NaiveDate,
+    start: SystemTime,
+    end: SystemTime,
+}
+
+/// When `date` starts in the local time zone. Where midnight is skipped by a daylight
+/// saving change, the day starts at the first instant after the gap.
+fn midnight(date: NaiveDate) -> SystemTime {
+    let mut time = NaiveDateTime::from(date);
+    loop {
+        if let Some(start) = Local.from_local_datetime(&time).earliest() {
+            return start.into();
+        }
+        time += TimeDelta::minutes(15);
+    }
+}
+
+impl Period {
+    pub fn day(date: NaiveDate) -> Self {
+        let next = date.succ_opt().unwrap_or(NaiveDate::MAX);
+        Self {
+            unit: Unit::Day,
+            first: date,
+            start: midnight(date),
+            end: midnight(next),
+        }
+    }
+
+    /// The month `month` of `year`, `None` if there is no such month.
+    pub fn month(year: i32, month: u32) -> Option<Self> {
+        let first = NaiveDate::from_ymd_opt(year, month, 1)?;
+        let next = match month {
+            12 => NaiveDate::from_ymd_opt(year + 1, 1, 1),
+            _ => NaiveDate::from_ymd_opt(year, month + 1, 1),
+        };
+
+        Some(Self {
+            unit: Unit::Month,
+            first,
+            start: midnight(first),
+            end: midnight(next.unwrap_or(NaiveDate::MAX)),
+        })
+    }
+
+    pub fn contains(&self, time: SystemTime) -> bool {
+        self.start <= time && time < self.end
+    }
+}
+
+/// Writes the period the way it is given, `2024-06-01` for a day and `2024-06` for a
+/// month.
+impl std::fmt::Display for Period {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.unit {
+            Unit::Day => write!(f, "{}", self.first.format("%Y-%m-%d")),
+            Unit::Month => write!(f, "{:04}-{:02}", self.first.year(), self.first.month()),
+        }
+    }
+}
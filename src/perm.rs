@@ -0,0 +1,145 @@
+use crate::mode;
+use crate::parse::{self, ParseError};
+use clap::ValueEnum;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Whom `--executable-by` asks about.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecutableBy {
+    /// The user running quaero, as access(2) decides for its effective user and groups
+    Me,
+    /// The owner of the file, by its user execute bit
+    Owner,
+    /// Anyone at all, by any of the execute bits
+    Any,
+}
+
+impl ExecutableBy {
+    /// Whether the file at `path`, with permission bits `mode`, can be executed. Only
+    /// [`ExecutableBy::Me`] looks at the file itself.
+    pub fn matches(self, path: &Path, mode: u32) -> bool {
+        match self {
+            ExecutableBy::Me => accessible(path, libc::X_OK),
+            ExecutableBy::Owner => mode & 0o100 != 0,
+            ExecutableBy::Any => mode & 0o111 != 0,
+        }
+    }
+}
+
+/// Whether the user running quaero may access the entry at `path`, or what it links to,
+/// as `mode` says, one or more of `R_OK`, `W_OK` and `X_OK`. access(2) decides for its
+/// effective user and groups, which takes ACLs, read-only mounts and root into account
+/// as the bits alone don't.
+pub fn accessible(path: &Path, mode: libc::c_int) -> bool {
+    let Ok(path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    unsafe { libc::faccessat(libc::AT_FDCWD, path.as_ptr(), mode, libc::AT_EACCESS) == 0 }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PermKind {
+    /// `MODE`: the permission bits are exactly MODE
+    Exact,
+    /// `-MODE`: all of the bits in MODE are set
+    All,
+    /// `/MODE`: any of the bits in MODE is set
+    Any,
+}
+
+/// A find-style `-perm` predicate, accepting both octal and symbolic modes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PermFilter {
+    pub(crate) kind: PermKind,
+    pub(crate) mode: u32,
+}
+
+impl PermFilter {
+    /// The predicate `-MODE`, that all of the bits of `mode` are set.
+    pub fn all_of(mode: u32) -> Self {
+        Self {
+            kind: PermKind::All,
+            mode,
+        }
+    }
+
+    pub fn matches(&self, mode: u32) -> bool {
+        let mode = mode & 0o7777;
+        match self.kind {
+            PermKind::Exact => mode == self.mode,
+            PermKind::All => mode & self.mode == self.mode,
+            PermKind::Any => self.mode == 0 || mode & self.mode != 0,
+        }
+    }
+}
+
+impl std::fmt::Display for PermFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let prefix = match self.kind {
+            PermKind::Exact => "",
+            PermKind::All => "-",
+            PermKind::Any => "/",
+        };
+        write!(f, "{}{:o}", prefix, self.mode)
+    }
+}
+
+impl FromStr for PermFilter {
+    type Err = ParseError;
+
+    fn from_str(value: &str) -> Result<Self, ParseError> {
+        parse::perm_filter(value)
+    }
+}
+
+/// A `--chmod` mode: octal bits to set, or symbolic clauses to apply to the bits a file
+/// already has.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModeChange {
+    Octal(u32),
+    Symbolic(String),
+}
+
+impl ModeChange {
+    /// The permission bits a file with `mode` gets, `X` in a symbolic mode making it
+    /// executable only if it is a directory or already executable by someone.
+    pub fn apply(&self, mode: u32, directory: bool) -> u32 {
+        let mode = mode & 0o7777;
+        match self {
+            ModeChange::Octal(bits) => *bits,
+            ModeChange::Symbolic(clauses) => {
+                mode::apply_symbolic(mode, clauses, directory || mode & 0o111 != 0).unwrap_or(mode)
+            }
+        }
+    }
+}
+
+impl FromStr for ModeChange {
+    type Err = ParseError;
+
+    fn from_str(value: &str) -> Result<Self, ParseError> {
+        parse::mode_change(value)
+    }
+}
+
+/// Renders the permission bits of `mode` the way `ls -l` does, e.g. `rwxr-sr-t`.
+pub fn symbolic(mode: u32) -> String {
+    let classes = [(6, 0o4000, 's'), (3, 0o2000, 's'), (0, 0o1000, 't')];
+
+    let mut rendered = String::with_capacity(9);
+    for (shift, special, special_char) in classes {
+        let bits = (mode >> shift) & 0o7;
+        rendered.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        rendered.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        rendered.push(match (bits & 0o1 != 0, mode & special != 0) {
+            (true, true) => special_char,
+            (false, true) => special_char.to_ascii_uppercase(),
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+
+    rendered
+}
@@ -0,0 +1,310 @@
+use crate::output::labeled;
+use crate::pipeline::Match;
+use anyhow::Result;
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{Attribute, Print, SetAttribute};
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, execute, queue};
+use quaero::fuzzy::{self, FuzzyMatch};
+use std::collections::BTreeSet;
+use std::fs::File;
+use std::io::Write;
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::Duration;
+
+/// How long to wait for a key press before looking for new candidates again.
+const TICK: Duration = Duration::from_millis(50);
+/// Upper bound on the candidates taken from the walk between two redraws.
+const BATCH_SIZE: usize = 4096;
+
+/// How an interactive session ended.
+pub enum Picked {
+    Selection(Vec<Match>),
+    Aborted,
+}
+
+/// Puts the terminal back the way it was, however the picker exits.
+struct Screen {
+    tty: File,
+}
+
+impl Screen {
+    fn enter() -> Result<Self> {
+        let mut tty = File::options().read(true).write(true).open("/dev/tty")?;
+        terminal::enable_raw_mode()?;
+        execute!(tty, terminal::EnterAlternateScreen)?;
+        Ok(Self { tty })
+    }
+}
+
+impl Drop for Screen {
+    fn drop(&mut self) {
+        let _ = execute!(self.tty, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+struct Candidate {
+    item: Match,
+    display: String,
+}
+
+#[derive(Default)]
+struct State {
+    candidates: Vec<Candidate>,
+    query: String,
+    /// Indices into `candidates` matching the query, best first
+    filtered: Vec<(usize, FuzzyMatch)>,
+    selected: BTreeSet<usize>,
+    cursor: usize,
+    offset: usize,
+    walking: bool,
+}
+
+impl State {
+    fn score(&self, index: usize) -> Option<FuzzyMatch> {
+        fuzzy::matches(&self.query, &self.candidates[index].display)
+    }
+
+    fn sort(&mut self) {
+        let candidates = &self.candidates;
+        self.filtered.sort_by(|(a, a_match), (b, b_match)| {
+            b_match
+                .score
+                .cmp(&a_match.score)
+                .then(
+                    candidates[*a]
+                        .display
+                        .len()
+                        .cmp(&candidates[*b].display.len()),
+                )
+                .then(a.cmp(b))
+        });
+    }
+
+    fn refilter(&mut self) {
+        self.filtered = (0..self.candidates.len())
+            .filter_map(|index| self.score(index).map(|found| (index, found)))
+            .collect();
+        self.sort();
+        self.cursor = 0;
+    }
+
+    fn add(&mut self, item: Match) {
+        let display = labeled(item.label.as_deref(), &item.entry.path);
+        self.candidates.push(Candidate { item, display });
+
+        let index = self.candidates.len() - 1;
+        if let Some(found) = self.score(index) {
+            self.filtered.push((index, found));
+        }
+    }
+
+    fn move_cursor(&mut self, delta: isize) {
+        let last = self.filtered.len().saturating_sub(1);
+        self.cursor = self.cursor.saturating_add_signed(delta).min(last);
+    }
+
+    fn toggle(&mut self) {
+        if let Some((index, _)) = self.filtered.get(self.cursor) {
+            if !self.selected.remove(index) {
+                self.selected.insert(*index);
+            }
+        }
+    }
+
+    fn render(&mut self, tty: &mut File) -> Result<()> {
+        let (width, height) = terminal::size()?;
+        let (width, rows) = (width as usize, height.saturating_sub(1) as usize);
+
+        if self.cursor < self.offset {
+            self.offset = self.cursor;
+        } else if rows > 0 && self.cursor >= self.offset + rows {
+            self.offset = self.cursor + 1 - rows;
+        }
+
+        queue!(tty, terminal::Clear(ClearType::All))?;
+        let visible = self
+            .filtered
+            .iter()
+            .enumerate()
+            .skip(self.offset)
+            .take(rows);
+        for (position, (index, found)) in visible {
+            let row = (position - self.offset + 1) as u16;
+            let marker = if self.selected.contains(index) {
+                "* "
+            } else {
+                "  "
+            };
+
+            queue!(tty, cursor::MoveTo(0, row))?;
+            if position == self.cursor {
+                queue!(tty, SetAttribute(Attribute::Reverse))?;
+            }
+            queue!(tty, Print(marker))?;
+
+            let mut positions = found.positions.iter().peekable();
+            for (position, c) in self.candidates[*index]
+                .display
+                .chars()
+                .enumerate()
+                .take(width.saturating_sub(marker.len()))
+            {
+                if positions.next_if_eq(&&position).is_some() {
+                    queue!(tty, SetAttribute(Attribute::Bold), Print(c))?;
+                    queue!(tty, SetAttribute(Attribute::NormalIntensity))?;
+                } else {
+                    queue!(tty, Print(c))?;
+                }
+            }
+            queue!(tty, SetAttribute(Attribute::Reset))?;
+        }
+
+        let status = format!(
+            "  {}/{}{}",
+            self.filtered.len(),
+            self.candidates.len(),
+            if self.walking { " …" } else { "" }
+        );
+        queue!(
+            tty,
+            cursor::MoveTo(0, 0),
+            Print("> "),
+            Print(&self.query),
+            SetAttribute(Attribute::Dim),
+            Print(status),
+            SetAttribute(Attribute::Reset),
+            cursor::MoveTo((2 + self.query.chars().count()).min(width) as u16, 0),
+        )?;
+
+        tty.flush()?;
+        Ok(())
+    }
+
+    fn into_selection(mut self) -> Vec<Match> {
+        if self.selected.is_empty() {
+            self.selected
+                .extend(self.filtered.get(self.cursor).map(|(index, _)| *index));
+        }
+
+        let mut candidates = self.candidates.into_iter().map(Some).collect::<Vec<_>>();
+        self.selected
+            .iter()
+            .filter_map(|index| candidates[*index].take())
+            .map(|candidate| candidate.item)
+            .collect()
+    }
+}
+
+/// What a key press asks the picker to do next.
+enum Action {
+    Continue,
+    Accept,
+    Abort,
+}
+
+fn handle_key(state: &mut State, key: KeyEvent) -> Action {
+    let control = key.modifiers.contains(KeyModifiers::CONTROL);
+    match key.code {
+        KeyCode::Esc => return Action::Abort,
+        KeyCode::Char('c' | 'g') if control => return Action::Abort,
+        KeyCode::Enter => return Action::Accept,
+        KeyCode::Up => state.move_cursor(-1),
+        KeyCode::Char('p' | 'k') if control => state.move_cursor(-1),
+        KeyCode::Down => state.move_cursor(1),
+        KeyCode::Char('n' | 'j') if control => state.move_cursor(1),
+        KeyCode::PageUp => state.move_cursor(-10),
+        KeyCode::PageDown => state.move_cursor(10),
+        KeyCode::Tab => {
+            state.toggle();
+            state.move_cursor(1);
+        }
+        KeyCode::BackTab => {
+            state.toggle();
+            state.move_cursor(-1);
+        }
+        KeyCode::Backspace => {
+            state.query.pop();
+            state.refilter();
+        }
+        KeyCode::Char('u') if control => {
+            state.query.clear();
+            state.refilter();
+        }
+        KeyCode::Char('w') if control => {
+            let kept = state
+                .query
+                .trim_end()
+                .rfind(' ')
+                .map_or(0, |index| index + 1);
+            state.query.truncate(kept);
+            state.refilter();
+        }
+        KeyCode::Char(c) if !control => {
+            state.query.push(c);
+            state.refilter();
+        }
+        _ => {}
+    }
+
+    Action::Continue
+}
+
+/// `--interactive`: lets the user fuzzy filter the candidates as they stream in from
+/// the walk, and pick one with Enter or several with Tab first.
+///
+/// The picker draws on the terminal directly, leaving stdout for the selection.
+pub fn pick(candidates: Receiver<Match>) -> Result<Picked> {
+    let mut screen = Screen::enter()?;
+    let mut state = State {
+        walking: true,
+        ..State::default()
+    };
+    let mut dirty = true;
+
+    loop {
+        if state.walking {
+            let added = state.filtered.len();
+            for _ in 0..BATCH_SIZE {
+                match candidates.try_recv() {
+                    Ok(item) => state.add(item),
+                    Err(TryRecvError::Empty) => break,
+                    Err(TryRecvError::Disconnected) => {
+                        state.walking = false;
+                        break;
+                    }
+                }
+            }
+            if state.filtered.len() != added {
+                state.sort();
+            }
+        }
+
+        // Redrawn on every tick while walking, which also covers up any error the walk
+        // has reported on the same terminal.
+        if state.walking || dirty {
+            state.render(&mut screen.tty)?;
+            dirty = false;
+        }
+
+        if !event::poll(TICK)? {
+            continue;
+        }
+
+        match event::read()? {
+            Event::Key(key) if key.kind != KeyEventKind::Release => {
+                match handle_key(&mut state, key) {
+                    Action::Continue => dirty = true,
+                    Action::Accept => break,
+                    Action::Abort => return Ok(Picked::Aborted),
+                }
+            }
+            Event::Resize(..) => dirty = true,
+            _ => {}
+        }
+    }
+
+    drop(screen);
+    Ok(Picked::Selection(state.into_selection()))
+}
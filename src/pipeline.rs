@@ -0,0 +1,491 @@
+use anyhow::{Context, Result};
+use quaero::{Entry, FileType};
+use regex::bytes::Regex;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ffi::OsStr;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::str::FromStr;
+
+/// A match on its way from the walk to the output.
+pub struct Match {
+    /// Workspace root the match was found under
+    pub label: Option<String>,
+    pub entry: Entry,
+    /// Hash of the contents, with --hash
+    pub hash: Option<String>,
+}
+
+impl Match {
+    pub fn new(label: Option<String>, entry: Entry) -> Self {
+        Self {
+            label,
+            entry,
+            hash: None,
+        }
+    }
+}
+
+/// A step between matching and output, able to drop, hold back or replace matches.
+pub trait Stage {
+    /// Returns the match to pass on to the next stage, if any.
+    fn process(&mut self, item: Match) -> Option<Match>;
+
+    /// Called once the walk is over, returning any match held back until then.
+    fn finish(&mut self) -> Result<Vec<Match>> {
+        Ok(Vec::new())
+    }
+}
+
+/// The stages every match goes through, in order.
+#[derive(Default)]
+pub struct Pipeline {
+    stages: Vec<Box<dyn Stage>>,
+}
+
+impl Pipeline {
+    pub fn push(&mut self, stage: impl Stage + 'static) {
+        self.stages.push(Box::new(stage));
+    }
+
+    pub fn process(&mut self, item: Match) -> Option<Match> {
+        self.stages
+            .iter_mut()
+            .try_fold(item, |item, stage| stage.process(item))
+    }
+
+    /// Flushes every stage in order, so that what an earlier stage held back still goes
+    /// through the later ones.
+    pub fn finish(&mut self) -> Result<Vec<Match>> {
+        let mut flushed = Vec::new();
+        for stage in &mut self.stages {
+            let mut passed = flushed
+                .into_iter()
+                .filter_map(|item| stage.process(item))
+                .collect::<Vec<_>>();
+            passed.extend(stage.finish()?);
+            flushed = passed;
+        }
+
+        Ok(flushed)
+    }
+}
+
+/// `--pipe-through`: feeds the path of every match to a shell command, one per line, and
+/// keeps whatever paths it prints back, in that order.
+pub struct PipeThrough {
+    command: String,
+    held: Vec<Match>,
+}
+
+impl PipeThrough {
+    pub fn new(command: String) -> Self {
+        Self {
+            command,
+            held: Vec::new(),
+        }
+    }
+}
+
+impl Stage for PipeThrough {
+    fn process(&mut self, item: Match) -> Option<Match> {
+        self.held.push(item);
+        None
+    }
+
+    fn finish(&mut self) -> Result<Vec<Match>> {
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .with_context(|| self.command.clone())?;
+
+        let mut input = Vec::new();
+        for item in &self.held {
+            input.extend_from_slice(item.entry.path.as_os_str().as_bytes());
+            input.push(b'\n');
+        }
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        // Written from another thread so that a command printing as it reads can't
+        // deadlock against us; it may also stop reading early, like head does.
+        let writer = std::thread::spawn(move || {
+            let _ = stdin.write_all(&input);
+        });
+
+        let mut pending: HashMap<PathBuf, Vec<Match>> = HashMap::new();
+        for item in self.held.drain(..).rev() {
+            pending
+                .entry(item.entry.path.clone())
+                .or_default()
+                .push(item);
+        }
+
+        let mut passed = Vec::new();
+        let stdout = child.stdout.take().expect("stdout is piped");
+        for line in BufReader::new(stdout).split(b'\n') {
+            let line = line?;
+            if line.is_empty() {
+                continue;
+            }
+
+            let path = Path::new(OsStr::from_bytes(&line));
+            if let Some(item) = pending.get_mut(path).and_then(Vec::pop) {
+                passed.push(item);
+                continue;
+            }
+
+            match Entry::from_path(path) {
+                Ok(entry) => passed.push(Match::new(None, entry)),
+                Err(error) => crate::report_error(&error.context(path.display().to_string())),
+            }
+        }
+
+        let _ = writer.join();
+        let status = child.wait()?;
+        if !status.success() {
+            return Err(anyhow::anyhow!(tr!(
+                "error-pipe-through-failed",
+                "command" => self.command.as_str(),
+                "status" => status.to_string(),
+            )));
+        }
+
+        Ok(passed)
+    }
+}
+
+/// `--filter-cmd`: keeps the matches a shell command exits successfully for, with `{}`
+/// in it standing for the path, or the path added at the end. Up to `jobs` of them run
+/// at once, and the matches are still passed on in the order they came in.
+pub struct FilterCommand {
+    command: String,
+    jobs: usize,
+    running: VecDeque<(Match, Child)>,
+}
+
+impl FilterCommand {
+    pub fn new(command: String, jobs: usize) -> Self {
+        Self {
+            command,
+            jobs,
+            running: VecDeque::new(),
+        }
+    }
+
+    fn spawn(&self, path: &Path) -> std::io::Result<Child> {
+        // The path is the shell's $1, with the bytes it has, instead of quoted into the
+        // command for every shell to read back its own way.
+        let command = match self.command.contains("{}") {
+            true => self.command.replace("{}", "\"$1\""),
+            false => format!("{} \"$1\"", self.command),
+        };
+
+        // What it prints would end up among the matches.
+        Command::new("sh")
+            .arg("-c")
+            .arg(command)
+            .arg("sh")
+            .arg(path)
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .spawn()
+    }
+
+    /// Waits for the command of the oldest match still running, and passes the match on
+    /// if it succeeded.
+    fn settle(&mut self) -> Option<Match> {
+        let (item, mut child) = self.running.pop_front()?;
+        match child.wait() {
+            Ok(status) => status.success().then_some(item),
+            Err(error) => {
+                crate::report_error(&anyhow::Error::from(error).context(self.command.clone()));
+                None
+            }
+        }
+    }
+}
+
+impl Stage for FilterCommand {
+    fn process(&mut self, item: Match) -> Option<Match> {
+        match self.spawn(&item.entry.path) {
+            Ok(child) => self.running.push_back((item, child)),
+            Err(error) => {
+                crate::report_error(&anyhow::Error::from(error).context(self.command.clone()));
+                return None;
+            }
+        }
+
+        match self.running.len() < self.jobs {
+            true => None,
+            false => self.settle(),
+        }
+    }
+
+    fn finish(&mut self) -> Result<Vec<Match>> {
+        let mut passed = Vec::new();
+        while !self.running.is_empty() {
+            passed.extend(self.settle());
+        }
+
+        Ok(passed)
+    }
+}
+
+/// `--rewrite`: a sed-style `s/REGEX/REPLACEMENT/[g]` applied to the printed path.
+///
+/// Any character can stand in for `/`, e.g. `s#^/mnt#/data#`. The replacement can refer
+/// to groups as `\1` or `$1`.
+#[derive(Debug, Clone)]
+pub struct Rewrite {
+    regex: Regex,
+    replacement: String,
+    global: bool,
+}
+
+impl FromStr for Rewrite {
+    type Err = anyhow::Error;
+
+    fn from_str(value: &str) -> Result<Self> {
+        let invalid = || anyhow::anyhow!("expected s/REGEX/REPLACEMENT/ or s/REGEX/REPLACEMENT/g");
+
+        let rest = value.strip_prefix('s').ok_or_else(invalid)?;
+        let mut chars = rest.chars();
+        let delimiter = chars.next().ok_or_else(invalid)?;
+
+        // Splits on the delimiter, unless it is escaped with a backslash.
+        let mut parts = vec![String::new()];
+        while let Some(c) = chars.next() {
+            let part = parts.last_mut().expect("parts is never empty");
+            match c {
+                '\\' => match chars.next() {
+                    Some(c) if c == delimiter => part.push(c),
+                    Some(c) => {
+                        part.push('\\');
+                        part.push(c);
+                    }
+                    None => part.push('\\'),
+                },
+                c if c == delimiter => parts.push(String::new()),
+                c => part.push(c),
+            }
+        }
+        let [pattern, replacement, flags] = &parts[..] else {
+            return Err(invalid());
+        };
+
+        let global = match flags.as_str() {
+            "" => false,
+            "g" => true,
+            _ => return Err(anyhow::anyhow!("unknown rewrite flags '{}'", flags)),
+        };
+
+        // sed's \1 is written ${1} in the regex crate's replacement syntax.
+        let mut converted = String::with_capacity(replacement.len());
+        let mut chars = replacement.chars();
+        while let Some(c) = chars.next() {
+            match (c, chars.clone().next()) {
+                ('\\', Some(digit @ '0'..='9')) => {
+                    chars.next();
+                    converted.push_str(&format!("${{{}}}", digit));
+                }
+                ('\\', Some(c)) => {
+                    chars.next();
+                    converted.push(c);
+                }
+                (c, _) => converted.push(c),
+            }
+        }
+
+        Ok(Self {
+            regex: Regex::new(pattern)?,
+            replacement: converted,
+            global,
+        })
+    }
+}
+
+impl Stage for Rewrite {
+    fn process(&mut self, mut item: Match) -> Option<Match> {
+        let path = item.entry.path.as_os_str().as_bytes();
+        let rewritten = match self.global {
+            true => self.regex.replace_all(path, self.replacement.as_bytes()),
+            false => self.regex.replace(path, self.replacement.as_bytes()),
+        };
+
+        if let Cow::Owned(rewritten) = rewritten {
+            item.entry.path = PathBuf::from(OsStr::from_bytes(&rewritten));
+        }
+
+        Some(item)
+    }
+}
+
+/// `--physical`: resolves the directories leading to each match, so that its path no
+/// longer goes through symbolic links. A symbolic link that matched is kept as itself.
+#[derive(Default)]
+pub struct Physical {
+    parents: HashMap<PathBuf, Option<PathBuf>>,
+}
+
+impl Stage for Physical {
+    fn process(&mut self, mut item: Match) -> Option<Match> {
+        let path = item.entry.path.as_path();
+        let (Some(parent), Some(name)) = (path.parent(), path.file_name()) else {
+            return Some(item);
+        };
+
+        let resolved = self.parents.entry(parent.to_path_buf()).or_insert_with(|| {
+            let parent = if parent.as_os_str().is_empty() {
+                Path::new(".")
+            } else {
+                parent
+            };
+            std::fs::canonicalize(parent).ok()
+        });
+
+        // A parent that vanished since it was walked keeps the path as traversed.
+        if let Some(resolved) = resolved {
+            item.entry.path = resolved.join(name);
+        }
+
+        Some(item)
+    }
+}
+
+/// `--absolute-path`: joins the relative paths to the current directory.
+pub struct Absolute {
+    current: PathBuf,
+}
+
+impl Absolute {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            current: std::env::current_dir()?,
+        })
+    }
+}
+
+impl Stage for Absolute {
+    fn process(&mut self, mut item: Match) -> Option<Match> {
+        let path = item.entry.path.as_path();
+        if path.is_relative() {
+            // Leaves out the `.` the path may start with.
+            let absolute = self.current.join(path).components().collect::<PathBuf>();
+            item.entry.path = absolute;
+        }
+
+        Some(item)
+    }
+}
+
+/// `--canonicalize`: resolves every symbolic link in the path of each match, the match
+/// itself included, unlike [`Physical`]. What can't be resolved, such as a broken link
+/// or a member of an archive, keeps its path.
+pub struct Canonical;
+
+impl Stage for Canonical {
+    fn process(&mut self, mut item: Match) -> Option<Match> {
+        if let Ok(canonical) = std::fs::canonicalize(&item.entry.path) {
+            item.entry.path = canonical;
+        }
+
+        Some(item)
+    }
+}
+
+/// Drops the matches already passed on under another path, as overlapping roots and
+/// followed links find them, told apart by their paths with every link on the way
+/// resolved. The last component of a link no one follows is the link itself.
+#[derive(Default)]
+pub struct Dedup {
+    seen: HashSet<PathBuf>,
+}
+
+impl Stage for Dedup {
+    fn process(&mut self, item: Match) -> Option<Match> {
+        let path = item.entry.path.as_path();
+        let resolved = match item.entry.file_type() {
+            FileType::SymLink => path.file_name().and_then(|name| {
+                let parent = path
+                    .parent()
+                    .filter(|parent| !parent.as_os_str().is_empty());
+                let parent = std::fs::canonicalize(parent.unwrap_or(Path::new("."))).ok()?;
+                Some(parent.join(name))
+            }),
+            _ => std::fs::canonicalize(path).ok(),
+        };
+        // What can't be resolved, such as an archive member, goes by its path.
+        let key = resolved.unwrap_or_else(|| path.to_path_buf());
+        self.seen.insert(key).then_some(item)
+    }
+}
+
+/// `--unique-inodes`: drops the matches that are hard links to a file, a device and
+/// inode, already passed on.
+#[derive(Default)]
+pub struct UniqueInodes {
+    seen: HashSet<(u64, u64)>,
+}
+
+impl Stage for UniqueInodes {
+    fn process(&mut self, item: Match) -> Option<Match> {
+        let Ok(metadata) = item.entry.metadata() else {
+            return Some(item);
+        };
+        self.seen
+            .insert((metadata.dev(), metadata.ino()))
+            .then_some(item)
+    }
+}
+
+/// `--max-per-dir`: passes on at most `max` of the matches in any one directory, and
+/// once the walk is over says on stderr how many more each directory had.
+pub struct PerDirectory {
+    max: usize,
+    counts: HashMap<PathBuf, usize>,
+}
+
+impl PerDirectory {
+    pub fn new(max: usize) -> Self {
+        Self {
+            max,
+            counts: HashMap::new(),
+        }
+    }
+}
+
+impl Stage for PerDirectory {
+    fn process(&mut self, item: Match) -> Option<Match> {
+        let parent = item.entry.path.parent().unwrap_or(Path::new(""));
+        let count = self.counts.entry(parent.to_path_buf()).or_default();
+        *count += 1;
+        (*count <= self.max).then_some(item)
+    }
+
+    fn finish(&mut self) -> Result<Vec<Match>> {
+        let mut more = self
+            .counts
+            .iter()
+            .filter(|(_, count)| **count > self.max)
+            .collect::<Vec<_>>();
+        more.sort();
+        for (directory, count) in more {
+            eprintln!(
+                "{}",
+                tr!(
+                    "max-per-dir-more",
+                    "directory" => quaero::clean_path(directory).into_owned(),
+                    "count" => count - self.max,
+                )
+            );
+        }
+
+        Ok(Vec::new())
+    }
+}
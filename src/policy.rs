@@ -0,0 +1,142 @@
+use crate::{content, Entry};
+use anyhow::{Context, Result};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::io::{BufRead, BufReader};
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+const IGNORE_FILES: [&str; 3] = [".gitignore", ".ignore", ".quaeroignore"];
+const DEFAULT_EXCLUDES: [&str; 3] = [".git", ".hg", ".svn"];
+
+/// What macOS leaves about for Finder, Spotlight and the trash, skipped along with the
+/// default excludes.
+#[cfg(target_os = "macos")]
+const SYSTEM_NOISE: &[&str] = &[
+    ".DS_Store",
+    ".Spotlight-V100",
+    ".Trashes",
+    ".fseventsd",
+    ".DocumentRevisions-V100",
+];
+#[cfg(not(target_os = "macos"))]
+const SYSTEM_NOISE: &[&str] = &[];
+
+/// Decides which entries the walker skips, relaxed one layer at a time by `-u`.
+#[derive(Debug, Clone)]
+pub struct IgnorePolicy {
+    pub ignore_files: bool,
+    pub hidden: bool,
+    pub default_excludes: bool,
+    /// Names skipped along with the default excludes, e.g. from the config file
+    pub excludes: Vec<String>,
+    /// Ignore lists given explicitly, e.g. with `--ignore-file`, which apply whatever
+    /// `-u` says and below the ignore files of the directories walked
+    pub lists: Vec<Gitignore>,
+}
+
+impl IgnorePolicy {
+    /// `-u` stops honoring ignore files, `-uu` also shows hidden files and `-uuu`
+    /// drops the default excludes as well.
+    pub fn from_unrestricted(level: u8) -> Self {
+        Self {
+            ignore_files: level < 1,
+            hidden: level < 2,
+            default_excludes: level < 3,
+            excludes: Vec::new(),
+            lists: Vec::new(),
+        }
+    }
+
+    /// Also skips the entries called any of `names`, as long as the default excludes
+    /// are skipped.
+    pub fn excluding(mut self, names: impl IntoIterator<Item = String>) -> Self {
+        self.excludes.extend(names);
+        self
+    }
+
+    /// Also skips what the ignore list `list` matches.
+    pub fn ignore_list(mut self, list: Gitignore) -> Self {
+        self.lists.push(list);
+        self
+    }
+
+    /// Reads the ignore list in gitignore syntax at `path`, its patterns anchored at
+    /// `root`.
+    pub fn read_list(root: &Path, path: &Path) -> Result<Gitignore> {
+        let mut builder = GitignoreBuilder::new(root);
+        // The error names the file already.
+        if let Some(error) = builder.add(path) {
+            return Err(error.into());
+        }
+
+        builder.build().with_context(|| path.display().to_string())
+    }
+
+    /// Loads the ignore files living in `directory`, if any apply under this policy.
+    pub fn load_ignores<T: AsRef<Path>>(&self, directory: T) -> Option<Gitignore> {
+        if !self.ignore_files {
+            return None;
+        }
+
+        let directory = directory.as_ref();
+        let mut builder = GitignoreBuilder::new(directory);
+        let mut found = false;
+        for name in IGNORE_FILES {
+            let path = directory.join(name);
+            if path.is_file() {
+                found |= add_ignore_file(&mut builder, &path);
+            }
+        }
+
+        found.then(|| builder.build().ok()).flatten()
+    }
+
+    pub fn should_skip(&self, entry: &Entry, ignores: &[Gitignore]) -> bool {
+        if self.default_excludes
+            && (DEFAULT_EXCLUDES.iter().any(|name| entry.name == *name)
+                || SYSTEM_NOISE.iter().any(|name| entry.name == *name)
+                || self.excludes.iter().any(|name| entry.name == name.as_str()))
+        {
+            return true;
+        }
+
+        if self.hidden && entry.name.as_bytes().starts_with(b".") {
+            return true;
+        }
+
+        let is_dir = entry.is_dir();
+        for ignore in ignores.iter().rev().chain(&self.lists) {
+            match ignore.matched(&entry.path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+        }
+
+        false
+    }
+}
+
+/// Adds the patterns of the ignore file at `path` to `builder`, as
+/// [`GitignoreBuilder::add`] does but opened by [`content::open`], for `--no-atime`.
+/// Returns whether all of it was read and every pattern is valid.
+fn add_ignore_file(builder: &mut GitignoreBuilder, path: &Path) -> bool {
+    let Ok(file) = content::open(path) else {
+        return false;
+    };
+
+    let mut valid = true;
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let Ok(line) = line else {
+            return false;
+        };
+        // As git, which skips the byte order mark a file may start with.
+        let line = match number {
+            0 => line.trim_start_matches('\u{feff}'),
+            _ => &line,
+        };
+        valid &= builder.add_line(Some(path.to_path_buf()), line).is_ok();
+    }
+    valid
+}
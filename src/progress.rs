@@ -0,0 +1,137 @@
+//! The line on stderr telling how far a long search has got, for a terminal to watch.
+
+use quaero::WalkStats;
+use std::io::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+/// How long a search runs before it shows its progress, as most are over by then.
+const DELAY: Duration = Duration::from_secs(1);
+
+/// How often the line is redrawn.
+const INTERVAL: Duration = Duration::from_millis(100);
+
+/// Whether the line is on the terminal, to be cleared before anything else goes there.
+static DRAWN: Mutex<bool> = Mutex::new(false);
+
+/// Runs `print` with the line taken off the terminal, for whatever it prints not to
+/// end up after it. The line is drawn again a moment later.
+pub fn interject<T>(print: impl FnOnce() -> T) -> T {
+    let mut drawn = DRAWN
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if *drawn {
+        let mut stderr = std::io::stderr().lock();
+        let _ = write!(stderr, "\r\x1b[K");
+        let _ = stderr.flush();
+        *drawn = false;
+    }
+    print()
+}
+
+struct Shared {
+    stats: Arc<WalkStats>,
+    matches: AtomicU64,
+}
+
+impl Shared {
+    fn draw(&self) {
+        let mut drawn = DRAWN
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let summary = tr!(
+            "progress",
+            "directories" => self.stats.directories.load(Ordering::Relaxed),
+            "matches" => self.matches.load(Ordering::Relaxed),
+        );
+        let current = self
+            .stats
+            .current
+            .lock()
+            .map(|current| current.display().to_string())
+            .unwrap_or_default();
+
+        // The line has to fit, or it wraps and can't be cleared; the end of the path
+        // says the most.
+        let width = crossterm::terminal::size()
+            .ok()
+            .filter(|(columns, _)| *columns > 0)
+            .map_or(80, |(columns, _)| columns as usize);
+        let room = width.saturating_sub(summary.chars().count() + 2);
+        let length = current.chars().count();
+        let current = match length > room {
+            true => {
+                let tail = current.chars().skip(length + 1 - room).collect::<String>();
+                format!("…{}", tail)
+            }
+            false => current,
+        };
+
+        let mut stderr = std::io::stderr().lock();
+        let _ = write!(stderr, "\r\x1b[K{} {}", summary, current);
+        let _ = stderr.flush();
+        *drawn = true;
+    }
+}
+
+/// Shows how many directories the walk has read and how many matches it found, and
+/// where it is, once the search has taken a while. Anything else printed to the
+/// terminal meanwhile goes through [`interject`].
+pub struct Progress {
+    shared: Arc<Shared>,
+    /// Both gone once it is finished
+    reporter: Mutex<Option<(Sender<()>, JoinHandle<()>)>>,
+}
+
+impl Progress {
+    pub fn start(stats: Arc<WalkStats>) -> Self {
+        let shared = Arc::new(Shared {
+            stats,
+            matches: AtomicU64::new(0),
+        });
+        let (stop, stopped) = std::sync::mpsc::channel();
+
+        let reporter = {
+            let shared = shared.clone();
+            std::thread::spawn(move || {
+                let mut wait = DELAY;
+                while let Err(RecvTimeoutError::Timeout) = stopped.recv_timeout(wait) {
+                    shared.draw();
+                    wait = INTERVAL;
+                }
+            })
+        };
+
+        Self {
+            shared,
+            reporter: Mutex::new(Some((stop, reporter))),
+        }
+    }
+
+    pub fn found(&self) {
+        self.shared.matches.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Stops showing the progress, for good.
+    pub fn finish(&self) {
+        let reporter = self
+            .reporter
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .take();
+        if let Some((stop, reporter)) = reporter {
+            drop(stop);
+            let _ = reporter.join();
+        }
+        interject(|| ());
+    }
+}
+
+impl Drop for Progress {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
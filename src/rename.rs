@@ -0,0 +1,326 @@
+//! `--rename`: the matched entries renamed by a sed-style substitution on their names.
+//! Every batch of renames is logged, for `quaero undo-rename` to take them back.
+
+use crate::output::Printer;
+use crate::state;
+use anyhow::{Context, Result};
+use quaero::parse::{self, ParseError};
+use quaero::Entry;
+use regex::{Regex, RegexBuilder};
+use std::borrow::Cow;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const SCHEMA_KIND: &str = "renames";
+const SCHEMA_VERSION: u32 = 1;
+const EXAMPLE: &str = "s/\\.jpeg$/.jpg/ or s/(.+)-draft/$1/";
+
+/// What `--rename` does to a name: the first match of a regular expression replaced,
+/// or every match with the `g` flag.
+#[derive(Debug, Clone)]
+pub struct Substitution {
+    regex: Regex,
+    with: String,
+    global: bool,
+}
+
+impl Substitution {
+    fn apply<'a>(&self, name: &'a str) -> Cow<'a, str> {
+        match self.global {
+            true => self.regex.replace_all(name, self.with.as_str()),
+            false => self.regex.replace(name, self.with.as_str()),
+        }
+    }
+}
+
+/// Splits `value` on the `delimiter`s not escaped by a backslash, an escaped one
+/// standing for itself.
+fn split_unescaped(value: &str, delimiter: char) -> Vec<String> {
+    let mut parts = vec![String::new()];
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' if chars.peek() == Some(&delimiter) => {
+                parts.last_mut().expect("there is a part").push(delimiter);
+                chars.next();
+            }
+            '\\' => {
+                let part = parts.last_mut().expect("there is a part");
+                part.push('\\');
+                part.extend(chars.next());
+            }
+            _ if c == delimiter => parts.push(String::new()),
+            _ => parts.last_mut().expect("there is a part").push(c),
+        }
+    }
+    parts
+}
+
+/// A substitution written as sed writes them, `s/REGEX/REPLACEMENT/FLAGS` with any
+/// delimiter after the s, where `$1`, `${name}` or, as in sed, `\1` stand for what a
+/// group matched. The flags are `g` to replace every match and `i` to ignore case.
+pub fn substitution(value: &str) -> Result<Substitution, ParseError> {
+    let invalid =
+        |message: &str| ParseError::new(value, 0..value.len().max(1), message).example(EXAMPLE);
+    let Some(rest) = value.strip_prefix('s') else {
+        return Err(invalid("expected s/REGEX/REPLACEMENT/"));
+    };
+    let Some(delimiter) = rest
+        .chars()
+        .next()
+        .filter(|c| !c.is_alphanumeric() && *c != '\\')
+    else {
+        return Err(invalid("expected a delimiter such as / after the s"));
+    };
+
+    let parts = split_unescaped(&rest[delimiter.len_utf8()..], delimiter);
+    let [pattern, with, flags] = parts.as_slice() else {
+        return Err(invalid("expected s/REGEX/REPLACEMENT/"));
+    };
+    if let Some(flag) = flags.chars().find(|flag| !matches!(flag, 'g' | 'i')) {
+        let start = value.len() - flags.len();
+        return Err(ParseError::new(
+            value,
+            start..start + flag.len_utf8(),
+            format!("unknown flag {}, expected g or i", flag),
+        ));
+    }
+
+    // Escaped delimiters make the pattern shorter than it is written, for the error to
+    // point a little early past one.
+    let offset = 1 + delimiter.len_utf8();
+    let mut regex =
+        parse::regex(pattern).map_err(|error| error.within(value, offset).example(EXAMPLE))?;
+    if flags.contains('i') {
+        regex = RegexBuilder::new(pattern)
+            .case_insensitive(true)
+            .build()
+            .map_err(|error| invalid(&error.to_string()))?;
+    }
+    // sed's \1 is the regex crate's ${1}.
+    let with = Regex::new(r"\\([0-9])")
+        .expect("the pattern is valid")
+        .replace_all(with, "$${$1}")
+        .into_owned();
+
+    Ok(Substitution {
+        regex,
+        with,
+        global: flags.contains('g'),
+    })
+}
+
+/// Where the logs of the renames are kept, one for every time files were renamed.
+fn log_directory() -> Option<PathBuf> {
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))?;
+
+    Some(data_home.join("quaero").join("renames"))
+}
+
+/// Writes down the `renames` made, each as the absolute path before and after, for
+/// them to be taken back. Returns where.
+fn write_log(renames: &[(PathBuf, PathBuf)]) -> Result<PathBuf> {
+    let directory = log_directory()
+        .ok_or_else(|| anyhow::anyhow!("could not determine where to log renames"))?;
+    std::fs::create_dir_all(&directory).with_context(|| directory.display().to_string())?;
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let location = directory.join(format!("{}-{}.log", seconds, std::process::id()));
+
+    let mut contents = state::schema_header(SCHEMA_KIND, SCHEMA_VERSION);
+    contents.push('\n');
+    for (from, to) in renames {
+        let line = serde_json::to_string(&(from, to)).context("failed to log a rename")?;
+        contents.push_str(&line);
+        contents.push('\n');
+    }
+    state::write_atomically(&location, contents.as_bytes())?;
+
+    Ok(location)
+}
+
+/// Renames the matched entries by `substitution` on their names, deepest first so that
+/// the paths of the others stay valid. An entry is skipped if its new name would take
+/// the place of something already there or of another rename, or isn't a name, and so
+/// is one that fails. So is one whose path isn't UTF-8, which the log can't hold.
+pub fn rename(
+    matches: &[Entry],
+    substitution: &Substitution,
+    dry_run: bool,
+    printer: &mut Printer,
+) -> Result<()> {
+    let (mut skipped, mut renames) = (0, Vec::new());
+    let mut taken: HashSet<PathBuf> = HashSet::new();
+
+    for entry in matches.iter().rev() {
+        let path = entry.path.as_path();
+        let Some(name) = path.file_name().map(|name| name.to_string_lossy()) else {
+            continue;
+        };
+        let new_name = substitution.apply(&name);
+        if new_name == name {
+            continue;
+        }
+
+        let display = quaero::clean_path(&entry.path).into_owned();
+        if path.to_str().is_none() {
+            eprintln!("{}", tr!("rename-skipping-not-utf8", "path" => display));
+            skipped += 1;
+            continue;
+        }
+        if new_name.is_empty() || new_name.contains('/') || new_name == "." || new_name == ".." {
+            eprintln!(
+                "{}",
+                tr!("rename-skipping-invalid", "path" => display, "name" => new_name.as_ref())
+            );
+            skipped += 1;
+            continue;
+        }
+
+        let destination = path.with_file_name(new_name.as_ref());
+        let destination_display = quaero::clean_path(&destination).into_owned();
+        // Only a change of case on a file system that ignores it finds the file itself.
+        let occupied = destination
+            .symlink_metadata()
+            .is_ok_and(|_| !same_file(path, &destination));
+        if taken.contains(&destination) || occupied {
+            eprintln!(
+                "{}",
+                tr!(
+                    "transfer-skipping-existing",
+                    "path" => display,
+                    "destination" => destination_display,
+                )
+            );
+            skipped += 1;
+            continue;
+        }
+
+        // The renames made so far are logged all the same.
+        if let Err(error) = (!dry_run)
+            .then(|| std::fs::rename(path, &destination))
+            .transpose()
+        {
+            let context = format!("{} -> {}", display, destination_display);
+            crate::report_error(&anyhow::Error::from(error).context(context));
+            skipped += 1;
+            continue;
+        }
+        printer.write(|out| writeln!(out, "{} -> {}", display, destination_display));
+        let absolute = |path: &Path| std::path::absolute(path).unwrap_or_else(|_| path.to_owned());
+        renames.push((absolute(path), absolute(&destination)));
+        taken.insert(destination);
+    }
+
+    let log = match dry_run || renames.is_empty() {
+        true => None,
+        false => Some(write_log(&renames)?),
+    };
+    eprintln!(
+        "{}",
+        tr!(
+            "rename-summary",
+            "dry-run" => if dry_run { "yes" } else { "no" },
+            "files" => renames.len(),
+            "skipped" => skipped,
+        )
+    );
+    if let Some(log) = log {
+        eprintln!(
+            "{}",
+            tr!("rename-logged", "path" => log.display().to_string())
+        );
+    }
+
+    Ok(())
+}
+
+fn same_file(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (a.symlink_metadata(), b.symlink_metadata()) {
+        (Ok(a), Ok(b)) => (a.dev(), a.ino()) == (b.dev(), b.ino()),
+        _ => false,
+    }
+}
+
+/// The most recent log in the log directory.
+fn latest_log() -> Result<PathBuf> {
+    let directory = log_directory()
+        .ok_or_else(|| anyhow::anyhow!("could not determine where renames are logged"))?;
+    let logs = match std::fs::read_dir(&directory) {
+        Ok(logs) => logs,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {
+            return Err(anyhow::anyhow!(tr!("error-no-rename-log")));
+        }
+        Err(error) => return Err(error).with_context(|| directory.display().to_string()),
+    };
+
+    logs.filter_map(|log| log.ok())
+        .filter(|log| {
+            log.path()
+                .extension()
+                .is_some_and(|extension| extension == "log")
+        })
+        .max_by_key(|log| log.metadata().and_then(|metadata| metadata.modified()).ok())
+        .map(|log| log.path())
+        .ok_or_else(|| anyhow::anyhow!(tr!("error-no-rename-log")))
+}
+
+/// `quaero undo-rename`: takes back the renames logged in `log`, the latest if none,
+/// last first. A rename whose entry has moved on since or whose old name was taken is
+/// left as it is. The log is removed once every rename is undone.
+pub fn undo(log: Option<&Path>) -> Result<()> {
+    let log = match log {
+        Some(log) => log.to_path_buf(),
+        None => latest_log()?,
+    };
+    let contents = std::fs::read_to_string(&log).with_context(|| log.display().to_string())?;
+    let (version, body) = state::parse_schema(&log, SCHEMA_KIND, &contents)?;
+    if version != SCHEMA_VERSION {
+        return Err(anyhow::anyhow!(
+            "{} is not a log of renames (schema v{}, expected v{})",
+            log.display(),
+            version,
+            SCHEMA_VERSION
+        ));
+    }
+
+    let mut renames = Vec::new();
+    for line in body.lines().filter(|line| !line.trim().is_empty()) {
+        let rename: (PathBuf, PathBuf) = serde_json::from_str(line)
+            .with_context(|| format!("{} has a malformed line: {}", log.display(), line))?;
+        renames.push(rename);
+    }
+
+    let (mut undone, mut skipped) = (0, 0);
+    for (from, to) in renames.iter().rev() {
+        let (from_display, to_display) = (from.display().to_string(), to.display().to_string());
+        if to.symlink_metadata().is_err() || from.symlink_metadata().is_ok() {
+            eprintln!(
+                "{}",
+                tr!("undo-rename-skipping", "path" => to_display, "destination" => from_display)
+            );
+            skipped += 1;
+            continue;
+        }
+
+        std::fs::rename(to, from).with_context(|| format!("{} -> {}", to_display, from_display))?;
+        println!("{} -> {}", to_display, from_display);
+        undone += 1;
+    }
+
+    if skipped == 0 {
+        std::fs::remove_file(&log).with_context(|| log.display().to_string())?;
+    }
+    eprintln!(
+        "{}",
+        tr!("undo-rename-summary", "files" => undone, "skipped" => skipped)
+    );
+
+    Ok(())
+}
@@ -0,0 +1,164 @@
+//! `--replace`: a regular expression replaced throughout the matched files, like sed -i
+//! over whatever a search picks.
+
+use crate::output::Printer;
+use anyhow::Result;
+use quaero::{content, xattr, Entry, FileType};
+use regex::bytes::Regex;
+use std::fs::Metadata;
+use std::io::Write;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+
+/// Writes `contents` over the file at `path` by way of a file beside it renamed into its
+/// place, so that nothing is left half-written. The file keeps its owner, permissions
+/// and extended attributes, those in `metadata`, and isn't written if it can't.
+fn rewrite(path: &Path, metadata: &Metadata, contents: &[u8]) -> std::io::Result<()> {
+    let name = path
+        .file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy();
+    let staged = path.with_file_name(format!(".{}.quaero-{}", name, std::process::id()));
+
+    let written = std::fs::File::create(&staged).and_then(|mut file| {
+        file.write_all(contents)?;
+        // The owner first, as chown takes away the set-user-ID and set-group-ID bits.
+        std::os::unix::fs::fchown(&file, Some(metadata.uid()), Some(metadata.gid()))?;
+        file.set_permissions(metadata.permissions())?;
+        let uncopied = xattr::copy(path, &file)?;
+        if !uncopied.is_empty() {
+            return Err(std::io::Error::other(tr!(
+                "replace-attributes-not-copied",
+                "names" => uncopied.join(", "),
+            )));
+        }
+        file.sync_all()
+    });
+    match written.and_then(|()| std::fs::rename(&staged, path)) {
+        Ok(()) => Ok(()),
+        Err(error) => {
+            let _ = std::fs::remove_file(&staged);
+            Err(error)
+        }
+    }
+}
+
+/// Reports what kept `path` from being rewritten, for it to be skipped.
+fn failed(path: &Path, error: impl Into<anyhow::Error>) {
+    let path = quaero::clean_path(path).into_owned();
+    crate::report_error(&error.into().context(path));
+}
+
+/// Replaces what `regex` matches in the matched text files with `with`, printing the
+/// path of every file changed, or with `dry_run` a diff of what would change instead.
+/// Binary files, anything but regular files and files with other hard links the rewrite
+/// would part them from are skipped, and so is what can't be read or written, reported.
+/// A link followed to a file has the file it points to rewritten.
+pub fn replace(
+    matches: &[Entry],
+    regex: &Regex,
+    with: &str,
+    dry_run: bool,
+    printer: &mut Printer,
+) -> Result<()> {
+    let (mut changed, mut replacements, mut skipped) = (0, 0, 0);
+
+    for entry in matches {
+        let path = entry.path.as_path();
+        let is_file = matches!(
+            entry.file_type(),
+            FileType::RegularFile | FileType::Executable
+        );
+        if !is_file {
+            skipped += 1;
+            continue;
+        }
+
+        let checked = || -> Result<_> {
+            let target = std::fs::canonicalize(path)?;
+            let metadata = std::fs::metadata(&target)?;
+            let binary = content::is_binary_file(&target)?;
+            Ok((target, metadata, binary))
+        };
+        let (target, metadata) = match checked() {
+            Ok((_, _, true)) => {
+                skipped += 1;
+                continue;
+            }
+            Ok((target, metadata, false)) => (target, metadata),
+            Err(error) => {
+                failed(path, error);
+                skipped += 1;
+                continue;
+            }
+        };
+        if metadata.nlink() > 1 {
+            eprintln!(
+                "{}",
+                tr!(
+                    "replace-skipping-hard-links",
+                    "path" => quaero::clean_path(path).into_owned(),
+                    "links" => metadata.nlink() - 1,
+                )
+            );
+            skipped += 1;
+            continue;
+        }
+
+        let contents = match std::fs::read(&target) {
+            Ok(contents) => contents,
+            Err(error) => {
+                failed(path, error);
+                skipped += 1;
+                continue;
+            }
+        };
+        let found = regex.find_iter(&contents).count();
+        if found == 0 {
+            continue;
+        }
+        let replaced = regex.replace_all(&contents, with.as_bytes());
+
+        let display = quaero::clean_path(&entry.path);
+        match dry_run {
+            true => {
+                let (before, after) = (
+                    String::from_utf8_lossy(&contents),
+                    String::from_utf8_lossy(&replaced),
+                );
+                let diff = similar::TextDiff::from_lines(before.as_ref(), after.as_ref());
+                printer.write(|out| {
+                    write!(
+                        out,
+                        "{}",
+                        diff.unified_diff()
+                            .header(&format!("a/{}", display), &format!("b/{}", display))
+                    )
+                });
+            }
+            false => {
+                if let Err(error) = rewrite(&target, &metadata, &replaced) {
+                    failed(path, error);
+                    skipped += 1;
+                    continue;
+                }
+                printer.write(|out| writeln!(out, "{}", display));
+            }
+        }
+        changed += 1;
+        replacements += found;
+    }
+
+    eprintln!(
+        "{}",
+        tr!(
+            "replace-summary",
+            "dry-run" => if dry_run { "yes" } else { "no" },
+            "replacements" => replacements,
+            "files" => changed,
+            "skipped" => skipped,
+        )
+    );
+
+    Ok(())
+}
@@ -0,0 +1,494 @@
+//! `--report`: a summary of the matches, printed once the search is done instead of
+//! the matches themselves.
+
+use crate::output::{self, OutputFormat};
+use crate::pipeline::Match;
+use clap::ValueEnum;
+use quaero::users;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+use std::io::Write;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Report {
+    /// How many files there are of each range of sizes, see --buckets
+    SizeBuckets,
+    /// How many matches there are at each depth below the starting directory, to pick
+    /// a --depth by
+    Depth,
+    /// The directories the matches add up to the most bytes in, see --top and
+    /// --top-depth
+    TopDirs,
+    /// How many files there are of each power of two of sizes, as a histogram, and the
+    /// --top largest of them
+    SizeHistogram,
+}
+
+/// What `--group-by` puts the matches together by.
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    /// Their extension, compound ones included
+    Ext,
+    /// Their type
+    Type,
+    /// The directory they are in
+    Dir,
+    /// The user that owns them
+    Owner,
+}
+
+/// How many characters the longest bar of a histogram takes.
+const BAR_WIDTH: usize = 40;
+
+/// Where the size buckets start when --buckets doesn't say: 4k, 1M and 100M.
+pub const DEFAULT_BUCKETS: &[u64] = &[4 << 10, 1 << 20, 100 << 20];
+
+/// Takes the matches in one at a time, printing what it made of them at the end.
+pub trait Summary {
+    fn add(&mut self, item: &Match);
+
+    fn print(&self, out: &mut dyn Write) -> std::io::Result<()>;
+}
+
+/// The summary for `report`, set up by the options that go with it: `--buckets`,
+/// `--top` and `--top-depth`.
+pub fn summary(
+    report: Report,
+    buckets: Option<&[u64]>,
+    top: usize,
+    top_depth: usize,
+) -> Box<dyn Summary> {
+    match report {
+        Report::SizeBuckets => Box::new(SizeBuckets::new(buckets.unwrap_or(DEFAULT_BUCKETS))),
+        Report::Depth => Box::new(Depths::default()),
+        Report::TopDirs => Box::new(TopDirs::new(top, top_depth)),
+        Report::SizeHistogram => Box::new(SizeHistogram::new(top)),
+    }
+}
+
+/// Counts the files, and adds up their sizes, in each range of sizes. Directories
+/// have no size worth counting and are left out.
+pub struct SizeBuckets {
+    /// Where every bucket but the first starts, in increasing order
+    bounds: Vec<u64>,
+    counts: Vec<u64>,
+    bytes: Vec<u64>,
+}
+
+impl SizeBuckets {
+    pub fn new(bounds: &[u64]) -> Self {
+        let mut bounds = bounds.to_vec();
+        bounds.sort_unstable();
+        bounds.dedup();
+        bounds.retain(|bound| *bound > 0);
+
+        Self {
+            counts: vec![0; bounds.len() + 1],
+            bytes: vec![0; bounds.len() + 1],
+            bounds,
+        }
+    }
+
+    fn range(&self, bucket: usize) -> String {
+        match (bucket.checked_sub(1), self.bounds.get(bucket)) {
+            (None, Some(end)) => format!("{}-{}", output::size(0), output::size(*end)),
+            (Some(start), Some(end)) => {
+                format!(
+                    "{}-{}",
+                    output::size(self.bounds[start]),
+                    output::size(*end)
+                )
+            }
+            (Some(start), None) => format!(">={}", output::size(self.bounds[start])),
+            (None, None) => tr!("report-size-any"),
+        }
+    }
+}
+
+impl Summary for SizeBuckets {
+    fn add(&mut self, item: &Match) {
+        if item.entry.is_dir() {
+            return;
+        }
+
+        let size = item.entry.size();
+        let bucket = self.bounds.partition_point(|bound| *bound <= size);
+        self.counts[bucket] += 1;
+        self.bytes[bucket] += size;
+    }
+
+    fn print(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        let ranges = (0..self.counts.len())
+            .map(|bucket| self.range(bucket))
+            .collect::<Vec<_>>();
+        let width = ranges.iter().map(String::len).max().unwrap_or(0);
+
+        for (bucket, range) in ranges.iter().enumerate() {
+            writeln!(
+                out,
+                "{}",
+                tr!(
+                    "report-size-bucket",
+                    "range" => format!("{:<width$}", range),
+                    "count" => self.counts[bucket],
+                    "size" => output::size(self.bytes[bucket]),
+                )
+            )?;
+        }
+        let total = self.bytes.iter().sum::<u64>();
+        writeln!(
+            out,
+            "{}",
+            tr!(
+                "report-size-total",
+                "count" => self.counts.iter().sum::<u64>(),
+                "size" => output::size(total),
+            )
+        )
+    }
+}
+
+/// Counts the matches at each depth, printing them as a histogram.
+#[derive(Default)]
+pub struct Depths {
+    counts: BTreeMap<usize, u64>,
+}
+
+impl Summary for Depths {
+    fn add(&mut self, item: &Match) {
+        *self.counts.entry(item.entry.depth).or_default() += 1;
+    }
+
+    fn print(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        let most = self.counts.values().copied().max().unwrap_or(0);
+        let (Some(shallowest), Some(deepest)) =
+            (self.counts.keys().next(), self.counts.keys().next_back())
+        else {
+            return Ok(());
+        };
+        let depth_width = deepest.to_string().len();
+        let count_width = most.to_string().len();
+
+        // Depths in between that nothing was found at are shown too, as a gap.
+        for depth in *shallowest..=*deepest {
+            let count = self.counts.get(&depth).copied().unwrap_or(0);
+            let bar = (count as u128 * BAR_WIDTH as u128).div_ceil(most as u128) as usize;
+            writeln!(
+                out,
+                "{:>depth_width$}  {:>count_width$}  {}",
+                depth,
+                count,
+                "#".repeat(bar)
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Adds up the matches, and the sizes of the files among them, under the directories
+/// `depth` levels below the starting directory, printing the `top` heaviest.
+pub struct TopDirs {
+    top: usize,
+    depth: usize,
+    /// Number of matches and bytes below each directory
+    totals: HashMap<PathBuf, (u64, u64)>,
+}
+
+impl TopDirs {
+    pub fn new(top: usize, depth: usize) -> Self {
+        Self {
+            top,
+            depth,
+            totals: HashMap::new(),
+        }
+    }
+}
+
+impl Summary for TopDirs {
+    fn add(&mut self, item: &Match) {
+        let entry = &item.entry;
+        // Matches right below the starting directory, or above the depth, count for
+        // the directory they are in.
+        let levels = entry.depth - entry.depth.saturating_sub(1).min(self.depth);
+        let Some(directory) = entry.path.ancestors().nth(levels) else {
+            return;
+        };
+
+        let (count, bytes) = self.totals.entry(directory.to_path_buf()).or_default();
+        *count += 1;
+        if !entry.is_dir() {
+            *bytes += entry.size();
+        }
+    }
+
+    fn print(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        let mut totals = self.totals.iter().collect::<Vec<_>>();
+        totals.sort_by(|(a_path, a), (b_path, b)| {
+            (b.1, b.0).cmp(&(a.1, a.0)).then_with(|| a_path.cmp(b_path))
+        });
+        totals.truncate(self.top);
+
+        let sizes = totals
+            .iter()
+            .map(|(_, (_, bytes))| output::size(*bytes))
+            .collect::<Vec<_>>();
+        let size_width = sizes.iter().map(String::len).max().unwrap_or(0);
+        let count_width = totals
+            .iter()
+            .map(|(_, (count, _))| count.to_string().len())
+            .max()
+            .unwrap_or(0);
+
+        for ((path, (count, _)), size) in totals.iter().zip(&sizes) {
+            let path = match path.as_os_str().is_empty() {
+                true => Path::new("."),
+                false => path.as_path(),
+            };
+            writeln!(
+                out,
+                "{:>size_width$}  {:>count_width$}  {}",
+                size,
+                count,
+                path.display()
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Counts the files whose sizes fall between each power of two and the next, empty
+/// ones on their own, and keeps the `top` largest. Directories are left out.
+pub struct SizeHistogram {
+    top: usize,
+    /// Number of files by the power of two their size starts at, 0 for the empty ones
+    counts: BTreeMap<u32, u64>,
+    /// The largest files so far, the smallest of them first out
+    largest: BinaryHeap<Reverse<(u64, PathBuf)>>,
+}
+
+impl SizeHistogram {
+    pub fn new(top: usize) -> Self {
+        Self {
+            top,
+            counts: BTreeMap::new(),
+            largest: BinaryHeap::new(),
+        }
+    }
+
+    fn range(bucket: u32) -> String {
+        match bucket {
+            0 => output::size(0),
+            _ => format!(
+                "{}-{}",
+                output::size(1 << (bucket - 1)),
+                output::size(1u64.checked_shl(bucket).unwrap_or(u64::MAX))
+            ),
+        }
+    }
+}
+
+impl Summary for SizeHistogram {
+    fn add(&mut self, item: &Match) {
+        if item.entry.is_dir() {
+            return;
+        }
+
+        let size = item.entry.size();
+        // 1 for a single byte, 2 for 2 to 3 bytes and so on.
+        let bucket = u64::BITS - size.leading_zeros();
+        *self.counts.entry(bucket).or_default() += 1;
+
+        if self.top > 0 {
+            self.largest.push(Reverse((size, item.entry.path.clone())));
+            if self.largest.len() > self.top {
+                self.largest.pop();
+            }
+        }
+    }
+
+    fn print(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        let (Some(smallest), Some(largest)) =
+            (self.counts.keys().next(), self.counts.keys().next_back())
+        else {
+            return Ok(());
+        };
+        let most = self.counts.values().copied().max().unwrap_or(0);
+        let ranges = (*smallest..=*largest)
+            .map(|bucket| (bucket, Self::range(bucket)))
+            .collect::<Vec<_>>();
+        let range_width = ranges
+            .iter()
+            .map(|(_, range)| range.len())
+            .max()
+            .unwrap_or(0);
+        let count_width = most.to_string().len();
+
+        // Sizes in between that no file has are shown too, as a gap.
+        for (bucket, range) in &ranges {
+            let count = self.counts.get(bucket).copied().unwrap_or(0);
+            let bar = (count as u128 * BAR_WIDTH as u128).div_ceil(most as u128) as usize;
+            writeln!(
+                out,
+                "{:>range_width$}  {:>count_width$}  {}",
+                range,
+                count,
+                "#".repeat(bar)
+            )?;
+        }
+
+        if self.largest.is_empty() {
+            return Ok(());
+        }
+        let mut files = self
+            .largest
+            .iter()
+            .map(|Reverse(file)| file)
+            .collect::<Vec<_>>();
+        files.sort_by(|(a_size, a_path), (b_size, b_path)| {
+            b_size.cmp(a_size).then_with(|| a_path.cmp(b_path))
+        });
+        let sizes = files
+            .iter()
+            .map(|(size, _)| output::size(*size))
+            .collect::<Vec<_>>();
+        let size_width = sizes.iter().map(String::len).max().unwrap_or(0);
+
+        writeln!(out)?;
+        writeln!(out, "{}", tr!("report-largest", "count" => files.len()))?;
+        for ((_, path), size) in files.iter().zip(&sizes) {
+            writeln!(out, "{:>size_width$}  {}", size, quaero::clean_path(path))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Counts the matches, and adds up the sizes of the files among them, in each group of
+/// `--group-by`, printing the groups heaviest first as text or in a structured
+/// `format`: csv and tsv with a header row, ndjson an object a group.
+pub struct Groups {
+    by: GroupBy,
+    format: Option<OutputFormat>,
+    compound_extensions: Vec<String>,
+    /// Number of matches and bytes of each group, `None` for the matches with no
+    /// extension
+    totals: HashMap<Option<String>, (u64, u64)>,
+}
+
+impl Groups {
+    pub fn new(
+        by: GroupBy,
+        format: Option<OutputFormat>,
+        compound_extensions: Vec<String>,
+    ) -> Self {
+        Self {
+            by,
+            format,
+            compound_extensions,
+            totals: HashMap::new(),
+        }
+    }
+
+    fn group(&self, item: &Match) -> Option<String> {
+        let entry = &item.entry;
+        Some(match self.by {
+            GroupBy::Ext if entry.is_dir() => return None,
+            GroupBy::Ext => {
+                let name = entry.name.to_string_lossy();
+                let (_, extension) = quaero::split_extension(&name, &self.compound_extensions);
+                extension?.to_owned()
+            }
+            GroupBy::Type => entry
+                .file_type()
+                .to_possible_value()
+                .map(|value| value.get_name().to_owned())
+                .unwrap_or_default(),
+            GroupBy::Dir => {
+                let path = quaero::clean_path(&entry.path);
+                match Path::new(path.as_ref()).parent() {
+                    Some(parent) if !parent.as_os_str().is_empty() => {
+                        parent.to_string_lossy().into_owned()
+                    }
+                    _ => ".".to_owned(),
+                }
+            }
+            GroupBy::Owner => {
+                let Ok(metadata) = entry.metadata() else {
+                    return None;
+                };
+                users::user_name(metadata.uid()).unwrap_or_else(|| metadata.uid().to_string())
+            }
+        })
+    }
+}
+
+impl Summary for Groups {
+    fn add(&mut self, item: &Match) {
+        let group = self.group(item);
+        let (count, bytes) = self.totals.entry(group).or_default();
+        *count += 1;
+        if !item.entry.is_dir() {
+            *bytes += item.entry.size();
+        }
+    }
+
+    fn print(&self, out: &mut dyn Write) -> std::io::Result<()> {
+        let mut totals = self.totals.iter().collect::<Vec<_>>();
+        totals.sort_by(|(a_group, a), (b_group, b)| {
+            (b.1, b.0)
+                .cmp(&(a.1, a.0))
+                .then_with(|| a_group.cmp(b_group))
+        });
+
+        match self.format {
+            Some(format @ (OutputFormat::Csv | OutputFormat::Tsv)) => {
+                writeln!(out, "{}", format.row(["group", "count", "size"]))?;
+                for (group, (count, bytes)) in &totals {
+                    let (count, bytes) = (count.to_string(), bytes.to_string());
+                    let row = format.row([group.as_deref().unwrap_or(""), &count, &bytes]);
+                    writeln!(out, "{}", row)?;
+                }
+            }
+            Some(OutputFormat::Ndjson | OutputFormat::NdjsonEvents) => {
+                for (group, (count, bytes)) in &totals {
+                    let record = serde_json::json!({
+                        "group": group,
+                        "count": count,
+                        "size": bytes,
+                    });
+                    writeln!(out, "{}", record)?;
+                }
+            }
+            Some(OutputFormat::Tree) | None => {
+                let sizes = totals
+                    .iter()
+                    .map(|(_, (_, bytes))| output::size(*bytes))
+                    .collect::<Vec<_>>();
+                let size_width = sizes.iter().map(String::len).max().unwrap_or(0);
+                let count_width = totals
+                    .iter()
+                    .map(|(_, (count, _))| count.to_string().len())
+                    .max()
+                    .unwrap_or(0);
+
+                for ((group, (count, _)), size) in totals.iter().zip(&sizes) {
+                    let group = match group {
+                        Some(group) => group.clone(),
+                        None => tr!("report-group-none"),
+                    };
+                    writeln!(
+                        out,
+                        "{:>size_width$}  {:>count_width$}  {}",
+                        size, count, group
+                    )?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
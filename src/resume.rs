@@ -0,0 +1,94 @@
+//! `--checkpoint` and `--resume`: the directories a search got through, saved every so
+//! often and once it is cut short, for a search of hours to go on from there instead of
+//! starting over.
+
+use crate::state;
+use anyhow::{Context, Result};
+use quaero::checkpoint::Checkpoint;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+const SCHEMA_KIND: &str = "checkpoint";
+const SCHEMA_VERSION: u32 = 2;
+
+/// How often a checkpoint is saved while the search goes on.
+const INTERVAL: Duration = Duration::from_secs(30);
+
+/// Loads the checkpoint saved at `path`.
+pub fn load(path: &Path) -> Result<Checkpoint> {
+    let contents = std::fs::read_to_string(path).with_context(|| path.display().to_string())?;
+    let (version, body) = state::parse_schema(path, SCHEMA_KIND, &contents)?;
+    if version != SCHEMA_VERSION {
+        return Err(anyhow::anyhow!(
+            "{} is not a checkpoint (schema v{}, expected v{})",
+            path.display(),
+            version,
+            SCHEMA_VERSION
+        ));
+    }
+
+    let mut directories = Vec::new();
+    for line in body.lines().filter(|line| !line.trim().is_empty()) {
+        let directory: String = serde_json::from_str(line)
+            .with_context(|| format!("{} has a malformed line: {}", path.display(), line))?;
+        directories.push(state::unescape_path(&directory));
+    }
+
+    Ok(Checkpoint::resuming(directories))
+}
+
+/// Saves `checkpoint` to `path`, a directory a line as a JSON string, escaped for a
+/// name that isn't UTF-8 to be read back as it is.
+fn save(path: &Path, checkpoint: &Checkpoint) -> Result<()> {
+    let mut contents = state::schema_header(SCHEMA_KIND, SCHEMA_VERSION);
+    contents.push('\n');
+    for directory in checkpoint.directories() {
+        contents.push_str(&serde_json::to_string(&state::escape_path(&directory))?);
+        contents.push('\n');
+    }
+    state::write_atomically(path, contents.as_bytes())
+}
+
+/// Saves a checkpoint every [`INTERVAL`] on a thread of its own until finished.
+pub struct Checkpointer {
+    /// Where to, taken once finished for the thread to save nothing more
+    path: Arc<Mutex<Option<PathBuf>>>,
+    checkpoint: Arc<Checkpoint>,
+}
+
+impl Checkpointer {
+    pub fn start(path: PathBuf, checkpoint: Arc<Checkpoint>) -> Self {
+        let path = Arc::new(Mutex::new(Some(path)));
+        let (saving, saved) = (path.clone(), checkpoint.clone());
+        std::thread::spawn(move || loop {
+            std::thread::sleep(INTERVAL);
+            let path = saving.lock().expect("checkpoint lock poisoned");
+            let Some(path) = path.as_deref() else {
+                return;
+            };
+            if let Err(error) = save(path, &saved) {
+                crate::report_error(&error);
+            }
+        });
+
+        Self { path, checkpoint }
+    }
+
+    /// Saves where the search got to if it was cut short, and removes the checkpoint
+    /// otherwise, there being nothing left to resume.
+    pub fn finish(self, complete: bool) -> Result<()> {
+        let Some(path) = self.path.lock().expect("checkpoint lock poisoned").take() else {
+            return Ok(());
+        };
+        match complete {
+            true => match std::fs::remove_file(&path) {
+                Err(error) if error.kind() != std::io::ErrorKind::NotFound => {
+                    Err(error).with_context(|| path.display().to_string())
+                }
+                _ => Ok(()),
+            },
+            false => save(&path, &self.checkpoint),
+        }
+    }
+}
@@ -0,0 +1,116 @@
+//! Entries matched by a Lua script, for `--filter-script`, for whatever the other
+//! filters can't tell.
+//!
+//! The script is run once when loaded and returns a function, called for every entry
+//! with a table of what is known of it: `path`, `name`, `ext`, `parent`, `type` (as
+//! given to `--type`), `size`, `allocated`, `mode`, `uid`, `gid`, `nlink`, `inode`,
+//! `device`, `mtime`, `atime`, `ctime` in seconds since the epoch and `depth`. The entry
+//! matches if it returns anything but `false` or `nil`, e.g.
+//!
+//! ```lua
+//! return function(entry)
+//!     return entry.type == "file" and entry.size > 1024
+//! end
+//! ```
+
+use crate::Entry;
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use mlua::{Function, Lua, Table, Value};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[derive(Debug)]
+pub struct Script {
+    pub path: PathBuf,
+    lua: Lua,
+    filter: Function,
+    /// Whether it failed already, for the error to be reported once rather than for
+    /// every entry
+    failed: AtomicBool,
+}
+
+impl Script {
+    pub fn load(path: &Path) -> Result<Self> {
+        let source = std::fs::read(path).with_context(|| path.display().to_string())?;
+        let lua = Lua::new();
+        let returned = lua
+            .load(source)
+            .set_name(format!("@{}", path.display()))
+            .eval::<Value>()
+            .map_err(|error| anyhow::anyhow!("{}", error))?;
+        let Value::Function(filter) = returned else {
+            return Err(anyhow::anyhow!(
+                "{}: the script returns a value of type {} instead of a function",
+                path.display(),
+                returned.type_name()
+            ));
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            lua,
+            filter,
+            failed: AtomicBool::new(false),
+        })
+    }
+
+    fn table(&self, entry: &Entry) -> mlua::Result<Table> {
+        let path = entry.path.as_path();
+        let metadata = entry.metadata().map_err(mlua::Error::external)?;
+        let table = self.lua.create_table()?;
+        table.set(
+            "path",
+            self.lua.create_string(entry.path.as_os_str().as_bytes())?,
+        )?;
+        table.set("name", self.lua.create_string(entry.name.as_bytes())?)?;
+        table.set(
+            "ext",
+            path.extension()
+                .map(|extension| extension.to_string_lossy()),
+        )?;
+        table.set(
+            "parent",
+            path.parent().map(|parent| parent.to_string_lossy()),
+        )?;
+        table.set(
+            "type",
+            entry
+                .file_type()
+                .to_possible_value()
+                .map(|value| value.get_name().to_owned()),
+        )?;
+        table.set("size", metadata.len())?;
+        table.set("allocated", entry.allocated_size())?;
+        table.set("mode", metadata.mode() & 0o7777)?;
+        table.set("uid", metadata.uid())?;
+        table.set("gid", metadata.gid())?;
+        table.set("nlink", metadata.nlink())?;
+        table.set("inode", metadata.ino())?;
+        table.set("device", metadata.dev())?;
+        table.set("mtime", metadata.mtime())?;
+        table.set("atime", metadata.atime())?;
+        table.set("ctime", metadata.ctime())?;
+        table.set("depth", entry.depth)?;
+        Ok(table)
+    }
+
+    /// Whether the function returns a true value for `entry`. An entry it fails for
+    /// doesn't match, the first failure being reported on stderr.
+    pub fn matches(&self, entry: &Entry) -> bool {
+        let verdict = self
+            .table(entry)
+            .and_then(|table| self.filter.call::<Value>(table));
+        match verdict {
+            Ok(value) => !matches!(value, Value::Nil | Value::Boolean(false)),
+            Err(error) => {
+                if !self.failed.swap(true, Ordering::Relaxed) {
+                    eprintln!("quaero: {}: {}", entry.path.display(), error);
+                }
+                false
+            }
+        }
+    }
+}
@@ -0,0 +1,127 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::os::unix::fs::PermissionsExt;
+
+const RELEASES_URL: &str = "https://api.github.com/repos/dead-tech/quaero/releases/latest";
+const USER_AGENT: &str = concat!("quaero/", env!("CARGO_PKG_VERSION"));
+const MAX_DOWNLOAD_SIZE: u64 = 256 * 1024 * 1024;
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let mut parts = version.trim_start_matches('v').splitn(3, '.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next()?.parse().ok()?;
+    let patch = parts.next()?.split(['-', '+']).next()?.parse().ok()?;
+
+    Some((major, minor, patch))
+}
+
+fn download(url: &str) -> Result<Vec<u8>> {
+    ureq::get(url)
+        .header("User-Agent", USER_AGENT)
+        .call()
+        .with_context(|| format!("failed to download {}", url))?
+        .body_mut()
+        .with_config()
+        .limit(MAX_DOWNLOAD_SIZE)
+        .read_to_vec()
+        .with_context(|| format!("failed to download {}", url))
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Replaces the running executable with the latest GitHub release of quaero.
+///
+/// The release must ship a `quaero-<arch>-<os>` binary next to a `.sha256` checksum
+/// file. The new binary is written beside the current one and renamed over it, so an
+/// interrupted update never leaves a truncated executable behind.
+pub fn self_update(check_only: bool) -> Result<()> {
+    let release: Release = ureq::get(RELEASES_URL)
+        .header("User-Agent", USER_AGENT)
+        .header("Accept", "application/vnd.github+json")
+        .call()
+        .context("failed to query the latest release")?
+        .body_mut()
+        .read_json()
+        .context("failed to parse the latest release")?;
+
+    let current = env!("CARGO_PKG_VERSION");
+    let (Some(current_version), Some(latest_version)) =
+        (parse_version(current), parse_version(&release.tag_name))
+    else {
+        return Err(anyhow::anyhow!(
+            "cannot compare versions {} and {}",
+            current,
+            release.tag_name
+        ));
+    };
+
+    if latest_version <= current_version {
+        println!("quaero {} is up to date", current);
+        return Ok(());
+    }
+
+    if check_only {
+        println!(
+            "quaero {} is available (current: {})",
+            release.tag_name, current
+        );
+        return Ok(());
+    }
+
+    let asset_name = format!("quaero-{}-{}", std::env::consts::ARCH, std::env::consts::OS);
+    let checksum_name = format!("{}.sha256", asset_name);
+    let find_asset = |name: &str| {
+        release
+            .assets
+            .iter()
+            .find(|asset| asset.name == name)
+            .ok_or_else(|| anyhow::anyhow!("release {} has no {} asset", release.tag_name, name))
+    };
+
+    let binary = download(&find_asset(&asset_name)?.browser_download_url)?;
+    let checksum = download(&find_asset(&checksum_name)?.browser_download_url)?;
+    let expected = String::from_utf8_lossy(&checksum)
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+
+    let actual = sha256_hex(&binary);
+    if actual != expected {
+        return Err(anyhow::anyhow!(
+            "checksum mismatch for {}: expected {}, got {}",
+            asset_name,
+            expected,
+            actual
+        ));
+    }
+
+    let executable = std::env::current_exe()?;
+    let staged = executable.with_extension("update");
+    std::fs::write(&staged, &binary)
+        .with_context(|| format!("failed to write {}", staged.display()))?;
+    std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o755))?;
+    std::fs::rename(&staged, &executable)
+        .with_context(|| format!("failed to replace {}", executable.display()))?;
+
+    println!("updated quaero {} -> {}", current, release.tag_name);
+    Ok(())
+}
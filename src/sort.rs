@@ -0,0 +1,124 @@
+use crate::frecency::FrecencyStore;
+use crate::pipeline::Match;
+use crate::template::Template;
+use anyhow::Result;
+use clap::ValueEnum;
+use quaero::{fuzzy, Entry};
+use std::cmp::Ordering;
+use std::time::SystemTime;
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Name,
+    NameDesc,
+    Size,
+    SizeDesc,
+    Mtime,
+    MtimeDesc,
+    Depth,
+    DepthDesc,
+    /// Most frequently and recently picked first, see `quaero mark-used`
+    Frecency,
+    /// Best --fuzzy match first
+    Score,
+}
+
+type Comparator = Box<dyn Fn(&Entry, &Entry) -> Ordering>;
+
+fn mtime(entry: &Entry) -> SystemTime {
+    entry.modified().unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+/// Stable sort of `items` by `key`, where `entry` extracts the entry to compare.
+/// `fuzzy` is the pattern [`SortKey::Score`] scores names against, or whole paths with
+/// `full_path`.
+pub fn sort<T>(
+    items: &mut [T],
+    key: SortKey,
+    fuzzy: Option<&str>,
+    full_path: bool,
+    entry: impl Fn(&T) -> &Entry,
+) -> Result<()> {
+    let compare: Comparator = match key {
+        SortKey::Name => Box::new(|a, b| a.name.cmp(&b.name)),
+        SortKey::NameDesc => Box::new(|a, b| b.name.cmp(&a.name)),
+        SortKey::Size => Box::new(|a, b| a.size().cmp(&b.size())),
+        SortKey::SizeDesc => Box::new(|a, b| b.size().cmp(&a.size())),
+        SortKey::Mtime => Box::new(|a, b| mtime(a).cmp(&mtime(b))),
+        SortKey::MtimeDesc => Box::new(|a, b| mtime(b).cmp(&mtime(a))),
+        SortKey::Depth => Box::new(|a, b| a.depth.cmp(&b.depth)),
+        SortKey::DepthDesc => Box::new(|a, b| b.depth.cmp(&a.depth)),
+        SortKey::Frecency => {
+            let store = FrecencyStore::load()?;
+            let score = move |entry: &Entry| {
+                std::fs::canonicalize(&entry.path).map_or(0.0, |path| store.score(path))
+            };
+            Box::new(move |a, b| score(b).total_cmp(&score(a)))
+        }
+        SortKey::Score => {
+            let pattern = fuzzy.unwrap_or_default().to_owned();
+            let score = move |entry: &Entry| {
+                let scored = match full_path {
+                    true => entry.path.to_string_lossy(),
+                    false => entry.name.to_string_lossy(),
+                };
+                fuzzy::matches(&pattern, &scored).map_or(i64::MIN, |found| found.score)
+            };
+            Box::new(move |a, b| {
+                score(b)
+                    .cmp(&score(a))
+                    .then(a.path.as_os_str().len().cmp(&b.path.as_os_str().len()))
+            })
+        }
+    };
+
+    items.sort_by(|a, b| compare(entry(a), entry(b)));
+    Ok(())
+}
+
+/// Compares `a` and `b` character by character, except for runs of digits, which are
+/// compared as the numbers they spell so that 9 comes before 10.
+fn natural(a: &str, b: &str) -> Ordering {
+    let (mut a, mut b) = (a, b);
+    loop {
+        let digits = |text: &str| {
+            text.find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(text.len())
+        };
+        let (a_digits, b_digits) = (digits(a), digits(b));
+        if a_digits > 0 && b_digits > 0 {
+            let (a_number, b_number) = (
+                a[..a_digits].trim_start_matches('0'),
+                b[..b_digits].trim_start_matches('0'),
+            );
+            let order = a_number
+                .len()
+                .cmp(&b_number.len())
+                .then_with(|| a_number.cmp(b_number));
+            if order != Ordering::Equal {
+                return order;
+            }
+            (a, b) = (&a[a_digits..], &b[b_digits..]);
+            continue;
+        }
+
+        let mut a_chars = a.chars();
+        let mut b_chars = b.chars();
+        match (a_chars.next(), b_chars.next()) {
+            (Some(a_char), Some(b_char)) if a_char == b_char => {
+                (a, b) = (a_chars.as_str(), b_chars.as_str());
+            }
+            (a_char, b_char) => return a_char.cmp(&b_char),
+        }
+    }
+}
+
+/// `--sort-by`: stable sort of `items` by what `template` renders for each.
+pub fn sort_by_template(items: &mut Vec<Match>, template: &Template) {
+    let mut keyed = items
+        .drain(..)
+        .map(|item| (template.render(&item), item))
+        .collect::<Vec<_>>();
+    keyed.sort_by(|(a, _), (b, _)| natural(a, b));
+    items.extend(keyed.into_iter().map(|(_, item)| item));
+}
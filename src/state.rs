@@ -0,0 +1,187 @@
+use anyhow::{Context, Result};
+use std::ffi::OsString;
+use std::fs::File;
+use std::io::{Read, Seek, Write};
+use std::os::unix::ffi::{OsStrExt, OsStringExt};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+const LOCK_TIMEOUT: Duration = Duration::from_secs(10);
+const LOCK_RETRY_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Exclusive lock guarding a state file across concurrent quaero invocations.
+///
+/// The lock lives next to the state file as `<name>.lock` and records the PID of its
+/// holder so a blocked invocation can say who it is waiting on. It is released when
+/// dropped, or by the kernel if the holder dies.
+pub struct StateLock {
+    file: File,
+}
+
+impl StateLock {
+    pub fn acquire<T: AsRef<Path>>(state_file: T) -> Result<Self> {
+        let state_file = state_file.as_ref();
+        if let Some(parent) = state_file.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        let lock_path = lock_path(state_file);
+        let mut file = File::options()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&lock_path)
+            .with_context(|| format!("failed to open {}", lock_path.display()))?;
+
+        let started = Instant::now();
+        let mut reported = false;
+        while unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } != 0 {
+            let holder = read_holder(&mut file);
+            if started.elapsed() >= LOCK_TIMEOUT {
+                return Err(anyhow::anyhow!(tr!(
+                    "lock-held",
+                    "path" => state_file.display().to_string(),
+                    "pid" => holder.as_str(),
+                )));
+            }
+
+            if !reported {
+                eprintln!(
+                    "{}",
+                    tr!(
+                        "lock-waiting",
+                        "path" => state_file.display().to_string(),
+                        "pid" => holder.as_str(),
+                    )
+                );
+                reported = true;
+            }
+            std::thread::sleep(LOCK_RETRY_INTERVAL);
+        }
+
+        file.set_len(0)?;
+        file.rewind()?;
+        write!(file, "{}", std::process::id())?;
+
+        Ok(Self { file })
+    }
+}
+
+impl Drop for StateLock {
+    fn drop(&mut self) {
+        let _ = self.file.set_len(0);
+        unsafe { libc::flock(self.file.as_raw_fd(), libc::LOCK_UN) };
+    }
+}
+
+fn lock_path(state_file: &Path) -> PathBuf {
+    let mut name = state_file.file_name().unwrap_or_default().to_owned();
+    name.push(".lock");
+    state_file.with_file_name(name)
+}
+
+fn read_holder(file: &mut File) -> String {
+    let mut holder = String::new();
+    let _ = file.rewind().and_then(|_| file.read_to_string(&mut holder));
+
+    match holder.trim() {
+        "" => "unknown".to_owned(),
+        pid => pid.to_owned(),
+    }
+}
+
+/// Replaces `path` with `contents` by writing a sibling temporary file and renaming it
+/// over the original, so readers never observe a half-written file.
+pub fn write_atomically<T: AsRef<Path>>(path: T, contents: &[u8]) -> Result<()> {
+    let path = path.as_ref();
+    let mut name = path.file_name().unwrap_or_default().to_owned();
+    name.push(format!(".tmp.{}", std::process::id()));
+    let temporary = path.with_file_name(name);
+
+    let mut file = File::create(&temporary)
+        .with_context(|| format!("failed to create {}", temporary.display()))?;
+    file.write_all(contents)?;
+    file.sync_all()?;
+
+    std::fs::rename(&temporary, path)
+        .with_context(|| format!("failed to replace {}", path.display()))
+}
+
+/// Header line identifying the kind and schema version of a state file.
+pub fn schema_header(kind: &str, version: u32) -> String {
+    format!("# quaero {} v{}", kind, version)
+}
+
+/// Splits the schema header off `contents`, returning the version it declares and the
+/// remaining body. Files written before versioning was introduced report version 0.
+pub fn parse_schema<'a>(path: &Path, kind: &str, contents: &'a str) -> Result<(u32, &'a str)> {
+    let prefix = format!("# quaero {} v", kind);
+    let Some(rest) = contents.strip_prefix(&prefix) else {
+        return Ok((0, contents));
+    };
+
+    let (version, body) = rest.split_once('\n').unwrap_or((rest, ""));
+    let version = version
+        .trim()
+        .parse()
+        .with_context(|| format!("{} has a malformed schema header", path.display()))?;
+
+    Ok((version, body))
+}
+
+/// Escapes the characters that would break the line based format of a state file, and
+/// the bytes that aren't UTF-8 as `\xHH`, for `path` to be read back byte for byte.
+pub fn escape_path(path: &Path) -> String {
+    let bytes = path.as_os_str().as_bytes();
+    let mut escaped = String::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        for c in chunk.valid().chars() {
+            match c {
+                '\\' => escaped.push_str("\\\\"),
+                '\n' => escaped.push_str("\\n"),
+                c => escaped.push(c),
+            }
+        }
+        for byte in chunk.invalid() {
+            escaped.push_str(&format!("\\x{:02x}", byte));
+        }
+    }
+
+    escaped
+}
+
+/// Reads back a path [`escape_path`] wrote.
+pub fn unescape_path(path: &str) -> PathBuf {
+    let mut unescaped = Vec::with_capacity(path.len());
+    let mut bytes = path.as_bytes().iter();
+    while let Some(&byte) = bytes.next() {
+        match (byte, bytes.as_slice().first()) {
+            (b'\\', Some(b'n')) => {
+                bytes.next();
+                unescaped.push(b'\n');
+            }
+            (b'\\', Some(b'\\')) => {
+                bytes.next();
+                unescaped.push(b'\\');
+            }
+            (b'\\', Some(b'x')) => {
+                let hex = bytes
+                    .as_slice()
+                    .get(1..3)
+                    .and_then(|hex| std::str::from_utf8(hex).ok());
+                match hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(escaped) => {
+                        bytes.nth(2);
+                        unescaped.push(escaped);
+                    }
+                    None => unescaped.push(byte),
+                }
+            }
+            (byte, _) => unescaped.push(byte),
+        }
+    }
+
+    PathBuf::from(OsString::from_vec(unescaped))
+}
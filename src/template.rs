@@ -0,0 +1,478 @@
+use crate::output;
+use crate::pipeline::Match;
+use anyhow::Result;
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, Local, Utc};
+use clap::ValueEnum;
+use quaero::parse::ParseError;
+use quaero::{perm, users};
+use std::fs::Metadata;
+use std::ops::Range;
+use std::os::unix::fs::MetadataExt;
+use std::path::Path;
+use std::str::FromStr;
+
+const DEFAULT_TIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+const TEMPLATE_EXAMPLE: &str = "{path}\\t{size:h}\\t{mtime:%Y-%m-%d}";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Path,
+    Name,
+    Stem,
+    Extension,
+    Parent,
+    Type,
+    Size,
+    Allocated,
+    Perm,
+    Nlink,
+    Entries,
+    Inode,
+    Device,
+    Uid,
+    Gid,
+    User,
+    Group,
+    Mtime,
+    Atime,
+    Ctime,
+    Depth,
+    LinkTarget,
+    Resolved,
+    Label,
+    Hash,
+}
+
+impl Field {
+    fn parse(name: &str) -> Option<Self> {
+        let field = match name {
+            "path" => Field::Path,
+            "name" => Field::Name,
+            "stem" => Field::Stem,
+            "ext" => Field::Extension,
+            "parent" => Field::Parent,
+            "type" => Field::Type,
+            "size" => Field::Size,
+            "allocated" => Field::Allocated,
+            "perm" => Field::Perm,
+            "nlink" => Field::Nlink,
+            "entries" => Field::Entries,
+            "inode" => Field::Inode,
+            "device" => Field::Device,
+            "uid" => Field::Uid,
+            "gid" => Field::Gid,
+            "user" => Field::User,
+            "group" => Field::Group,
+            "mtime" => Field::Mtime,
+            "atime" => Field::Atime,
+            "ctime" => Field::Ctime,
+            "depth" => Field::Depth,
+            "link_target" => Field::LinkTarget,
+            "resolved" => Field::Resolved,
+            "label" => Field::Label,
+            "hash" => Field::Hash,
+            _ => return None,
+        };
+
+        Some(field)
+    }
+
+    fn is_time(self) -> bool {
+        matches!(self, Field::Mtime | Field::Atime | Field::Ctime)
+    }
+
+    /// Checks the part after the `:` of a placeholder, e.g. the `h` of `{size:h}`.
+    fn accepts(self, format: &str) -> bool {
+        match self {
+            Field::Size | Field::Allocated => format == "h",
+            Field::Perm => format == "rwx",
+            _ if self.is_time() => !StrftimeItems::new(format).any(|item| item == Item::Error),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Segment {
+    Literal(String),
+    Field(Field, Option<String>),
+}
+
+/// A `--template` such as `{path}\t{size:h}\t{mtime:%Y-%m-%d}`, rendered once per match.
+///
+/// Placeholders are written `{field}` or `{field:format}`; `\t`, `\n`, `\0`, `\\`,
+/// `\{` and `\}` are escapes.
+#[derive(Debug, Clone)]
+pub struct Template {
+    segments: Vec<Segment>,
+    /// Whether times are rendered in UTC rather than in the local time zone
+    utc: bool,
+    /// `--human-readable`: sizes and times without a format of their own are rendered
+    /// as 4.2M and 2 hours ago
+    human: bool,
+    /// `--iso-time`: times are rendered in ISO 8601 whatever their format
+    iso: bool,
+    /// Extensions of more than one part that `{stem}` and `{ext}` split off whole
+    compound_extensions: Vec<String>,
+}
+
+impl FromStr for Template {
+    type Err = ParseError;
+
+    fn from_str(template: &str) -> Result<Self, ParseError> {
+        let invalid = |span: Range<usize>, message: &str| {
+            ParseError::new(template, span, message).example(TEMPLATE_EXAMPLE)
+        };
+        let mut segments = Vec::new();
+        let mut literal = String::new();
+        let mut chars = template.char_indices();
+
+        while let Some((offset, c)) = chars.next() {
+            match c {
+                '\\' => literal.push(match chars.next().map(|(_, c)| c) {
+                    Some('t') => '\t',
+                    Some('n') => '\n',
+                    Some('r') => '\r',
+                    Some('0') => '\0',
+                    Some(c @ ('\\' | '{' | '}')) => c,
+                    Some(c) => {
+                        let span = offset..offset + 1 + c.len_utf8();
+                        return Err(invalid(
+                            span,
+                            "unknown escape, expected \\t, \\n, \\r, \\0, \\\\, \\{ or \\}",
+                        ));
+                    }
+                    None => {
+                        return Err(invalid(offset..offset + 1, "expected an escape after '\\'"))
+                    }
+                }),
+                '{' => {
+                    let Some(length) = template[offset + 1..].find('}') else {
+                        return Err(invalid(
+                            offset..template.len(),
+                            "expected a '}' to close the placeholder",
+                        ));
+                    };
+                    let placeholder = &template[offset + 1..offset + 1 + length];
+                    chars.by_ref().find(|&(_, c)| c == '}');
+
+                    let (name, format) = match placeholder.split_once(':') {
+                        Some((name, format)) => (name, Some(format)),
+                        None => (placeholder, None),
+                    };
+                    let name_span = offset + 1..offset + 1 + name.len().max(1);
+                    let field =
+                        Field::parse(name).ok_or_else(|| invalid(name_span, "unknown field"))?;
+                    if let Some(format) = format.filter(|format| !field.accepts(format)) {
+                        let start = offset + 2 + name.len();
+                        return Err(invalid(
+                            start..start + format.len().max(1),
+                            "invalid format for this field",
+                        ));
+                    }
+
+                    if !literal.is_empty() {
+                        segments.push(Segment::Literal(std::mem::take(&mut literal)));
+                    }
+                    segments.push(Segment::Field(field, format.map(str::to_owned)));
+                }
+                '}' => {
+                    return Err(invalid(
+                        offset..offset + 1,
+                        "unmatched '}', write '\\}' for a literal brace",
+                    ))
+                }
+                c => literal.push(c),
+            }
+        }
+
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+
+        Ok(Self {
+            segments,
+            utc: false,
+            human: false,
+            iso: false,
+            compound_extensions: Vec::new(),
+        })
+    }
+}
+
+/// How long ago `time` was, in the largest unit there was at least one of, e.g.
+/// 3 days ago.
+fn ago(time: DateTime<Utc>) -> String {
+    let seconds = Utc::now().signed_duration_since(time).num_seconds();
+    let (unit, count) = match seconds {
+        ..0 => return tr!("ago-future"),
+        0..60 => ("second", seconds),
+        60..3600 => ("minute", seconds / 60),
+        3600..86400 => ("hour", seconds / 3600),
+        86400..2592000 => ("day", seconds / 86400),
+        2592000..31536000 => ("month", seconds / 2592000),
+        _ => ("year", seconds / 31536000),
+    };
+    tr!("ago", "unit" => unit, "count" => count)
+}
+
+fn time(template: &Template, seconds: i64, nanoseconds: i64, format: Option<&str>) -> String {
+    let Some(time) = DateTime::from_timestamp(seconds, nanoseconds as u32) else {
+        return String::new();
+    };
+    let format = match (template.iso, format) {
+        (true, _) => None,
+        (false, Some(format)) => Some(format),
+        (false, None) if template.human => return ago(time),
+        (false, None) => Some(DEFAULT_TIME_FORMAT),
+    };
+    match (format, template.utc) {
+        (Some(format), true) => time.format(format).to_string(),
+        (Some(format), false) => time.with_timezone(&Local).format(format).to_string(),
+        (None, true) => time.to_rfc3339(),
+        (None, false) => time.with_timezone(&Local).to_rfc3339(),
+    }
+}
+
+fn render_field(template: &Template, field: Field, format: Option<&str>, item: &Match) -> String {
+    let compound = &template.compound_extensions;
+    let entry = &item.entry;
+    let cleaned = quaero::clean_path(&entry.path);
+    let path = Path::new(cleaned.as_ref());
+    let lossy = |value: &std::ffi::OsStr| value.to_string_lossy().into_owned();
+
+    match field {
+        Field::Path => cleaned.to_string(),
+        Field::Name => lossy(&entry.name),
+        Field::Stem => quaero::split_extension(&lossy(&entry.name), compound)
+            .0
+            .to_owned(),
+        Field::Extension => quaero::split_extension(&lossy(&entry.name), compound)
+            .1
+            .unwrap_or_default()
+            .to_owned(),
+        Field::Parent => path
+            .parent()
+            .map(|parent| lossy(parent.as_os_str()))
+            .unwrap_or_default(),
+        Field::Type => entry
+            .file_type()
+            .to_possible_value()
+            .map(|value| value.get_name().to_owned())
+            .unwrap_or_default(),
+        Field::Allocated => match format.is_some() || template.human {
+            true => output::size(entry.allocated_size()),
+            false => entry.allocated_size().to_string(),
+        },
+        Field::Entries => entry
+            .entry_count()
+            .map(|count| count.to_string())
+            .unwrap_or_default(),
+        Field::Depth => entry.depth.to_string(),
+        Field::LinkTarget => entry
+            .link_target()
+            .map(|target| lossy(target.as_os_str()))
+            .unwrap_or_default(),
+        Field::Resolved => entry
+            .resolved()
+            .map(|resolved| lossy(resolved.as_os_str()))
+            .unwrap_or_default(),
+        Field::Label => item.label.clone().unwrap_or_default(),
+        Field::Hash => item.hash.clone().unwrap_or_default(),
+        // Left empty for an entry gone by now.
+        field => entry
+            .metadata()
+            .map(|metadata| render_metadata(template, field, format, metadata))
+            .unwrap_or_default(),
+    }
+}
+
+/// Renders one of the fields the metadata of an entry tells.
+fn render_metadata(
+    template: &Template,
+    field: Field,
+    format: Option<&str>,
+    metadata: &Metadata,
+) -> String {
+    match field {
+        Field::Size => match format.is_some() || template.human {
+            true => output::size(metadata.len()),
+            false => metadata.len().to_string(),
+        },
+        Field::Perm => match format {
+            Some(_) => perm::symbolic(metadata.mode()),
+            None => format!("{:o}", metadata.mode() & 0o7777),
+        },
+        Field::Nlink => metadata.nlink().to_string(),
+        Field::Inode => metadata.ino().to_string(),
+        Field::Device => metadata.dev().to_string(),
+        Field::Uid => metadata.uid().to_string(),
+        Field::Gid => metadata.gid().to_string(),
+        Field::User => {
+            users::user_name(metadata.uid()).unwrap_or_else(|| metadata.uid().to_string())
+        }
+        Field::Group => {
+            users::group_name(metadata.gid()).unwrap_or_else(|| metadata.gid().to_string())
+        }
+        Field::Mtime => time(template, metadata.mtime(), metadata.mtime_nsec(), format),
+        Field::Atime => time(template, metadata.atime(), metadata.atime_nsec(), format),
+        Field::Ctime => time(template, metadata.ctime(), metadata.ctime_nsec(), format),
+        _ => String::new(),
+    }
+}
+
+impl Template {
+    /// The same template, rendering times in UTC.
+    pub fn utc(mut self) -> Self {
+        self.utc = true;
+        self
+    }
+
+    /// The same template, rendering the sizes and times that have no format of their own
+    /// for people to read, as 4.2M and 2 hours ago.
+    pub fn human_readable(mut self) -> Self {
+        self.human = true;
+        self
+    }
+
+    /// The same template, rendering every time in ISO 8601, e.g.
+    /// 2026-10-14T09:48:22+02:00, for scripts to parse.
+    pub fn iso_times(mut self) -> Self {
+        self.iso = true;
+        self
+    }
+
+    /// The same template, with `{stem}` and `{ext}` taking `extensions` such as `tar.gz`
+    /// for a single extension.
+    pub fn compound_extensions(mut self, extensions: Vec<String>) -> Self {
+        self.compound_extensions = extensions;
+        self
+    }
+
+    /// Whether the template has fields that depend on what counts as an extension.
+    pub fn splits_extensions(&self) -> bool {
+        self.segments
+            .iter()
+            .any(|segment| matches!(segment, Segment::Field(Field::Stem | Field::Extension, _)))
+    }
+
+    /// Renders the template for a match.
+    pub fn render(&self, item: &Match) -> String {
+        let mut rendered = String::new();
+        for segment in &self.segments {
+            match segment {
+                Segment::Literal(literal) => rendered.push_str(literal),
+                Segment::Field(field, format) => {
+                    rendered.push_str(&render_field(self, *field, format.as_deref(), item))
+                }
+            }
+        }
+
+        rendered
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments(template: &str) -> Vec<Segment> {
+        template.parse::<Template>().unwrap().segments
+    }
+
+    fn literal(text: &str) -> Segment {
+        Segment::Literal(text.to_owned())
+    }
+
+    /// Asserts that `template` is rejected with `message`, pointing at `span` of it.
+    fn rejects(template: &str, span: Range<usize>, message: &str) {
+        let error = template.parse::<Template>().unwrap_err();
+        assert_eq!(
+            (error.span(), error.message()),
+            (span, message),
+            "{:?}",
+            template
+        );
+    }
+
+    #[test]
+    fn fields_and_literals() {
+        assert_eq!(segments(""), vec![]);
+        assert_eq!(segments("plain"), vec![literal("plain")]);
+        assert_eq!(
+            segments("{path}\\t{size:h}"),
+            vec![
+                Segment::Field(Field::Path, None),
+                literal("\t"),
+                Segment::Field(Field::Size, Some("h".to_owned())),
+            ]
+        );
+        assert_eq!(
+            segments("x{name}y{ext}"),
+            vec![
+                literal("x"),
+                Segment::Field(Field::Name, None),
+                literal("y"),
+                Segment::Field(Field::Extension, None),
+            ]
+        );
+        assert_eq!(
+            segments("{mtime:%Y-%m-%d}{perm:rwx}"),
+            vec![
+                Segment::Field(Field::Mtime, Some("%Y-%m-%d".to_owned())),
+                Segment::Field(Field::Perm, Some("rwx".to_owned())),
+            ]
+        );
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(
+            segments("a\\{b\\}\\\\\\n\\r\\0"),
+            vec![literal("a{b}\\\n\r\0")]
+        );
+        assert_eq!(
+            segments("\\{name\\}{name}"),
+            vec![literal("{name}"), Segment::Field(Field::Name, None)]
+        );
+    }
+
+    #[test]
+    fn splits_extensions() {
+        let splits = |template: &str| template.parse::<Template>().unwrap().splits_extensions();
+        assert!(splits("{stem}.bak"));
+        assert!(splits("{path} {ext}"));
+        assert!(!splits("{name} {size}"));
+    }
+
+    #[test]
+    fn error_spans() {
+        rejects("{path", 0..5, "expected a '}' to close the placeholder");
+        rejects(
+            "{path}{size",
+            6..11,
+            "expected a '}' to close the placeholder",
+        );
+        rejects("a{nope}b", 2..6, "unknown field");
+        rejects("é{nope}", 3..7, "unknown field");
+        rejects("{}", 1..2, "unknown field");
+        rejects("{:h}", 1..2, "unknown field");
+        rejects("{size:x}", 6..7, "invalid format for this field");
+        rejects("{name:h}", 6..7, "invalid format for this field");
+        rejects("{size:}", 6..7, "invalid format for this field");
+        rejects("{mtime:%Q}", 7..9, "invalid format for this field");
+        rejects("x}", 1..2, "unmatched '}', write '\\}' for a literal brace");
+        rejects(
+            "{path}}",
+            6..7,
+            "unmatched '}', write '\\}' for a literal brace",
+        );
+        rejects(
+            "a\\q",
+            1..3,
+            "unknown escape, expected \\t, \\n, \\r, \\0, \\\\, \\{ or \\}",
+        );
+        rejects("a\\", 1..2, "expected an escape after '\\'");
+    }
+}
@@ -0,0 +1,74 @@
+//! `--touch`: the access and modification times of the matched files set anew.
+
+use crate::output::Printer;
+use anyhow::Result;
+use quaero::Entry;
+use std::ffi::CString;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::time::SystemTime;
+
+/// Sets both times of the entry at `path` by its path, so that neither its permissions
+/// nor a reader it would wait for as a named pipe get in the way, and those of a link
+/// rather than of the file it points to.
+fn set_times(path: &Path, time: SystemTime) -> std::io::Result<()> {
+    let since_epoch = time
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidInput, error))?;
+    let time = libc::timespec {
+        tv_sec: since_epoch.as_secs() as libc::time_t,
+        tv_nsec: since_epoch.subsec_nanos() as _,
+    };
+    let path = CString::new(path.as_os_str().as_bytes())?;
+    let times = [time, time];
+
+    let set = unsafe {
+        libc::utimensat(
+            libc::AT_FDCWD,
+            path.as_ptr(),
+            times.as_ptr(),
+            libc::AT_SYMLINK_NOFOLLOW,
+        )
+    };
+    match set {
+        0 => Ok(()),
+        _ => Err(std::io::Error::last_os_error()),
+    }
+}
+
+/// Sets the times of the matched entries to `time`, those of links themselves for
+/// links, printing them through `printer`. What can't be touched is reported and
+/// skipped, the rest is touched all the same.
+pub fn touch(
+    matches: &[Entry],
+    time: SystemTime,
+    dry_run: bool,
+    printer: &mut Printer,
+) -> Result<()> {
+    let (mut touched, mut skipped) = (0, 0);
+
+    for entry in matches {
+        if !dry_run {
+            if let Err(error) = set_times(&entry.path, time) {
+                let path = quaero::clean_path(&entry.path).into_owned();
+                crate::report_error(&anyhow::Error::from(error).context(path));
+                skipped += 1;
+                continue;
+            }
+        }
+        printer.write(|out| writeln!(out, "{}", quaero::clean_path(&entry.path)));
+        touched += 1;
+    }
+
+    eprintln!(
+        "{}",
+        tr!(
+            "touch-summary",
+            "dry-run" => if dry_run { "yes" } else { "no" },
+            "files" => touched,
+            "skipped" => skipped,
+        )
+    );
+
+    Ok(())
+}
@@ -0,0 +1,193 @@
+//! `--copy-to` and `--move-to`: the matched files gathered into a directory.
+
+use crate::output::Printer;
+use anyhow::Result;
+use quaero::{Entry, FileType};
+use std::collections::HashSet;
+use std::io::ErrorKind;
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transfer {
+    Copy,
+    Move,
+}
+
+#[derive(Default)]
+struct Summary {
+    files: usize,
+    skipped: usize,
+}
+
+impl Summary {
+    /// Reports what kept `path` from being copied or moved, and goes on without it.
+    fn failed(&mut self, path: &Path, error: std::io::Error) {
+        let path = quaero::clean_path(path).into_owned();
+        crate::report_error(&anyhow::Error::from(error).context(path));
+        self.skipped += 1;
+    }
+}
+
+/// Where `entry` goes below `to`: by its file name, or with `preserve_structure` by its
+/// path from the root it was found below.
+fn destination(entry: &Entry, to: &Path, preserve_structure: bool) -> PathBuf {
+    let path = entry.path.as_path();
+    if !preserve_structure || entry.depth == 0 {
+        return to.join(path.file_name().unwrap_or(path.as_os_str()));
+    }
+
+    let components = path.components().collect::<Vec<_>>();
+    let below_root = components.len().saturating_sub(entry.depth);
+    to.join(components[below_root..].iter().collect::<PathBuf>())
+}
+
+/// The directory `path` is in, without symbolic links.
+fn directory_of(path: &Path) -> std::io::Result<PathBuf> {
+    let parent = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty());
+    std::fs::canonicalize(parent.unwrap_or(Path::new(".")))
+}
+
+/// `target` of the link at `source` as it has to read at `destination` to point at the
+/// same file. A relative target is relative to the directory the link is in, so it
+/// changes with that; an absolute one is kept.
+fn rebased(target: PathBuf, source: &Path, destination: &Path) -> std::io::Result<PathBuf> {
+    if target.is_absolute() {
+        return Ok(target);
+    }
+
+    // The directory the link is in has no links, so the `..` leading the target can
+    // be taken off it as they come; past those they are kept.
+    let mut pointed = directory_of(source)?;
+    let mut components = target.components().peekable();
+    while let Some(leading) =
+        components.next_if(|component| !matches!(component, Component::Normal(_)))
+    {
+        if leading == Component::ParentDir {
+            pointed.pop();
+        }
+    }
+    pointed.extend(components);
+    let from = directory_of(destination)?;
+    let common = from
+        .components()
+        .zip(pointed.components())
+        .take_while(|(from, pointed)| from == pointed)
+        .count();
+    let rebased = std::iter::repeat_n(Component::ParentDir, from.components().count() - common)
+        .chain(pointed.components().skip(common))
+        .collect::<PathBuf>();
+
+    Ok(match rebased.as_os_str().is_empty() {
+        true => PathBuf::from("."),
+        false => rebased,
+    })
+}
+
+/// Copies the file at `source`, or recreates the symbolic link at `source` pointing
+/// at the same file from `destination`.
+fn copy(source: &Path, destination: &Path, file_type: FileType) -> std::io::Result<()> {
+    match file_type {
+        FileType::SymLink => {
+            let target = rebased(std::fs::read_link(source)?, source, destination)?;
+            std::os::unix::fs::symlink(target, destination)
+        }
+        _ => std::fs::copy(source, destination).map(|_| ()),
+    }
+}
+
+/// Renames the file, or copies it and removes the original where a rename can't take
+/// it to another file system. A symbolic link is always recreated, for a relative one
+/// to point at the same file from where it is moved to.
+fn rename(source: &Path, destination: &Path, file_type: FileType) -> std::io::Result<()> {
+    if file_type == FileType::SymLink {
+        copy(source, destination, file_type)?;
+        return std::fs::remove_file(source);
+    }
+
+    match std::fs::rename(source, destination) {
+        Err(error) if error.kind() == ErrorKind::CrossesDevices => {
+            copy(source, destination, file_type)?;
+            std::fs::remove_file(source)
+        }
+        renamed => renamed,
+    }
+}
+
+/// Copies or moves the matched files, links and executables into `to`, creating the
+/// directories it takes. Directories and special files are skipped, and so is a file
+/// that would take the place of one already there or of another match. What can't be
+/// copied or moved is reported and skipped, the rest is transferred all the same.
+pub fn transfer(
+    matches: &[Entry],
+    transfer: Transfer,
+    to: &Path,
+    preserve_structure: bool,
+    dry_run: bool,
+    printer: &mut Printer,
+) -> Result<()> {
+    let mut summary = Summary::default();
+    let mut taken: HashSet<PathBuf> = HashSet::new();
+
+    for entry in matches {
+        if matches!(entry.file_type(), FileType::Directory | FileType::Special) {
+            summary.skipped += 1;
+            continue;
+        }
+
+        let source = entry.path.as_path();
+        let destination = destination(entry, to, preserve_structure);
+        let display = quaero::clean_path(&destination).into_owned();
+        if taken.contains(&destination) || destination.symlink_metadata().is_ok() {
+            eprintln!(
+                "{}",
+                tr!(
+                    "transfer-skipping-existing",
+                    "path" => quaero::clean_path(&entry.path).into_owned(),
+                    "destination" => display,
+                )
+            );
+            summary.skipped += 1;
+            continue;
+        }
+
+        if !dry_run {
+            if let Some(parent) = destination.parent() {
+                if let Err(error) = std::fs::create_dir_all(parent) {
+                    summary.failed(parent, error);
+                    continue;
+                }
+            }
+            let transferred = match transfer {
+                Transfer::Copy => copy(source, &destination, entry.file_type()),
+                Transfer::Move => rename(source, &destination, entry.file_type()),
+            };
+            if let Err(error) = transferred {
+                summary.failed(&entry.path, error);
+                continue;
+            }
+        }
+
+        let source = quaero::clean_path(&entry.path);
+        printer.write(|out| writeln!(out, "{} -> {}", source, display));
+        taken.insert(destination);
+        summary.files += 1;
+    }
+
+    eprintln!(
+        "{}",
+        tr!(
+            "transfer-summary",
+            "action" => match transfer {
+                Transfer::Copy => "copy",
+                Transfer::Move => "move",
+            },
+            "dry-run" => if dry_run { "yes" } else { "no" },
+            "files" => summary.files,
+            "skipped" => summary.skipped,
+        )
+    );
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use crate::output::labeled;
+use crate::pipeline::Match;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+
+#[derive(Default)]
+struct Node {
+    children: Vec<(String, Node)>,
+}
+
+impl Node {
+    fn child(&mut self, name: &str) -> &mut Node {
+        let index = match self.children.iter().position(|(child, _)| child == name) {
+            Some(index) => index,
+            None => {
+                self.children.push((name.to_owned(), Node::default()));
+                self.children.len() - 1
+            }
+        };
+
+        &mut self.children[index].1
+    }
+
+    fn sort(&mut self) {
+        self.children.sort_by(|(a, _), (b, _)| a.cmp(b));
+        for (_, child) in &mut self.children {
+            child.sort();
+        }
+    }
+
+    fn print(&self, prefix: &str, out: &mut dyn Write) -> std::io::Result<()> {
+        for (index, (name, child)) in self.children.iter().enumerate() {
+            let last = index + 1 == self.children.len();
+            let (branch, indent) = match last {
+                true => ("└── ", "    "),
+                false => ("├── ", "│   "),
+            };
+
+            writeln!(out, "{}{}{}", prefix, branch, name)?;
+            child.print(&format!("{}{}", prefix, indent), out)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits `path` into the root it was found under and the components below it, using
+/// its depth: an entry at depth `n` is `n` components below its root.
+fn split_root(path: &Path, depth: usize) -> (PathBuf, Vec<String>) {
+    let components = path.components().collect::<Vec<_>>();
+    let split = components.len().saturating_sub(depth);
+
+    let root = components[..split].iter().collect::<PathBuf>();
+    let below = components[split..]
+        .iter()
+        .filter_map(|component| match component {
+            Component::Normal(name) => Some(name.to_string_lossy().into_owned()),
+            _ => None,
+        })
+        .collect();
+
+    (root, below)
+}
+
+/// `--format tree`: prints the matches as an indented tree under each root, along with
+/// the directories leading to them. Siblings are ordered by name, unless `keep_order`
+/// asks for the order the matches come in.
+pub fn print(matches: &[Match], keep_order: bool, out: &mut dyn Write) -> std::io::Result<()> {
+    let mut roots = Node::default();
+    for item in matches {
+        let (root, below) = split_root(&item.entry.path, item.entry.depth);
+
+        let mut node = roots.child(&labeled(item.label.as_deref(), &root));
+        for component in &below {
+            node = node.child(component);
+        }
+    }
+
+    if !keep_order {
+        roots.sort();
+    }
+
+    for (root, node) in &roots.children {
+        writeln!(out, "{}", root)?;
+        node.print("", out)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,92 @@
+//! The metadata of a whole directory asked for at once through io_uring, for
+//! [`Backend::IoUring`]. On a file system where every stat is a round trip to a server,
+//! NFS or FUSE, the statx calls of a batch overlap instead of waiting on one another.
+//!
+//! The standard library can't make metadata out of what statx returns, so the results
+//! are dropped: what they leave in the caches of the kernel and of the file system is
+//! what answers the stat the walk does next without a round trip.
+//!
+//! [`Backend::IoUring`]: crate::walk::Backend::IoUring
+
+use io_uring::{opcode, types, IoUring};
+use std::ffi::{CString, OsStr};
+use std::os::fd::{AsRawFd, BorrowedFd};
+use std::os::unix::ffi::OsStrExt;
+
+/// How many statx calls go in at once, as many as the ring has room for.
+const BATCH: usize = 256;
+
+pub(crate) struct Prefetcher {
+    ring: IoUring,
+}
+
+impl Prefetcher {
+    pub fn new() -> std::io::Result<Self> {
+        Ok(Self {
+            ring: IoUring::new(BATCH as u32)?,
+        })
+    }
+
+    /// Stats the entries `names` of `directory`, without following links, and waits
+    /// for all of them. An entry that fails is for the walk's own stat to report.
+    ///
+    /// After an error the kernel may still be writing to the buffers, which are leaked
+    /// rather than freed, and the prefetcher is not to be used again.
+    pub fn prefetch<'a>(
+        &mut self,
+        directory: BorrowedFd<'_>,
+        names: impl IntoIterator<Item = &'a OsStr>,
+    ) -> std::io::Result<()> {
+        let names = names
+            .into_iter()
+            .filter_map(|name| CString::new(name.as_bytes()).ok())
+            .collect::<Vec<_>>();
+        // Nothing reads them, they are only somewhere for the kernel to write to.
+        let mut buffers = vec![unsafe { std::mem::zeroed::<libc::statx>() }; BATCH];
+
+        for batch in names.chunks(BATCH) {
+            if let Err(error) = self.run(directory, batch, &mut buffers) {
+                std::mem::forget(names);
+                std::mem::forget(buffers);
+                return Err(error);
+            }
+        }
+        Ok(())
+    }
+
+    fn run(
+        &mut self,
+        directory: BorrowedFd<'_>,
+        batch: &[CString],
+        buffers: &mut [libc::statx],
+    ) -> std::io::Result<()> {
+        {
+            let mut submission = self.ring.submission();
+            for (name, buffer) in batch.iter().zip(buffers.iter_mut()) {
+                let statx = opcode::Statx::new(
+                    types::Fd(directory.as_raw_fd()),
+                    name.as_ptr(),
+                    (buffer as *mut libc::statx).cast(),
+                )
+                .flags(libc::AT_SYMLINK_NOFOLLOW)
+                .mask(libc::STATX_BASIC_STATS)
+                .build();
+                // The name and the buffer outlive the call, and the ring has room for a
+                // whole batch.
+                unsafe { submission.push(&statx) }
+                    .map_err(|_| std::io::Error::other("the submission queue is full"))?;
+            }
+        }
+
+        let mut completed = 0;
+        while completed < batch.len() {
+            match self.ring.submit_and_wait(batch.len() - completed) {
+                Ok(_) => {}
+                Err(error) if error.kind() == std::io::ErrorKind::Interrupted => {}
+                Err(error) => return Err(error),
+            }
+            completed += self.ring.completion().count();
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::ffi::{CStr, CString};
+use std::sync::{Mutex, OnceLock};
+
+const BUFFER_SIZE: usize = 16 * 1024;
+
+fn lookup_user_name(name: &str) -> Option<u32> {
+    let name = CString::new(name).ok()?;
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut result = std::ptr::null_mut();
+
+    unsafe {
+        libc::getpwnam_r(
+            name.as_ptr(),
+            &mut passwd,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut result,
+        )
+    };
+
+    (!result.is_null()).then_some(passwd.pw_uid)
+}
+
+fn lookup_group_name(name: &str) -> Option<u32> {
+    let name = CString::new(name).ok()?;
+    let mut group: libc::group = unsafe { std::mem::zeroed() };
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut result = std::ptr::null_mut();
+
+    unsafe {
+        libc::getgrnam_r(
+            name.as_ptr(),
+            &mut group,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut result,
+        )
+    };
+
+    (!result.is_null()).then_some(group.gr_gid)
+}
+
+fn uid_name(uid: u32) -> Option<String> {
+    let mut passwd: libc::passwd = unsafe { std::mem::zeroed() };
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut result = std::ptr::null_mut();
+
+    unsafe {
+        libc::getpwuid_r(
+            uid,
+            &mut passwd,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut result,
+        )
+    };
+
+    (!result.is_null()).then(|| {
+        unsafe { CStr::from_ptr(passwd.pw_name) }
+            .to_string_lossy()
+            .into_owned()
+    })
+}
+
+fn gid_name(gid: u32) -> Option<String> {
+    let mut group: libc::group = unsafe { std::mem::zeroed() };
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut result = std::ptr::null_mut();
+
+    unsafe {
+        libc::getgrgid_r(
+            gid,
+            &mut group,
+            buffer.as_mut_ptr(),
+            buffer.len(),
+            &mut result,
+        )
+    };
+
+    (!result.is_null()).then(|| {
+        unsafe { CStr::from_ptr(group.gr_name) }
+            .to_string_lossy()
+            .into_owned()
+    })
+}
+
+/// Parses a user name (or a numeric uid) into a uid, see [`parse::user`](crate::parse::user).
+pub fn parse_user(value: &str) -> Option<u32> {
+    value.parse().ok().or_else(|| lookup_user_name(value))
+}
+
+/// Parses a group name (or a numeric gid) into a gid, see [`parse::group`](crate::parse::group).
+pub fn parse_group(value: &str) -> Option<u32> {
+    value.parse().ok().or_else(|| lookup_group_name(value))
+}
+
+/// Whom `--chown` gives files to, the user or the group left as they are if missing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Owner {
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+}
+
+type NameCache = OnceLock<Mutex<HashMap<u32, Option<String>>>>;
+
+fn cached(cache: &NameCache, id: u32, lookup: fn(u32) -> Option<String>) -> Option<String> {
+    let mut cache = cache.get_or_init(Default::default).lock().unwrap();
+    cache.entry(id).or_insert_with(|| lookup(id)).clone()
+}
+
+static USER_NAMES: NameCache = OnceLock::new();
+static GROUP_NAMES: NameCache = OnceLock::new();
+
+/// The name of the account `uid` belongs to, if it is in the users database.
+pub fn user_name(uid: u32) -> Option<String> {
+    cached(&USER_NAMES, uid, uid_name)
+}
+
+/// The name of the group `gid` belongs to, if it is in the group database.
+pub fn group_name(gid: u32) -> Option<String> {
+    cached(&GROUP_NAMES, gid, gid_name)
+}
+
+/// Whether `uid` belongs to an account in the users database.
+pub fn has_user(uid: u32) -> bool {
+    user_name(uid).is_some()
+}
+
+/// Whether `gid` belongs to a group in the group database.
+pub fn has_group(gid: u32) -> bool {
+    group_name(gid).is_some()
+}
+
+/// The effective uid of quaero, the user `--mine` matches the entries of.
+pub fn effective_uid() -> u32 {
+    unsafe { libc::geteuid() }
+}
@@ -0,0 +1,894 @@
+use crate::checkpoint::Checkpoint;
+#[cfg(target_os = "linux")]
+use crate::dirents::{Dirent, Dirents};
+use crate::filter::{Descend, DirFilter, EntryFilter, PruneIf};
+#[cfg(target_os = "linux")]
+use crate::uring::Prefetcher;
+use crate::{debug, is_vanished, Entry, FileType, IgnorePolicy, Matcher};
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use ignore::gitignore::Gitignore;
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::ffi::OsStr;
+use std::fs::{DirEntry, ReadDir};
+use std::os::unix::ffi::OsStrExt;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Counters a [`Walk`] updates as it goes, shareable between walks running in parallel.
+#[derive(Debug, Default)]
+pub struct WalkStats {
+    pub entries: AtomicU64,
+    pub directories: AtomicU64,
+    pub errors: AtomicU64,
+    /// Entries gone again between being listed and being looked at, which are skipped
+    /// without an error
+    pub vanished: AtomicU64,
+    /// Entries the walk looked at with a stat, rather than going by the type their
+    /// directory lists them as: the roots, links followed and those listed without a
+    /// type. The metadata filters and the output look up later isn't counted
+    pub lookups: AtomicU64,
+    /// The directory read last, for showing where the walk is
+    pub current: Mutex<PathBuf>,
+}
+
+impl WalkStats {
+    fn bump(counter: &AtomicU64) {
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Which symbolic links a walk follows, as the -P, -H and -L of find. A followed link is
+/// looked at as what it leads to, for its type, size and times, and walked into if that
+/// is a directory; any other is an entry of its own. The roots are listed whatever they
+/// are, being where to search.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Follow {
+    /// No link
+    #[default]
+    Never,
+    /// Only the roots, where they are entries themselves at a depth of 0
+    Roots,
+    /// Every link, but those leading back to a directory the walk is in, which fail
+    Always,
+}
+
+/// How a walk reads directories.
+#[derive(ValueEnum, Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// Through the standard library
+    #[default]
+    Std,
+    /// With getdents64 in large batches, on Linux. The entries that can't be ruled out
+    /// by their name and type take longer to look at than with std, so it pays off for
+    /// searches by name. Elsewhere the same as std
+    Fast,
+    /// Experimental: as fast, but with the metadata of each directory's entries asked
+    /// for all at once through io_uring, for the round trips of a network or FUSE file
+    /// system to overlap. Elsewhere, or where io_uring is unavailable, the same as fast
+    IoUring,
+}
+
+/// Configures a walk over one or more roots, depth-first unless
+/// [`WalkBuilder::breadth_first`] says otherwise.
+pub struct WalkBuilder {
+    roots: Vec<PathBuf>,
+    avoids: Vec<PathBuf>,
+    max_depth: usize,
+    sorted: bool,
+    breadth_first: bool,
+    policy: IgnorePolicy,
+    follow: Follow,
+    backend: Backend,
+    dir_filters: Vec<Arc<dyn DirFilter>>,
+    entry_filters: Vec<Arc<dyn EntryFilter>>,
+    /// The matchers given, for the fast backend to rule entries out with before it
+    /// looks at them
+    matchers: Vec<Matcher>,
+    mount_points: Vec<PathBuf>,
+    stats: Arc<WalkStats>,
+    interrupt: Arc<AtomicBool>,
+    checkpoint: Option<Arc<Checkpoint>>,
+}
+
+impl WalkBuilder {
+    pub fn new<T: AsRef<Path>>(root: T) -> Self {
+        Self {
+            roots: vec![root.as_ref().to_path_buf()],
+            avoids: Vec::new(),
+            max_depth: usize::MAX,
+            sorted: false,
+            breadth_first: false,
+            policy: IgnorePolicy::from_unrestricted(0),
+            follow: Follow::Never,
+            backend: Backend::Std,
+            dir_filters: Vec::new(),
+            entry_filters: Vec::new(),
+            matchers: Vec::new(),
+            mount_points: Vec::new(),
+            stats: Arc::default(),
+            interrupt: Arc::default(),
+            checkpoint: None,
+        }
+    }
+
+    /// Walks `root` after the roots added so far.
+    pub fn add_root<T: AsRef<Path>>(mut self, root: T) -> Self {
+        self.roots.push(root.as_ref().to_path_buf());
+        self
+    }
+
+    /// Skips `path` and everything below it.
+    pub fn avoid<T: AsRef<Path>>(mut self, path: T) -> Self {
+        self.avoids.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    pub fn avoids(mut self, paths: impl IntoIterator<Item = PathBuf>) -> Self {
+        self.avoids.extend(paths);
+        self
+    }
+
+    /// How deep to walk: 0 for the root alone and 1 for its children as well. The root is
+    /// always yielded first, at depth 0, if the entry filters accept it.
+    pub fn max_depth(mut self, depth: usize) -> Self {
+        self.max_depth = depth;
+        self
+    }
+
+    /// Walks the entries of every directory in the byte order of their names, instead of
+    /// the order the file system lists them in, which can differ between two runs.
+    pub fn sorted(mut self, sorted: bool) -> Self {
+        self.sorted = sorted;
+        self
+    }
+
+    /// Yields every entry of a root at one depth before any entry deeper down, instead of
+    /// walking into each directory as soon as it is found. The directories waiting to be
+    /// listed are kept in memory, up to a whole level of the tree at once.
+    pub fn breadth_first(mut self, breadth_first: bool) -> Self {
+        self.breadth_first = breadth_first;
+        self
+    }
+
+    pub fn policy(mut self, policy: IgnorePolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn follow(mut self, follow: Follow) -> Self {
+        self.follow = follow;
+        self
+    }
+
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Passes over the file systems mounted on `paths`, absolute and without symbolic
+    /// links as /proc/mounts has them: their mount points aren't looked at, let alone
+    /// walked into, as a dead network mount hangs whatever looks at it.
+    pub fn skip_mount_points(mut self, paths: impl IntoIterator<Item = PathBuf>) -> Self {
+        self.mount_points.extend(paths);
+        self
+    }
+
+    /// Only yields entries accepted by `matcher`. Directories are still descended into
+    /// whether they match or not, unless nothing below them could.
+    pub fn matcher(mut self, matcher: Matcher) -> Self {
+        self.matchers.push(matcher.clone());
+        self.dir_filter(matcher.clone()).entry_filter(matcher)
+    }
+
+    /// Asks `filter` about every directory before walking into it.
+    pub fn dir_filter(mut self, filter: impl DirFilter + 'static) -> Self {
+        self.dir_filters.push(Arc::new(filter));
+        self
+    }
+
+    /// Doesn't walk into the directories `predicate` is true of, such as those with a
+    /// marker file in them, whatever the matchers say; they are still yielded if they
+    /// match. It is given each directory as an [`Entry`], whose `depth` says how far
+    /// below its root it is.
+    pub fn prune_if(self, predicate: impl Fn(&Entry) -> bool + Send + Sync + 'static) -> Self {
+        self.dir_filter(PruneIf(predicate))
+    }
+
+    /// Only yields entries accepted by `filter`, on top of the filters added so far.
+    pub fn entry_filter(mut self, filter: impl EntryFilter + 'static) -> Self {
+        self.entry_filters.push(Arc::new(filter));
+        self
+    }
+
+    /// Records what the walk does into `stats`.
+    pub fn stats(mut self, stats: Arc<WalkStats>) -> Self {
+        self.stats = stats;
+        self
+    }
+
+    /// Ends the walk early once `interrupt` is set, from any thread.
+    pub fn interrupt(mut self, interrupt: Arc<AtomicBool>) -> Self {
+        self.interrupt = interrupt;
+        self
+    }
+
+    /// Records the directories the walk gets through into `checkpoint`, and leaves out
+    /// those it says an earlier walk got through, roots included. Only a depth-first
+    /// walk records any, as a breadth-first one gets through none until the end.
+    pub fn checkpoint(mut self, checkpoint: Arc<Checkpoint>) -> Self {
+        self.dir_filters.push(checkpoint.clone());
+        self.checkpoint = Some(checkpoint);
+        self
+    }
+
+    /// The entry a walk would reach at `path`, before the entry filters are applied.
+    /// `None` if the path is outside the roots, too deep, avoided, skipped by the policy
+    /// or left out by a directory filter, itself or through one of the directories
+    /// leading to it.
+    ///
+    /// This is for paths learned about some other way, e.g. from file system events.
+    pub fn admit<T: AsRef<Path>>(&self, path: T) -> Result<Option<Entry>> {
+        let path = path.as_ref();
+        let Some((root, below)) = self
+            .roots
+            .iter()
+            .find_map(|root| Some((root, path.strip_prefix(root).ok()?)))
+        else {
+            return Ok(None);
+        };
+
+        let mut components = Vec::new();
+        for component in below.components() {
+            match component {
+                Component::Normal(name) => components.push(name),
+                _ => return Ok(None),
+            }
+        }
+        if components.is_empty() || components.len() > self.max_depth {
+            return Ok(None);
+        }
+
+        let avoids = canonical(&self.avoids);
+        let mut ignores = Vec::new();
+        let mut current = root.clone();
+        let mut absolute = absolute(root);
+        for (index, name) in components.iter().enumerate() {
+            ignores.extend(self.policy.load_ignores(&current));
+            current.push(name);
+            absolute.push(name);
+
+            let mut entry = Entry::from_path(&current)?;
+            if self.follow == Follow::Always {
+                entry = entry.followed();
+            }
+            entry.depth = index + 1;
+            if self.policy.should_skip(&entry, &ignores) || is_avoided(&avoids, &absolute) {
+                return Ok(None);
+            }
+
+            let last = index + 1 == components.len();
+            let descend = match entry.is_dir() {
+                true => descend(&self.dir_filters, &entry)?,
+                false => Descend::No,
+            };
+            match descend {
+                Descend::Skip => return Ok(None),
+                _ if last => return Ok(Some(entry)),
+                Descend::No => return Ok(None),
+                Descend::Yes => {}
+            }
+        }
+
+        unreachable!("the last component returns")
+    }
+
+    pub fn build(self) -> Walk {
+        Walk {
+            roots: self.roots.into(),
+            avoids: canonical(&self.avoids),
+            max_depth: self.max_depth,
+            sorted: self.sorted,
+            breadth_first: self.breadth_first,
+            policy: self.policy,
+            follow: self.follow,
+            backend: self.backend,
+            dir_filters: self.dir_filters,
+            entry_filters: self.entry_filters,
+            prefilter: Prefilter::new(&self.matchers, self.follow),
+            mount_points: self.mount_points,
+            stats: self.stats,
+            interrupt: self.interrupt,
+            checkpoint: self.checkpoint,
+            #[cfg(target_os = "linux")]
+            prefetcher: match self.backend {
+                Backend::IoUring => Prefetcher::new()
+                    .inspect_err(|error| debug(format_args!("no io_uring: {}", error)))
+                    .ok(),
+                Backend::Std | Backend::Fast => None,
+            },
+            stack: Vec::new(),
+            queue: VecDeque::new(),
+            ignores: Arc::default(),
+        }
+    }
+}
+
+/// What the directory filters agree on for `directory`: left out if any filter says so,
+/// else not walked into if any filter says so.
+fn descend(filters: &[Arc<dyn DirFilter>], directory: &Entry) -> Result<Descend> {
+    let mut decided = Descend::Yes;
+    for filter in filters {
+        match filter.descend(directory)? {
+            Descend::Skip => return Ok(Descend::Skip),
+            Descend::No => decided = Descend::No,
+            Descend::Yes => {}
+        }
+    }
+
+    Ok(decided)
+}
+
+/// The avoided paths as absolute paths without symbolic links, leaving out the ones
+/// that don't exist since there is nothing to avoid there.
+fn canonical(avoids: &[PathBuf]) -> Vec<PathBuf> {
+    avoids
+        .iter()
+        .filter_map(|avoid| std::fs::canonicalize(avoid).ok())
+        .collect()
+}
+
+/// `root` as an absolute path without symbolic links. The paths below it only need to
+/// be joined onto this one, but for those of the links the walk follows.
+fn absolute(root: &Path) -> PathBuf {
+    std::fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf())
+}
+
+/// Whether the entry at the absolute path `path` is below one of the canonical `avoids`.
+fn is_avoided(avoids: &[PathBuf], path: &Path) -> bool {
+    avoids.iter().any(|avoid| path.starts_with(avoid))
+}
+
+/// Whether the entry `name` of the directory at the absolute path `directory` is one
+/// of `mount_points`.
+fn is_mount_point(mount_points: &[PathBuf], directory: &Path, name: &OsStr) -> bool {
+    mount_points
+        .iter()
+        .any(|point| point.file_name() == Some(name) && point.parent() == Some(directory))
+}
+
+/// An entry as its directory lists it, before it is looked at.
+enum Found {
+    Std(DirEntry),
+    #[cfg(target_os = "linux")]
+    Raw(Dirent),
+}
+
+impl Found {
+    fn name(&self) -> Cow<'_, OsStr> {
+        match self {
+            Found::Std(entry) => Cow::Owned(entry.file_name()),
+            #[cfg(target_os = "linux")]
+            Found::Raw(dirent) => Cow::Borrowed(&dirent.name),
+        }
+    }
+}
+
+/// The type of an entry by the `DT_*` type its directory lists it as.
+#[cfg(target_os = "linux")]
+fn listed_type(kind: u8) -> Option<FileType> {
+    match kind {
+        libc::DT_REG => Some(FileType::RegularFile),
+        libc::DT_DIR => Some(FileType::Directory),
+        libc::DT_LNK => Some(FileType::SymLink),
+        libc::DT_UNKNOWN => None,
+        _ => Some(FileType::Special),
+    }
+}
+
+/// The entries of a directory, as listed, or all read at once to be sorted by name or
+/// looked at ahead.
+enum Entries {
+    Listed(ReadDir),
+    #[cfg(target_os = "linux")]
+    Raw(Dirents),
+    Collected(std::vec::IntoIter<std::io::Result<Found>>),
+}
+
+impl Iterator for Entries {
+    type Item = std::io::Result<Found>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Entries::Listed(entries) => Some(entries.next()?.map(Found::Std)),
+            #[cfg(target_os = "linux")]
+            Entries::Raw(entries) => Some(entries.next()?.map(Found::Raw)),
+            Entries::Collected(entries) => entries.next(),
+        }
+    }
+}
+
+struct Frame {
+    entries: Entries,
+    /// The directory as walked, which the entries of the fast backend are joined onto
+    path: PathBuf,
+    /// Absolute path of the directory, to tell the avoided entries by
+    directory: PathBuf,
+    depth: usize,
+    pushed_ignore: bool,
+    /// Device and inode of the directory and of those leading to it, to tell a link
+    /// leading back to one of them by. Only kept when following every link.
+    ancestors: Arc<Vec<(u64, u64)>>,
+}
+
+/// A directory found by a breadth-first walk, to be listed once everything before it is.
+struct Pending {
+    path: PathBuf,
+    absolute: PathBuf,
+    depth: usize,
+    /// The ignore files of the directories leading to it
+    ignores: Arc<Vec<Gitignore>>,
+    ancestors: Arc<Vec<(u64, u64)>>,
+}
+
+/// What the matchers given can tell of an entry by its name and the type its directory
+/// lists it as, for the walk to pass it over without the stat of looking at it. Regular
+/// files are taken for either kind of file, as only their mode tells executables.
+struct Prefilter {
+    /// The matchers, or the operands of those that all have to match, that only need
+    /// the name, which is asked about where the directory lists it
+    by_name: Vec<Matcher>,
+    /// Those that look at the rest of the path, which has to be joined for them first
+    by_path: Vec<Matcher>,
+    follow: Follow,
+}
+
+impl Prefilter {
+    fn new(matchers: &[Matcher], follow: Follow) -> Self {
+        let (by_path, by_name) = matchers
+            .iter()
+            .flat_map(conjuncts)
+            .partition(Matcher::looks_at_path);
+        Self {
+            by_name,
+            by_path,
+            follow,
+        }
+    }
+
+    /// The file type to ask the matchers about, unless the entry has to be looked at
+    /// anyway.
+    fn file_type(&self, listed: Option<FileType>) -> Option<FileType> {
+        match listed {
+            // A link followed is whatever it leads to.
+            Some(FileType::SymLink) if self.follow == Follow::Always => None,
+            // Directories are looked at anyway, to be walked into.
+            Some(FileType::Directory) | None => None,
+            file_type => file_type,
+        }
+    }
+
+    /// Whether a matcher that only needs the name rejects the entry called `name`.
+    fn rules_out_by_name(&self, name: &OsStr, listed: Option<FileType>) -> bool {
+        let Some(file_type) = self.file_type(listed).filter(|_| !self.by_name.is_empty()) else {
+            return false;
+        };
+
+        // The name stands in for the path, their last components being the same.
+        self.by_name
+            .iter()
+            .any(|matcher| matcher.matches_path(Path::new(name), name, file_type) == Some(false))
+    }
+
+    /// Whether a matcher that looks at the rest of the path rejects the entry at `path`.
+    fn rules_out_by_path(&self, path: &Path, name: &OsStr, listed: Option<FileType>) -> bool {
+        let Some(file_type) = self.file_type(listed).filter(|_| !self.by_path.is_empty()) else {
+            return false;
+        };
+
+        self.by_path
+            .iter()
+            .any(|matcher| matcher.matches_path(path, name, file_type) == Some(false))
+    }
+
+    /// Whether any matcher rejects the entry called `name` in `directory`, which is only
+    /// joined onto it if a matcher needs the path.
+    fn rules_out(&self, directory: &Path, name: &OsStr, listed: Option<FileType>) -> bool {
+        self.rules_out_by_name(name, listed)
+            || (!self.by_path.is_empty()
+                && self.rules_out_by_path(&directory.join(name), name, listed))
+    }
+}
+
+/// The operands of `matcher` if they all have to match, or else the matcher itself.
+fn conjuncts(matcher: &Matcher) -> Vec<Matcher> {
+    match matcher {
+        Matcher::All(matchers) => matchers.iter().flat_map(conjuncts).collect(),
+        Matcher::Adaptive(adaptive) => adaptive.matchers().iter().flat_map(conjuncts).collect(),
+        matcher => vec![matcher.clone()],
+    }
+}
+
+/// Iterator over the entries below the roots of a [`WalkBuilder`].
+pub struct Walk {
+    roots: VecDeque<PathBuf>,
+    avoids: Vec<PathBuf>,
+    max_depth: usize,
+    sorted: bool,
+    breadth_first: bool,
+    policy: IgnorePolicy,
+    follow: Follow,
+    backend: Backend,
+    dir_filters: Vec<Arc<dyn DirFilter>>,
+    entry_filters: Vec<Arc<dyn EntryFilter>>,
+    prefilter: Prefilter,
+    mount_points: Vec<PathBuf>,
+    stats: Arc<WalkStats>,
+    interrupt: Arc<AtomicBool>,
+    checkpoint: Option<Arc<Checkpoint>>,
+    /// The ring of the io_uring backend, if it could be set up
+    #[cfg(target_os = "linux")]
+    prefetcher: Option<Prefetcher>,
+    /// The directories being listed, only ever the one when breadth-first
+    stack: Vec<Frame>,
+    queue: VecDeque<Pending>,
+    /// Only shared with the pending directories of a breadth-first walk, so a
+    /// depth-first one never copies it
+    ignores: Arc<Vec<Gitignore>>,
+}
+
+impl Walk {
+    fn push_directory<T: AsRef<Path>>(
+        &mut self,
+        directory: T,
+        absolute: PathBuf,
+        depth: usize,
+        ancestors: Arc<Vec<(u64, u64)>>,
+    ) -> Result<()> {
+        let entries = match self.backend {
+            #[cfg(target_os = "linux")]
+            Backend::Fast => Dirents::open(directory.as_ref()).map(Entries::Raw),
+            #[cfg(target_os = "linux")]
+            Backend::IoUring => Dirents::open(directory.as_ref())
+                .map(|dirents| self.prefetch(&directory, &absolute, dirents)),
+            _ => std::fs::read_dir(&directory).map(Entries::Listed),
+        }
+        .with_context(|| directory.as_ref().display().to_string())?;
+        let entries = match self.sorted {
+            true => {
+                let mut entries = entries.collect::<Vec<_>>();
+                // Errors go first, they have no name to sort by.
+                entries.sort_by(|a, b| match (a, b) {
+                    (Ok(a), Ok(b)) => a.name().as_bytes().cmp(b.name().as_bytes()),
+                    (a, b) => a.is_ok().cmp(&b.is_ok()),
+                });
+                Entries::Collected(entries.into_iter())
+            }
+            false => entries,
+        };
+        WalkStats::bump(&self.stats.directories);
+        // Another walk just took it; where it is will do as well.
+        if let Ok(mut current) = self.stats.current.try_lock() {
+            *current = directory.as_ref().to_path_buf();
+        }
+
+        let ignore = self.policy.load_ignores(&directory);
+        let pushed_ignore = ignore.is_some();
+        if let Some(ignore) = ignore {
+            Arc::make_mut(&mut self.ignores).push(ignore);
+        }
+
+        self.stack.push(Frame {
+            entries,
+            path: directory.as_ref().to_path_buf(),
+            directory: absolute,
+            depth,
+            pushed_ignore,
+            ancestors,
+        });
+
+        Ok(())
+    }
+
+    /// Reads all of `dirents`, the entries of `directory` at `absolute`, and stats those that can't be
+    /// ruled out in batches through io_uring, for the walk to find their metadata cached.
+    #[cfg(target_os = "linux")]
+    fn prefetch<T: AsRef<Path>>(
+        &mut self,
+        directory: T,
+        absolute: &Path,
+        mut dirents: Dirents,
+    ) -> Entries {
+        let listed = dirents.by_ref().collect::<Vec<_>>();
+        if self.prefetcher.is_some() {
+            let names = listed
+                .iter()
+                .flatten()
+                .filter(|dirent| !is_mount_point(&self.mount_points, absolute, &dirent.name))
+                .filter(|dirent| {
+                    let listed = listed_type(dirent.kind);
+                    !self
+                        .prefilter
+                        .rules_out(directory.as_ref(), &dirent.name, listed)
+                })
+                .map(|dirent| dirent.name.as_os_str())
+                .collect::<Vec<_>>();
+            let prefetcher = self.prefetcher.as_mut().expect("checked above");
+            if let Err(error) = prefetcher.prefetch(dirents.fd(), names) {
+                debug(format_args!("giving up on io_uring: {}", error));
+                self.prefetcher = None;
+            }
+        }
+
+        let found = listed.into_iter().map(|dirent| dirent.map(Found::Raw));
+        Entries::Collected(found.collect::<Vec<_>>().into_iter())
+    }
+
+    /// Counts `error` as a vanished entry if that is all it says, in which case the walk
+    /// goes on as if it had never been listed.
+    fn skip_vanished(&self, error: &anyhow::Error) -> bool {
+        if !is_vanished(error) {
+            return false;
+        }
+
+        WalkStats::bump(&self.stats.vanished);
+        debug(format_args!("skipped an entry that vanished: {:#}", error));
+        true
+    }
+
+    fn pop_directory(&mut self) {
+        if let Some(frame) = self.stack.pop() {
+            // The next directory of a breadth-first walk brings its own.
+            if frame.pushed_ignore && !self.breadth_first {
+                Arc::make_mut(&mut self.ignores).pop();
+            }
+            if let Some(checkpoint) = self.checkpoint.as_ref().filter(|_| !self.breadth_first) {
+                checkpoint.finished(&frame.path);
+            }
+        }
+    }
+
+    fn next_entry(&mut self) -> Option<Result<Entry>> {
+        loop {
+            if self.interrupt.load(Ordering::Relaxed) {
+                return None;
+            }
+
+            let Some(frame) = self.stack.last_mut() else {
+                if let Some(pending) = self.queue.pop_front() {
+                    self.ignores = pending.ignores;
+                    match self.push_directory(
+                        pending.path,
+                        pending.absolute,
+                        pending.depth,
+                        pending.ancestors,
+                    ) {
+                        Err(error) if self.skip_vanished(&error) => {}
+                        Err(error) => return Some(Err(error)),
+                        Ok(()) => {}
+                    }
+                    continue;
+                }
+
+                let root = self.roots.pop_front()?;
+                if let Some(checkpoint) = &self.checkpoint {
+                    if checkpoint.is_done(&root) {
+                        continue;
+                    }
+                }
+                // The root is an entry like any other, at depth 0, and is listed once
+                // it has been yielded.
+                WalkStats::bump(&self.stats.entries);
+                WalkStats::bump(&self.stats.lookups);
+                let entry = Entry::from_path(&root).map(|entry| match self.follow {
+                    Follow::Never => entry,
+                    Follow::Roots | Follow::Always => entry.followed(),
+                });
+                let entry = match entry.with_context(|| root.display().to_string()) {
+                    Ok(entry) => entry,
+                    Err(error) => return Some(Err(error)),
+                };
+                // A link to a directory is listed as one, whatever is followed.
+                let listed = match entry.file_type() {
+                    FileType::Directory => true,
+                    FileType::SymLink => std::fs::metadata(&root).is_ok_and(|meta| meta.is_dir()),
+                    _ => false,
+                };
+                if self.max_depth > 0 && listed {
+                    let ancestors = match self.follow {
+                        Follow::Always => match std::fs::metadata(&root) {
+                            Ok(metadata) => vec![(metadata.dev(), metadata.ino())],
+                            Err(_) => Vec::new(),
+                        },
+                        Follow::Never | Follow::Roots => Vec::new(),
+                    };
+                    self.queue.push_back(Pending {
+                        absolute: absolute(&root),
+                        path: root,
+                        depth: 1,
+                        ignores: Arc::default(),
+                        ancestors: Arc::new(ancestors),
+                    });
+                }
+                return Some(Ok(entry));
+            };
+
+            // What the matchers rule out by name alone is passed over where the
+            // directory lists it, without a copy of the name or a path joined for it.
+            #[cfg(target_os = "linux")]
+            if let Entries::Raw(dirents) = &mut frame.entries {
+                let (prefilter, stats) = (&self.prefilter, &self.stats);
+                dirents.skip_while(|name, kind| {
+                    let skip = prefilter.rules_out_by_name(name, listed_type(kind));
+                    if skip {
+                        WalkStats::bump(&stats.entries);
+                    }
+                    skip
+                });
+            }
+
+            let depth = frame.depth;
+            let Some(entry) = frame.entries.next() else {
+                self.pop_directory();
+                continue;
+            };
+
+            WalkStats::bump(&self.stats.entries);
+            if !self.mount_points.is_empty()
+                && matches!(&entry, Ok(found) if is_mount_point(&self.mount_points, &frame.directory, &found.name()))
+            {
+                continue;
+            }
+            let entry = match entry {
+                Ok(Found::Std(entry)) => {
+                    let listed = entry.file_type().ok().map(crate::listed_type);
+                    let name = entry.file_name();
+                    if self.prefilter.rules_out_by_name(&name, listed) {
+                        continue;
+                    }
+                    let path = entry.path();
+                    if self.prefilter.rules_out_by_path(&path, &name, listed) {
+                        continue;
+                    }
+                    match listed {
+                        Some(file_type) => Ok(Entry::listed(path, name, file_type)),
+                        None => {
+                            WalkStats::bump(&self.stats.lookups);
+                            Entry::try_from(entry).with_context(|| path.display().to_string())
+                        }
+                    }
+                }
+                #[cfg(target_os = "linux")]
+                Ok(Found::Raw(dirent)) => {
+                    let listed = listed_type(dirent.kind);
+                    if self.prefilter.rules_out_by_name(&dirent.name, listed) {
+                        continue;
+                    }
+                    let path = frame.path.join(&dirent.name);
+                    if self
+                        .prefilter
+                        .rules_out_by_path(&path, &dirent.name, listed)
+                    {
+                        continue;
+                    }
+                    match listed {
+                        Some(file_type) => Ok(Entry::listed(path, dirent.name, file_type)),
+                        None => {
+                            WalkStats::bump(&self.stats.lookups);
+                            Entry::from_path(&path).with_context(|| path.display().to_string())
+                        }
+                    }
+                }
+                Err(error) => Err(error.into()),
+            };
+            let mut entry = match entry {
+                Ok(entry) => entry,
+                Err(error) if self.skip_vanished(&error) => continue,
+                Err(error) => return Some(Err(error)),
+            };
+            entry.depth = depth;
+            let mut followed = false;
+            if entry.is_symlink() && self.follow == Follow::Always {
+                WalkStats::bump(&self.stats.lookups);
+                entry = entry.followed();
+                followed = !entry.is_symlink();
+            }
+
+            if self.policy.should_skip(&entry, &self.ignores) {
+                continue;
+            }
+
+            // Only the paths that are compared or walked into are worth joining.
+            let directory = entry.is_dir();
+            let frame = &self.stack[self.stack.len() - 1];
+            let absolute = match directory || !self.avoids.is_empty() {
+                // Where a followed link goes is anywhere.
+                true if followed => absolute(&entry.path),
+                true => frame.directory.join(&entry.name),
+                false => PathBuf::new(),
+            };
+            if is_avoided(&self.avoids, &absolute) {
+                continue;
+            }
+
+            let mut ancestors = Arc::default();
+            if directory && self.follow == Follow::Always {
+                let metadata = match entry.metadata() {
+                    Ok(metadata) => metadata,
+                    Err(error) => {
+                        let error =
+                            anyhow::Error::from(error).context(entry.path.display().to_string());
+                        match self.skip_vanished(&error) {
+                            true => continue,
+                            false => return Some(Err(error)),
+                        }
+                    }
+                };
+                let id = (metadata.dev(), metadata.ino());
+                if frame.ancestors.contains(&id) {
+                    return Some(Err(anyhow::anyhow!(
+                        "{}: leads back to a directory it is in",
+                        entry.path.display()
+                    )));
+                }
+                let mut chain = frame.ancestors.to_vec();
+                chain.push(id);
+                ancestors = Arc::new(chain);
+            }
+
+            if directory {
+                match descend(&self.dir_filters, &entry) {
+                    Ok(Descend::Skip) => continue,
+                    Ok(Descend::Yes) if depth < self.max_depth && self.breadth_first => {
+                        self.queue.push_back(Pending {
+                            path: PathBuf::from(&entry.path),
+                            absolute,
+                            depth: depth + 1,
+                            ignores: self.ignores.clone(),
+                            ancestors,
+                        });
+                    }
+                    Ok(Descend::Yes) if depth < self.max_depth => {
+                        match self.push_directory(&entry.path, absolute, depth + 1, ancestors) {
+                            Err(error) if self.skip_vanished(&error) => continue,
+                            Err(error) => return Some(Err(error)),
+                            Ok(()) => {}
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(error) if self.skip_vanished(&error) => continue,
+                    Err(error) => return Some(Err(error)),
+                }
+            }
+
+            return Some(Ok(entry));
+        }
+    }
+}
+
+impl Iterator for Walk {
+    type Item = Result<Entry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entry = match self.next_entry()? {
+                Ok(entry) => entry,
+                Err(error) => {
+                    WalkStats::bump(&self.stats.errors);
+                    return Some(Err(error));
+                }
+            };
+
+            if self
+                .entry_filters
+                .iter()
+                .all(|filter| filter.accepts(&entry))
+            {
+                return Some(Ok(entry));
+            }
+        }
+    }
+}
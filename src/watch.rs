@@ -0,0 +1,756 @@
+use crate::capabilities::Capability;
+use crate::{is_vanished, report_error, Root, Search};
+use anyhow::Result;
+use notify::event::{ModifyKind, RenameMode};
+use notify::{EventKind, RecursiveMode, Watcher};
+use quaero::number::format_size;
+use quaero::users;
+use quaero::{Entry, WalkBuilder};
+use std::collections::{HashMap, HashSet};
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::RecvTimeoutError;
+use std::time::{Duration, Instant};
+
+/// How long after the last new match a batch of them is given to --exec-on-new, unless
+/// --debounce says otherwise.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// What `--watch` does with the new matches besides printing them.
+#[derive(Debug, Default)]
+pub struct Options {
+    /// `--exec-on-new`: a shell command run for every batch of new matches, with their
+    /// paths as its arguments
+    pub exec_on_new: Option<String>,
+    /// `--exec-batch`: the most paths one run of `exec_on_new` is given
+    pub exec_batch: Option<usize>,
+    /// `--debounce`: how long no new match has to come in for a burst of them to be
+    /// over, the matches being held back until then and reported at once
+    pub debounce: Option<Duration>,
+    /// `--coalesce-dir`: the matches of a burst in the same directory are reported once,
+    /// as that directory, or as the directory made in the burst they are all below
+    pub coalesce_dir: bool,
+    /// `--alert-growth`: how much a matched file, or the files in a matched directory,
+    /// may grow, and optionally within how long, before it is alerted about
+    pub alert_growth: Option<(u64, Option<Duration>)>,
+    /// `--alert-command`: a shell command run for every alert instead of printing it,
+    /// with the path as its argument
+    pub alert_command: Option<String>,
+    /// `--watch-metadata`: whether the changes of the permissions and the owners of the
+    /// matches are reported as well
+    pub metadata: bool,
+}
+
+impl Options {
+    /// Whether the matches are held back until the burst they came in is over.
+    fn holds(&self) -> bool {
+        self.debounce.is_some() || self.coalesce_dir
+    }
+}
+
+struct Watched<'a> {
+    label: Option<&'a str>,
+    path: &'a Path,
+    /// The root as it was watched, made absolute but with its links left in place:
+    /// events name paths below this
+    absolute: PathBuf,
+    builder: WalkBuilder,
+}
+
+impl Watched<'_> {
+    /// The path an event names as the same path below the root as it was given, if it
+    /// is below it.
+    fn given(&self, path: &Path) -> Option<PathBuf> {
+        let below = path.strip_prefix(&self.absolute).ok()?;
+        Some(self.path.join(below))
+    }
+}
+
+struct Watch<'a, 's, F> {
+    search: &'a Search<'s>,
+    roots: Vec<Watched<'a>>,
+    /// Paths already reported, as events name them, forgotten again once they are
+    /// removed or renamed away
+    reported: HashSet<PathBuf>,
+    on_match: F,
+    options: Options,
+    /// The paths of the new matches `exec_on_new` is yet to be run for
+    batch: Vec<PathBuf>,
+    /// The matches of the burst going on, with the paths events name them by, held back
+    /// until it is over
+    held: Vec<(usize, PathBuf, Entry)>,
+    /// The directories made in the burst going on, for `coalesce_dir`
+    made: Vec<PathBuf>,
+    /// When the burst is over, and the batch run, unless more matches come in before
+    due: Option<Instant>,
+    /// The sizes the files had when they last changed, for `alert_growth`, by the paths
+    /// events name them by
+    sizes: HashMap<PathBuf, u64>,
+    /// How much the matched files and directories have grown
+    growing: HashMap<PathBuf, Growing>,
+    /// Whether the files that changed, and the directories they are in, match
+    matching: HashMap<PathBuf, bool>,
+    /// The permissions and owners of the matches, for `metadata`, by the paths events
+    /// name them by
+    attributes: HashMap<PathBuf, Attributes>,
+}
+
+/// What `--watch-metadata` reports the changes of.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct Attributes {
+    mode: u32,
+    uid: u32,
+    gid: u32,
+}
+
+impl Attributes {
+    fn of(metadata: &std::fs::Metadata) -> Self {
+        Self {
+            mode: metadata.mode() & 0o7777,
+            uid: metadata.uid(),
+            gid: metadata.gid(),
+        }
+    }
+
+    fn owner(&self) -> String {
+        let user = users::user_name(self.uid).unwrap_or_else(|| self.uid.to_string());
+        let group = users::group_name(self.gid).unwrap_or_else(|| self.gid.to_string());
+        format!("{}:{}", user, group)
+    }
+
+    /// What changed from `before`, as `mode 644 -> 600, owner ada:staff -> root:staff`.
+    fn changes(&self, before: &Self) -> Vec<String> {
+        let mut changes = Vec::new();
+        if self.mode != before.mode {
+            changes.push(tr!(
+                "watch-mode-changed",
+                "from" => format!("{:o}", before.mode),
+                "to" => format!("{:o}", self.mode),
+            ));
+        }
+        if (self.uid, self.gid) != (before.uid, before.gid) {
+            changes.push(tr!(
+                "watch-owner-changed",
+                "from" => before.owner(),
+                "to" => self.owner(),
+            ));
+        }
+        changes
+    }
+}
+
+struct Growing {
+    /// Since when, the start of the window of `alert_growth`
+    since: Instant,
+    bytes: u64,
+}
+
+impl<F: FnMut(Option<&str>, Entry) -> bool> Watch<'_, '_, F> {
+    /// Reports `path` if it matches. A directory that appeared with its contents, e.g.
+    /// by being moved in, is searched as well since its children raise no events.
+    ///
+    /// Returns whether watching should go on.
+    fn appeared(&mut self, path: &Path) -> bool {
+        let mut admitted = None;
+        for (index, root) in self.roots.iter().enumerate() {
+            let Some(given) = root.given(path) else {
+                continue;
+            };
+
+            match root.builder.admit(given) {
+                Ok(Some(entry)) => {
+                    admitted = Some((index, entry));
+                    break;
+                }
+                Ok(None) => {}
+                Err(error) if is_vanished(&error) => return true,
+                Err(error) => {
+                    report_error(&error);
+                    return true;
+                }
+            }
+        }
+        let Some((root, entry)) = admitted else {
+            return true;
+        };
+
+        let is_directory = entry.is_dir();
+        let (directory, depth) = (PathBuf::from(&entry.path), entry.depth);
+        if !self.report(root, entry) {
+            return false;
+        }
+
+        if is_directory {
+            if self.options.coalesce_dir {
+                self.made.push(directory.clone());
+            }
+            let remaining = self.search.args.depth.map_or(usize::MAX, |max| max - depth);
+            for child in self
+                .search
+                .walk_builder(directory)
+                .max_depth(remaining)
+                .build()
+            {
+                let admitted = child.and_then(|child| self.roots[root].builder.admit(&child.path));
+                match admitted {
+                    Ok(Some(child)) => {
+                        if !self.report(root, child) {
+                            return false;
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(error) if is_vanished(&error) => {}
+                    Err(error) => report_error(&error),
+                }
+            }
+        }
+
+        true
+    }
+
+    fn report(&mut self, root: usize, entry: Entry) -> bool {
+        let watched = &self.roots[root];
+        let below = entry
+            .path
+            .strip_prefix(watched.path)
+            .expect("admitted entries are below their root");
+        let absolute = watched.absolute.join(below);
+        if !self.search.matcher.matches(&entry) || !self.reported.insert(absolute.clone()) {
+            return true;
+        }
+
+        if self.options.metadata {
+            if let Ok(metadata) = entry.metadata() {
+                self.attributes
+                    .insert(absolute.clone(), Attributes::of(metadata));
+            }
+        }
+        if self.options.holds() {
+            self.held.push((root, absolute, entry));
+            self.due = Some(Instant::now() + self.options.debounce.unwrap_or(DEBOUNCE));
+            return true;
+        }
+        self.emit(root, entry)
+    }
+
+    /// Reports the matches of the burst that is over, their directories instead with
+    /// `coalesce_dir`, and runs the batch.
+    fn settled(&mut self) -> bool {
+        let held = std::mem::take(&mut self.held);
+        let made = std::mem::take(&mut self.made);
+        let going_on = match self.options.coalesce_dir {
+            true => coalesced(held, &made)
+                .into_iter()
+                .all(|(root, entry)| self.emit(root, entry)),
+            false => held
+                .into_iter()
+                .all(|(root, _, entry)| self.emit(root, entry)),
+        };
+        self.run_batch();
+        going_on
+    }
+
+    fn emit(&mut self, root: usize, entry: Entry) -> bool {
+        let path = quaero::cleaned_path(&entry.path).into_owned();
+        if !(self.on_match)(self.roots[root].label, entry) {
+            return false;
+        }
+        if self.options.exec_on_new.is_some() {
+            self.batch.push(path);
+            if !self.options.holds() {
+                self.due = Some(Instant::now() + DEBOUNCE);
+            }
+            if self
+                .options
+                .exec_batch
+                .is_some_and(|most| self.batch.len() >= most)
+            {
+                self.run_batch();
+            }
+        }
+        true
+    }
+
+    /// Runs `--exec-on-new` for the matches batched up.
+    fn run_batch(&mut self) {
+        self.due = None;
+        let Some(command) = &self.options.exec_on_new else {
+            return;
+        };
+        if !self.batch.is_empty() {
+            run("exec-on-new", command, std::mem::take(&mut self.batch));
+        }
+    }
+
+    /// Adds what the file at `path` grew by since it was last seen to it and to the
+    /// directories it is in, for those that match, and alerts about those that have
+    /// grown by as much as `alert_growth` allows.
+    fn grew(&mut self, path: &Path) {
+        let Some((limit, within)) = self.options.alert_growth else {
+            return;
+        };
+        let size = match std::fs::symlink_metadata(path) {
+            Ok(metadata) if metadata.is_file() => metadata.len(),
+            _ => return,
+        };
+        // A file first seen changing is only measured from then on.
+        let Some(last) = self.sizes.insert(path.to_path_buf(), size) else {
+            return;
+        };
+        let Some(root) = self
+            .roots
+            .iter()
+            .position(|root| root.given(path).is_some())
+        else {
+            return;
+        };
+        if size <= last {
+            return;
+        }
+
+        let now = Instant::now();
+        let absolute = self.roots[root].absolute.clone();
+        for grown in path
+            .ancestors()
+            .take_while(|grown| grown.starts_with(&absolute))
+        {
+            if !self.matches(root, grown) {
+                continue;
+            }
+            let growing = self.growing.entry(grown.to_path_buf()).or_insert(Growing {
+                since: now,
+                bytes: 0,
+            });
+            if within.is_some_and(|within| now.duration_since(growing.since) > within) {
+                *growing = Growing {
+                    since: now,
+                    bytes: 0,
+                };
+            }
+            growing.bytes += size - last;
+            if growing.bytes < limit {
+                continue;
+            }
+
+            let bytes = std::mem::replace(
+                growing,
+                Growing {
+                    since: now,
+                    bytes: 0,
+                },
+            )
+            .bytes;
+            let given = self.roots[root].given(grown).expect("below the root");
+            let given = quaero::cleaned_path(&given).into_owned();
+            match &self.options.alert_command {
+                Some(command) => run("alert-command", command, vec![given]),
+                None => eprintln!(
+                    "{}",
+                    tr!(
+                        "watch-growth",
+                        "path" => given.to_string_lossy().into_owned(),
+                        "size" => format_size(bytes),
+                    )
+                ),
+            }
+        }
+    }
+
+    /// Reports what changed of the permissions and the owner of the match at `path`, if it
+    /// is one and they did rather than only its times.
+    fn changed(&mut self, path: &Path) {
+        let Some(before) = self.attributes.get_mut(path) else {
+            return;
+        };
+        let Ok(metadata) = std::fs::symlink_metadata(path) else {
+            return;
+        };
+        let after = Attributes::of(&metadata);
+        let changes = after.changes(before);
+        *before = after;
+        if changes.is_empty() {
+            return;
+        }
+
+        let Some(given) = self.roots.iter().find_map(|root| root.given(path)) else {
+            return;
+        };
+        eprintln!(
+            "{}",
+            tr!(
+                "watch-metadata-changed",
+                "path" => quaero::clean_path(&given).into_owned(),
+                "changes" => changes.join(", "),
+            )
+        );
+    }
+
+    /// Records the permissions and owners of the matches there are to begin with, for
+    /// their changes to be reported.
+    fn record_attributes(&mut self, roots: &[Root]) {
+        for (root, watched) in roots.iter().zip(&self.roots) {
+            for entry in self.search.root_builder(root).build() {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(error) if is_vanished(&error) => continue,
+                    Err(error) => {
+                        report_error(&error);
+                        continue;
+                    }
+                };
+                let Ok(below) = entry.path.strip_prefix(watched.path) else {
+                    continue;
+                };
+                if !self.search.matcher.matches(&entry) {
+                    continue;
+                }
+                if let Ok(metadata) = entry.metadata() {
+                    self.attributes
+                        .insert(watched.absolute.join(below), Attributes::of(metadata));
+                }
+            }
+        }
+    }
+
+    /// Whether the entry at `path`, as an event names it, matches, as it did when first
+    /// asked about.
+    fn matches(&mut self, root: usize, path: &Path) -> bool {
+        if let Some(&matches) = self.matching.get(path) {
+            return matches;
+        }
+
+        let watched = &self.roots[root];
+        let matches = match watched
+            .given(path)
+            .map(|given| watched.builder.admit(given))
+        {
+            Some(Ok(Some(entry))) => self.search.matcher.matches(&entry),
+            Some(Ok(None)) | None => false,
+            Some(Err(error)) => {
+                if !is_vanished(&error) {
+                    report_error(&error);
+                }
+                return false;
+            }
+        };
+        self.matching.insert(path.to_path_buf(), matches);
+        matches
+    }
+
+    /// Forgets `path` and everything below it, the matches held back among them
+    /// included, e.g. for the temporary files rsync renames once written.
+    fn disappeared(&mut self, path: &Path) {
+        self.reported.retain(|reported| !reported.starts_with(path));
+        self.sizes.retain(|sized, _| !sized.starts_with(path));
+        self.growing.retain(|grown, _| !grown.starts_with(path));
+        self.matching
+            .retain(|matched, _| !matched.starts_with(path));
+        self.attributes
+            .retain(|attributed, _| !attributed.starts_with(path));
+        self.held
+            .retain(|(_, absolute, _)| !absolute.starts_with(path));
+    }
+}
+
+/// Runs the shell command of `--option`, with `{}` in it standing for `paths`, or the
+/// paths added at the end, and waits for it to finish.
+fn run(option: &str, command: &str, paths: Vec<PathBuf>) {
+    // The paths are the shell's "$@", with the bytes they have, as with --filter-cmd.
+    let script = match command.contains("{}") {
+        true => command.replace("{}", "\"$@\""),
+        false => format!("{} \"$@\"", command),
+    };
+    let status = Command::new("sh")
+        .arg("-c")
+        .arg(script)
+        .arg("sh")
+        .args(paths)
+        .stdin(Stdio::null())
+        .status();
+    match status {
+        Ok(status) if status.success() => {}
+        Ok(status) => report_error(&anyhow::anyhow!(tr!(
+            "error-watch-command-failed",
+            "option" => option,
+            "command" => command,
+            "status" => status.to_string(),
+        ))),
+        Err(error) => report_error(&anyhow::Error::from(error).context(command.to_owned())),
+    }
+}
+
+/// Groups the matches of a burst by the outermost directory made in it they are in, or
+/// else by their parent, and stands for every group of more than one with its directory.
+fn coalesced(held: Vec<(usize, PathBuf, Entry)>, made: &[PathBuf]) -> Vec<(usize, Entry)> {
+    let mut groups: Vec<(usize, PathBuf, Vec<Entry>)> = Vec::new();
+    let mut indices = HashMap::new();
+    for (root, _, entry) in held {
+        let directory = made
+            .iter()
+            .filter(|made| entry.path.starts_with(made))
+            .map(PathBuf::as_path)
+            .min_by_key(|made| made.components().count())
+            .or_else(|| entry.path.parent())
+            .unwrap_or(&entry.path)
+            .to_path_buf();
+        let index = *indices.entry((root, directory.clone())).or_insert_with(|| {
+            groups.push((root, directory, Vec::new()));
+            groups.len() - 1
+        });
+        groups[index].2.push(entry);
+    }
+
+    let mut coalesced = Vec::new();
+    for (root, directory, mut entries) in groups {
+        if entries.len() == 1 {
+            coalesced.extend(entries.pop().map(|entry| (root, entry)));
+            continue;
+        }
+        if let Some(index) = entries.iter().position(|entry| entry.path == directory) {
+            coalesced.push((root, entries.swap_remove(index)));
+            continue;
+        }
+        match Entry::from_path(&directory) {
+            Ok(entry) => coalesced.push((root, entry)),
+            // A directory gone since has taken its matches with it.
+            Err(error) if is_vanished(&error) => {}
+            Err(error) => {
+                report_error(&error.context(directory.display().to_string()));
+                coalesced.extend(entries.into_iter().map(|entry| (root, entry)));
+            }
+        }
+    }
+
+    coalesced
+}
+
+/// `--watch`: keeps running after the initial search, reporting the entries that are
+/// created or renamed into the roots and match, until `on_match` returns false, and
+/// doing with them what `options` say.
+pub fn watch(
+    search: &Search,
+    options: Options,
+    on_match: impl FnMut(Option<&str>, Entry) -> bool,
+) -> Result<()> {
+    let roots = search.roots()?;
+    let (sender, events) = std::sync::mpsc::channel();
+    if !Capability::Inotify.check_for("--watch") {
+        return Ok(());
+    }
+
+    // Whatever can't be watched is warned about, and the search has been done anyway.
+    let mut watcher = match notify::recommended_watcher(sender) {
+        Ok(watcher) => watcher,
+        Err(error) => {
+            eprintln!(
+                "{}",
+                tr!("watch-unavailable", "reason" => error.to_string())
+            );
+            return Ok(());
+        }
+    };
+    let mut watch = Watch {
+        search,
+        roots: roots
+            .iter()
+            .map(|root| Watched {
+                label: root.label.as_deref(),
+                path: &root.path,
+                absolute: std::path::absolute(&root.path).unwrap_or_else(|_| root.path.clone()),
+                builder: search.root_builder(root),
+            })
+            .collect(),
+        reported: HashSet::new(),
+        on_match,
+        options,
+        batch: Vec::new(),
+        held: Vec::new(),
+        made: Vec::new(),
+        due: None,
+        sizes: HashMap::new(),
+        growing: HashMap::new(),
+        matching: HashMap::new(),
+        attributes: HashMap::new(),
+    };
+
+    let mut watched = 0;
+    for root in &watch.roots {
+        match watcher.watch(&root.absolute, RecursiveMode::Recursive) {
+            Ok(()) => watched += 1,
+            Err(error) => eprintln!(
+                "{}",
+                tr!(
+                    "watch-root-unavailable",
+                    "path" => root.path.display().to_string(),
+                    "reason" => error.to_string(),
+                )
+            ),
+        }
+    }
+    if watched == 0 {
+        return Ok(());
+    }
+    if watch.options.metadata {
+        watch.record_attributes(&roots);
+    }
+
+    loop {
+        // A burst, and a batch, waits for the matches that come in right after it.
+        let event = match watch.due {
+            Some(due) => events.recv_timeout(due.saturating_duration_since(Instant::now())),
+            None => events.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        let event = match event {
+            Ok(Ok(event)) => event,
+            Ok(Err(error)) => {
+                report_error(&error.into());
+                continue;
+            }
+            Err(RecvTimeoutError::Timeout) => match watch.settled() {
+                true => continue,
+                false => return Ok(()),
+            },
+            Err(RecvTimeoutError::Disconnected) => break,
+        };
+
+        match event.kind {
+            EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                for path in &event.paths {
+                    // What a file made while watching grows by is counted from nothing.
+                    if watch.options.alert_growth.is_some() {
+                        watch.sizes.insert(path.clone(), 0);
+                        watch.grew(path);
+                    }
+                    if !watch.appeared(path) {
+                        watch.settled();
+                        return Ok(());
+                    }
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+                if let [from, to] = &event.paths[..] {
+                    watch.disappeared(from);
+                    if !watch.appeared(to) {
+                        watch.settled();
+                        return Ok(());
+                    }
+                }
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::From)) | EventKind::Remove(_) => {
+                for path in &event.paths {
+                    watch.disappeared(path);
+                }
+            }
+            EventKind::Modify(ModifyKind::Metadata(_)) => {
+                for path in &event.paths {
+                    watch.changed(path);
+                }
+            }
+            EventKind::Modify(ModifyKind::Data(_) | ModifyKind::Any) => {
+                for path in &event.paths {
+                    watch.grew(path);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    watch.settled();
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use notify::Event;
+    use std::time::Duration;
+
+    #[test]
+    fn symlinked_root() {
+        let directory = std::env::temp_dir().join(format!("quaero-watch-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(directory.join("real")).unwrap();
+        std::os::unix::fs::symlink("real", directory.join("link")).unwrap();
+
+        let link = directory.join("link");
+        let root = Watched {
+            label: None,
+            path: &link,
+            absolute: std::path::absolute(&link).unwrap(),
+            builder: WalkBuilder::new(&link),
+        };
+        let (sender, events) = std::sync::mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender).unwrap();
+        watcher
+            .watch(&root.absolute, RecursiveMode::Recursive)
+            .unwrap();
+        std::fs::write(directory.join("real/new.txt"), "").unwrap();
+
+        let created = std::iter::from_fn(|| events.recv_timeout(Duration::from_secs(5)).ok())
+            .filter_map(|event: notify::Result<Event>| event.ok())
+            .find(|event| matches!(event.kind, EventKind::Create(_)))
+            .expect("an event for the new file");
+        let admitted = root
+            .given(&created.paths[0])
+            .map(|given| root.builder.admit(given).unwrap());
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        let entry = admitted.flatten().expect("the new file below the link");
+        assert_eq!(entry.path, link.join("new.txt"));
+    }
+
+    #[test]
+    fn attribute_changes() {
+        let before = Attributes {
+            mode: 0o644,
+            uid: 0,
+            gid: 0,
+        };
+        assert!(before.changes(&before).is_empty());
+
+        let after = Attributes {
+            mode: 0o4755,
+            ..before
+        };
+        assert_eq!(after.changes(&before), ["mode 644 -> 4755"]);
+
+        let after = Attributes {
+            gid: 54321,
+            ..after
+        };
+        let changes = after.changes(&before);
+        assert_eq!(changes.len(), 2);
+        assert!(changes[1].ends_with(":54321"), "{:?}", changes);
+    }
+
+    #[test]
+    fn coalescing() {
+        let directory =
+            std::env::temp_dir().join(format!("quaero-coalesce-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(directory.join("made/below")).unwrap();
+        std::fs::create_dir_all(directory.join("old")).unwrap();
+        let paths = ["made/a", "made/below/b", "old/c", "old/d", "e"];
+        for path in paths {
+            std::fs::write(directory.join(path), "").unwrap();
+        }
+
+        let held = paths
+            .iter()
+            .map(|path| {
+                let path = directory.join(path);
+                (0, path.clone(), Entry::from_path(path).unwrap())
+            })
+            .collect();
+        let made = [directory.join("made/below"), directory.join("made")];
+        let coalesced: Vec<_> = coalesced(held, &made)
+            .into_iter()
+            .map(|(_, entry)| entry.path)
+            .collect();
+        std::fs::remove_dir_all(&directory).unwrap();
+
+        let expected = ["made", "old", "e"].map(|path| directory.join(path));
+        assert_eq!(coalesced, expected);
+    }
+}
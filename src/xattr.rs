@@ -0,0 +1,119 @@
+//! Extended attributes of files, for `--has-xattr` and `--xattr`, and for `--replace`
+//! to carry over into a file's rewritten copy. Those of a symbolic link are its own, not
+//! those of the file it points to.
+
+use std::ffi::CString;
+use std::fs::File;
+use std::os::fd::{AsRawFd, RawFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+
+#[cfg(target_os = "linux")]
+unsafe fn get(path: &CString, name: &CString, value: *mut u8, size: usize) -> isize {
+    libc::lgetxattr(path.as_ptr(), name.as_ptr(), value.cast(), size)
+}
+
+#[cfg(target_os = "macos")]
+unsafe fn get(path: &CString, name: &CString, value: *mut u8, size: usize) -> isize {
+    libc::getxattr(
+        path.as_ptr(),
+        name.as_ptr(),
+        value.cast(),
+        size,
+        0,
+        libc::XATTR_NOFOLLOW,
+    )
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+unsafe fn get(_: &CString, _: &CString, _: *mut u8, _: usize) -> isize {
+    -1
+}
+
+#[cfg(target_os = "linux")]
+unsafe fn list(path: &CString, names: *mut u8, size: usize) -> isize {
+    libc::llistxattr(path.as_ptr(), names.cast(), size)
+}
+
+#[cfg(target_os = "macos")]
+unsafe fn list(path: &CString, names: *mut u8, size: usize) -> isize {
+    libc::listxattr(path.as_ptr(), names.cast(), size, libc::XATTR_NOFOLLOW)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+unsafe fn list(_: &CString, _: *mut u8, _: usize) -> isize {
+    -1
+}
+
+#[cfg(target_os = "linux")]
+unsafe fn set(file: RawFd, name: &CString, value: &[u8]) -> libc::c_int {
+    libc::fsetxattr(file, name.as_ptr(), value.as_ptr().cast(), value.len(), 0)
+}
+
+#[cfg(target_os = "macos")]
+unsafe fn set(file: RawFd, name: &CString, value: &[u8]) -> libc::c_int {
+    libc::fsetxattr(
+        file,
+        name.as_ptr(),
+        value.as_ptr().cast(),
+        value.len(),
+        0,
+        0,
+    )
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+unsafe fn set(_: RawFd, _: &CString, _: &[u8]) -> libc::c_int {
+    -1
+}
+
+/// What `read` fills in, with `read` called again for as long as what there is to read
+/// grows between asking for its size and reading it.
+fn read_all(read: impl Fn(*mut u8, usize) -> isize) -> Option<Vec<u8>> {
+    loop {
+        let size = read(std::ptr::null_mut(), 0);
+        let mut value = vec![0u8; usize::try_from(size).ok()?];
+        match usize::try_from(read(value.as_mut_ptr(), value.len())) {
+            Ok(read) => {
+                value.truncate(read);
+                return Some(value);
+            }
+            Err(_) if std::io::Error::last_os_error().raw_os_error() == Some(libc::ERANGE) => {}
+            Err(_) => return None,
+        }
+    }
+}
+
+/// The value of the attribute `name` of the file at `path`, `None` if it has no such
+/// attribute or it can't be read.
+pub fn value(path: &Path, name: &str) -> Option<Vec<u8>> {
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let name = CString::new(name).ok()?;
+
+    read_all(|value, size| unsafe { get(&path, &name, value, size) })
+}
+
+/// Gives `file` the attributes of the file at `path`, returning the names of those it
+/// couldn't be given. An error if they couldn't be listed, none where attributes aren't
+/// supported at all.
+pub fn copy(path: &Path, file: &File) -> std::io::Result<Vec<String>> {
+    let path = CString::new(path.as_os_str().as_bytes())?;
+    let Some(names) = read_all(|names, size| unsafe { list(&path, names, size) }) else {
+        let error = std::io::Error::last_os_error();
+        return match error.raw_os_error() {
+            Some(libc::ENOTSUP) => Ok(Vec::new()),
+            _ => Err(error),
+        };
+    };
+
+    Ok(names
+        .split(|byte| *byte == 0)
+        .filter(|name| !name.is_empty())
+        .filter_map(|name| {
+            let name = CString::new(name).ok()?;
+            let copied = read_all(|value, size| unsafe { get(&path, &name, value, size) })
+                .is_some_and(|value| unsafe { set(file.as_raw_fd(), &name, &value) } == 0);
+            (!copied).then(|| name.to_string_lossy().into_owned())
+        })
+        .collect())
+}